@@ -0,0 +1,70 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Compares repainting a window of 500 labels with and without
+//! `PaintCtx::with_cache`.
+//!
+//! Run with `cargo bench --bench paint_cache`.
+
+use std::time::{Duration, Instant};
+
+use masonry::testing::{ModularWidget, TestHarness};
+use masonry::widget::{Flex, Label, WidgetPod};
+use masonry::{Point, Size};
+use smallvec::smallvec;
+
+const LABEL_COUNT: usize = 500;
+const WARMUP_FRAMES: u32 = 5;
+const FRAMES: u32 = 50;
+
+// A label that paints through the paint cache if `cached` is set.
+fn label(index: usize, cached: bool) -> ModularWidget<WidgetPod<Label>> {
+    let label = WidgetPod::new(Label::new(format!("Label number {index}")));
+    ModularWidget::new(label)
+        .lifecycle_fn(|label, ctx, event, env| label.lifecycle(ctx, event, env))
+        .layout_fn(|label, ctx, bc, env| {
+            let size = label.layout(ctx, bc, env);
+            ctx.place_child(label, Point::ORIGIN, env);
+            size
+        })
+        .paint_fn(move |label, ctx, env| {
+            if cached {
+                ctx.with_cache((), |ctx| label.paint(ctx, env));
+            } else {
+                label.paint(ctx, env);
+            }
+        })
+        .children_fn(|label| smallvec![label.as_dyn()])
+}
+
+// The average time it takes to repaint the whole window.
+fn repaint_time(cached: bool) -> Duration {
+    let mut column = Flex::column();
+    for index in 0..LABEL_COUNT {
+        column = column.with_child(label(index, cached));
+    }
+    let mut harness = TestHarness::create_with_size(column, Size::new(200.0, 12_000.0));
+
+    for _ in 0..WARMUP_FRAMES {
+        harness.invalidate_window();
+        let _ = harness.render();
+    }
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        harness.invalidate_window();
+        let _ = harness.render();
+    }
+    start.elapsed() / FRAMES
+}
+
+fn main() {
+    let uncached = repaint_time(false);
+    let cached = repaint_time(true);
+    println!("repaint of {LABEL_COUNT} labels, uncached: {uncached:?}");
+    println!("repaint of {LABEL_COUNT} labels, cached:   {cached:?}");
+    println!(
+        "speedup: {:.2}x",
+        uncached.as_secs_f64() / cached.as_secs_f64()
+    );
+}
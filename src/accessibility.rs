@@ -0,0 +1,180 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Describing the widget tree to assistive technologies.
+//!
+//! Widgets describe themselves in [`Widget::accessibility`]; [`WidgetPod`](crate::WidgetPod)
+//! nests the resulting nodes, and [`WindowRoot::accessibility_tree`](crate::WindowRoot::accessibility_tree)
+//! collects them into an [`AccessTree`] for the whole window.
+
+use crate::widget::WidgetRef;
+use crate::{AccessCtx, Env, Rect, Widget, WidgetId};
+
+/// What kind of element an [`AccessNode`] describes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Role {
+    /// A widget without a more specific role.
+    ///
+    /// This is the role of widgets that hold the keyboard focus without setting
+    /// a role, so that assistive technologies still have a node to follow.
+    Unknown,
+    /// Text that names or describes another element.
+    Label,
+    /// Text that isn't associated with another element, eg a paragraph.
+    StaticText,
+    /// The heading of a section, at the given level, from 1 to 6.
+    Heading {
+        /// The heading's level; 1 is the top level.
+        level: u8,
+    },
+    /// A control that triggers an action when pressed.
+    Button,
+}
+
+impl Role {
+    /// Whether a node with this role and no name of its own is named after the
+    /// text of its descendants, eg a button after its label.
+    pub fn is_named_from_contents(self) -> bool {
+        matches!(self, Role::Button)
+    }
+}
+
+/// The description of one widget for assistive technologies.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessNode {
+    /// The id of the widget this node describes.
+    pub id: WidgetId,
+    /// What kind of element the widget is.
+    pub role: Role,
+    /// The text assistive technologies announce for this node.
+    pub name: Option<String>,
+    /// The layout rect of the widget, in window coordinates.
+    pub bounds: Rect,
+    /// Whether the widget has the keyboard focus.
+    pub is_focused: bool,
+    /// Whether the widget is disabled.
+    pub is_disabled: bool,
+    /// Whether the widget can't be seen: it's stashed, or entirely outside the
+    /// window or the viewport of a scroll area containing it.
+    ///
+    /// Hidden nodes stay in the tree, so that assistive technologies can still
+    /// scroll to them.
+    pub is_hidden: bool,
+    /// The nodes of the widget's descendants.
+    pub children: Vec<AccessNode>,
+}
+
+/// The accessibility nodes of a window.
+///
+/// Created with [`WindowRoot::accessibility_tree`](crate::WindowRoot::accessibility_tree).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AccessTree {
+    /// The top-level nodes.
+    pub nodes: Vec<AccessNode>,
+    /// The id of the focused node, if any.
+    pub focus: Option<WidgetId>,
+}
+
+impl AccessNode {
+    /// Recursively find the node with the given id.
+    pub fn find(&self, id: WidgetId) -> Option<&AccessNode> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.children.iter().find_map(|child| child.find(id))
+    }
+}
+
+impl AccessTree {
+    /// Return the node with the given id.
+    pub fn get(&self, id: WidgetId) -> Option<&AccessNode> {
+        self.nodes.iter().find_map(|node| node.find(id))
+    }
+
+    /// Return the node of the widget that has the keyboard focus.
+    pub fn focused_node(&self) -> Option<&AccessNode> {
+        self.get(self.focus?)
+    }
+}
+
+/// Add the node of `widget` and its descendants to `parent_ctx`.
+///
+/// Widgets without a role don't get a node; their descendants' nodes are
+/// added to `parent_ctx` directly.
+pub(crate) fn add_widget_nodes(
+    widget: WidgetRef<'_, dyn Widget>,
+    parent_ctx: &mut AccessCtx,
+    env: &Env,
+) {
+    let state = widget.state();
+    let bounds = state.window_layout_rect();
+    let is_hidden = parent_ctx.is_hidden
+        || state.is_stashed
+        || parent_ctx.visible_rect.intersect(bounds).is_empty();
+    // Scroll areas clip their content to their own rect.
+    let visible_rect = if state.is_portal {
+        parent_ctx.visible_rect.intersect(bounds)
+    } else {
+        parent_ctx.visible_rect
+    };
+
+    let mut ctx = AccessCtx {
+        widget_state: state,
+        focus_widget: parent_ctx.focus_widget,
+        visible_rect,
+        is_hidden,
+        role: None,
+        name: None,
+        children: Vec::new(),
+    };
+    widget.deref().accessibility(&mut ctx, env);
+    for child in widget.children() {
+        add_widget_nodes(child, &mut ctx, env);
+    }
+
+    let is_focused = ctx.is_focused();
+    let role = match ctx.role {
+        Some(role) => role,
+        None if is_focused => Role::Unknown,
+        None => {
+            parent_ctx.children.append(&mut ctx.children);
+            return;
+        }
+    };
+    let name = ctx.name.or_else(|| {
+        if role.is_named_from_contents() {
+            let mut name = String::new();
+            contents_text(&ctx.children, &mut name);
+            Some(name).filter(|name| !name.is_empty())
+        } else {
+            None
+        }
+    });
+
+    parent_ctx.children.push(AccessNode {
+        id: state.id,
+        role,
+        name,
+        bounds,
+        is_focused,
+        is_disabled: state.is_disabled(),
+        is_hidden,
+        children: ctx.children,
+    });
+}
+
+fn contents_text(nodes: &[AccessNode], text: &mut String) {
+    for node in nodes {
+        match &node.name {
+            Some(name) => {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(name);
+            }
+            None => contents_text(&node.children, text),
+        }
+    }
+}
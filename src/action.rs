@@ -21,6 +21,8 @@ pub enum Action {
     TextChanged(String),
     TextEntered(String),
     CheckboxChecked(bool),
+    TabSelected(usize),
+    SegmentSelected(usize),
     // FIXME - This is a huge hack
     Other(Arc<dyn Any>),
 }
@@ -32,6 +34,8 @@ impl PartialEq for Action {
             (Self::TextChanged(l0), Self::TextChanged(r0)) => l0 == r0,
             (Self::TextEntered(l0), Self::TextEntered(r0)) => l0 == r0,
             (Self::CheckboxChecked(l0), Self::CheckboxChecked(r0)) => l0 == r0,
+            (Self::TabSelected(l0), Self::TabSelected(r0)) => l0 == r0,
+            (Self::SegmentSelected(l0), Self::SegmentSelected(r0)) => l0 == r0,
             #[allow(clippy::vtable_address_comparisons)]
             (Self::Other(val_l), Self::Other(val_r)) => Arc::ptr_eq(val_l, val_r),
             _ => false,
@@ -46,6 +50,8 @@ impl std::fmt::Debug for Action {
             Self::TextChanged(text) => f.debug_tuple("TextChanged").field(text).finish(),
             Self::TextEntered(text) => f.debug_tuple("TextEntered").field(text).finish(),
             Self::CheckboxChecked(b) => f.debug_tuple("CheckboxChecked").field(b).finish(),
+            Self::TabSelected(index) => f.debug_tuple("TabSelected").field(index).finish(),
+            Self::SegmentSelected(index) => f.debug_tuple("SegmentSelected").field(index).finish(),
             Self::Other(_) => write!(f, "Other(...)"),
         }
     }
@@ -16,13 +16,13 @@ use std::rc::Rc;
 use druid_shell::text::InputHandler;
 // TODO - rename Application to AppHandle in glazier
 // See https://github.com/linebender/glazier/issues/44
-use druid_shell::{Application as AppHandle, WindowHandle};
+use druid_shell::{Application as AppHandle, KbKey, WindowHandle};
 use druid_shell::{
     Cursor, FileDialogToken, FileInfo, Region, TextFieldToken, TimerToken, WindowBuilder,
 };
 // Automatically defaults to std::time::Instant on non Wasm platforms
-use instant::Instant;
-use tracing::{error, info, info_span};
+use instant::{Duration, Instant};
+use tracing::{error, info, info_span, warn};
 
 use crate::action::ActionQueue;
 use crate::app_delegate::{AppDelegate, DelegateCtx, NullDelegate};
@@ -30,8 +30,8 @@ use crate::command::CommandQueue;
 use crate::contexts::GlobalPassCtx;
 use crate::debug_logger::DebugLogger;
 use crate::ext_event::{ExtEventQueue, ExtEventSink, ExtMessage};
-use crate::kurbo::{Point, Size};
-use crate::piet::{Color, Piet, RenderContext};
+use crate::kurbo::{Point, Rect, Size};
+use crate::piet::{Color, Device, Piet, RenderContext};
 use crate::platform::{
     DialogInfo, WindowConfig, WindowSizePolicy, EXT_EVENT_IDLE_TOKEN, RUN_COMMANDS_TOKEN,
 };
@@ -39,10 +39,10 @@ use crate::testing::MockTimerQueue;
 use crate::text::TextFieldRegistration;
 use crate::widget::{FocusChange, StoreInWidgetMut, WidgetMut, WidgetRef, WidgetState};
 use crate::{
-    command as sys_cmd, ArcStr, BoxConstraints, Command, Env, Event, EventCtx, Handled,
-    InternalEvent, InternalLifeCycle, LayoutCtx, LifeCycle, LifeCycleCtx, MasonryWinHandler,
-    PaintCtx, PlatformError, Target, Widget, WidgetCtx, WidgetId, WidgetPod, WindowDescription,
-    WindowId,
+    command as sys_cmd, AccessCtx, AccessTree, ArcStr, BoxConstraints, Command, ContextMenu, Env,
+    Event, EventCtx, Handled, InternalEvent, InternalLifeCycle, LayoutCtx, LifeCycle, LifeCycleCtx,
+    MasonryWinHandler, PaintCtx, PlatformError, Target, Widget, WidgetCtx, WidgetId, WidgetPod,
+    WindowDescription, WindowId,
 };
 
 /// The type of a function that will be called once an IME field is updated.
@@ -105,6 +105,10 @@ pub struct WindowRoot {
     invalid: Region,
     // Is `Some` if the most recently displayed frame was an animation frame.
     pub(crate) last_anim: Option<Instant>,
+    // How long widgets took to handle the last `AnimFrame` event.
+    last_anim_duration: Option<Duration>,
+    // How many `AnimFrame` events took longer than `Env::ANIM_FRAME_BUDGET`.
+    anim_frame_overruns: u64,
     pub(crate) last_mouse_pos: Option<Point>,
     pub(crate) focus: Option<WidgetId>,
     pub(crate) ext_event_sink: ExtEventSink,
@@ -112,9 +116,18 @@ pub struct WindowRoot {
     pub(crate) timers: HashMap<TimerToken, WidgetId>,
     // Used in unit tests - see `src/testing/mock_timer_queue.rs`
     pub(crate) mock_timer_queue: Option<MockTimerQueue>,
+    // Set for windows that aren't shown by the platform, eg in the test harness.
+    // Platform UI such as context menus isn't shown for them.
+    pub(crate) headless: bool,
     pub(crate) transparent: bool,
     pub(crate) ime_handlers: Vec<(TextFieldToken, TextFieldRegistration)>,
     pub(crate) ime_focus_change: Option<Option<TextFieldToken>>,
+    pub(crate) ime_caret_rect: Option<Rect>,
+    // The most recently shown context menu, and the commands of its items by menu id.
+    pub(crate) context_menu: Option<(ContextMenu, Point)>,
+    pub(crate) menu_commands: HashMap<u32, Command>,
+    // Used by `PaintCtx::with_cache`, kept so that it isn't created on every paint.
+    paint_device: Option<Device>,
 }
 
 // ---
@@ -269,8 +282,20 @@ impl AppRoot {
     /// the `window_id` will be `Some(_)`, otherwise (such as if no window
     /// is open but a menu exists, as on macOS) it will be `None`.
     pub fn handle_system_cmd(&mut self, cmd_id: u32, window_id: Option<WindowId>) {
-        #![allow(unused_variables)]
-        todo!();
+        // TODO - application menus
+        let command = window_id.and_then(|window_id| {
+            let inner = self.inner();
+            let window = inner.active_windows.get(&window_id)?;
+            window.menu_commands.get(&cmd_id).cloned()
+        });
+        if let Some(command) = command {
+            self.do_cmd(command);
+            self.process_commands_and_actions();
+            self.process_ime_changes();
+            self.inner().invalidate_paint_regions();
+        } else {
+            error!("unknown menu item {}", cmd_id);
+        }
     }
 
     // TODO - Promises
@@ -876,14 +901,21 @@ impl WindowRoot {
             title,
             transparent,
             last_anim: None,
+            last_anim_duration: None,
+            anim_frame_overruns: 0,
             last_mouse_pos: None,
             focus: None,
             ext_event_sink,
             handle,
             timers: HashMap::new(),
             mock_timer_queue,
+            headless: false,
             ime_handlers: Vec::new(),
             ime_focus_change: None,
+            ime_caret_rect: None,
+            context_menu: None,
+            menu_commands: HashMap::new(),
+            paint_device: None,
         }
     }
 
@@ -906,6 +938,24 @@ impl WindowRoot {
         widget_id == self.root.id() || self.root.state().children.may_contain(&widget_id)
     }
 
+    fn show_context_menu(&mut self, menu: ContextMenu, pos: Point) {
+        self.menu_commands.clear();
+        for (id, item) in menu.items().iter().enumerate() {
+            self.menu_commands.insert(id as u32, item.command().clone());
+        }
+
+        // Headless windows don't have a platform to show menus with; tests
+        // inspect `self.context_menu` instead.
+        if !self.headless {
+            let mut platform_menu = druid_shell::Menu::new_for_popup();
+            for (id, item) in menu.items().iter().enumerate() {
+                platform_menu.add_item(id as u32, item.title(), None, None, item.is_enabled());
+            }
+            self.handle.show_context_menu(platform_menu, pos);
+        }
+        self.context_menu = Some((menu, pos));
+    }
+
     pub(crate) fn post_event_processing(
         &mut self,
         widget_state: &mut WidgetState,
@@ -1001,6 +1051,26 @@ impl WindowRoot {
             self.ime_handlers.push((token, ime_field));
         }
 
+        if let Some((menu, pos)) = widget_state.context_menu.take() {
+            self.show_context_menu(menu, pos);
+        }
+
+        if let Some(caret_rect) = widget_state.ime_caret_rect.take() {
+            if self.ime_caret_rect != Some(caret_rect) {
+                self.ime_caret_rect = Some(caret_rect);
+                // Ask the platform to query the caret position again.
+                let focused_text_field = self
+                    .ime_handlers
+                    .iter()
+                    .find(|(_, reg)| Some(reg.widget_id) == self.focus)
+                    .map(|(token, _)| *token);
+                if let Some(token) = focused_text_field {
+                    self.handle
+                        .update_text_field(token, druid_shell::text::Event::LayoutChanged);
+                }
+            }
+        }
+
         // If there are any commands and they should be processed
         if process_commands && !command_queue.is_empty() {
             // Ask the handler to call us back on idle
@@ -1022,6 +1092,11 @@ impl WindowRoot {
         action_queue: &mut ActionQueue,
         env: &Env,
     ) -> Handled {
+        let context_menu_pos = match &event {
+            Event::MouseDown(mouse) if mouse.button.is_right() => Some(mouse.pos),
+            _ => None,
+        };
+
         match &event {
             Event::WindowSize(size) => self.size = *size,
             Event::MouseDown(e) | Event::MouseUp(e) | Event::MouseMove(e) | Event::Wheel(e) => {
@@ -1054,6 +1129,17 @@ impl WindowRoot {
             );
         }
 
+        // Widgets with expanded hit areas need to know what the pointer is
+        // over, see `Widget::allow_pointer_events_outside_bounds`.
+        let pointer_hit_target = match &event {
+            Event::MouseDown(e) | Event::MouseUp(e) | Event::MouseMove(e) | Event::Wheel(e) => self
+                .root
+                .as_dyn()
+                .find_widget_at_pos(e.pos)
+                .map(|widget| widget.state().id),
+            _ => None,
+        };
+
         let mut widget_state = WidgetState::new(self.root.id(), Some(self.size), "<root>");
         let is_handled = {
             let mut global_state = GlobalPassCtx::new(
@@ -1067,6 +1153,7 @@ impl WindowRoot {
                 self.id,
                 self.focus,
             );
+            global_state.pointer_hit_target = pointer_hit_target;
             let mut notifications = VecDeque::new();
 
             let mut ctx = EventCtx {
@@ -1075,7 +1162,6 @@ impl WindowRoot {
                 notifications: &mut notifications,
                 is_handled: false,
                 is_root: true,
-                request_pan_to_child: None,
             };
 
             {
@@ -1097,6 +1183,23 @@ impl WindowRoot {
             Handled::from(ctx.is_handled)
         };
 
+        // Tab and Shift+Tab move the focus along the focus chain, unless a
+        // widget used the key itself. Other modifiers are left to the platform
+        // and the app, e.g. Ctrl+Tab to switch tabs.
+        if let Event::KeyDown(key) = &event {
+            if key.key == KbKey::Tab
+                && !(key.mods.ctrl() || key.mods.alt() || key.mods.meta())
+                && !is_handled.is_handled()
+                && widget_state.request_focus.is_none()
+            {
+                widget_state.request_focus = Some(if key.mods.shift() {
+                    FocusChange::Previous
+                } else {
+                    FocusChange::Next
+                });
+            }
+        }
+
         // Clean up the timer token and do it immediately after the event handling
         // because the token may be reused and re-added in a lifecycle pass below.
         if let Event::Internal(InternalEvent::RouteTimer(token, _)) = event {
@@ -1132,6 +1235,16 @@ impl WindowRoot {
 
         self.root.as_dyn().debug_validate(false);
 
+        if let Some(pos) = context_menu_pos {
+            self.event(
+                Event::ContextMenu { pos },
+                debug_logger,
+                command_queue,
+                action_queue,
+                env,
+            );
+        }
+
         is_handled
     }
 
@@ -1218,6 +1331,8 @@ impl WindowRoot {
         let last = self.last_anim.take();
         let elapsed_ns = last.map(|t| now.duration_since(t).as_nanos()).unwrap_or(0) as u64;
 
+        // Every widget animating in this frame gets the same event, so they all
+        // advance by the same interval.
         if self.wants_animation_frame() {
             self.event(
                 Event::AnimFrame(elapsed_ns),
@@ -1227,6 +1342,17 @@ impl WindowRoot {
                 env,
             );
             self.last_anim = Some(now);
+
+            let duration = now.elapsed();
+            let budget = Duration::from_secs_f64(env.get(Env::ANIM_FRAME_BUDGET) / 1000.0);
+            if duration > budget {
+                self.anim_frame_overruns += 1;
+                warn!(
+                    "Animation frame took {:?}, over the budget of {:?}",
+                    duration, budget
+                );
+            }
+            self.last_anim_duration = Some(duration);
         }
     }
 
@@ -1350,6 +1476,7 @@ impl WindowRoot {
             self.id,
             self.focus,
         );
+        global_state.paint_device = self.paint_device.take();
         let mut ctx = PaintCtx {
             render_ctx: piet,
             global_state: &mut global_state,
@@ -1375,6 +1502,7 @@ impl WindowRoot {
                 });
             });
         }
+        self.paint_device = global_state.paint_device.take();
 
         if self.wants_animation_frame() {
             self.handle.request_anim_frame();
@@ -1507,31 +1635,32 @@ impl WindowRoot {
     }
 
     fn widget_from_focus_chain(&self, forward: bool) -> Option<WidgetId> {
-        self.focus.and_then(|focus| {
-            self.focus_chain()
-                .iter()
-                // Find where the focused widget is in the focus chain
-                .position(|id| id == &focus)
-                .map(|idx| {
-                    // Return the id that's next to it in the focus chain
-                    let len = self.focus_chain().len();
-                    let new_idx = if forward {
-                        (idx + 1) % len
-                    } else {
-                        (idx + len - 1) % len
-                    };
-                    self.focus_chain()[new_idx]
-                })
-                .or_else(|| {
-                    // If the currently focused widget isn't in the focus chain,
-                    // then we'll just return the first/last entry of the chain, if any.
-                    if forward {
-                        self.focus_chain().first().copied()
-                    } else {
-                        self.focus_chain().last().copied()
-                    }
-                })
-        })
+        self.focus
+            .and_then(|focus| {
+                self.focus_chain()
+                    .iter()
+                    // Find where the focused widget is in the focus chain
+                    .position(|id| id == &focus)
+            })
+            .map(|idx| {
+                // Return the id that's next to it in the focus chain
+                let len = self.focus_chain().len();
+                let new_idx = if forward {
+                    (idx + 1) % len
+                } else {
+                    (idx + len - 1) % len
+                };
+                self.focus_chain()[new_idx]
+            })
+            .or_else(|| {
+                // If no widget is focused, or the focused widget isn't in the focus chain,
+                // then we'll just return the first/last entry of the chain, if any.
+                if forward {
+                    self.focus_chain().first().copied()
+                } else {
+                    self.focus_chain().last().copied()
+                }
+            })
     }
 
     /// Return the root widget.
@@ -1553,4 +1682,45 @@ impl WindowRoot {
     pub fn focused_widget(&self) -> Option<WidgetRef<'_, dyn Widget>> {
         self.find_widget_by_id(self.focus?)
     }
+
+    /// Build the accessibility tree of the window's widgets.
+    ///
+    /// The focused widget's node is marked, so that assistive technologies can
+    /// follow the keyboard focus.
+    pub fn accessibility_tree(&self, env: &Env) -> AccessTree {
+        let root_state = WidgetState::new(self.root.id(), Some(self.size), "<root>");
+        let mut ctx = AccessCtx::new_root(&root_state, self.focus, self.size.to_rect());
+        self.root.accessibility(&mut ctx, env);
+
+        let mut tree = AccessTree {
+            nodes: ctx.children,
+            focus: None,
+        };
+        tree.focus = self.focus.filter(|id| tree.get(*id).is_some());
+        tree
+    }
+
+    /// How long the widgets took to handle the last [`Event::AnimFrame`], if
+    /// there was one.
+    pub fn last_anim_frame_duration(&self) -> Option<Duration> {
+        self.last_anim_duration
+    }
+
+    /// How many [`Event::AnimFrame`]s took longer to handle than [`Env::ANIM_FRAME_BUDGET`].
+    ///
+    /// Each of these also logs a warning.
+    pub fn anim_frame_overruns(&self) -> u64 {
+        self.anim_frame_overruns
+    }
+
+    /// Return the last caret rect reported with `set_ime_caret_rect`, in window coordinates.
+    pub fn ime_caret_rect(&self) -> Option<Rect> {
+        self.ime_caret_rect
+    }
+
+    /// Return the last menu shown with `show_context_menu`, and its position in
+    /// window coordinates.
+    pub fn context_menu(&self) -> Option<&(ContextMenu, Point)> {
+        self.context_menu.as_ref()
+    }
 }
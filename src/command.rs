@@ -65,6 +65,8 @@ pub struct Command {
     symbol: SelectorSymbol,
     payload: Arc<dyn Any>,
     target: Target,
+    source: Option<WidgetId>,
+    subscribers_only: bool,
 }
 
 /// A message passed up the tree from a [`Widget`] to its ancestors.
@@ -330,6 +332,8 @@ impl Command {
             symbol: selector.symbol(),
             payload: Arc::new(payload),
             target: target.into(),
+            source: None,
+            subscribers_only: false,
         }
     }
 
@@ -339,6 +343,8 @@ impl Command {
             symbol,
             payload: payload.into(),
             target,
+            source: None,
+            subscribers_only: false,
         }
         .default_to(Target::Global)
     }
@@ -373,6 +379,33 @@ impl Command {
         self
     }
 
+    /// Record the widget that submitted this `Command`.
+    pub(crate) fn with_source(mut self, source: WidgetId) -> Self {
+        self.source = Some(source);
+        self
+    }
+
+    /// Only deliver this `Command` to widgets subscribed to its selector.
+    ///
+    /// Widgets subscribe with [`LifeCycleCtx::subscribe_to`]. This only affects
+    /// [`Target::Global`] and [`Target::Window`] commands: instead of waking every
+    /// widget, the command is routed to subscribed widgets only. Ancestors of
+    /// subscribed widgets pass it along without receiving it. Commands targeting a
+    /// specific widget are delivered to it whether it subscribed or not.
+    ///
+    /// [`LifeCycleCtx::subscribe_to`]: crate::LifeCycleCtx::subscribe_to
+    pub fn to_subscribers(mut self) -> Self {
+        self.subscribers_only = true;
+        self
+    }
+
+    /// Returns `true` if this `Command` is only delivered to subscribed widgets.
+    ///
+    /// See [`to_subscribers`](Self::to_subscribers).
+    pub fn is_for_subscribers(&self) -> bool {
+        self.subscribers_only
+    }
+
     /// Returns the `Command`'s [`Target`].
     ///
     /// [`Command::to`] can be used to change the [`Target`].
@@ -380,6 +413,19 @@ impl Command {
         self.target
     }
 
+    /// The [`WidgetId`] of the widget that submitted this `Command`.
+    ///
+    /// Returns `None` for commands that didn't come from a widget, eg commands
+    /// sent through an [`ExtEventSink`](crate::ext_event::ExtEventSink).
+    pub fn source(&self) -> Option<WidgetId> {
+        self.source
+    }
+
+    /// The name of this `Command`'s [`Selector`].
+    pub fn selector_name(&self) -> &'static str {
+        self.symbol
+    }
+
     /// Returns `true` if `self` matches this `selector`.
     pub fn is<T>(&self, selector: Selector<T>) -> bool {
         self.symbol == selector.symbol()
@@ -482,6 +528,8 @@ impl From<Selector> for Command {
             symbol: selector.symbol(),
             payload: Arc::new(()),
             target: Target::Auto,
+            source: None,
+            subscribers_only: false,
         }
     }
 }
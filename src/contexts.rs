@@ -5,27 +5,35 @@
 //! The context types that are passed into various widget methods.
 
 use std::any::Any;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
 use std::ops::{Deref, DerefMut};
 use std::rc::Rc;
 use std::time::Duration;
 
 use druid_shell::text::Event as ImeInvalidation;
 use druid_shell::{Cursor, Region, TimerToken, WindowHandle};
+use instant::Instant;
 use tracing::{error, trace, warn};
 
+use crate::accessibility::{AccessNode, Role};
 use crate::action::{Action, ActionQueue};
-use crate::command::{Command, CommandQueue, Notification, SingleUse};
+use crate::command::{Command, CommandQueue, Notification, Selector, SingleUse};
 use crate::debug_logger::DebugLogger;
 use crate::ext_event::ExtEventSink;
-use crate::piet::{Piet, PietText, RenderContext};
+use crate::piet::{Device, ImageFormat, InterpolationMode, Piet, PietText, RenderContext};
 use crate::platform::WindowDescription;
 use crate::promise::PromiseToken;
 use crate::testing::MockTimerQueue;
 use crate::text::{ImeHandlerRef, TextFieldRegistration};
-use crate::widget::{CursorChange, FocusChange, StoreInWidgetMut, WidgetMut, WidgetState};
+use crate::widget::{
+    CursorChange, FocusChange, Label, PaintCache, StoreInWidgetMut, WidgetMut, WidgetRef,
+    WidgetState,
+};
 use crate::{
-    Affine, Env, Insets, Point, Rect, Size, Target, Vec2, Widget, WidgetId, WidgetPod, WindowId,
+    Affine, BoxConstraints, Color, ContextMenu, Env, ImageBuf, Insets, LifeCycle, Point, Rect,
+    Size, Target, Vec2, Widget, WidgetId, WidgetPod, WindowId,
 };
 
 /// A macro for implementing methods on multiple contexts.
@@ -60,6 +68,12 @@ pub(crate) struct GlobalPassCtx<'a> {
     pub(crate) text: PietText,
     /// The id of the widget that currently has focus.
     pub(crate) focus_widget: Option<WidgetId>,
+    /// During pointer events, the innermost widget whose layout rect contains the
+    /// pointer, ignoring expanded hit areas.
+    pub(crate) pointer_hit_target: Option<WidgetId>,
+    /// The device [`PaintCtx::with_cache`] renders with, kept across paint passes
+    /// by the window.
+    pub(crate) paint_device: Option<Device>,
 }
 
 /// A context provided to implementors of [`StoreInWidgetMut`].
@@ -85,7 +99,6 @@ pub struct EventCtx<'a, 'b> {
     pub(crate) notifications: &'a mut VecDeque<Notification>,
     pub(crate) is_handled: bool,
     pub(crate) is_root: bool,
-    pub(crate) request_pan_to_child: Option<Rect>,
 }
 
 /// A context provided to the [`lifecycle`] method on widgets.
@@ -132,6 +145,22 @@ pub struct PaintCtx<'a, 'b, 'c> {
     pub(crate) depth: u32,
 }
 
+/// A context passed to the [`accessibility`](Widget::accessibility) method of widgets.
+///
+/// The widget describes itself by setting a role and a name; the bounds, focus
+/// and visibility of its node are filled in from the widget's state.
+pub struct AccessCtx<'a> {
+    pub(crate) widget_state: &'a WidgetState,
+    pub(crate) focus_widget: Option<WidgetId>,
+    /// The part of the window not clipped out by scroll areas, in window coordinates.
+    pub(crate) visible_rect: Rect,
+    pub(crate) is_hidden: bool,
+    pub(crate) role: Option<Role>,
+    pub(crate) name: Option<String>,
+    /// The nodes of descendants, added as children of this widget's node.
+    pub(crate) children: Vec<AccessNode>,
+}
+
 impl_context_method!(
     WidgetCtx<'_, '_>,
     EventCtx<'_, '_>,
@@ -494,6 +523,38 @@ impl_context_method!(WidgetCtx<'_, '_>, EventCtx<'_, '_>, LifeCycleCtx<'_, '_>,
             .to(Target::Window(self.window_id()));
         self.submit_command(cmd);
     }
+
+    /// Report the position of the text caret to the platform input method.
+    ///
+    /// The rect is given in this widget's coordinate space. Widgets handling
+    /// [`Event::Ime`](crate::Event::Ime) should call this whenever their caret
+    /// (or their composition region) moves, so that the IME candidate window is
+    /// displayed next to it. Only the focused widget should call this.
+    ///
+    /// The rect is moved to each ancestor's coordinate space as it's propagated
+    /// up the tree, so it stays correct even if an ancestor moves this widget
+    /// later in the same pass.
+    pub fn set_ime_caret_rect(&mut self, rect: Rect) {
+        trace!("set_ime_caret_rect {}", rect);
+        self.widget_state.ime_caret_rect = Some(rect);
+    }
+
+    /// Ask ancestor scroll containers to scroll so that `rect` becomes visible.
+    ///
+    /// The rect is given in this widget's coordinate space. Each scroll container
+    /// (eg [`Portal`](crate::widget::Portal)) between this widget and the root
+    /// scrolls as little as possible to show the rect, then forwards the part of it
+    /// that is visible in its own viewport to its ancestors. With nested scroll
+    /// containers, the innermost one scrolls first, and outer ones only scroll to
+    /// bring the inner one's visible part into view.
+    ///
+    /// If several widgets make this request during the same pass, the last one wins.
+    ///
+    /// Widgets gaining focus automatically scroll themselves into view.
+    pub fn scroll_to_visible(&mut self, rect: Rect) {
+        trace!("scroll_to_visible {}", rect);
+        self.widget_state.scroll_to_visible = Some(rect);
+    }
 });
 
 // methods on everyone but paintctx
@@ -516,7 +577,8 @@ impl_context_method!(
         /// [`update`]: trait.Widget.html#tymethod.update
         pub fn submit_command(&mut self, cmd: impl Into<Command>) {
             trace!("submit_command");
-            self.global_state.submit_command(cmd.into())
+            let cmd = cmd.into().with_source(self.widget_state.id);
+            self.global_state.submit_command(cmd)
         }
 
         /// Submit an [`Action`].
@@ -581,6 +643,23 @@ impl_context_method!(
             self.global_state
                 .request_timer(deadline, self.widget_state.id)
         }
+
+        /// Propagate the state of a child up to this widget.
+        ///
+        /// When a child requests a layout or paint pass, gains focus, becomes active,
+        /// etc, its parent needs to know so that the framework recurses into it
+        /// during the next pass. [`WidgetPod`] methods like
+        /// [`on_event`](WidgetPod::on_event) and [`layout`](WidgetPod::layout)
+        /// already do this merge before returning, so container widgets normally don't
+        /// need to call this method.
+        ///
+        /// You only need to call it if you changed a child's state through other
+        /// means, and want the result reflected in this widget's [`WidgetState`]
+        /// (eg in [`WidgetState::needs_paint`]) before the end of the pass.
+        /// Merging is idempotent, so calling this more than once is harmless.
+        pub fn merge_child_state(&mut self, child: &mut WidgetPod<impl Widget>) {
+            self.widget_state.merge_up(&mut child.state);
+        }
     }
 );
 
@@ -624,9 +703,15 @@ impl EventCtx<'_, '_> {
         );
     }
 
-    /// Send a signal to parent widgets to scroll this widget into view.
-    pub fn request_pan_to_this(&mut self) {
-        self.request_pan_to_child = Some(self.widget_state.layout_rect());
+    /// Show a context menu at the given position, in this widget's coordinate space.
+    ///
+    /// This is usually called in response to [`Event::ContextMenu`](crate::Event::ContextMenu).
+    /// The menu is shown once the current event has been handled; if several
+    /// widgets call this during the same event, the last call wins.
+    pub fn show_context_menu(&mut self, menu: ContextMenu, pos: Point) {
+        trace!("show_context_menu {}", pos);
+        let window_pos = pos + self.widget_state.window_origin().to_vec2();
+        self.widget_state.context_menu = Some((menu, window_pos));
     }
 
     /// Set the "active" state of the widget.
@@ -741,10 +826,29 @@ impl LifeCycleCtx<'_, '_> {
         self.widget_state.children.add(&child_id);
     }
 
+    /// Subscribe this widget to commands for `selector` sent with [`Command::to_subscribers`].
+    ///
+    /// This should only be called in response to a [`LifeCycle::WidgetAdded`] event.
+    ///
+    /// Subscriptions last as long as the widget is in the tree; there is no need
+    /// to unsubscribe. When a widget is removed, its parent stops routing its
+    /// subscribed commands towards it once it processes the children change.
+    pub fn subscribe_to<T>(&mut self, selector: Selector<T>) {
+        let symbol = selector.symbol();
+        trace!("subscribe_to {}", symbol);
+        if !self.widget_state.subscribed_selectors.contains(&symbol) {
+            self.widget_state.subscribed_selectors.push(symbol);
+        }
+        self.widget_state.subscriptions.add(&symbol);
+    }
+
     /// Register this widget to be eligile to accept focus automatically.
     ///
     /// This should only be called in response to a [`LifeCycle::BuildFocusChain`] event.
     ///
+    /// Registered widgets are visited in the order they registered in when the
+    /// user presses Tab (or Shift+Tab, backwards) and no widget handles the key.
+    ///
     /// See [`EventCtx::is_focused`](Self::is_focused) for more information about focus.
     ///
     /// [`LifeCycle::BuildFocusChain`]: enum.Lifecycle.html#variant.BuildFocusChain
@@ -772,6 +876,15 @@ impl LifeCycleCtx<'_, '_> {
 }
 
 impl LayoutCtx<'_, '_> {
+    /// Request an animation frame.
+    ///
+    /// This lets a widget start animating in response to its new layout, eg
+    /// when its content starts overflowing.
+    pub fn request_anim_frame(&mut self) {
+        trace!("request_anim_frame");
+        self.widget_state.request_anim = true;
+    }
+
     /// Set explicit paint [`Insets`] for this widget.
     ///
     /// You are not required to set explicit paint bounds unless you need
@@ -803,6 +916,61 @@ impl LayoutCtx<'_, '_> {
         self.widget_state.baseline_offset = baseline
     }
 
+    /// Set the positions of this widget's first and last baselines.
+    ///
+    /// Widgets with several lines of text can use this so that their parent
+    /// can align siblings with either the top or bottom line. Unlike
+    /// [`set_baseline_offset`](Self::set_baseline_offset), the values are y
+    /// positions in the widget's own coordinate space, measured from the *top*.
+    ///
+    /// This doesn't change the baseline offset, which should still be set for
+    /// containers that only know about a single baseline.
+    pub fn set_baselines(&mut self, first: f64, last: f64) {
+        trace!("set_baselines {} {}", first, last);
+        self.widget_state.baselines = Some((first, last));
+    }
+
+    /// Check that the widget laid out its `children` correctly, panicking in debug
+    /// builds if it didn't.
+    ///
+    /// Every child laid out in this pass must have been placed with
+    /// [`place_child`](Self::place_child). With the `debug_layout` feature, the
+    /// size of every child must also fit the constraints it was laid out with.
+    ///
+    /// [`WidgetPod`] calls this after the widget's `layout`, with the widget's
+    /// short type name as `name`.
+    pub(crate) fn validate_children(&self, name: &str, children: &[WidgetRef<'_, dyn Widget>]) {
+        for child in children {
+            let state = child.state();
+            if state.is_expecting_place_child_call {
+                debug_panic!(
+                    "Error in '{}' #{}: missing call to place_child method for child widget '{}' #{}. During layout pass, if a widget calls WidgetPod::layout() on its child, it then needs to call LayoutCtx::place_child() on the same child.",
+                    name,
+                    self.widget_state.id.to_raw(),
+                    child.deref().short_type_name(),
+                    state.id.to_raw(),
+                );
+            }
+
+            if !cfg!(feature = "debug_layout") || state.is_stashed {
+                continue;
+            }
+            if let Some(bc) = state.layout_constraints {
+                if !bc.contains(state.size) {
+                    debug_panic!(
+                        "Error in '{}' #{}: child widget '{}' #{} has size {} outside of its constraints {:?}.",
+                        name,
+                        self.widget_state.id.to_raw(),
+                        child.deref().short_type_name(),
+                        state.id.to_raw(),
+                        state.size,
+                        bc,
+                    );
+                }
+            }
+        }
+    }
+
     /// Set the position of a child widget, in the paren't coordinate space. This
     /// will also implicitly change "hot" status and affect the parent's display rect.
     ///
@@ -823,12 +991,41 @@ impl LayoutCtx<'_, '_> {
             &mut child.state,
             self.global_state,
             layout_rect,
+            self.widget_state.id,
             self.mouse_pos,
             env,
         ) {
             self.widget_state.merge_up(&mut child.state);
         }
     }
+
+    /// Compute the size `child` would have under the given constraints, without
+    /// adding it to the widget tree.
+    ///
+    /// This is meant for widgets that need to know another widget's size before
+    /// showing it, eg to position a tooltip. The child is laid out under a
+    /// throwaway parent: nothing it requests during measurement (layout, paint,
+    /// focus changes, etc) reaches the current widget, and it is never painted.
+    ///
+    /// If `child` hasn't been added to a tree yet, it receives
+    /// [`LifeCycle::WidgetAdded`] first, and stays
+    /// initialized afterwards; if it is later inserted in the tree, it is routed like
+    /// any existing child and doesn't receive `WidgetAdded` again.
+    ///
+    /// Afterwards, `child` is marked as needing layout, so that it's laid out under
+    /// its real constraints once inserted. The focus requests, text input
+    /// registrations, IME caret rects and scroll requests it made while measured
+    /// are kept on it, and reach the tree then.
+    ///
+    /// `child` must not be one of this widget's children.
+    pub fn measure_offscreen(
+        &mut self,
+        child: &mut WidgetPod<impl Widget>,
+        bc: &BoxConstraints,
+        env: &Env,
+    ) -> Size {
+        self.global_state.measure_offscreen(child, bc, env)
+    }
 }
 
 impl PaintCtx<'_, '_, '_> {
@@ -902,6 +1099,160 @@ impl PaintCtx<'_, '_, '_> {
         }
     }
 
+    /// Paint with `f`, or draw what it painted the previous time if nothing changed.
+    ///
+    /// What `f` paints within the widget's [`paint_rect`](crate::WidgetPod::paint_rect)
+    /// is rendered to an offscreen image, which later calls draw instead of calling
+    /// `f` again, until either:
+    /// - `key` changes; use it for any state that `paint` reads but that doesn't
+    ///   request a paint when it changes.
+    /// - The widget is laid out, which happens when its size or the [`Env`] change.
+    /// - The widget or one of its descendants requests a paint.
+    /// - The scale it is painted at changes.
+    ///
+    /// Replaying the image is much cheaper than painting text or complex paths,
+    /// at the cost of the memory it takes. A widget should only have one call to
+    /// this method in its `paint`. Operations `f` schedules with
+    /// [`paint_with_z_index`](Self::paint_with_z_index) aren't supported, and are dropped.
+    ///
+    /// If the offscreen image can't be created, `f` is called directly.
+    pub fn with_cache(&mut self, key: impl Hash, f: impl FnOnce(&mut PaintCtx)) {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        let key = hasher.finish();
+        let rect =
+            self.widget_state.paint_rect() - self.widget_state.layout_rect().origin().to_vec2();
+        let scale = self.render_scale();
+
+        let is_cached = self
+            .widget_state
+            .paint_cache
+            .borrow()
+            .as_ref()
+            .map_or(false, |cache| {
+                cache.key == key && cache.rect == rect && cache.scale == scale
+            });
+        if !is_cached {
+            match self.paint_to_image(rect, scale, f) {
+                Ok(Some(image)) => {
+                    let image = image.to_image(self.render_ctx);
+                    self.widget_state.paint_cache.replace(Some(PaintCache {
+                        key,
+                        rect,
+                        scale,
+                        image,
+                    }));
+                }
+                Ok(None) => return,
+                Err(f) => {
+                    f(self);
+                    return;
+                }
+            }
+        }
+
+        if let Some(cache) = &*self.widget_state.paint_cache.borrow() {
+            self.render_ctx
+                .draw_image(&cache.image, rect, InterpolationMode::Bilinear);
+        }
+    }
+
+    // The number of device pixels per logical pixel at the current transform.
+    pub(crate) fn render_scale(&mut self) -> f64 {
+        self.render_ctx
+            .current_transform()
+            .determinant()
+            .abs()
+            .sqrt()
+    }
+
+    // Renders what `f` paints in `rect` to an image with `scale` pixels per
+    // logical pixel. Gives `f` back if it can't render, and returns `None` if
+    // `f` was called but the image couldn't be read back.
+    pub(crate) fn paint_to_image<F: FnOnce(&mut PaintCtx)>(
+        &mut self,
+        rect: Rect,
+        scale: f64,
+        f: F,
+    ) -> Result<Option<ImageBuf>, F> {
+        let width = (rect.width() * scale).ceil() as usize;
+        let height = (rect.height() * scale).ceil() as usize;
+        if width == 0 || height == 0 {
+            return Err(f);
+        }
+        // Creating a device can be expensive, so one is kept for the whole
+        // window. A nested call creates its own while this one is in use.
+        let mut device = match self.global_state.paint_device.take() {
+            Some(device) => device,
+            None => match Device::new() {
+                Ok(device) => device,
+                Err(_) => return Err(f),
+            },
+        };
+        let image = self.paint_to_device(&mut device, rect, scale, width, height, f);
+        self.global_state.paint_device = Some(device);
+        image
+    }
+
+    fn paint_to_device<F: FnOnce(&mut PaintCtx)>(
+        &mut self,
+        device: &mut Device,
+        rect: Rect,
+        scale: f64,
+        width: usize,
+        height: usize,
+        f: F,
+    ) -> Result<Option<ImageBuf>, F> {
+        let Ok(mut target) = device.bitmap_target(width, height, scale) else {
+            return Err(f);
+        };
+
+        {
+            let mut piet = target.render_context();
+            piet.transform(Affine::translate(-rect.origin().to_vec2()));
+            let mut cache_ctx = PaintCtx {
+                global_state: self.global_state,
+                widget_state: self.widget_state,
+                render_ctx: &mut piet,
+                z_ops: Vec::new(),
+                region: rect.into(),
+                depth: self.depth,
+            };
+            f(&mut cache_ctx);
+            if !cache_ctx.z_ops.is_empty() {
+                warn!("paint_with_z_index isn't supported in PaintCtx::with_cache");
+            }
+            if let Err(e) = piet.finish() {
+                error!("Failed to render paint cache: '{}'", e);
+            }
+        }
+
+        match target.to_image_buf(ImageFormat::RgbaPremul) {
+            Ok(image) => Ok(Some(image)),
+            Err(e) => {
+                error!("Failed to read paint cache: '{}'", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Draw the text of `label` on a rounded background, like a tag or a chip,
+    /// and return the background's rect.
+    ///
+    /// The background is padded by `radius` on the left and right and half of it
+    /// above and below the text; use [`Label::draw_pill`] for other padding.
+    pub fn draw_text_pill(
+        &mut self,
+        label: &Label,
+        origin: impl Into<Point>,
+        background: Color,
+        radius: f64,
+    ) -> Rect {
+        let radius = radius.max(0.0);
+        let padding = Insets::uniform_xy(radius, radius / 2.0);
+        label.draw_pill(self, origin, background, radius, padding)
+    }
+
     /// Allows to specify order for paint operations.
     ///
     /// Larger `z_index` indicate that an operation will be executed later.
@@ -919,6 +1270,68 @@ impl PaintCtx<'_, '_, '_> {
     }
 }
 
+impl<'a> AccessCtx<'a> {
+    pub(crate) fn new_root(
+        root_state: &'a WidgetState,
+        focus_widget: Option<WidgetId>,
+        window_rect: Rect,
+    ) -> Self {
+        AccessCtx {
+            widget_state: root_state,
+            focus_widget,
+            visible_rect: window_rect,
+            is_hidden: false,
+            role: None,
+            name: None,
+            children: Vec::new(),
+        }
+    }
+
+    /// get the `WidgetId` of the current widget.
+    pub fn widget_id(&self) -> WidgetId {
+        self.widget_state.id
+    }
+
+    /// The layout size.
+    pub fn size(&self) -> Size {
+        self.widget_state.size()
+    }
+
+    /// Returns `true` if this specific widget is focused.
+    pub fn is_focused(&self) -> bool {
+        self.focus_widget == Some(self.widget_id())
+    }
+
+    /// Returns `true` if this widget or any of its ancestors is disabled.
+    pub fn is_disabled(&self) -> bool {
+        self.widget_state.is_disabled()
+    }
+
+    /// Returns `true` if the widget's node will be reported as hidden.
+    ///
+    /// See [`AccessNode::is_hidden`].
+    pub fn is_hidden(&self) -> bool {
+        self.is_hidden
+    }
+
+    /// Set the role of the widget's node.
+    ///
+    /// Widgets that don't set a role don't get a node, unless they have the
+    /// keyboard focus; the nodes of their descendants are attached to their
+    /// closest ancestor that has one.
+    pub fn set_role(&mut self, role: Role) {
+        self.role = Some(role);
+    }
+
+    /// Set the name assistive technologies announce for the widget.
+    ///
+    /// If the widget's role [is named from its contents](Role::is_named_from_contents)
+    /// and no name is set, the names of its descendants' nodes are used.
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+}
+
 impl<'a> GlobalPassCtx<'a> {
     #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
@@ -942,10 +1355,58 @@ impl<'a> GlobalPassCtx<'a> {
             window,
             window_id,
             focus_widget,
+            pointer_hit_target: None,
+            paint_device: None,
             text: window.text(),
         }
     }
 
+    /// The current time, simulated in unit tests.
+    pub(crate) fn now(&self) -> Instant {
+        match &self.mock_timer_queue {
+            Some(timer_queue) => timer_queue.now(),
+            None => Instant::now(),
+        }
+    }
+
+    /// See [`LayoutCtx::measure_offscreen`].
+    pub(crate) fn measure_offscreen(
+        &mut self,
+        child: &mut WidgetPod<impl Widget>,
+        bc: &BoxConstraints,
+        env: &Env,
+    ) -> Size {
+        let mut scratch_state = WidgetState::new(WidgetId::OFFSCREEN_PARENT, None, "<offscreen>");
+
+        if !child.is_initialized() {
+            let mut ctx = LifeCycleCtx {
+                global_state: self,
+                widget_state: &mut scratch_state,
+            };
+            child.lifecycle(&mut ctx, &LifeCycle::WidgetAdded, env);
+        }
+
+        let mut ctx = LayoutCtx {
+            global_state: self,
+            widget_state: &mut scratch_state,
+            mouse_pos: None,
+        };
+        let size = child.layout(&mut ctx, bc, env);
+        ctx.place_child(child, Point::ORIGIN, env);
+
+        // Undo what the throwaway parent took from the child. The child is at
+        // its origin, so rects don't need to be moved back.
+        child.state.needs_layout = true;
+        child.state.request_focus = scratch_state.request_focus.take();
+        child
+            .state
+            .text_registrations
+            .append(&mut scratch_state.text_registrations);
+        child.state.ime_caret_rect = scratch_state.ime_caret_rect.take();
+        child.state.scroll_to_visible = scratch_state.scroll_to_visible.take();
+        size
+    }
+
     pub(crate) fn submit_command(&mut self, command: Command) {
         trace!("submit_command");
         self.command_queue
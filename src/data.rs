@@ -31,6 +31,34 @@ use crate::piet;
 /// Also, `druid_derive::Data` won't implement the Masonry version
 /// of this trait.
 ///
+/// For enums with fields, `same` should first compare the variants, then
+/// compare the fields of matching variants with `same`:
+///
+/// ```
+/// # use masonry::Data;
+/// #[derive(Clone)]
+/// enum Price {
+///     Unknown,
+///     Fixed(f64),
+///     Range { min: f64, max: f64 },
+/// }
+///
+/// impl Data for Price {
+///     fn same(&self, other: &Self) -> bool {
+///         match (self, other) {
+///             (Price::Unknown, Price::Unknown) => true,
+///             (Price::Fixed(a), Price::Fixed(b)) => a.same(b),
+///             (Price::Range { min: a0, max: a1 }, Price::Range { min: b0, max: b1 }) => {
+///                 a0.same(b0) && a1.same(b1)
+///             }
+///             _ => false,
+///         }
+///     }
+/// }
+/// ```
+///
+/// Fieldless enums can implement `PartialEq` and use `self == other`.
+///
 /// ## Collection types
 ///
 /// `Data` is not implemented for `std` collection types, because comparing them
@@ -563,6 +591,23 @@ mod test {
         assert!(!input.same(&[1u8, 1, 0, 1, 0]));
     }
 
+    #[test]
+    fn enum_data() {
+        let ok: Result<(i32, f64), &'static str> = Ok((1, 2.0));
+        // Same variant, same fields
+        assert!(ok.same(&Ok((1, 2.0))));
+        // Same variant, different fields
+        assert!(!ok.same(&Ok((1, 3.0))));
+        // Different variant
+        assert!(!ok.same(&Err("error")));
+
+        let bound = std::ops::Bound::Included(1.0);
+        assert!(bound.same(&std::ops::Bound::Included(1.0)));
+        assert!(!bound.same(&std::ops::Bound::Included(2.0)));
+        assert!(!bound.same(&std::ops::Bound::Excluded(1.0)));
+        assert!(!bound.same(&std::ops::Bound::Unbounded));
+    }
+
     #[test]
     #[cfg(feature = "im")]
     fn im_data() {
@@ -240,6 +240,18 @@ impl Env {
     /// [`WidgetExt::debug_widget`]: trait.WidgetExt.html#method.debug_widget
     pub const DEBUG_WIDGET: Key<bool> = Key::new("org.linebender.masonry.built-in.debug-widget");
 
+    /// How long, in milliseconds, widgets may take to handle an [`Event::AnimFrame`]
+    /// before the frame is reported as overrun.
+    ///
+    /// This is for diagnostics only: overruns are logged and counted (see
+    /// [`WindowRoot::anim_frame_overruns`]), but the animation isn't changed.
+    /// The default is one frame at 60Hz.
+    ///
+    /// [`Event::AnimFrame`]: crate::Event::AnimFrame
+    /// [`WindowRoot::anim_frame_overruns`]: crate::WindowRoot::anim_frame_overruns
+    pub const ANIM_FRAME_BUDGET: Key<f64> =
+        Key::new("org.linebender.masonry.built-in.anim-frame-budget");
+
     /// Gets a value from the environment, expecting it to be present.
     ///
     /// Note that the return value is a reference for "expensive" types such
@@ -357,6 +369,36 @@ impl Env {
         Ok(())
     }
 
+    /// Blend this environment with `other`, key by key.
+    ///
+    /// `t` is the blend factor: `0.0` returns this environment's values, `1.0`
+    /// returns `other`'s. Colors, numbers and geometric values are interpolated
+    /// (see [`Value::interpolate`]); other values, such as fonts and strings,
+    /// switch from this environment's value to `other`'s at the midpoint.
+    ///
+    /// Keys present in only one of the environments keep their value.
+    ///
+    /// This is used to animate theme changes, e.g. with
+    /// [`EnvTransition`](crate::widget::EnvTransition).
+    pub fn interpolate(&self, other: &Env, t: f64) -> Env {
+        if Arc::ptr_eq(&self.0, &other.0) {
+            return self.clone();
+        }
+        let mut map = self.0.map.clone();
+        for (key, to) in &other.0.map {
+            match map.entry(key.clone()) {
+                Entry::Occupied(mut e) => {
+                    let value = e.get().interpolate(to, t);
+                    e.insert(value);
+                }
+                Entry::Vacant(e) => {
+                    e.insert(to.clone());
+                }
+            }
+        }
+        Env(Arc::new(EnvImpl { map }))
+    }
+
     /// Given an id, returns one of 18 distinct colors
     #[doc(hidden)]
     pub fn get_debug_color(&self, id: u64) -> Color {
@@ -429,6 +471,55 @@ impl Value {
         }
     }
 
+    /// Blend this value with `other`.
+    ///
+    /// Points, sizes, rects, insets, colors, floats and corner radii are
+    /// linearly interpolated, with `t` going from `0.0` (this value) to `1.0`
+    /// (`other`). Any other value, or a value of a different type than `other`,
+    /// switches to `other` once `t` reaches `0.5`.
+    pub fn interpolate(&self, other: &Value, t: f64) -> Value {
+        let lerp = |a: f64, b: f64| a + (b - a) * t;
+        match (self, other) {
+            (Value::Point(a), Value::Point(b)) => Value::Point(a.lerp(*b, t)),
+            (Value::Size(a), Value::Size(b)) => {
+                Value::Size(Size::new(lerp(a.width, b.width), lerp(a.height, b.height)))
+            }
+            (Value::Rect(a), Value::Rect(b)) => Value::Rect(Rect::new(
+                lerp(a.x0, b.x0),
+                lerp(a.y0, b.y0),
+                lerp(a.x1, b.x1),
+                lerp(a.y1, b.y1),
+            )),
+            (Value::Insets(a), Value::Insets(b)) => Value::Insets(Insets::new(
+                lerp(a.x0, b.x0),
+                lerp(a.y0, b.y0),
+                lerp(a.x1, b.x1),
+                lerp(a.y1, b.y1),
+            )),
+            (Value::Color(a), Value::Color(b)) => {
+                let (r0, g0, b0, a0) = a.as_rgba();
+                let (r1, g1, b1, a1) = b.as_rgba();
+                Value::Color(Color::rgba(
+                    lerp(r0, r1),
+                    lerp(g0, g1),
+                    lerp(b0, b1),
+                    lerp(a0, a1),
+                ))
+            }
+            (Value::Float(a), Value::Float(b)) => Value::Float(lerp(*a, *b)),
+            (Value::RoundedRectRadii(a), Value::RoundedRectRadii(b)) => {
+                Value::RoundedRectRadii(RoundedRectRadii::new(
+                    lerp(a.top_left, b.top_left),
+                    lerp(a.top_right, b.top_right),
+                    lerp(a.bottom_right, b.bottom_right),
+                    lerp(a.bottom_left, b.bottom_left),
+                ))
+            }
+            _ if t < 0.5 => self.clone(),
+            _ => other.clone(),
+        }
+    }
+
     fn is_same_type(&self, other: &Value) -> bool {
         use Value::*;
         matches!(
@@ -520,7 +611,8 @@ impl Env {
         let env = Env::empty()
             .adding(Env::DEBUG_PAINT, false)
             .adding(Env::DEBUG_WIDGET_ID, false)
-            .adding(Env::DEBUG_WIDGET, false);
+            .adding(Env::DEBUG_WIDGET, false)
+            .adding(Env::ANIM_FRAME_BUDGET, 1000.0 / 60.0);
 
         crate::theme::add_to_env(env)
     }
@@ -533,7 +625,7 @@ impl<T> From<Key<T>> for ArcStr {
 }
 
 impl ValueTypeError {
-    fn new(expected: &'static str, found: Value) -> ValueTypeError {
+    pub(crate) fn new(expected: &'static str, found: Value) -> ValueTypeError {
         ValueTypeError { expected, found }
     }
 }
@@ -691,4 +783,31 @@ mod tests {
 
         assert_send_sync::<Key<()>>();
     }
+
+    #[test]
+    fn interpolate() {
+        const FLOAT_KEY: Key<f64> = Key::new("org.linebender.test.float-key");
+        const FONT_KEY: Key<FontDescriptor> = Key::new("org.linebender.test.font-key");
+        const ONLY_FROM: Key<f64> = Key::new("org.linebender.test.only-from");
+
+        let regular = FontDescriptor::default();
+        let mono = FontDescriptor::new(crate::piet::FontFamily::MONOSPACE);
+        let from = Env::empty()
+            .adding(FLOAT_KEY, 10.0)
+            .adding(FONT_KEY, regular.clone())
+            .adding(ONLY_FROM, 1.0);
+        let to = Env::empty()
+            .adding(FLOAT_KEY, 20.0)
+            .adding(FONT_KEY, mono.clone());
+
+        let early = from.interpolate(&to, 0.25);
+        assert_eq!(early.get(FLOAT_KEY), 12.5);
+        assert_eq!(early.get(FONT_KEY), regular);
+        assert_eq!(early.get(ONLY_FROM), 1.0);
+
+        let late = from.interpolate(&to, 0.5);
+        assert_eq!(late.get(FLOAT_KEY), 15.0);
+        assert_eq!(late.get(FONT_KEY), mono);
+        assert_eq!(late.get(ONLY_FROM), 1.0);
+    }
 }
@@ -4,9 +4,11 @@
 
 //! Events.
 
+use std::time::Duration;
+
 use druid_shell::{Clipboard, KeyEvent, TimerToken};
 
-use crate::kurbo::{Rect, Size};
+use crate::kurbo::{Point, Size};
 use crate::mouse::MouseEvent;
 // TODO - See issue #14
 use crate::promise::PromiseResult;
@@ -90,6 +92,20 @@ pub enum Event {
     /// Called when the mouse wheel or trackpad is scrolled.
     Wheel(MouseEvent),
 
+    /// Called when the user asks for a context menu, by right-clicking.
+    ///
+    /// This is sent right after the [`MouseDown`](Event::MouseDown) event of a
+    /// right click, whether or not that event was handled, to the widgets
+    /// whose layout rect contains `pos`. `pos` is in the receiving widget's
+    /// coordinate space.
+    ///
+    /// No menu is shown unless a widget calls
+    /// [`EventCtx::show_context_menu`](crate::EventCtx::show_context_menu).
+    ContextMenu {
+        /// The position of the click.
+        pos: Point,
+    },
+
     /// Called when a key is pressed.
     KeyDown(KeyEvent),
 
@@ -113,6 +129,35 @@ pub enum Event {
     /// as necessary.
     ImeStateChange,
 
+    /// Called when the platform input method composes or commits text.
+    ///
+    /// Like keyboard events, this is only sent to the focused widget and its
+    /// ancestors. See [`ImeEvent`] for the meaning of each variant.
+    Ime(ImeEvent),
+
+    /// Sent to a widget when the pointer has stayed over it for a while.
+    ///
+    /// This is sent once the widget has been hot (see [`StatusChange::HotChanged`])
+    /// for [`theme::HOVER_DWELL_TIME`](crate::theme::HOVER_DWELL_TIME), even if
+    /// the pointer moves within the widget in the meantime. If the pointer leaves
+    /// before then, nothing is sent, so quickly passing over a widget doesn't
+    /// trigger hover logic such as tooltips.
+    ///
+    /// This is only sent to the widget itself, not to its children; they receive
+    /// their own `HoverStart` events.
+    HoverStart,
+
+    /// Sent to a widget that received [`HoverStart`](Event::HoverStart) when
+    /// the pointer leaves it.
+    ///
+    /// This is sent before the event that made the widget lose its hot status.
+    /// If that happened during layout (e.g. because the widget moved), it is
+    /// sent along with the next event reaching the widget.
+    HoverEnd {
+        /// How long the pointer was over the widget, including the dwell time.
+        duration: Duration,
+    },
+
     /// Called when the trackpad is pinched.
     ///
     /// The value is a delta.
@@ -124,6 +169,11 @@ pub enum Event {
     /// will be 0. (This logic is presently per-window but might change to
     /// per-widget to make it more consistent). Otherwise it is in nanoseconds.
     ///
+    /// Animation frames are sent at most once per paint, which the platform
+    /// schedules at the display's refresh rate, however many widgets requested
+    /// one. All the widgets animating in a frame receive the same `interval`,
+    /// so animations that started together stay in step.
+    ///
     /// The `paint` method will be called shortly after this event is finished.
     /// As a result, you should try to avoid doing anything computationally
     /// intensive in response to an `AnimFrame` event: it might make the app miss
@@ -172,6 +222,32 @@ pub enum Event {
     Internal(InternalEvent),
 }
 
+/// Text input from a platform input method (IME).
+///
+/// Input methods let users enter text that doesn't map directly to keys, such
+/// as CJK characters or accented letters built from dead keys. Text first goes
+/// through a *composition* phase, where the in-progress "preedit" text is shown
+/// at the caret, before being committed.
+///
+/// Widgets receiving these events should report where their caret is with
+/// [`EventCtx::set_ime_caret_rect`](crate::EventCtx::set_ime_caret_rect), so
+/// that the platform can place its candidate window next to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImeEvent {
+    /// The composition text has changed.
+    ///
+    /// Preedit text should be displayed, but it must *not* be committed to the
+    /// widget's contents: each `Preedit` replaces the previous one entirely, and
+    /// an empty string means the composition was cleared or cancelled.
+    Preedit(String),
+
+    /// The input method has committed some text.
+    ///
+    /// This ends the current composition: the widget should discard its preedit
+    /// text and insert the committed text into its contents.
+    Commit(String),
+}
+
 /// Internal events used by Masonry inside [`WidgetPod`].
 ///
 /// These events are translated into regular [`Event`]s
@@ -228,9 +304,10 @@ pub enum LifeCycle {
     /// of events.
     WidgetAdded,
 
-    // TODO - Put in StatusChange
     /// Called when the Disabled state of the widgets is changed.
     ///
+    /// Widgets also receive [`StatusChange::DisabledChanged`] afterwards.
+    ///
     /// To check if a widget is disabled, see [`is_disabled`].
     ///
     /// To change a widget's disabled state, see [`set_disabled`].
@@ -250,10 +327,6 @@ pub enum LifeCycle {
     /// [`focus_prev`]: crate::EventCtx::focus_prev
     BuildFocusChain,
 
-    /// Called when a child widgets uses
-    /// [`EventCtx::request_pan_to_this`](crate::EventCtx::request_pan_to_this).
-    RequestPanToChild(Rect),
-
     /// Internal Masonry lifecycle event.
     ///
     /// This should always be passed down to descendant [`WidgetPod`]s.
@@ -313,6 +386,14 @@ pub enum StatusChange {
     ///
     /// [`EventCtx::is_focused`]: struct.EventCtx.html#method.is_focused
     FocusChanged(bool),
+
+    /// Called when the widget becomes disabled or enabled, either because it
+    /// was disabled itself or because one of its ancestors was.
+    ///
+    /// This is sent after the widget and its descendants have received
+    /// [`LifeCycle::DisabledChanged`]. Widgets that only change their appearance
+    /// when disabled should prefer handling this.
+    DisabledChanged(bool),
 }
 
 impl Event {
@@ -349,10 +430,14 @@ impl Event {
             | Event::MouseUp(_)
             | Event::MouseMove(_)
             | Event::Wheel(_)
+            | Event::ContextMenu { .. }
             | Event::KeyDown(_)
             | Event::KeyUp(_)
             | Event::Paste(_)
             | Event::ImeStateChange
+            | Event::Ime(_)
+            | Event::HoverStart
+            | Event::HoverEnd { .. }
             | Event::Zoom(_) => false,
         }
     }
@@ -382,10 +467,14 @@ impl Event {
             Event::MouseUp(_) => "MouseUp",
             Event::MouseMove(_) => "MouseMove",
             Event::Wheel(_) => "Wheel",
+            Event::ContextMenu { .. } => "ContextMenu",
             Event::KeyDown(_) => "KeyDown",
             Event::KeyUp(_) => "KeyUp",
             Event::Paste(_) => "Paste",
             Event::ImeStateChange => "ImeStateChange",
+            Event::Ime(_) => "Ime",
+            Event::HoverStart => "HoverStart",
+            Event::HoverEnd { .. } => "HoverEnd",
             Event::Zoom(_) => "Zoom",
         }
     }
@@ -407,7 +496,6 @@ impl LifeCycle {
             LifeCycle::WidgetAdded => true,
             LifeCycle::DisabledChanged(_) => true,
             LifeCycle::BuildFocusChain => false,
-            LifeCycle::RequestPanToChild(_) => false,
         }
     }
 
@@ -425,7 +513,6 @@ impl LifeCycle {
             LifeCycle::WidgetAdded => "WidgetAdded",
             LifeCycle::DisabledChanged(_) => "DisabledChanged",
             LifeCycle::BuildFocusChain => "BuildFocusChain",
-            LifeCycle::RequestPanToChild(_) => "RequestPanToChild",
         }
     }
 }
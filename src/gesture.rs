@@ -0,0 +1,197 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Recognizing gestures from raw mouse events.
+//!
+//! Widgets that react to clicks, like buttons, can feed their mouse events to a
+//! [`GestureState`] instead of tracking presses and releases themselves.
+
+use druid_shell::MouseButton;
+
+use crate::kurbo::Point;
+use crate::{Event, EventCtx};
+
+/// The default distance, in pixels, the pointer may move between press and
+/// release for the pair to still count as a tap.
+pub const DEFAULT_TAP_SLOP: f64 = 8.0;
+
+/// A gesture recognized by [`GestureState`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Gesture {
+    /// The primary button was pressed and released over the widget, without
+    /// the pointer moving further than the tap slop in between.
+    ///
+    /// `pos` is where the button was released, in the widget's coordinate space.
+    Tap {
+        /// Where the button was released.
+        pos: Point,
+    },
+}
+
+/// Turns the mouse events a widget receives into [`Gesture`]s.
+///
+/// Pass every event to [`on_mouse`](Self::on_mouse) from the widget's
+/// [`on_event`](crate::Widget::on_event), and call [`reset`](Self::reset) from
+/// [`reset_interaction_state`](crate::Widget::reset_interaction_state).
+///
+/// The widget is made active while a press that may become a tap is in
+/// progress, so that it receives the release even if the pointer leaves it. A
+/// tap is cancelled if the pointer moves further than the tap slop from where
+/// it was pressed, or if the button is released outside the widget.
+#[derive(Debug, Clone)]
+pub struct GestureState {
+    tap_slop: f64,
+    // Where the primary button was pressed, while a tap is possible.
+    press_pos: Option<Point>,
+}
+
+impl GestureState {
+    /// Create a recognizer using [`DEFAULT_TAP_SLOP`].
+    pub fn new() -> Self {
+        Self {
+            tap_slop: DEFAULT_TAP_SLOP,
+            press_pos: None,
+        }
+    }
+
+    /// Builder-style method for setting how far, in pixels, the pointer may
+    /// move between press and release for a tap.
+    pub fn with_tap_slop(mut self, tap_slop: f64) -> Self {
+        self.tap_slop = tap_slop.max(0.0);
+        self
+    }
+
+    /// Whether the primary button was pressed on the widget and may still
+    /// become a tap.
+    pub fn is_pressed(&self) -> bool {
+        self.press_pos.is_some()
+    }
+
+    /// Update the recognizer with an event, returning the gesture it completes,
+    /// if any.
+    ///
+    /// Events other than mouse presses, moves and releases are ignored. Presses
+    /// on a disabled widget are ignored too.
+    pub fn on_mouse(&mut self, ctx: &mut EventCtx, event: &Event) -> Option<Gesture> {
+        match event {
+            Event::MouseDown(mouse) if mouse.button == MouseButton::Left => {
+                if !ctx.is_disabled() {
+                    self.press_pos = Some(mouse.pos);
+                    ctx.set_active(true);
+                }
+                None
+            }
+            Event::MouseMove(mouse) => {
+                let press_pos = self.press_pos?;
+                if press_pos.distance(mouse.pos) > self.tap_slop {
+                    self.press_pos = None;
+                    ctx.set_active(false);
+                }
+                None
+            }
+            Event::MouseUp(mouse) if mouse.button == MouseButton::Left => {
+                // The press may have been forgotten by `reset`, but the widget
+                // is still active until the button is released.
+                if ctx.is_active() {
+                    ctx.set_active(false);
+                }
+                let press_pos = self.press_pos.take()?;
+                let is_inside = ctx.size().to_rect().contains(mouse.pos);
+                let is_tap = is_inside && press_pos.distance(mouse.pos) <= self.tap_slop;
+                is_tap.then_some(Gesture::Tap { pos: mouse.pos })
+            }
+            _ => None,
+        }
+    }
+
+    /// Forget about any press in progress.
+    ///
+    /// The widget stays active until the button is released, so that it still
+    /// gets the release.
+    pub fn reset(&mut self) {
+        self.press_pos = None;
+    }
+}
+
+impl Default for GestureState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::testing::{ModularWidget, TestHarness};
+    use crate::widget::{CrossAxisAlignment, Flex, SizedBox};
+    use crate::Size;
+
+    // A 40x40 widget at the origin that records the gestures it recognizes.
+    fn recorder() -> (impl crate::Widget, Rc<RefCell<Vec<Gesture>>>) {
+        let gestures = Rc::new(RefCell::new(Vec::new()));
+        let recorded = gestures.clone();
+        let widget = ModularWidget::new(GestureState::new())
+            .event_fn(move |state, ctx, event, _| {
+                if let Some(gesture) = state.on_mouse(ctx, event) {
+                    recorded.borrow_mut().push(gesture);
+                }
+            })
+            .layout_fn(|_, _, bc, _| bc.constrain(Size::new(40.0, 40.0)));
+        (widget, gestures)
+    }
+
+    fn harness() -> (TestHarness, Rc<RefCell<Vec<Gesture>>>) {
+        let (widget, gestures) = recorder();
+        let widget = Flex::column()
+            .cross_axis_alignment(CrossAxisAlignment::Start)
+            .with_child(widget)
+            .with_child(SizedBox::empty().width(40.0).height(40.0));
+        (TestHarness::create(widget), gestures)
+    }
+
+    #[test]
+    fn tap() {
+        let (mut harness, gestures) = harness();
+
+        harness.mouse_move((10.0, 10.0));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move((14.0, 13.0));
+        harness.mouse_button_release(MouseButton::Left);
+
+        let tap = Gesture::Tap {
+            pos: Point::new(14.0, 13.0),
+        };
+        assert_eq!(*gestures.borrow(), vec![tap]);
+    }
+
+    #[test]
+    fn drag_cancels_tap() {
+        let (mut harness, gestures) = harness();
+
+        harness.mouse_move((10.0, 10.0));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move((10.0, 30.0));
+        // Coming back doesn't restore the tap.
+        harness.mouse_move((10.0, 10.0));
+        harness.mouse_button_release(MouseButton::Left);
+
+        assert!(gestures.borrow().is_empty());
+    }
+
+    #[test]
+    fn release_outside_cancels_tap() {
+        let (mut harness, gestures) = harness();
+
+        // Within the tap slop, but below the widget.
+        harness.mouse_move((10.0, 36.0));
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move((10.0, 42.0));
+        harness.mouse_button_release(MouseButton::Left);
+
+        assert!(gestures.borrow().is_empty());
+    }
+}
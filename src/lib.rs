@@ -99,6 +99,7 @@ pub use druid_shell::{kurbo, piet};
 #[macro_use]
 mod util;
 
+pub mod accessibility;
 mod action;
 mod app_delegate;
 mod app_launcher;
@@ -111,6 +112,8 @@ mod data;
 pub mod env;
 mod event;
 pub mod ext_event;
+pub mod gesture;
+mod menu;
 mod mouse;
 mod platform;
 pub mod promise;
@@ -123,18 +126,20 @@ pub mod widget;
 pub mod debug_logger;
 pub mod debug_values;
 
+pub use accessibility::{AccessNode, AccessTree, Role};
 pub use action::Action;
 pub use app_delegate::{AppDelegate, DelegateCtx};
 pub use app_launcher::AppLauncher;
 pub use app_root::{AppRoot, WindowRoot};
 pub use box_constraints::BoxConstraints;
 pub use command::{Command, Notification, Selector, SingleUse, Target};
-pub use contexts::{EventCtx, LayoutCtx, LifeCycleCtx, PaintCtx, WidgetCtx};
+pub use contexts::{AccessCtx, EventCtx, LayoutCtx, LifeCycleCtx, PaintCtx, WidgetCtx};
 pub use data::Data;
 pub use druid_shell::Error as PlatformError;
 pub use env::{Env, Key, KeyOrValue, Value, ValueType, ValueTypeError};
-pub use event::{Event, InternalEvent, InternalLifeCycle, LifeCycle, StatusChange};
+pub use event::{Event, ImeEvent, InternalEvent, InternalLifeCycle, LifeCycle, StatusChange};
 pub use kurbo::{Affine, Insets, Point, Rect, Size, Vec2};
+pub use menu::{ContextMenu, MenuItem};
 pub use mouse::MouseEvent;
 pub use piet::{Color, ImageBuf, LinearGradient, RadialGradient, RenderContext, UnitPoint};
 pub use platform::{
@@ -142,4 +147,7 @@ pub use platform::{
 };
 pub use text::ArcStr;
 pub use util::{AsAny, Handled};
-pub use widget::{BackgroundBrush, Widget, WidgetId, WidgetPod, WidgetState};
+pub use widget::{
+    BackgroundBrush, Widget, WidgetExt, WidgetId, WidgetIdLease, WidgetIdPool, WidgetPod,
+    WidgetState,
+};
@@ -0,0 +1,85 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Context menus.
+
+use crate::{ArcStr, Command};
+
+/// A menu shown at the mouse position, usually in response to
+/// [`Event::ContextMenu`](crate::Event::ContextMenu).
+///
+/// Selecting an item submits its command. Show a menu with
+/// [`EventCtx::show_context_menu`](crate::EventCtx::show_context_menu).
+#[derive(Debug, Clone, Default)]
+pub struct ContextMenu {
+    items: Vec<MenuItem>,
+}
+
+/// An entry in a [`ContextMenu`].
+#[derive(Debug, Clone)]
+pub struct MenuItem {
+    title: ArcStr,
+    command: Command,
+    enabled: bool,
+}
+
+impl ContextMenu {
+    /// Create an empty menu.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder-style method to add an item at the end of the menu.
+    pub fn with_item(mut self, item: MenuItem) -> Self {
+        self.items.push(item);
+        self
+    }
+
+    /// The items of this menu, in display order.
+    pub fn items(&self) -> &[MenuItem] {
+        &self.items
+    }
+
+    /// Return `true` if the menu has no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+impl MenuItem {
+    /// Create a menu item which submits `command` when selected.
+    ///
+    /// The command is submitted as-is, so it should usually be targeted at the
+    /// widget that showed the menu.
+    pub fn new(title: impl Into<ArcStr>, command: impl Into<Command>) -> Self {
+        MenuItem {
+            title: title.into(),
+            command: command.into(),
+            enabled: true,
+        }
+    }
+
+    /// Builder-style method to enable or disable the item.
+    ///
+    /// Disabled items are greyed out and can't be selected.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// The text of the item.
+    pub fn title(&self) -> &ArcStr {
+        &self.title
+    }
+
+    /// The command submitted when the item is selected.
+    pub fn command(&self) -> &Command {
+        &self.command
+    }
+
+    /// Return `true` if the item can be selected.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
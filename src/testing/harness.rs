@@ -13,20 +13,20 @@ pub use druid_shell::{
     WindowState,
 };
 use image::io::Reader as ImageReader;
-use instant::Duration;
+use instant::{Duration, Instant};
 use shell::text::Selection;
 
 use super::screenshots::{get_image_diff, get_rgba_image};
 use super::snapshot_utils::get_cargo_workspace;
-use super::MockTimerQueue;
+use super::{EventScript, MockTimerQueue};
 use crate::action::{Action, ActionQueue};
 //use crate::ext_event::ExtEventHost;
 use crate::command::CommandQueue;
 use crate::contexts::GlobalPassCtx;
 use crate::debug_logger::DebugLogger;
-use crate::ext_event::ExtEventQueue;
-use crate::piet::{BitmapTarget, Device, ImageFormat, Piet};
-use crate::widget::{StoreInWidgetMut, WidgetMut, WidgetRef};
+use crate::ext_event::{ExtEventQueue, ExtMessage};
+use crate::piet::{BitmapTarget, Device, ImageBuf, ImageFormat, Piet};
+use crate::widget::{FocusChange, StoreInWidgetMut, WidgetMut, WidgetRef};
 use crate::*;
 
 /// Default screen size for tests.
@@ -64,7 +64,8 @@ pub const HARNESS_DEFAULT_SIZE: Size = Size::new(400., 400.);
 /// The passage of time is simulated with the [`move_timers_forward`](Self::move_timers_forward) methods. **(TODO -
 /// Doesn't move animations forward.)**
 ///
-/// **(TODO - ExtEvents aren't handled.)**
+/// ExtEvents (including the results of background tasks) are only handled when
+/// calling [`wait_for_ext_events`](Self::wait_for_ext_events).
 ///
 /// **(TODO - Painting invalidation might not be accurate.)**
 ///
@@ -87,8 +88,8 @@ pub const HARNESS_DEFAULT_SIZE: Size = Size::new(400., 400.);
 /// use masonry::assert_render_snapshot;
 /// use masonry::testing::widget_ids;
 /// use masonry::testing::TestHarness;
-/// use masonry::testing::TestWidgetExt;
 /// use masonry::theme::PRIMARY_LIGHT;
+/// use masonry::WidgetExt;
 ///
 /// #[test]
 /// fn simple_button() {
@@ -114,6 +115,45 @@ pub struct TestHarness {
     mock_app: MockAppRoot,
     mouse_state: MouseEvent,
     window_size: Size,
+    command_recording: Option<Vec<RecordedCommand>>,
+    // Set after layout, so that the next render paints the whole window.
+    repaint_window: bool,
+    // What happened since the last `simulate_frame`.
+    frame_relaid_out: Vec<WidgetId>,
+    // The commands dispatched by the frame `simulate_frame` is running, if any.
+    frame_commands: Option<Vec<RecordedCommand>>,
+}
+
+/// What happened in the window since the previous frame.
+///
+/// Returned by [`TestHarness::simulate_frame`].
+#[derive(Clone, Debug)]
+pub struct FrameSummary {
+    /// The widgets that were laid out again because they or their descendants
+    /// requested it, in the order their layout was invalidated, parents first.
+    pub relaid_out: Vec<WidgetId>,
+    /// The region that was repainted, in window coordinates.
+    ///
+    /// This is what widgets asked to repaint, or the whole window if there was
+    /// a layout pass, since the harness then repaints everything.
+    pub invalid: Region,
+    /// Whether a widget asked for another animation frame.
+    pub wants_animation_frame: bool,
+    /// The commands that were dispatched during the frame, in order.
+    pub commands: Vec<RecordedCommand>,
+}
+
+/// A command dispatched while the harness was recording.
+///
+/// See [`TestHarness::start_recording_commands`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedCommand {
+    /// The widget that submitted the command, if any.
+    pub source: Option<WidgetId>,
+    /// The name of the command's [`Selector`].
+    pub selector: &'static str,
+    /// Where the command was sent.
+    pub target: Target,
 }
 
 /// Assert a snapshot of a rendered frame of your app.
@@ -138,12 +178,32 @@ macro_rules! assert_render_snapshot {
     };
 }
 
+impl RecordedCommand {
+    /// Create the entry expected for a command with the given source, selector and target.
+    pub fn new<T>(source: Option<WidgetId>, selector: Selector<T>, target: Target) -> Self {
+        RecordedCommand {
+            source,
+            selector: selector.symbol(),
+            target,
+        }
+    }
+
+    fn from_command(cmd: &Command) -> Self {
+        RecordedCommand {
+            source: cmd.source(),
+            selector: cmd.selector_name(),
+            target: cmd.target(),
+        }
+    }
+}
+
 // TODO - merge
 /// All of the state except for the `Piet` (render context). We need to pass
 /// that in to get around some lifetime issues.
 struct MockAppRoot {
     env: Env,
     window: WindowRoot,
+    ext_event_queue: ExtEventQueue,
     command_queue: CommandQueue,
     action_queue: ActionQueue,
     debug_logger: DebugLogger,
@@ -162,10 +222,9 @@ impl TestHarness {
         //let ext_host = ExtEventHost::default();
         //let ext_handle = ext_host.make_sink();
 
-        // FIXME
         let event_queue = ExtEventQueue::new();
 
-        let window = WindowRoot::new(
+        let mut window = WindowRoot::new(
             WindowId::next(),
             Default::default(),
             event_queue.make_sink(),
@@ -175,6 +234,7 @@ impl TestHarness {
             WindowSizePolicy::User,
             Some(MockTimerQueue::new()),
         );
+        window.headless = true;
 
         let mouse_state = MouseEvent {
             pos: Point::ZERO,
@@ -191,12 +251,17 @@ impl TestHarness {
             mock_app: MockAppRoot {
                 env: Env::with_theme(),
                 window,
+                ext_event_queue: event_queue,
                 command_queue: VecDeque::new(),
                 action_queue: VecDeque::new(),
                 debug_logger: DebugLogger::new(false),
             },
             mouse_state,
             window_size,
+            command_recording: None,
+            repaint_window: false,
+            frame_relaid_out: Vec::new(),
+            frame_commands: None,
         };
 
         // verify that all widgets are marked as having children_changed
@@ -223,6 +288,12 @@ impl TestHarness {
     fn process_state_after_event(&mut self) {
         loop {
             let cmd = self.mock_app.command_queue.pop_front();
+            if let (Some(cmd), Some(frame_commands)) = (&cmd, &mut self.frame_commands) {
+                frame_commands.push(RecordedCommand::from_command(cmd));
+            }
+            if let (Some(cmd), Some(recording)) = (&cmd, &mut self.command_recording) {
+                recording.push(RecordedCommand::from_command(cmd));
+            }
             match cmd {
                 Some(cmd) => self
                     .mock_app
@@ -233,8 +304,20 @@ impl TestHarness {
 
         // TODO - this might be too coarse
         if self.root_widget().state().needs_layout {
+            fn needing_layout(widget: WidgetRef<'_, dyn Widget>, ids: &mut Vec<WidgetId>) {
+                // A widget needs layout if one of its descendants does.
+                if widget.state().needs_layout {
+                    ids.push(widget.id());
+                    for child in widget.children() {
+                        needing_layout(child, ids);
+                    }
+                }
+            }
+            let root = self.mock_app.window.root.as_dyn();
+            needing_layout(root, &mut self.frame_relaid_out);
+
             self.mock_app.layout();
-            *self.window_mut().invalid_mut() = Region::from(self.window_size.to_rect());
+            self.repaint_window = true;
         }
     }
 
@@ -256,13 +339,37 @@ impl TestHarness {
         let mut piet = RenderContextGuard(render_target.render_context());
 
         // FIXME - this doesn't make sense given we might render to a fresh surface
-        let invalid = std::mem::replace(self.window_mut().invalid_mut(), Region::EMPTY);
+        let mut invalid = std::mem::replace(self.window_mut().invalid_mut(), Region::EMPTY);
+        if std::mem::take(&mut self.repaint_window) {
+            invalid = Region::from(self.window_size.to_rect());
+        }
         self.mock_app.paint_region(&mut piet.0, &invalid);
     }
 
+    /// Mark the whole window as needing to be painted, as if it had been
+    /// uncovered, without invalidating any widget.
+    pub fn invalidate_window(&mut self) {
+        *self.window_mut().invalid_mut() = Region::from(self.window_size.to_rect());
+    }
+
     /// Create a Piet bitmap render context (an array of pixels), paint the
     /// window and return the bitmap.
     pub fn render(&mut self) -> Arc<[u8]> {
+        self.render_image(ImageFormat::RgbaPremul)
+            .raw_pixels_shared()
+    }
+
+    /// Paint the window into an offscreen bitmap and return it as an image.
+    ///
+    /// Pending layout and paint invalidations are processed first, like with
+    /// [`render`](Self::render). The image is the size of the window, in
+    /// [`ImageFormat::RgbaSeparate`], so the bytes of the pixel at `(x, y)` start
+    /// at `(y * width + x) * 4`.
+    pub fn render_to_pixels(&mut self) -> ImageBuf {
+        self.render_image(ImageFormat::RgbaSeparate)
+    }
+
+    fn render_image(&mut self, format: ImageFormat) -> ImageBuf {
         let mut device = Device::new().expect("harness failed to get device");
         let mut render_target = device
             .bitmap_target(
@@ -274,10 +381,7 @@ impl TestHarness {
 
         self.render_to(&mut render_target);
 
-        render_target
-            .to_image_buf(ImageFormat::RgbaPremul)
-            .unwrap()
-            .raw_pixels_shared()
+        render_target.to_image_buf(format).unwrap()
     }
 
     // --- Event helpers ---
@@ -386,6 +490,109 @@ impl TestHarness {
         self.process_event(event);
     }
 
+    /// Wait for external events, then process every pending one.
+    ///
+    /// Background tasks (eg [`EventCtx::compute_in_background`](crate::EventCtx::compute_in_background))
+    /// run on other threads and send their results through an [`ExtEventSink`](crate::ext_event::ExtEventSink).
+    /// This method blocks until at least one such event has been received, then
+    /// dispatches all received events as if they came from the platform.
+    ///
+    /// ## Panics
+    ///
+    /// Panics if no event was received before `timeout`.
+    pub fn wait_for_ext_events(&mut self, timeout: Duration) {
+        let start = Instant::now();
+        while !self.mock_app.ext_event_queue.has_pending_items() {
+            if start.elapsed() > timeout {
+                panic!("no external event received after {:?}", timeout);
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+
+        while let Some(message) = self.mock_app.ext_event_queue.recv() {
+            match message {
+                ExtMessage::Command(selector, payload, target) => {
+                    self.submit_command(Command::from_ext(selector, payload, target));
+                }
+                ExtMessage::Promise(promise_result, widget_id, _window_id) => {
+                    self.process_event(Event::Internal(InternalEvent::RoutePromiseResult(
+                        promise_result,
+                        widget_id,
+                    )));
+                }
+            }
+        }
+    }
+
+    /// Start an [`EventScript`] to send a sequence of events, with assertions
+    /// between them.
+    ///
+    /// The script records dispatched commands, discarding any previous recording.
+    pub fn script(&mut self) -> EventScript<'_> {
+        EventScript::new(self)
+    }
+
+    /// Move the keyboard focus to the given widget, or clear it if `id` is `None`.
+    ///
+    /// This behaves as if the widget had called
+    /// [`EventCtx::set_focus`](crate::EventCtx::set_focus).
+    pub fn focus_on(&mut self, id: Option<WidgetId>) {
+        let mut widget_state = WidgetState::new(self.mock_app.window.root.id(), None, "<root>");
+        widget_state.request_focus = Some(match id {
+            Some(id) => FocusChange::Focus(id),
+            None => FocusChange::Resign,
+        });
+
+        self.mock_app.window.post_event_processing(
+            &mut widget_state,
+            &mut self.mock_app.debug_logger,
+            &mut self.mock_app.command_queue,
+            &mut self.mock_app.action_queue,
+            &self.mock_app.env,
+            false,
+        );
+        self.process_state_after_event();
+    }
+
+    /// Run the animation step the window runs before painting.
+    ///
+    /// If any widget requested an animation frame, an [`Event::AnimFrame`] is
+    /// sent to all of them, with the wall-clock time since the previous
+    /// animation frame (zero for the first one).
+    pub fn animate_frame(&mut self) {
+        self.mock_app.prepare_paint();
+        self.process_state_after_event();
+    }
+
+    /// Run a whole frame of the window, and return what happened since the
+    /// previous one.
+    ///
+    /// This runs the animation step, any pending layout and paint, the way the
+    /// platform does before showing a frame. The summary covers every event,
+    /// edit and frame since the previous call to this method (or since the
+    /// harness was created): the widgets that were laid out again and the
+    /// region that was repainted. Its commands are only the ones dispatched
+    /// during the frame itself, eg by animations; use
+    /// [`start_recording_commands`](Self::start_recording_commands) to record
+    /// the others.
+    pub fn simulate_frame(&mut self) -> FrameSummary {
+        self.frame_commands = Some(Vec::new());
+        self.animate_frame();
+        let invalid = if self.repaint_window {
+            Region::from(self.window_size.to_rect())
+        } else {
+            self.window().invalid().clone()
+        };
+        let summary = FrameSummary {
+            relaid_out: std::mem::take(&mut self.frame_relaid_out),
+            invalid,
+            wants_animation_frame: self.window().wants_animation_frame(),
+            commands: self.frame_commands.take().unwrap_or_default(),
+        };
+        let _ = self.render();
+        summary
+    }
+
     /// Simulate the passage of time.
     ///
     /// If you create any timer in a widget, this method is the only way to trigger
@@ -447,6 +654,13 @@ impl TestHarness {
         self.mock_app.window.focused_widget()
     }
 
+    /// Build the accessibility tree of the window.
+    ///
+    /// See [`WindowRoot::accessibility_tree`].
+    pub fn accessibility_tree(&self) -> AccessTree {
+        self.mock_app.window.accessibility_tree(&self.mock_app.env)
+    }
+
     /// Call the provided visitor on every widget in the widget tree.
     pub fn inspect_widgets(&mut self, f: impl Fn(WidgetRef<'_, dyn Widget>) + 'static) {
         fn inspect(
@@ -462,6 +676,32 @@ impl TestHarness {
         inspect(self.mock_app.window.root.as_dyn(), &f);
     }
 
+    /// Compute the size of a widget that isn't in the tree, under the given constraints.
+    ///
+    /// See [`LayoutCtx::measure_offscreen`].
+    pub fn measure_offscreen(
+        &mut self,
+        widget: &mut WidgetPod<impl Widget>,
+        bc: BoxConstraints,
+    ) -> Size {
+        let window = &mut self.mock_app.window;
+        let mut timers = HashMap::new();
+        let mut global_state = GlobalPassCtx::new(
+            window.ext_event_sink.clone(),
+            &mut self.mock_app.debug_logger,
+            &mut self.mock_app.command_queue,
+            &mut self.mock_app.action_queue,
+            &mut timers,
+            window.mock_timer_queue.as_mut(),
+            &window.handle,
+            window.id,
+            window.focus,
+        );
+        let size = global_state.measure_offscreen(widget, &bc, &self.mock_app.env);
+        self.process_state_after_event();
+        size
+    }
+
     /// Get a [`WidgetMut`] to the root widget.
     ///
     /// Because of how WidgetMut works, it can only be passed to a user-provided callback.
@@ -519,6 +759,15 @@ impl TestHarness {
         res
     }
 
+    /// Modify the [`Env`] and lay out the whole widget tree again.
+    ///
+    /// This simulates a theme change.
+    pub fn edit_env(&mut self, f: impl FnOnce(&mut Env)) {
+        f(&mut self.mock_app.env);
+        self.mock_app.layout();
+        self.invalidate_window();
+    }
+
     /// Pop next action from the queue
     ///
     /// Note: Actions are still a WIP feature.
@@ -527,6 +776,32 @@ impl TestHarness {
         Some((action, widget_id))
     }
 
+    /// Start recording the commands dispatched by the widget tree.
+    ///
+    /// Commands are recorded in the order they are delivered. Any previous
+    /// recording is discarded.
+    pub fn start_recording_commands(&mut self) {
+        self.command_recording = Some(Vec::new());
+    }
+
+    /// Return the commands recorded since recording started or since the last
+    /// call to this method.
+    ///
+    /// Returns an empty `Vec` if [`start_recording_commands`](Self::start_recording_commands)
+    /// wasn't called.
+    pub fn take_recorded_commands(&mut self) -> Vec<RecordedCommand> {
+        self.command_recording
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    /// Return the context menu shown since the last call to this method, if any,
+    /// and its position in window coordinates.
+    pub fn pop_context_menu(&mut self) -> Option<(ContextMenu, Point)> {
+        self.mock_app.window.context_menu.take()
+    }
+
     // --- Screenshots ---
 
     /// Method used by [`assert_render_snapshot`]. Use the macro instead.
@@ -648,6 +923,15 @@ impl MockAppRoot {
         );
     }
 
+    fn prepare_paint(&mut self) {
+        self.window.prepare_paint(
+            &mut self.debug_logger,
+            &mut self.command_queue,
+            &mut self.action_queue,
+            &self.env,
+        );
+    }
+
     fn paint_region(&mut self, piet: &mut Piet, invalid: &Region) {
         self.window.do_paint(
             piet,
@@ -18,7 +18,7 @@ use std::rc::Rc;
 use smallvec::SmallVec;
 
 use crate::event::StatusChange;
-use crate::widget::{SizedBox, WidgetRef};
+use crate::widget::WidgetRef;
 use crate::*;
 
 pub type EventFn<S> = dyn FnMut(&mut S, &mut EventCtx, &Event, &Env);
@@ -99,10 +99,6 @@ pub trait TestWidgetExt: Widget + Sized + 'static {
             recording: recording.clone(),
         }
     }
-
-    fn with_id(self, id: WidgetId) -> SizedBox {
-        SizedBox::new_with_id(self, id)
-    }
 }
 
 impl<W: Widget + 'static> TestWidgetExt for W {}
@@ -314,4 +310,16 @@ impl<W: Widget> Widget for Recorder<W> {
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
         self.child.children()
     }
+
+    fn accessibility(&self, ctx: &mut AccessCtx, env: &Env) {
+        self.child.accessibility(ctx, env)
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.child.reset_interaction_state()
+    }
+
+    fn allow_pointer_events_outside_bounds(&self) -> Insets {
+        self.child.allow_pointer_events_outside_bounds()
+    }
 }
@@ -15,14 +15,17 @@ mod mock_timer_queue;
 #[cfg(not(tarpaulin_include))]
 mod screenshots;
 #[cfg(not(tarpaulin_include))]
+mod script;
+#[cfg(not(tarpaulin_include))]
 mod snapshot_utils;
 
 use druid_shell::{Modifiers, MouseButton, MouseButtons};
-pub use harness::{TestHarness, HARNESS_DEFAULT_SIZE};
+pub use harness::{FrameSummary, RecordedCommand, TestHarness, HARNESS_DEFAULT_SIZE};
 pub use helper_widgets::{
     ModularWidget, Record, Recorder, Recording, ReplaceChild, TestWidgetExt, REPLACE_CHILD,
 };
 pub(crate) use mock_timer_queue::MockTimerQueue;
+pub use script::EventScript;
 
 use crate::kurbo::{Point, Vec2};
 use crate::{MouseEvent, WidgetId};
@@ -0,0 +1,301 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A fluent API for multi-step interaction tests.
+
+use druid_shell::MouseButton;
+use instant::Duration;
+
+use super::{RecordedCommand, TestHarness};
+use crate::action::Action;
+use crate::{Event, Point, Selector, Size, WidgetId};
+
+/// A sequence of events and assertions run against a [`TestHarness`].
+///
+/// Each method sends events to the harness or checks its state, and returns the
+/// script so that calls can be chained. Assertions panic with the number and
+/// description of the step that preceded them, which makes failures in long
+/// sequences easier to locate.
+///
+/// The script records the commands dispatched while it runs (see
+/// [`TestHarness::start_recording_commands`]); [`expect_command`](Self::expect_command)
+/// checks them.
+///
+/// Created with [`TestHarness::script`].
+///
+/// ```ignore
+/// harness
+///     .script()
+///     .mouse_move(button_center)
+///     .expect_hot(button_id, true)
+///     .mouse_down(button_center)
+///     .mouse_up(button_center)
+///     .expect_action(Action::ButtonPressed, button_id);
+/// ```
+pub struct EventScript<'a> {
+    harness: &'a mut TestHarness,
+    step: usize,
+    step_name: String,
+    // Commands recorded but not yet matched by `expect_command`.
+    commands: Vec<RecordedCommand>,
+}
+
+impl<'a> EventScript<'a> {
+    pub(crate) fn new(harness: &'a mut TestHarness) -> Self {
+        harness.start_recording_commands();
+        EventScript {
+            harness,
+            step: 0,
+            step_name: "<start>".to_string(),
+            commands: Vec::new(),
+        }
+    }
+
+    /// Return the harness the script runs on.
+    pub fn harness(&mut self) -> &mut TestHarness {
+        self.harness
+    }
+
+    fn begin_step(&mut self, name: String) {
+        self.step += 1;
+        self.step_name = name;
+    }
+
+    #[track_caller]
+    fn fail(&self, message: std::fmt::Arguments) -> ! {
+        panic!(
+            "script failed after step {} ({}): {}",
+            self.step, self.step_name, message
+        );
+    }
+
+    // --- Events ---
+
+    /// Move the mouse to `pos`, in window coordinates.
+    pub fn mouse_move(self, pos: impl Into<Point>) -> Self {
+        let pos = pos.into();
+        self.run(format!("mouse_move({pos})"), |harness| {
+            harness.mouse_move(pos);
+        })
+    }
+
+    /// Move the mouse to `pos` and press the left button.
+    pub fn mouse_down(self, pos: impl Into<Point>) -> Self {
+        let pos = pos.into();
+        self.run(format!("mouse_down({pos})"), |harness| {
+            harness.mouse_move(pos);
+            harness.mouse_button_press(MouseButton::Left);
+        })
+    }
+
+    /// Move the mouse to `pos` and release the left button.
+    pub fn mouse_up(self, pos: impl Into<Point>) -> Self {
+        let pos = pos.into();
+        self.run(format!("mouse_up({pos})"), |harness| {
+            harness.mouse_move(pos);
+            harness.mouse_button_release(MouseButton::Left);
+        })
+    }
+
+    /// Press and release the left button at `pos`.
+    pub fn click(self, pos: impl Into<Point>) -> Self {
+        let pos = pos.into();
+        self.mouse_down(pos).mouse_up(pos)
+    }
+
+    /// Press and release the left button at the center of the given widget.
+    pub fn click_on(self, id: WidgetId) -> Self {
+        let center = self
+            .harness
+            .get_widget(id)
+            .state()
+            .window_layout_rect()
+            .center();
+        self.click(center)
+    }
+
+    /// Send an arbitrary event.
+    pub fn event(self, event: Event) -> Self {
+        self.run(format!("event({})", event.short_name()), |harness| {
+            harness.process_event(event);
+        })
+    }
+
+    /// Let `duration` pass.
+    ///
+    /// Timers that expire during `duration` fire, then, if any widget requested
+    /// an animation frame, a single [`Event::AnimFrame`] covering the whole
+    /// duration is sent.
+    pub fn advance(self, duration: Duration) -> Self {
+        self.run(format!("advance({duration:?})"), |harness| {
+            harness.move_timers_forward(duration);
+            if harness.window().wants_animation_frame() {
+                harness.process_event(Event::AnimFrame(duration.as_nanos() as u64));
+            }
+        })
+    }
+
+    fn run(mut self, name: String, f: impl FnOnce(&mut TestHarness)) -> Self {
+        self.begin_step(name);
+        f(self.harness);
+        let recorded = self.harness.take_recorded_commands();
+        self.commands.extend(recorded);
+        self
+    }
+
+    // --- Assertions ---
+
+    /// Assert that the given widget is (or isn't) hot.
+    #[track_caller]
+    pub fn expect_hot(self, id: WidgetId, hot: bool) -> Self {
+        let is_hot = self.harness.get_widget(id).state().is_hot;
+        if is_hot != hot {
+            self.fail(format_args!("expected {id:?} hot == {hot}, got {is_hot}"));
+        }
+        self
+    }
+
+    /// Assert that the given widget has the keyboard focus, or that no widget
+    /// does if `id` is `None`.
+    #[track_caller]
+    pub fn expect_focus(self, id: Option<WidgetId>) -> Self {
+        let focused = self.harness.focused_widget().map(|widget| widget.id());
+        if focused != id {
+            self.fail(format_args!("expected focus on {id:?}, got {focused:?}"));
+        }
+        self
+    }
+
+    /// Assert the layout size of the given widget.
+    #[track_caller]
+    pub fn expect_size(self, id: WidgetId, size: Size) -> Self {
+        let actual = self.harness.get_widget(id).state().layout_rect().size();
+        if actual != size {
+            self.fail(format_args!("expected {id:?} size {size}, got {actual}"));
+        }
+        self
+    }
+
+    /// Assert that a command with the given selector was dispatched since the
+    /// script started, and hasn't been matched by a previous call.
+    ///
+    /// Commands recorded before the matching one are discarded.
+    #[track_caller]
+    pub fn expect_command<T>(mut self, selector: Selector<T>) -> Self {
+        let symbol = selector.symbol();
+        match self.commands.iter().position(|cmd| cmd.selector == symbol) {
+            Some(index) => {
+                self.commands.drain(..=index);
+            }
+            None => self.fail(format_args!(
+                "expected command {symbol:?}, got {:?}",
+                self.commands
+            )),
+        }
+        self
+    }
+
+    /// Assert that no command was dispatched since the script started, apart
+    /// from those matched by [`expect_command`](Self::expect_command).
+    #[track_caller]
+    pub fn expect_no_command(self) -> Self {
+        if !self.commands.is_empty() {
+            self.fail(format_args!("expected no command, got {:?}", self.commands));
+        }
+        self
+    }
+
+    /// Assert that the next action in the queue is `action`, submitted by `id`.
+    #[track_caller]
+    pub fn expect_action(self, action: Action, id: WidgetId) -> Self {
+        let expected = (action, id);
+        let actual = self.harness.pop_action();
+        if actual.as_ref() != Some(&expected) {
+            self.fail(format_args!("expected action {expected:?}, got {actual:?}"));
+        }
+        self
+    }
+
+    /// Assert that the action queue is empty.
+    #[track_caller]
+    pub fn expect_no_action(self) -> Self {
+        if let Some(actual) = self.harness.pop_action() {
+            self.fail(format_args!("expected no action, got {actual:?}"));
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, ModularWidget};
+    use crate::widget::{Button, Flex, SizedBox};
+    use crate::LifeCycle;
+    use crate::WidgetExt as _;
+
+    const CLICKED: Selector = Selector::new("masonry-test.script-clicked");
+
+    #[test]
+    fn click_button() {
+        let [button_id] = widget_ids();
+        let widget = Flex::column().with_child_id(Button::new("Hello"), button_id);
+        let mut harness = TestHarness::create(widget);
+        let center = harness
+            .get_widget(button_id)
+            .state()
+            .window_layout_rect()
+            .center();
+
+        harness
+            .script()
+            .expect_hot(button_id, false)
+            .mouse_move(center)
+            .expect_hot(button_id, true)
+            .mouse_down(center)
+            .expect_no_action()
+            .mouse_up(center)
+            .expect_action(Action::ButtonPressed, button_id)
+            .mouse_move(Point::ZERO)
+            .expect_hot(button_id, false)
+            .expect_no_command();
+    }
+
+    #[test]
+    fn click_submits_command() {
+        let [child_id] = widget_ids();
+        let child = ModularWidget::new(())
+            .event_fn(|_, ctx, event, _| {
+                if let Event::MouseUp(_) = event {
+                    ctx.submit_command(CLICKED);
+                    ctx.request_focus();
+                }
+            })
+            .lifecycle_fn(|_, ctx, event, _| {
+                if let LifeCycle::BuildFocusChain = event {
+                    ctx.register_for_focus();
+                }
+            })
+            .layout_fn(|_, _, bc, _| bc.max())
+            .with_id(child_id);
+        let widget = Flex::column().with_child(SizedBox::new(child).width(40.0).height(20.0));
+
+        TestHarness::create(widget)
+            .script()
+            .expect_size(child_id, Size::new(40.0, 20.0))
+            .click_on(child_id)
+            .expect_focus(Some(child_id))
+            .expect_command(CLICKED)
+            .expect_no_command();
+    }
+
+    #[test]
+    #[should_panic(expected = "script failed after step 1 (mouse_move")]
+    fn failure_names_step() {
+        TestHarness::create(SizedBox::empty())
+            .script()
+            .mouse_move(Point::ZERO)
+            .expect_command(CLICKED);
+    }
+}
@@ -8,14 +8,15 @@ use std::ops::Range;
 
 use super::FontDescriptor;
 use crate::piet::{Color, FontFamily, FontStyle, FontWeight, TextAttribute as PietAttr};
-use crate::{Env, KeyOrValue};
+use crate::{Command, Env, KeyOrValue};
 
-// TODO - Should also hold an associated Command, maybe.
 /// A clickable range of text
 #[derive(Debug, Clone)]
 pub struct Link {
     /// The range of text for the link.
     pub range: Range<usize>,
+    /// The command submitted when the link is clicked.
+    pub command: Command,
 }
 
 /// A collection of spans of attributes of various kinds.
@@ -93,9 +94,12 @@ pub enum Attribute {
 }
 
 impl Link {
-    /// Create a new `Link`.
-    pub fn new(range: Range<usize>) -> Self {
-        Self { range }
+    /// Create a new `Link`, which submits `command` when clicked.
+    pub fn new(range: Range<usize>, command: impl Into<Command>) -> Self {
+        Self {
+            range,
+            command: command.into(),
+        }
     }
 
     /// Get this `Link`'s range.
@@ -110,6 +114,17 @@ impl AttributeSpans {
         Default::default()
     }
 
+    /// Returns `true` if no attribute was added.
+    pub fn is_empty(&self) -> bool {
+        self.family.spans.is_empty()
+            && self.size.spans.is_empty()
+            && self.weight.spans.is_empty()
+            && self.fg_color.spans.is_empty()
+            && self.style.spans.is_empty()
+            && self.underline.spans.is_empty()
+            && self.font_descriptor.spans.is_empty()
+    }
+
     /// Add a new [`Attribute`] over the provided [`Range`].
     pub fn add(&mut self, range: Range<usize>, attr: Attribute) {
         match attr {
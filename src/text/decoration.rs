@@ -0,0 +1,100 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Underline and strikethrough lines.
+
+use crate::kurbo::{BezPath, Point, Rect};
+use crate::piet::{Color, RenderContext};
+use crate::PaintCtx;
+
+/// The pattern used to draw a decoration line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecorationLineStyle {
+    /// A continuous line.
+    #[default]
+    Solid,
+    /// A dashed line.
+    Dashed,
+    /// A wavy line, as commonly used for spellcheck errors.
+    Wavy,
+}
+
+/// Controls how underlines and strikethroughs are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DecorationStyle {
+    /// The thickness of the line.
+    ///
+    /// If `None`, the thickness is derived from the font size.
+    pub thickness: Option<f64>,
+    /// A vertical offset from the line's default position; positive values move it down.
+    pub offset: Option<f64>,
+    /// The pattern used to draw the line.
+    pub line_style: DecorationLineStyle,
+}
+
+/// A decoration line under (or through) a single visual line of text.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct DecorationSpan {
+    pub x0: f64,
+    pub x1: f64,
+    /// The vertical center of the line.
+    pub y: f64,
+    pub thickness: f64,
+}
+
+impl DecorationStyle {
+    /// The thickness used for text of the given (scaled) font size.
+    pub(crate) fn resolved_thickness(&self, font_size: f64) -> f64 {
+        self.thickness
+            .unwrap_or_else(|| (font_size / 15.0).max(1.0))
+    }
+}
+
+impl DecorationSpan {
+    pub(crate) fn draw(&self, ctx: &mut PaintCtx, line_style: DecorationLineStyle, color: &Color) {
+        let DecorationSpan {
+            x0,
+            x1,
+            y,
+            thickness,
+        } = *self;
+        let half = thickness / 2.0;
+
+        match line_style {
+            DecorationLineStyle::Solid => {
+                ctx.fill(Rect::new(x0, y - half, x1, y + half), color);
+            }
+            DecorationLineStyle::Dashed => {
+                let dash = 3.0 * thickness;
+                let gap = 2.0 * thickness;
+                let mut x = x0;
+                while x < x1 {
+                    ctx.fill(Rect::new(x, y - half, (x + dash).min(x1), y + half), color);
+                    x += dash + gap;
+                }
+            }
+            DecorationLineStyle::Wavy => {
+                // Each half wave spans two thicknesses and peaks one thickness
+                // away from the center line.
+                let half_wave = 2.0 * thickness;
+                let mut path = BezPath::new();
+                path.move_to(Point::new(x0, y));
+                let mut x = x0;
+                let mut up = true;
+                while x < x1 {
+                    let end = (x + half_wave).min(x1);
+                    let peak = if up {
+                        y - 2.0 * thickness
+                    } else {
+                        y + 2.0 * thickness
+                    };
+                    path.quad_to(Point::new((x + end) / 2.0, peak), Point::new(end, y));
+                    x = end;
+                    up = !up;
+                }
+                ctx.stroke(path, color, thickness);
+            }
+        }
+    }
+}
@@ -0,0 +1,243 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Choosing between the text and emoji presentation of characters.
+//!
+//! Some characters, like ☎ or ❤, can be drawn either as a (usually monochrome)
+//! text glyph or as a (usually colorful) emoji. The variation selectors VS15 and
+//! VS16 ask for one or the other; without a selector, the font decides.
+
+/// The variation selector requesting text presentation (VS15).
+pub const TEXT_PRESENTATION_SELECTOR: char = '\u{FE0E}';
+/// The variation selector requesting emoji presentation (VS16).
+pub const EMOJI_PRESENTATION_SELECTOR: char = '\u{FE0F}';
+
+/// How characters that have both a text and an emoji presentation are drawn,
+/// when they aren't followed by a variation selector.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum EmojiPresentation {
+    /// The font picks the presentation.
+    #[default]
+    Auto,
+    /// Characters are drawn as text.
+    Text,
+    /// Characters are drawn as emoji.
+    Emoji,
+}
+
+impl EmojiPresentation {
+    /// The variation selector requesting this presentation, if any.
+    pub fn selector(self) -> Option<char> {
+        match self {
+            EmojiPresentation::Auto => None,
+            EmojiPresentation::Text => Some(TEXT_PRESENTATION_SELECTOR),
+            EmojiPresentation::Emoji => Some(EMOJI_PRESENTATION_SELECTOR),
+        }
+    }
+}
+
+// Characters with standardized emoji variation sequences, as inclusive ranges.
+//
+// Keycap bases (`#`, `*` and digits) are left out: they only turn into emoji as
+// part of a keycap sequence, and are plain text otherwise.
+const VARIATION_BASES: &[(u32, u32)] = &[
+    (0x00A9, 0x00A9),
+    (0x00AE, 0x00AE),
+    (0x203C, 0x203C),
+    (0x2049, 0x2049),
+    (0x2122, 0x2122),
+    (0x2139, 0x2139),
+    (0x2194, 0x2199),
+    (0x21A9, 0x21AA),
+    (0x231A, 0x231B),
+    (0x2328, 0x2328),
+    (0x23CF, 0x23CF),
+    (0x23ED, 0x23EF),
+    (0x23F1, 0x23F3),
+    (0x23F8, 0x23FA),
+    (0x24C2, 0x24C2),
+    (0x25AA, 0x25AB),
+    (0x25B6, 0x25B6),
+    (0x25C0, 0x25C0),
+    (0x25FB, 0x25FE),
+    (0x2600, 0x2604),
+    (0x260E, 0x260E),
+    (0x2611, 0x2611),
+    (0x2614, 0x2615),
+    (0x2618, 0x2618),
+    (0x261D, 0x261D),
+    (0x2620, 0x2620),
+    (0x2622, 0x2623),
+    (0x2626, 0x2626),
+    (0x262A, 0x262A),
+    (0x262E, 0x262F),
+    (0x2638, 0x263A),
+    (0x2640, 0x2640),
+    (0x2642, 0x2642),
+    (0x2648, 0x2653),
+    (0x265F, 0x2660),
+    (0x2663, 0x2663),
+    (0x2665, 0x2666),
+    (0x2668, 0x2668),
+    (0x267B, 0x267B),
+    (0x267E, 0x267F),
+    (0x2692, 0x2697),
+    (0x2699, 0x2699),
+    (0x269B, 0x269C),
+    (0x26A0, 0x26A1),
+    (0x26A7, 0x26A7),
+    (0x26AA, 0x26AB),
+    (0x26B0, 0x26B1),
+    (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5),
+    (0x26C8, 0x26C8),
+    (0x26CE, 0x26CF),
+    (0x26D1, 0x26D1),
+    (0x26D3, 0x26D4),
+    (0x26E9, 0x26EA),
+    (0x26F0, 0x26F5),
+    (0x26F7, 0x26FA),
+    (0x26FD, 0x26FD),
+    (0x2702, 0x2702),
+    (0x2705, 0x2705),
+    (0x2708, 0x270D),
+    (0x270F, 0x270F),
+    (0x2712, 0x2712),
+    (0x2714, 0x2714),
+    (0x2716, 0x2716),
+    (0x271D, 0x271D),
+    (0x2721, 0x2721),
+    (0x2733, 0x2734),
+    (0x2744, 0x2744),
+    (0x2747, 0x2747),
+    (0x2757, 0x2757),
+    (0x2763, 0x2764),
+    (0x27A1, 0x27A1),
+    (0x2934, 0x2935),
+    (0x2B05, 0x2B07),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55),
+    (0x3030, 0x3030),
+    (0x303D, 0x303D),
+    (0x3297, 0x3297),
+    (0x3299, 0x3299),
+    (0x1F004, 0x1F004),
+    (0x1F170, 0x1F171),
+    (0x1F17E, 0x1F17F),
+    (0x1F202, 0x1F202),
+    (0x1F21A, 0x1F21A),
+    (0x1F22F, 0x1F22F),
+    (0x1F237, 0x1F237),
+    (0x1F321, 0x1F321),
+    (0x1F324, 0x1F32C),
+    (0x1F336, 0x1F336),
+    (0x1F37D, 0x1F37D),
+    (0x1F396, 0x1F397),
+    (0x1F399, 0x1F39B),
+    (0x1F39E, 0x1F39F),
+    (0x1F3CB, 0x1F3CE),
+    (0x1F3D4, 0x1F3DF),
+    (0x1F3F3, 0x1F3F3),
+    (0x1F3F5, 0x1F3F5),
+    (0x1F3F7, 0x1F3F7),
+    (0x1F43F, 0x1F43F),
+    (0x1F441, 0x1F441),
+    (0x1F4FD, 0x1F4FD),
+    (0x1F549, 0x1F54A),
+    (0x1F56F, 0x1F570),
+    (0x1F573, 0x1F579),
+    (0x1F587, 0x1F587),
+    (0x1F58A, 0x1F58D),
+    (0x1F590, 0x1F590),
+    (0x1F5A5, 0x1F5A5),
+    (0x1F5A8, 0x1F5A8),
+    (0x1F5B1, 0x1F5B2),
+    (0x1F5BC, 0x1F5BC),
+    (0x1F5C2, 0x1F5C4),
+    (0x1F5D1, 0x1F5D3),
+    (0x1F5DC, 0x1F5DE),
+    (0x1F5E1, 0x1F5E1),
+    (0x1F5E3, 0x1F5E3),
+    (0x1F5E8, 0x1F5E8),
+    (0x1F5EF, 0x1F5EF),
+    (0x1F5F3, 0x1F5F3),
+    (0x1F5FA, 0x1F5FA),
+    (0x1F6CB, 0x1F6CB),
+    (0x1F6CD, 0x1F6CF),
+    (0x1F6E0, 0x1F6E5),
+    (0x1F6E9, 0x1F6E9),
+    (0x1F6F0, 0x1F6F0),
+    (0x1F6F3, 0x1F6F3),
+];
+
+/// Returns `true` if `c` can be drawn both as text and as an emoji.
+pub fn has_emoji_variations(c: char) -> bool {
+    let c = c as u32;
+    VARIATION_BASES
+        .binary_search_by(|&(start, end)| {
+            if end < c {
+                std::cmp::Ordering::Less
+            } else if start > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Returns the byte offsets in `text` where `presentation` inserts its
+/// variation selector.
+///
+/// Those are the ends of characters with both presentations that aren't
+/// already followed by a variation selector, a skin tone modifier or a
+/// zero-width joiner; explicit selectors and emoji sequences are kept as is.
+pub(crate) fn selector_offsets(text: &str, presentation: EmojiPresentation) -> Vec<usize> {
+    if presentation == EmojiPresentation::Auto {
+        return Vec::new();
+    }
+    let mut chars = text.char_indices().peekable();
+    let mut offsets = Vec::new();
+    while let Some((i, c)) = chars.next() {
+        if !has_emoji_variations(c) {
+            continue;
+        }
+        let is_in_sequence = chars.peek().map_or(false, |&(_, next)| {
+            let is_skin_tone = ('\u{1F3FB}'..='\u{1F3FF}').contains(&next);
+            next == TEXT_PRESENTATION_SELECTOR
+                || next == EMOJI_PRESENTATION_SELECTOR
+                || next == '\u{200D}'
+                || is_skin_tone
+        });
+        if !is_in_sequence {
+            offsets.push(i + c.len_utf8());
+        }
+    }
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variation_bases() {
+        assert!(has_emoji_variations('\u{260E}'));
+        assert!(has_emoji_variations('\u{2764}'));
+        assert!(has_emoji_variations('\u{1F6F3}'));
+        assert!(!has_emoji_variations('a'));
+        assert!(!has_emoji_variations('1'));
+        assert!(!has_emoji_variations('\u{1F600}'));
+    }
+
+    #[test]
+    fn explicit_selectors_win() {
+        let text = "a\u{260E} \u{2764}\u{FE0E} \u{261D}\u{1F3FD}";
+        assert_eq!(selector_offsets(text, EmojiPresentation::Auto), vec![]);
+        // Only the telephone has no selector or modifier after it.
+        assert_eq!(selector_offsets(text, EmojiPresentation::Emoji), vec![4]);
+        assert_eq!(selector_offsets(text, EmojiPresentation::Text), vec![4]);
+    }
+}
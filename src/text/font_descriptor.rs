@@ -57,6 +57,12 @@ impl FontDescriptor {
         self.style = style;
         self
     }
+
+    /// Return a descriptor with the font size multiplied by `factor`.
+    pub fn scaled(mut self, factor: f64) -> Self {
+        self.size *= factor;
+        self
+    }
 }
 
 impl Default for FontDescriptor {
@@ -0,0 +1,132 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Snapping byte offsets to grapheme cluster boundaries.
+//!
+//! A caret or selection edge inside a grapheme cluster (e.g. between the code
+//! points of an emoji ZWJ sequence, or between a letter and its combining
+//! accent) would split a character the user perceives as one. These helpers
+//! move such offsets to the edge of the cluster.
+
+use std::ops::Range;
+
+use unicode_segmentation::GraphemeCursor;
+
+/// Returns `true` if `offset` is a grapheme cluster boundary in `text`.
+///
+/// The start and end of the text are boundaries; offsets past the end aren't.
+pub fn is_grapheme_boundary(text: &str, offset: usize) -> bool {
+    if offset > text.len() || !text.is_char_boundary(offset) {
+        return false;
+    }
+    GraphemeCursor::new(offset, text.len(), true)
+        .is_boundary(text, 0)
+        .unwrap_or(false)
+}
+
+/// Returns the last grapheme cluster boundary at or before `offset`.
+///
+/// Offsets past the end of the text are clamped to its length.
+pub fn grapheme_boundary_before(text: &str, offset: usize) -> usize {
+    let offset = floor_char_boundary(text, offset);
+    if is_grapheme_boundary(text, offset) {
+        return offset;
+    }
+    GraphemeCursor::new(offset, text.len(), true)
+        .prev_boundary(text, 0)
+        .ok()
+        .flatten()
+        .unwrap_or(0)
+}
+
+/// Returns the first grapheme cluster boundary at or after `offset`.
+///
+/// Offsets past the end of the text are clamped to its length.
+pub fn grapheme_boundary_after(text: &str, offset: usize) -> usize {
+    let offset = ceil_char_boundary(text, offset);
+    if is_grapheme_boundary(text, offset) {
+        return offset;
+    }
+    GraphemeCursor::new(offset, text.len(), true)
+        .next_boundary(text, 0)
+        .ok()
+        .flatten()
+        .unwrap_or(text.len())
+}
+
+/// Returns the grapheme cluster boundary nearest to `offset`, in bytes.
+///
+/// Offsets past the end of the text are clamped to its length. An offset
+/// exactly halfway through a cluster snaps to its start.
+pub fn nearest_grapheme_boundary(text: &str, offset: usize) -> usize {
+    let before = grapheme_boundary_before(text, offset);
+    let after = grapheme_boundary_after(text, offset);
+    let offset = offset.min(text.len());
+    if after - offset < offset - before {
+        after
+    } else {
+        before
+    }
+}
+
+/// Returns the smallest range made of whole grapheme clusters that contains `range`.
+pub fn grapheme_range(text: &str, range: Range<usize>) -> Range<usize> {
+    let start = grapheme_boundary_before(text, range.start);
+    let end = grapheme_boundary_after(text, range.end.max(range.start));
+    start..end
+}
+
+fn floor_char_boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+fn ceil_char_boundary(text: &str, offset: usize) -> usize {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset += 1;
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A family emoji: three code points joined by zero-width joiners.
+    const FAMILY: &str = "a\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}b";
+
+    #[test]
+    fn snap_inside_cluster() {
+        let emoji_end = FAMILY.len() - 1;
+        assert!(is_grapheme_boundary(FAMILY, 1));
+        assert!(!is_grapheme_boundary(FAMILY, 5));
+        assert!(!is_grapheme_boundary(FAMILY, 2));
+
+        for offset in 2..emoji_end {
+            assert_eq!(grapheme_boundary_before(FAMILY, offset), 1);
+            assert_eq!(grapheme_boundary_after(FAMILY, offset), emoji_end);
+            let nearest = nearest_grapheme_boundary(FAMILY, offset);
+            assert!(
+                nearest == 1 || nearest == emoji_end,
+                "{offset} -> {nearest}"
+            );
+        }
+        assert_eq!(nearest_grapheme_boundary(FAMILY, 3), 1);
+        assert_eq!(nearest_grapheme_boundary(FAMILY, emoji_end - 1), emoji_end);
+        assert_eq!(nearest_grapheme_boundary(FAMILY, 100), FAMILY.len());
+    }
+
+    #[test]
+    fn combining_mark() {
+        let text = "e\u{301}x";
+        assert_eq!(nearest_grapheme_boundary(text, 1), 0);
+        assert_eq!(grapheme_boundary_after(text, 1), 3);
+        assert_eq!(grapheme_range(text, 1..2), 0..3);
+        assert_eq!(grapheme_range(text, 3..4), 3..4);
+    }
+}
@@ -4,16 +4,22 @@
 
 //! A type for laying out, drawing, and interacting with text.
 
-use std::ops::Range;
+use std::cell::RefCell;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Range};
 use std::rc::Rc;
 
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::emoji::{selector_offsets, EmojiPresentation};
+use super::grapheme::{grapheme_boundary_before, grapheme_range, nearest_grapheme_boundary};
 use super::{FontDescriptor, Link, TextStorage};
-use crate::kurbo::{Line, Point, Rect, Size};
+use crate::kurbo::{Line, Point, Rect, Size, Vec2};
 use crate::piet::{
-    Color, PietText, PietTextLayout, Text as _, TextAlignment, TextAttribute, TextLayout as _,
-    TextLayoutBuilder as _,
+    Color, ImageBuf, InterpolationMode, PietImage, PietText, PietTextLayout, Text as _,
+    TextAlignment, TextAttribute, TextLayout as _, TextLayoutBuilder as _, TextStorage as _,
 };
-use crate::{Env, KeyOrValue, PaintCtx, RenderContext};
+use crate::{ArcStr, Env, KeyOrValue, PaintCtx, RenderContext};
 
 /// A component for displaying text on screen.
 ///
@@ -43,12 +49,42 @@ pub struct TextLayout<T> {
     // This provides an easy way to change only the font size, while still
     // using a `FontDescriptor` in the `Env`.
     text_size_override: Option<KeyOrValue<f64>>,
+    // Multiplies the font size, on top of the `Env`'s font scale.
+    text_size_scale: f64,
     text_color: KeyOrValue<Color>,
     layout: Option<PietTextLayout>,
     wrap_width: f64,
     alignment: TextAlignment,
+    break_strategy: BreakStrategy,
+    word_spacing: f64,
+    letter_spacing: f64,
+    emoji_presentation: EmojiPresentation,
+    // The text with the characters inserted for letter spacing, emoji
+    // presentation and inline objects, if any. This is what the platform lays
+    // out; it's kept until the text changes.
+    inserted: Option<InsertedText>,
+    // The text laid out without a wrap width, to find optimal line breaks in.
+    // It's kept while only the wrap width changes.
+    unwrapped_layout: Option<PietTextLayout>,
+    // A multiple of the font size.
+    line_height: Option<f64>,
+    // Where each line of the built layout is moved to apply `line_height`.
+    // Empty without a line height.
+    line_boxes: Rc<[LineBox]>,
     links: Rc<[(Rect, usize)]>,
+    inline_objects: Rc<[InlineObject]>,
+    // Relative to the layout's origin, one per inline object; `None` if the
+    // object's range isn't valid for the current text.
+    inline_object_rects: Rc<[Option<Rect>]>,
+    // The inline objects' images, uploaded to the render context when first drawn.
+    inline_images: Rc<RefCell<Vec<Option<PietImage>>>>,
     text_is_rtl: bool,
+    // The resolved (and scaled) font and the text color that the current layout
+    // was built with.
+    built_with: Option<(FontDescriptor, Color)>,
+    // Incremented every time the layout is rebuilt.
+    generation: u64,
+    shape_cache_stats: ShapeCacheStats,
 }
 
 /// Metrics describing the layout text.
@@ -58,11 +94,83 @@ pub struct LayoutMetrics {
     pub size: Size,
     /// The distance from the nominal top of the layout to the first baseline.
     pub first_baseline: f64,
+    /// The distance from the nominal top of the layout to the last baseline.
+    pub last_baseline: f64,
     /// The width of the layout, inclusive of trailing whitespace.
     pub trailing_whitespace_width: f64,
     //TODO: add inking_rect
 }
 
+/// How a [`TextLayout`] chooses where to wrap lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BreakStrategy {
+    /// Each line is filled with as many words as fit before wrapping.
+    ///
+    /// This is fast, but can leave a very short line after a long one.
+    #[default]
+    Greedy,
+    /// Line breaks are chosen for the whole paragraph at once, to make lines
+    /// as even in length as possible, in the style of Knuth and Plass.
+    ///
+    /// This is more expensive: the text is shaped twice, once unwrapped to
+    /// measure words, and choosing breaks takes time quadratic in the number of
+    /// words in a paragraph. It's best kept for short paragraphs, or text that
+    /// is rarely re-laid out.
+    ///
+    /// Only spaces are considered as break opportunities; words too long for a
+    /// line are still broken greedily.
+    ///
+    /// Justified text always uses greedy breaking, since the chosen breaks are
+    /// laid out as hard line breaks, which are never justified.
+    Optimal,
+}
+
+/// An object, such as a custom emoji, drawn inline with a [`TextLayout`]'s text.
+///
+/// The object takes the place of a run of the text: the run's glyphs are hidden
+/// and take up exactly `size.width`, and `image` is drawn over them, scaled to
+/// `size`, with its bottom edge on the line's baseline. A run wider than the
+/// object is drawn smaller; a narrower one is padded with spaces, so that its
+/// font size, and the line's height, don't change. Surrounding text flows and
+/// wraps around the object as it would around a glyph of that width.
+///
+/// The run is typically a single U+FFFC OBJECT REPLACEMENT CHARACTER, but it can
+/// be any text, e.g. a `:shortcode:` that should still be there when the text is
+/// copied. The object doesn't make its line taller; a tall object overflows
+/// upwards.
+#[derive(Debug, Clone)]
+pub struct InlineObject {
+    /// The byte range of the text replaced by the object.
+    pub range: Range<usize>,
+    /// The size of the box reserved for the object.
+    pub size: Size,
+    /// The image drawn in the box.
+    pub image: ImageBuf,
+}
+
+/// Counters describing how often a [`TextLayout`] was rebuilt.
+///
+/// Every call to [`TextLayout::rebuild_if_needed`] either reuses the current
+/// layout (a hit) or rebuilds it (a miss). A text that is reshaped on every
+/// layout pass even though it didn't change usually means something keeps
+/// invalidating it, e.g. a setter called with a new but equal value.
+///
+/// Stats can be added together, to aggregate them across several layouts or widgets.
+///
+/// The counters are only kept in debug builds; in release builds they stay at zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShapeCacheStats {
+    /// The number of layouts built by the platform's text shaper.
+    ///
+    /// This can be higher than `misses`, since some features (e.g. word spacing)
+    /// need to shape additional text for measurements.
+    pub shapes: u32,
+    /// The number of rebuilds skipped because the layout was up to date.
+    pub hits: u32,
+    /// The number of rebuilds that had to build a new layout.
+    pub misses: u32,
+}
+
 impl<T> TextLayout<T> {
     /// Create a new `TextLayout` object.
     ///
@@ -75,11 +183,26 @@ impl<T> TextLayout<T> {
             font: crate::theme::UI_FONT.into(),
             text_color: crate::theme::TEXT_COLOR.into(),
             text_size_override: None,
+            text_size_scale: 1.0,
             layout: None,
             wrap_width: f64::INFINITY,
             alignment: Default::default(),
+            break_strategy: BreakStrategy::Greedy,
+            word_spacing: 0.0,
+            letter_spacing: 0.0,
+            emoji_presentation: EmojiPresentation::Auto,
+            inserted: None,
+            unwrapped_layout: None,
+            line_height: None,
+            line_boxes: Rc::new([]),
             links: Rc::new([]),
+            inline_objects: Rc::new([]),
+            inline_object_rects: Rc::new([]),
+            inline_images: Rc::default(),
             text_is_rtl: false,
+            built_with: None,
+            generation: 0,
+            shape_cache_stats: ShapeCacheStats::default(),
         }
     }
 
@@ -88,7 +211,7 @@ impl<T> TextLayout<T> {
         let color = color.into();
         if color != self.text_color {
             self.text_color = color;
-            self.layout = None;
+            self.invalidate();
         }
     }
 
@@ -104,7 +227,7 @@ impl<T> TextLayout<T> {
         let font = font.into();
         if font != self.font {
             self.font = font;
-            self.layout = None;
+            self.invalidate();
             self.text_size_override = None;
         }
     }
@@ -119,7 +242,34 @@ impl<T> TextLayout<T> {
         let size = size.into();
         if Some(&size) != self.text_size_override.as_ref() {
             self.text_size_override = Some(size);
-            self.layout = None;
+            self.invalidate();
+        }
+    }
+
+    /// Set a factor the font size is multiplied by, e.g. to shrink text to fit.
+    ///
+    /// This is applied on top of the size set with [`set_text_size`](Self::set_text_size)
+    /// or by the font, and on top of the [`Env`]'s font scale. It defaults to `1.0`.
+    pub fn set_text_size_scale(&mut self, scale: f64) {
+        if scale != self.text_size_scale {
+            self.text_size_scale = scale;
+            self.invalidate();
+        }
+    }
+
+    /// The factor set with [`set_text_size_scale`](Self::set_text_size_scale).
+    pub fn text_size_scale(&self) -> f64 {
+        self.text_size_scale
+    }
+
+    /// The font size this layout is built with, before the [`Env`]'s font scale is applied.
+    ///
+    /// This is the size set with [`set_text_size`](Self::set_text_size) if any,
+    /// otherwise the size of the font.
+    pub fn text_size(&self, env: &Env) -> f64 {
+        match &self.text_size_override {
+            Some(size) => size.resolve(env),
+            None => self.font.resolve(env).size,
         }
     }
 
@@ -132,20 +282,136 @@ impl<T> TextLayout<T> {
         // 1e-4 is an arbitrary small-enough value that we don't care to rewrap
         if (width - self.wrap_width).abs() > 1e-4 {
             self.wrap_width = width;
+            // The text is laid out again, but its optimal line breaks are found
+            // from the same unwrapped layout.
             self.layout = None;
         }
     }
 
     /// Set the [`TextAlignment`] for this layout.
     ///
+    /// With [`TextAlignment::Justified`], every wrapped line of a paragraph
+    /// except the last has its inter-word spacing stretched to fill the wrap
+    /// width; the last line is aligned to the start.
+    ///
     /// [`TextAlignment`]: enum.TextAlignment.html
     pub fn set_text_alignment(&mut self, alignment: TextAlignment) {
         if self.alignment != alignment {
             self.alignment = alignment;
-            self.layout = None;
+            self.invalidate();
+        }
+    }
+
+    /// Set the [`BreakStrategy`] used to wrap lines.
+    ///
+    /// This has no effect without a wrap width.
+    pub fn set_break_strategy(&mut self, strategy: BreakStrategy) {
+        if self.break_strategy != strategy {
+            self.break_strategy = strategy;
+            self.invalidate();
+        }
+    }
+
+    /// Set extra spacing added to each space between words, in pixels.
+    ///
+    /// This is added to the normal width of space characters (including
+    /// non-breaking spaces), so it affects the layout's width and where lines
+    /// wrap; other glyphs are spaced normally. Negative values tighten spaces,
+    /// down to zero width.
+    ///
+    /// This is implemented by changing the font size of space characters, so
+    /// large values may increase the height of the lines they're on.
+    pub fn set_word_spacing(&mut self, spacing: f64) {
+        if self.word_spacing != spacing {
+            self.word_spacing = spacing;
+            self.invalidate();
         }
     }
 
+    /// Set extra spacing added after each grapheme cluster, in pixels.
+    ///
+    /// This is the tracking of the text: it's added between the characters of
+    /// words, and between the end of a word and the following space, so it
+    /// affects the layout's width and where lines wrap. Nothing is added at the
+    /// end of a line. Glyphs can only be moved apart, so negative values are
+    /// treated as zero.
+    ///
+    /// This is implemented by laying out the text with no-break spaces after
+    /// each cluster, which also keeps ligatures and kerning from joining the
+    /// clusters. Offsets taken and returned by `TextLayout` methods are into
+    /// the text itself, but the underlying [`layout`](Self::layout) has the spaces.
+    pub fn set_letter_spacing(&mut self, spacing: f64) {
+        let spacing = spacing.max(0.0);
+        if self.letter_spacing != spacing {
+            self.letter_spacing = spacing;
+            self.invalidate();
+        }
+    }
+
+    /// Set whether characters like ☎ or ❤, which have both a text and an emoji
+    /// presentation, are drawn as text or as emoji.
+    ///
+    /// This only applies to characters without a variation selector; explicit
+    /// selectors in the text always win. Like letter spacing, the selectors are
+    /// only added to the text given to the underlying [`layout`](Self::layout).
+    pub fn set_emoji_presentation(&mut self, presentation: EmojiPresentation) {
+        if self.emoji_presentation != presentation {
+            self.emoji_presentation = presentation;
+            self.invalidate();
+        }
+    }
+
+    /// Set the height of each line, as a multiple of the font size, or `None` to
+    /// use the font's own line spacing.
+    ///
+    /// Each line is centered vertically in a box of that height, so this also
+    /// changes the height of single-line text. The box of a line with a larger
+    /// [`FontSize`](TextAttribute::FontSize) attribute is sized for the largest
+    /// one; glyphs may still overlap neighbouring lines if the line height is small.
+    ///
+    /// This is done by moving the lines of the built layout. The methods of
+    /// `TextLayout` take it into account, but the underlying [`layout`](Self::layout)
+    /// doesn't; use [`line_offset`](Self::line_offset) to convert its positions.
+    pub fn set_line_height(&mut self, line_height: Option<f64>) {
+        if self.line_height != line_height {
+            self.line_height = line_height;
+            self.invalidate();
+        }
+    }
+
+    /// How far the line at index `line` is moved down by the line height set
+    /// with [`set_line_height`](Self::set_line_height).
+    ///
+    /// This is zero without a line height, or before the layout is built.
+    pub fn line_offset(&self, line: usize) -> f64 {
+        self.line_boxes.get(line).map_or(0.0, |line| line.offset)
+    }
+
+    /// Set the objects drawn inline with the text, replacing any previous ones.
+    ///
+    /// Objects whose range is empty, out of bounds, or not on character
+    /// boundaries of the current text are ignored. Ranges are not adjusted when
+    /// the text changes.
+    pub fn set_inline_objects(&mut self, objects: Vec<InlineObject>) {
+        self.inline_objects = objects.into();
+        self.inline_images = Rc::default();
+        self.inserted = None;
+        self.invalidate();
+    }
+
+    /// The objects set with [`set_inline_objects`](Self::set_inline_objects).
+    pub fn inline_objects(&self) -> &[InlineObject] {
+        &self.inline_objects
+    }
+
+    /// Returns the box of the inline object at `index`, relative to the layout's origin.
+    ///
+    /// Returns `None` if there is no such object, if its range isn't valid for
+    /// the text, or if the layout hasn't been built yet.
+    pub fn inline_object_rect(&self, index: usize) -> Option<Rect> {
+        self.inline_object_rects.get(index).copied().flatten()
+    }
+
     /// Returns `true` if this layout's text appears to be right-to-left.
     ///
     /// See [`piet::util::first_strong_rtl`] for more information.
@@ -181,7 +447,8 @@ impl<T: TextStorage> TextLayout<T> {
         if self.text.is_none() || !self.text.as_ref().unwrap().same(&text) {
             self.text_is_rtl = crate::piet::util::first_strong_rtl(text.as_str());
             self.text = Some(text);
-            self.layout = None;
+            self.inserted = None;
+            self.invalidate();
         }
     }
 
@@ -199,8 +466,33 @@ impl<T: TextStorage> TextLayout<T> {
         }
     }
 
+    /// Returns how often this layout was rebuilt since it was created, or since
+    /// the last call to [`reset_shape_cache_stats`](Self::reset_shape_cache_stats).
+    ///
+    /// This is only counted in debug builds.
+    pub fn shape_cache_stats(&self) -> ShapeCacheStats {
+        self.shape_cache_stats
+    }
+
+    /// A number that changes every time the layout is rebuilt, so that things
+    /// derived from it can be cached until it changes.
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Reset the counters returned by [`shape_cache_stats`](Self::shape_cache_stats).
+    ///
+    /// This can be called at the start of every frame to get per-frame counts.
+    pub fn reset_shape_cache_stats(&mut self) {
+        self.shape_cache_stats = ShapeCacheStats::default();
+    }
+
     /// Returns the inner Piet [`TextLayout`] type.
     ///
+    /// With letter spacing or an emoji presentation, this is a layout of the
+    /// text with extra characters, so its offsets don't match the text's; use
+    /// [`line_range`](Self::line_range) for the text of a line.
+    ///
     /// [`TextLayout`]: ./piet/trait.TextLayout.html
     pub fn layout(&self) -> Option<&PietTextLayout> {
         self.layout.as_ref()
@@ -214,7 +506,13 @@ impl<T: TextStorage> TextLayout<T> {
     pub fn size(&self) -> Size {
         self.layout
             .as_ref()
-            .map(|layout| layout.size())
+            .map(|layout| {
+                let mut size = layout.size();
+                if let Some(last) = self.line_boxes.last() {
+                    size.height = last.bottom;
+                }
+                size
+            })
             .unwrap_or_default()
     }
 
@@ -232,11 +530,16 @@ impl<T: TextStorage> TextLayout<T> {
         );
 
         if let Some(layout) = self.layout.as_ref() {
-            let first_baseline = layout.line_metric(0).unwrap().baseline;
-            let size = layout.size();
+            let first_baseline = layout.line_metric(0).unwrap().baseline + self.line_offset(0);
+            let last_index = layout.line_count() - 1;
+            let last_line = layout.line_metric(last_index).unwrap();
+            let size = self.size();
             LayoutMetrics {
                 size,
                 first_baseline,
+                last_baseline: last_line.y_offset
+                    + last_line.baseline
+                    + self.line_offset(last_index),
                 trailing_whitespace_width: layout.trailing_whitespace_width(),
             }
         } else {
@@ -249,37 +552,109 @@ impl<T: TextStorage> TextLayout<T> {
     pub fn text_position_for_point(&self, point: Point) -> usize {
         self.layout
             .as_ref()
-            .map(|layout| layout.hit_test_point(point).idx)
+            .map(|layout| {
+                let idx = layout.hit_test_point(self.unmoved_point(point)).idx;
+                let idx = self.text_offset(idx);
+                // The platform may return an offset between the code points of
+                // a cluster, e.g. an emoji sequence.
+                nearest_grapheme_boundary(self.text_str(), idx)
+            })
             .unwrap_or_default()
     }
 
-    /// Given the utf-8 position of a character boundary in the underlying text,
-    /// return the `Point` (relative to this object's origin) representing the
-    /// boundary of the containing grapheme.
-    ///
-    /// # Panics
+    /// Given a utf-8 position in the underlying text, return the `Point`
+    /// (relative to this object's origin) representing the boundary of the
+    /// containing grapheme.
     ///
-    /// Panics if `text_pos` is not a character boundary.
+    /// Positions inside a grapheme cluster are moved to its start.
     pub fn point_for_text_position(&self, text_pos: usize) -> Point {
         self.layout
             .as_ref()
-            .map(|layout| layout.hit_test_text_position(text_pos).point)
+            .map(|layout| {
+                let text_pos = grapheme_boundary_before(self.text_str(), text_pos);
+                let hit = layout.hit_test_text_position(self.layout_offset(text_pos));
+                hit.point + Vec2::new(0.0, self.line_offset(hit.line))
+            })
             .unwrap_or_default()
     }
 
     /// Given a utf-8 range in the underlying text, return a `Vec` of `Rect`s
     /// representing the nominal bounding boxes of the text in that range.
     ///
-    /// # Panics
-    ///
-    /// Panics if the range start or end is not a character boundary.
+    /// The range is extended to whole grapheme clusters.
     pub fn rects_for_range(&self, range: Range<usize>) -> Vec<Rect> {
         self.layout
             .as_ref()
-            .map(|layout| layout.rects_for_range(range))
+            .map(|layout| {
+                let range = grapheme_range(self.text_str(), range);
+                let rects = layout.rects_for_range(self.layout_range(range));
+                move_rects_to_lines(&self.line_boxes, rects)
+            })
             .unwrap_or_default()
     }
 
+    // Converts a point in the moved lines to the coordinates of the underlying layout.
+    fn unmoved_point(&self, point: Point) -> Point {
+        if self.line_boxes.is_empty() {
+            return point;
+        }
+        let line = self
+            .line_boxes
+            .partition_point(|line| line.bottom <= point.y)
+            .min(self.line_boxes.len() - 1);
+        point - Vec2::new(0.0, self.line_boxes[line].offset)
+    }
+
+    // Drops the layout, to be rebuilt from scratch on the next rebuild.
+    fn invalidate(&mut self) {
+        self.layout = None;
+        self.unwrapped_layout = None;
+    }
+
+    fn text_str(&self) -> &str {
+        self.text.as_ref().map(|text| text.as_str()).unwrap_or("")
+    }
+
+    // The text the layout is built from.
+    fn layout_str(&self) -> &str {
+        match &self.inserted {
+            Some(inserted) => &inserted.text,
+            None => self.text_str(),
+        }
+    }
+
+    // Converts an offset into the text to one into the layout's text.
+    fn layout_offset(&self, offset: usize) -> usize {
+        self.inserted
+            .as_ref()
+            .map_or(offset, |inserted| inserted.layout_offset(offset))
+    }
+
+    fn layout_range(&self, range: Range<usize>) -> Range<usize> {
+        self.layout_offset(range.start)..self.layout_offset(range.end)
+    }
+
+    // Converts an offset into the layout's text to one into the text.
+    fn text_offset(&self, layout_offset: usize) -> usize {
+        self.inserted.as_ref().map_or(layout_offset, |inserted| {
+            inserted.text_offset(layout_offset)
+        })
+    }
+
+    /// The number of visual lines in the layout, or zero before it's built.
+    pub fn line_count(&self) -> usize {
+        self.layout.as_ref().map_or(0, |layout| layout.line_count())
+    }
+
+    /// The byte range of the text on the visual line at index `line`, without
+    /// trailing whitespace, or `None` if there is no such line.
+    pub fn line_range(&self, line: usize) -> Option<Range<usize>> {
+        let metric = self.layout.as_ref()?.line_metric(line)?;
+        let start = self.text_offset(metric.start_offset);
+        let end = self.text_offset(metric.end_offset - metric.trailing_whitespace);
+        Some(start..end.max(start))
+    }
+
     /// Return a line suitable for underlining a range of text.
     ///
     /// This is really only intended to be used to indicate the composition
@@ -290,26 +665,32 @@ impl<T: TextStorage> TextLayout<T> {
         self.layout
             .as_ref()
             .map(|layout| {
+                let range = self.layout_range(range);
                 let p1 = layout.hit_test_text_position(range.start);
                 let p2 = layout.hit_test_text_position(range.end);
                 let line_metric = layout.line_metric(p1.line).unwrap();
                 // heuristic; 1/5 of height is a rough guess at the descender pos?
-                let y_pos = line_metric.baseline + (line_metric.height / 5.0);
+                let y_pos =
+                    line_metric.baseline + (line_metric.height / 5.0) + self.line_offset(p1.line);
                 Line::new((p1.point.x, y_pos), (p2.point.x, y_pos))
             })
             .unwrap_or_else(|| Line::new(Point::ZERO, Point::ZERO))
     }
 
-    /// Given the utf-8 position of a character boundary in the underlying text,
-    /// return a `Line` suitable for drawing a vertical cursor at that boundary.
+    /// Given a utf-8 position in the underlying text, return a `Line` suitable
+    /// for drawing a vertical cursor at that position.
+    ///
+    /// Positions inside a grapheme cluster are moved to its start.
     pub fn cursor_line_for_text_position(&self, text_pos: usize) -> Line {
         self.layout
             .as_ref()
             .map(|layout| {
-                let pos = layout.hit_test_text_position(text_pos);
+                let text_pos = grapheme_boundary_before(self.text_str(), text_pos);
+                let pos = layout.hit_test_text_position(self.layout_offset(text_pos));
                 let line_metrics = layout.line_metric(pos.line).unwrap();
-                let p1 = (pos.point.x, line_metrics.y_offset);
-                let p2 = (pos.point.x, (line_metrics.y_offset + line_metrics.height));
+                let top = line_metrics.y_offset + self.line_offset(pos.line);
+                let p1 = (pos.point.x, top);
+                let p2 = (pos.point.x, top + line_metrics.height);
                 Line::new(p1, p2)
             })
             .unwrap_or_else(|| Line::new(Point::ZERO, Point::ZERO))
@@ -323,13 +704,43 @@ impl<T: TextStorage> TextLayout<T> {
     ///
     /// [`Link`]: super::attribute::Link
     pub fn link_for_pos(&self, pos: Point) -> Option<&Link> {
-        let (_, i) = self
-            .links
-            .iter()
-            .rfind(|(hit_box, _)| hit_box.contains(pos))?;
+        self.link_for_pos_with_tolerance(pos, 0.0)
+    }
 
+    /// Returns the [`Link`] near the provided point, accepting points up to
+    /// `tolerance` pixels away from the link's glyph boxes.
+    ///
+    /// This makes thin links easier to hit with imprecise pointers. When the padded
+    /// regions of several links overlap, the link whose glyph box is nearest to `pos`
+    /// wins; a point inside a glyph box always resolves to that link. With a
+    /// `tolerance` of zero this is identical to [`link_for_pos`](Self::link_for_pos).
+    ///
+    /// [`Link`]: super::attribute::Link
+    pub fn link_for_pos_with_tolerance(&self, pos: Point, tolerance: f64) -> Option<&Link> {
+        let mut nearest: Option<(f64, usize)> = None;
+        // Iterate backwards so that, on ties, later links win like in `link_for_pos`.
+        for (hit_box, i) in self.links.iter().rev() {
+            let distance = if hit_box.contains(pos) {
+                0.0
+            } else if tolerance > 0.0 {
+                let dx = (hit_box.x0 - pos.x).max(pos.x - hit_box.x1).max(0.0);
+                let dy = (hit_box.y0 - pos.y).max(pos.y - hit_box.y1).max(0.0);
+                let distance = dx.hypot(dy);
+                if distance > tolerance {
+                    continue;
+                }
+                distance
+            } else {
+                continue;
+            };
+            if nearest.map_or(true, |(best, _)| distance < best) {
+                nearest = Some((distance, *i));
+            }
+        }
+
+        let (_, i) = nearest?;
         let text = self.text()?;
-        text.links().get(*i)
+        text.links().get(i)
     }
 
     /// Rebuild the inner layout as needed.
@@ -342,47 +753,289 @@ impl<T: TextStorage> TextLayout<T> {
     /// A simple way to ensure this is correct is to always call this method
     /// as part of your widget's [`layout`] method.
     ///
+    /// Font sizes are multiplied by [`theme::FONT_SCALE`] and [`theme::TEXT_SCALE`].
+    /// The font and text color are resolved on every call, and the layout is
+    /// rebuilt if they changed since the last call, e.g. because the [`Env`]
+    /// is being animated.
+    ///
     /// [`layout`]: trait.Widget.html#method.layout
+    /// [`theme::FONT_SCALE`]: crate::theme::FONT_SCALE
+    /// [`theme::TEXT_SCALE`]: crate::theme::TEXT_SCALE
     pub fn rebuild_if_needed(&mut self, factory: &mut PietText, env: &Env) {
-        if let Some(text) = &self.text {
-            if self.layout.is_none() {
-                let font = self.font.resolve(env);
-                let color = self.text_color.resolve(env);
-                let size_override = self.text_size_override.as_ref().map(|key| key.resolve(env));
+        let font_scale = env.get(crate::theme::FONT_SCALE) * env.get(crate::theme::TEXT_SCALE);
+
+        if self.text.is_some() {
+            let font = self.font.resolve(env);
+            let color = self.text_color.resolve(env);
+            let size_override = self.text_size_override.as_ref().map(|key| key.resolve(env));
+
+            let descriptor = if let Some(size) = size_override {
+                font.with_size(size)
+            } else {
+                font
+            };
+            let descriptor = descriptor.scaled(font_scale * self.text_size_scale);
 
-                let descriptor = if let Some(size) = size_override {
-                    font.with_size(size)
+            let built_with = Some((descriptor.clone(), color));
+            if built_with != self.built_with {
+                self.built_with = built_with;
+                self.invalidate();
+            }
+
+            if self.layout.is_some() {
+                self.shape_cache_stats.add_hit();
+            } else if let Some(text) = self.text.clone() {
+                self.shape_cache_stats.add_miss();
+                self.shape_cache_stats.add_shape();
+                self.generation += 1;
+                let space_size = if self.word_spacing != 0.0 {
+                    self.shape_cache_stats.add_shape();
+                    space_size_for_word_spacing(factory, &descriptor, self.word_spacing)
                 } else {
-                    font
+                    None
+                };
+                let spacing = self.measure_spacing(factory, &text, &descriptor);
+                let key = spacing.insertion_key(self.emoji_presentation);
+                if key.is_empty() {
+                    self.inserted = None;
+                } else if self
+                    .inserted
+                    .as_ref()
+                    .map_or(true, |inserted| inserted.key != key)
+                {
+                    self.inserted =
+                        Some(InsertedText::new(text.as_str(), key, &self.inline_objects));
+                }
+                let storage = match &self.inserted {
+                    Some(inserted) => LayoutStorage::Inserted(inserted.text.clone()),
+                    None => LayoutStorage::Text(text.clone()),
                 };
 
-                let builder = factory
-                    .new_text_layout(text.clone())
-                    .max_width(self.wrap_width)
-                    .alignment(self.alignment)
-                    .font(descriptor.family.clone(), descriptor.size)
-                    .default_attribute(descriptor.weight)
-                    .default_attribute(descriptor.style)
-                    .default_attribute(TextAttribute::TextColor(color));
-                let layout = text.add_attributes(builder, env).build().unwrap();
+                let breaks = match self.break_strategy {
+                    BreakStrategy::Optimal
+                        if self.wrap_width.is_finite()
+                            && self.alignment != TextAlignment::Justified =>
+                    {
+                        let unwrapped = match self.unwrapped_layout.take() {
+                            Some(unwrapped) => unwrapped,
+                            None => {
+                                self.shape_cache_stats.add_shape();
+                                self.build_layout(
+                                    factory,
+                                    env,
+                                    &text,
+                                    storage.clone(),
+                                    &descriptor,
+                                    color,
+                                    space_size,
+                                    &spacing,
+                                    f64::INFINITY,
+                                )
+                            }
+                        };
+                        let breaks = optimal_breaks(&unwrapped, storage.as_str(), self.wrap_width);
+                        self.unwrapped_layout = Some(unwrapped);
+                        breaks
+                    }
+                    _ => Vec::new(),
+                };
+                let storage = if breaks.is_empty() {
+                    storage
+                } else {
+                    // Spaces and newlines are both one byte long, so offsets into
+                    // the broken text are valid in the original one.
+                    let mut broken = storage.as_str().as_bytes().to_vec();
+                    for i in breaks {
+                        broken[i] = b'\n';
+                    }
+                    LayoutStorage::Broken(String::from_utf8(broken).unwrap())
+                };
+                let layout = self.build_layout(
+                    factory,
+                    env,
+                    &text,
+                    storage,
+                    &descriptor,
+                    color,
+                    space_size,
+                    &spacing,
+                    self.wrap_width,
+                );
+
+                self.line_boxes = match self.line_height {
+                    Some(line_height) => {
+                        let font_sizes: Vec<_> = text
+                            .attributes(env)
+                            .into_iter()
+                            .filter_map(|(range, attr)| match attr {
+                                TextAttribute::FontSize(size) => {
+                                    Some((self.layout_range(range), size))
+                                }
+                                _ => None,
+                            })
+                            .collect();
+                        line_boxes(&layout, line_height, descriptor.size, &font_sizes)
+                    }
+                    None => Rc::new([]),
+                };
+                let line_boxes = self.line_boxes.clone();
 
                 self.links = text
                     .links()
                     .iter()
                     .enumerate()
                     .flat_map(|(i, link)| {
-                        layout
-                            .rects_for_range(link.range())
+                        let rects = layout.rects_for_range(self.layout_range(link.range()));
+                        move_rects_to_lines(&line_boxes, rects)
                             .into_iter()
                             .map(move |rect| (rect, i))
                     })
                     .collect();
 
+                self.inline_object_rects = self
+                    .inline_objects
+                    .iter()
+                    .map(|object| {
+                        inline_object_run(text.as_str(), &object.range)?;
+                        let range = self.inline_object_range(&object.range);
+                        let x = layout.rects_for_range(range.clone()).first()?.x0;
+                        let line = layout.hit_test_text_position(range.start).line;
+                        let metric = layout.line_metric(line)?;
+                        let line_offset = line_boxes.get(line).map_or(0.0, |line| line.offset);
+                        let baseline = metric.y_offset + metric.baseline + line_offset;
+                        Some(Rect::from_origin_size(
+                            Point::new(x, baseline - object.size.height),
+                            object.size,
+                        ))
+                    })
+                    .collect();
+
                 self.layout = Some(layout);
             }
         }
     }
 
+    // Builds a layout of `storage`, which is `text` or the text with inserted
+    // characters, possibly with some spaces replaced by line breaks.
+    #[allow(clippy::too_many_arguments)]
+    fn build_layout(
+        &mut self,
+        factory: &mut PietText,
+        env: &Env,
+        text: &T,
+        storage: LayoutStorage<T>,
+        descriptor: &FontDescriptor,
+        color: Color,
+        space_size: Option<f64>,
+        spacing: &Spacing,
+        max_width: f64,
+    ) -> PietTextLayout {
+        let mut builder = factory
+            .new_text_layout(storage)
+            .max_width(max_width)
+            .alignment(self.alignment)
+            .font(descriptor.family.clone(), descriptor.size)
+            .default_attribute(descriptor.weight)
+            .default_attribute(descriptor.style)
+            .default_attribute(TextAttribute::TextColor(color));
+        if self.inserted.is_some() {
+            for (range, attr) in text.attributes(env) {
+                builder = builder.range_attribute(self.layout_range(range), attr);
+            }
+        } else {
+            builder = text.add_attributes(builder, env);
+        }
+        if let Some(size) = space_size {
+            for (i, c) in text.as_str().char_indices() {
+                if c == ' ' || c == '\u{a0}' {
+                    let range = self.layout_range(i..i + c.len_utf8());
+                    builder = builder.range_attribute(range, TextAttribute::FontSize(size));
+                }
+            }
+        }
+        if let Some(inserted) = &self.inserted {
+            let size = TextAttribute::FontSize(spacing.letters.size);
+            for range in inserted.spacer_ranges() {
+                builder = builder.range_attribute(range, size.clone());
+            }
+        }
+        // Added after word spacing, so that spaces in an object's run are
+        // sized with the rest of the run.
+        for (object, fit) in self.inline_objects.iter().zip(spacing.objects.iter()) {
+            let Some(fit) = fit else {
+                continue;
+            };
+            let range = self.inline_object_range(&object.range);
+            builder = builder
+                .range_attribute(range.clone(), TextAttribute::FontSize(fit.run_size))
+                .range_attribute(range, TextAttribute::TextColor(Color::TRANSPARENT));
+            if let Some(range) = self
+                .inserted
+                .as_ref()
+                .and_then(|inserted| inserted.object_spacer_range(object.range.end))
+            {
+                let size = TextAttribute::FontSize(fit.spacers.size);
+                builder = builder.range_attribute(range, size);
+            }
+        }
+        builder.build().unwrap()
+    }
+
+    // Measures the spaces used for letter spacing and to size inline objects.
+    fn measure_spacing(
+        &mut self,
+        factory: &mut PietText,
+        text: &T,
+        descriptor: &FontDescriptor,
+    ) -> Spacing {
+        let nbsp_width = if self.letter_spacing > 0.0 || !self.inline_objects.is_empty() {
+            self.shape_cache_stats.add_shape();
+            text_width(factory, descriptor, "\u{a0}")
+        } else {
+            None
+        };
+        let spacers = |width: f64| {
+            nbsp_width.map_or(Spacers::default(), |nbsp_width| {
+                Spacers::for_width(width, nbsp_width, descriptor.size)
+            })
+        };
+
+        let letters = spacers(self.letter_spacing);
+        let objects = self
+            .inline_objects
+            .iter()
+            .map(|object| {
+                let run = inline_object_run(text.as_str(), &object.range)?;
+                self.shape_cache_stats.add_shape();
+                let run_width = text_width(factory, descriptor, run).unwrap_or(0.0);
+                let width = object.size.width.max(0.0);
+                // Shrinking the run is fine, but growing it would make the line
+                // taller, so narrow runs are padded with spaces instead.
+                Some(if width < run_width {
+                    ObjectFit {
+                        run_size: descriptor.size * width / run_width,
+                        spacers: Spacers::default(),
+                    }
+                } else {
+                    ObjectFit {
+                        run_size: descriptor.size,
+                        spacers: spacers(width - run_width),
+                    }
+                })
+            })
+            .collect();
+        Spacing { letters, objects }
+    }
+
+    // The range of the layout's text covered by an inline object, including the
+    // spaces padding it but not the letter spacing after it.
+    fn inline_object_range(&self, range: &Range<usize>) -> Range<usize> {
+        match &self.inserted {
+            Some(inserted) => inserted.layout_offset(range.start)..inserted.object_end(range.end),
+            None => range.clone(),
+        }
+    }
+
     ///  Draw the layout at the provided `Point`.
     ///
     ///  The origin of the layout is the top-left corner.
@@ -401,11 +1054,638 @@ impl<T: TextStorage> TextLayout<T> {
                 .unwrap_or("layout is missing text")
         );
         if let Some(layout) = self.layout.as_ref() {
-            ctx.draw_text(layout, point);
+            let point = point.into();
+            if self.line_boxes.is_empty() {
+                ctx.draw_text(layout, point);
+                self.draw_inline_objects(ctx, point, None);
+            } else {
+                let shifts: Vec<Vec2> = self
+                    .line_boxes
+                    .iter()
+                    .map(|line| Vec2::new(0.0, line.offset))
+                    .collect();
+                self.draw_shifted_lines(ctx, layout, point, &shifts);
+            }
+        }
+    }
+
+    // Draws each line of `layout` moved by its entry in `shifts`.
+    //
+    // Consecutive lines moved by the same amount are drawn at once, by clipping
+    // the layout to their band; if all lines are, the layout is drawn unclipped.
+    fn draw_shifted_lines(
+        &self,
+        ctx: &mut PaintCtx,
+        layout: &PietTextLayout,
+        point: Point,
+        shifts: &[Vec2],
+    ) {
+        // The band must be wide enough to never cut shifted glyphs.
+        let band_width = self.align_width(layout).max(layout.size().width);
+
+        let mut start = 0;
+        while start < shifts.len() {
+            let shift = shifts[start];
+            let end = start
+                + shifts[start..]
+                    .iter()
+                    .take_while(|other| **other == shift)
+                    .count();
+            // Inline object rects already include the line offset.
+            let objects_point = point + Vec2::new(shift.x, 0.0);
+            if start == 0 && end == shifts.len() {
+                ctx.draw_text(layout, point + shift);
+                self.draw_inline_objects(ctx, objects_point, None);
+                return;
+            }
+
+            let first = layout.line_metric(start).unwrap();
+            let last = layout.line_metric(end - 1).unwrap();
+            let top = first.y_offset + shift.y;
+            let bottom = last.y_offset + last.height + shift.y;
+            let band = Rect::new(-band_width, top, 2.0 * band_width, bottom);
+            ctx.with_save(|ctx| {
+                ctx.clip(band + point.to_vec2());
+                ctx.draw_text(layout, point + shift);
+            });
+            self.draw_inline_objects(ctx, objects_point, Some(top..bottom));
+            start = end;
+        }
+    }
+
+    // The width lines are aligned within: the wrap width, if any.
+    fn align_width(&self, layout: &PietTextLayout) -> f64 {
+        if self.wrap_width.is_finite() {
+            self.wrap_width
+        } else {
+            layout.size().width
+        }
+    }
+
+    // Draws the inline objects whose baseline is in `lines`, or all of them.
+    fn draw_inline_objects(&self, ctx: &mut PaintCtx, point: Point, lines: Option<Range<f64>>) {
+        let mut images = self.inline_images.borrow_mut();
+        images.resize_with(self.inline_objects.len(), || None);
+        for ((object, rect), image) in self
+            .inline_objects
+            .iter()
+            .zip(self.inline_object_rects.iter())
+            .zip(images.iter_mut())
+        {
+            let Some(rect) = rect else {
+                continue;
+            };
+            if object.image.size().is_empty() {
+                continue;
+            }
+            if lines
+                .as_ref()
+                .map_or(false, |lines| !lines.contains(&rect.y1))
+            {
+                continue;
+            }
+            let image = image.get_or_insert_with(|| object.image.to_image(ctx.render_ctx));
+            ctx.draw_image(image, *rect + point.to_vec2(), InterpolationMode::Bilinear);
+        }
+    }
+
+    /// Draw the layout at the provided `Point`, re-aligning some visual lines.
+    ///
+    /// Each entry of `overrides` is a visual line index and the alignment to use
+    /// for that line instead of the layout's own alignment. Indices refer to lines
+    /// after wrapping, and indices past the last line are ignored. If a line
+    /// appears several times, the last entry wins.
+    ///
+    /// Lines are aligned within the wrap width, or within the width of the widest
+    /// line if there is no wrap width. [`TextAlignment::Justified`] is treated as
+    /// `Start`. Only drawing is affected: hit-testing and
+    /// [`rects_for_range`](Self::rects_for_range) still use the base alignment.
+    pub fn draw_with_line_alignments(
+        &self,
+        ctx: &mut PaintCtx,
+        point: impl Into<Point>,
+        overrides: &[(usize, TextAlignment)],
+    ) {
+        let point = point.into();
+        let (layout, text) = match (self.layout.as_ref(), self.text.as_ref()) {
+            (Some(layout), Some(_)) if !overrides.is_empty() => (layout, self.layout_str()),
+            _ => return self.draw(ctx, point),
+        };
+
+        let align_width = self.align_width(layout);
+        let mut shifts: Vec<Vec2> = (0..layout.line_count())
+            .map(|line| Vec2::new(0.0, self.line_offset(line)))
+            .collect();
+        // Only overridden lines are measured; the others keep their position,
+        // so they are drawn together.
+        for &(line, alignment) in overrides {
+            let Some(metric) = layout.line_metric(line) else {
+                continue;
+            };
+            let text_end = metric.start_offset
+                + text[metric.start_offset..metric.end_offset]
+                    .trim_end()
+                    .len();
+            let line_bounds = layout
+                .rects_for_range(metric.start_offset..text_end)
+                .into_iter()
+                .reduce(|a, b| a.union(b));
+
+            shifts[line].x = match line_bounds {
+                Some(bounds) => {
+                    let free_space = align_width - bounds.width();
+                    let x = match (alignment, self.text_is_rtl) {
+                        (TextAlignment::Center, _) => free_space / 2.0,
+                        (TextAlignment::End, false) | (TextAlignment::Start, true) => free_space,
+                        _ => 0.0,
+                    };
+                    x - bounds.x0
+                }
+                None => 0.0,
+            };
+        }
+        self.draw_shifted_lines(ctx, layout, point, &shifts);
+    }
+}
+
+/// Returns the font size for which a space is `spacing` pixels wider than
+/// at the descriptor's size.
+fn space_size_for_word_spacing(
+    factory: &mut PietText,
+    descriptor: &FontDescriptor,
+    spacing: f64,
+) -> Option<f64> {
+    let width = text_width(factory, descriptor, " ")?;
+    Some(descriptor.size * ((width + spacing) / width).max(0.0))
+}
+
+/// Returns the width of `text` on a single line, including trailing whitespace,
+/// or `None` if it has no width.
+fn text_width(factory: &mut PietText, descriptor: &FontDescriptor, text: &str) -> Option<f64> {
+    let layout = factory
+        .new_text_layout(text.to_string())
+        .font(descriptor.family.clone(), descriptor.size)
+        .default_attribute(descriptor.weight)
+        .default_attribute(descriptor.style)
+        .build()
+        .ok()?;
+    let width = layout.trailing_whitespace_width();
+    if width <= 0.0 {
+        return None;
+    }
+    Some(width)
+}
+
+/// Returns the byte offsets of the spaces to break lines at so that the lines of
+/// each paragraph fit in `max_width` and are as even as possible.
+///
+/// `layout` must be a layout of `text` without a wrap width, so that each line is
+/// a paragraph. The raggedness of a line is the square of its unused width; the
+/// last line of a paragraph is free to be short.
+fn optimal_breaks(layout: &PietTextLayout, text: &str, max_width: f64) -> Vec<usize> {
+    let mut breaks = Vec::new();
+    for line in 0..layout.line_count() {
+        let metric = layout.line_metric(line).unwrap();
+        let line_text = &text[metric.start_offset..metric.end_offset];
+        let content_end = metric.start_offset + line_text.trim_end_matches(['\n', '\r']).len();
+        let x = |offset: usize| layout.hit_test_text_position(offset).point.x;
+
+        let spaces: Vec<usize> = line_text
+            .match_indices(' ')
+            .map(|(i, _)| metric.start_offset + i)
+            .filter(|&i| i < content_end)
+            .collect();
+        // Node 0 is the start of the paragraph, node `k` in `1..=n` a break at
+        // `spaces[k - 1]`, and node `n + 1` the end of the paragraph.
+        let n = spaces.len();
+        let line_start = |node: usize| match node {
+            0 => x(metric.start_offset),
+            _ => x(spaces[node - 1] + 1),
+        };
+        let line_end = |node: usize| {
+            if node == n + 1 {
+                x(content_end)
+            } else {
+                x(spaces[node - 1])
+            }
+        };
+        let starts: Vec<f64> = (0..=n).map(line_start).collect();
+        let ends: Vec<f64> = (1..=n + 1).map(line_end).collect();
+
+        let mut cost = vec![f64::INFINITY; n + 2];
+        let mut previous = vec![0; n + 2];
+        cost[0] = 0.0;
+        for end in 1..=n + 1 {
+            for start in 0..end {
+                if cost[start].is_infinite() {
+                    continue;
+                }
+                let width = (ends[end - 1] - starts[start]).abs();
+                let badness = if width > max_width {
+                    // A single word that doesn't fit can't be helped.
+                    if end != start + 1 {
+                        continue;
+                    }
+                    0.0
+                } else if end == n + 1 {
+                    0.0
+                } else {
+                    (max_width - width).powi(2)
+                };
+                if cost[start] + badness < cost[end] {
+                    cost[end] = cost[start] + badness;
+                    previous[end] = start;
+                }
+            }
+        }
+
+        let first_break = breaks.len();
+        let mut node = previous[n + 1];
+        while node != 0 {
+            breaks.push(spaces[node - 1]);
+            node = previous[node];
+        }
+        breaks[first_break..].reverse();
+    }
+    breaks
+}
+
+/// Where a line of a layout is moved to apply a line height.
+#[derive(Clone, Copy, Debug)]
+struct LineBox {
+    // How far the line is moved down.
+    offset: f64,
+    // The bottom of the line in the built layout.
+    layout_bottom: f64,
+    // The bottom of the line's box, once moved.
+    bottom: f64,
+}
+
+/// Centers each line of `layout` in a box `line_height` times its font size.
+///
+/// The font size of a line is the largest of `font_size` and the sizes in
+/// `font_sizes` covering part of the line.
+fn line_boxes(
+    layout: &PietTextLayout,
+    line_height: f64,
+    font_size: f64,
+    font_sizes: &[(Range<usize>, f64)],
+) -> Rc<[LineBox]> {
+    let mut top = 0.0;
+    (0..layout.line_count())
+        .filter_map(|i| layout.line_metric(i))
+        .map(|metric| {
+            let size = font_sizes
+                .iter()
+                .filter(|(range, _)| {
+                    range.start < metric.end_offset && metric.start_offset < range.end
+                })
+                .fold(font_size, |max, (_, size)| max.max(*size));
+            let height = line_height * size;
+            let offset = top + (height - metric.height) / 2.0 - metric.y_offset;
+            top += height;
+            LineBox {
+                offset,
+                layout_bottom: metric.y_offset + metric.height,
+                bottom: top,
+            }
+        })
+        .collect()
+}
+
+/// Moves rects of a layout, as returned by its `rects_for_range`, down by the
+/// offset of the line each is on.
+fn move_rects_to_lines(lines: &[LineBox], rects: Vec<Rect>) -> Vec<Rect> {
+    if lines.is_empty() {
+        return rects;
+    }
+    rects
+        .into_iter()
+        .map(|rect| {
+            let y = rect.center().y;
+            let line = lines
+                .partition_point(|line| line.layout_bottom <= y)
+                .min(lines.len() - 1);
+            rect + Vec2::new(0.0, lines[line].offset)
+        })
+        .collect()
+}
+
+/// The no-break spaces inserted into a layout's text, measured for its font.
+struct Spacing {
+    // Inserted after each grapheme cluster for letter spacing.
+    letters: Spacers,
+    // How each inline object fits its run, or `None` if its range is invalid.
+    objects: Vec<Option<ObjectFit>>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Spacers {
+    count: usize,
+    // The font size of the spaces.
+    size: f64,
+}
+
+#[derive(Clone, Copy)]
+struct ObjectFit {
+    // The font size of the object's run.
+    run_size: f64,
+    // The spaces inserted after the run, if the object is wider than it.
+    spacers: Spacers,
+}
+
+impl Spacing {
+    fn insertion_key(&self, presentation: EmojiPresentation) -> InsertionKey {
+        InsertionKey {
+            presentation,
+            letter_spacers: self.letters.count,
+            object_spacers: self
+                .objects
+                .iter()
+                .map(|fit| fit.map_or(0, |fit| fit.spacers.count))
+                .collect(),
+        }
+    }
+}
+
+impl Spacers {
+    /// Returns the no-break spaces that are `width` pixels wide together, given
+    /// the width of one at `font_size`.
+    ///
+    /// Enough spaces are used that they're no larger than the text, so that they
+    /// don't make lines taller.
+    fn for_width(width: f64, nbsp_width: f64, font_size: f64) -> Self {
+        if width.is_nan() || width <= 0.0 {
+            return Spacers::default();
+        }
+        let count = (width / nbsp_width).ceil().max(1.0);
+        Spacers {
+            count: count as usize,
+            size: font_size * width / (count * nbsp_width),
+        }
+    }
+}
+
+/// What is inserted into a text, which only changes when the text needs to be
+/// copied again.
+#[derive(Clone, PartialEq)]
+struct InsertionKey {
+    presentation: EmojiPresentation,
+    // The number of no-break spaces inserted for letter spacing.
+    letter_spacers: usize,
+    // The number of no-break spaces inserted after each inline object.
+    object_spacers: Vec<usize>,
+}
+
+impl InsertionKey {
+    fn is_empty(&self) -> bool {
+        self.presentation == EmojiPresentation::Auto
+            && self.letter_spacers == 0
+            && self.object_spacers.iter().all(|&count| count == 0)
+    }
+}
+
+/// A text with characters inserted into it before it's laid out.
+///
+/// The variation selector of the emoji presentation is added after characters
+/// that have both presentations and no selector. Inline objects that are wider
+/// than their run are padded with no-break spaces after it. Letter spacing is
+/// added with no-break spaces after each grapheme cluster that's followed by
+/// more text on the same line, except after whitespace and inside inline objects.
+#[derive(Clone)]
+struct InsertedText {
+    key: InsertionKey,
+    text: ArcStr,
+    insertions: Rc<[Insertion]>,
+}
+
+#[derive(Clone, Copy)]
+struct Insertion {
+    // The offset in the original text where characters are inserted.
+    at: usize,
+    // The total length of the characters inserted up to and including here.
+    shift: usize,
+    // The length of the spaces padding inline objects, which come before the
+    // spaces for letter spacing.
+    object_len: usize,
+    // The length of the spaces for letter spacing, which come last.
+    spacer_len: usize,
+}
+
+impl InsertedText {
+    fn new(text: &str, key: InsertionKey, objects: &[InlineObject]) -> Self {
+        let mut selectors = selector_offsets(text, key.presentation)
+            .into_iter()
+            .peekable();
+        let spacer = "\u{a0}".repeat(key.letter_spacers);
+        let mut spacer_offsets = Vec::new();
+        if key.letter_spacers > 0 {
+            let mut graphemes = text.grapheme_indices(true).peekable();
+            while let Some((i, grapheme)) = graphemes.next() {
+                let end = i + grapheme.len();
+                let is_followed = graphemes
+                    .peek()
+                    .map_or(false, |(_, next)| !next.starts_with(['\n', '\r']));
+                let is_in_object = objects
+                    .iter()
+                    .any(|object| object.range.start < end && end < object.range.end);
+                if is_followed && !is_in_object && !grapheme.chars().all(char::is_whitespace) {
+                    spacer_offsets.push(end);
+                }
+            }
+        }
+        let mut spacer_offsets = spacer_offsets.into_iter().peekable();
+        let mut object_ends: Vec<(usize, usize)> = objects
+            .iter()
+            .zip(&key.object_spacers)
+            .filter(|(_, &count)| count > 0)
+            .map(|(object, &count)| (object.range.end, count))
+            .collect();
+        object_ends.sort_unstable();
+        let mut object_ends = object_ends.into_iter().peekable();
+
+        let mut inserted = String::with_capacity(text.len());
+        let mut insertions: Vec<Insertion> = Vec::new();
+        let mut start = 0;
+        loop {
+            let Some(at) = [
+                selectors.peek(),
+                object_ends.peek().map(|(end, _)| end),
+                spacer_offsets.peek(),
+            ]
+            .into_iter()
+            .flatten()
+            .copied()
+            .min() else {
+                break;
+            };
+            inserted.push_str(&text[start..at]);
+            start = at;
+            let shift = insertions.last().map_or(0, |insertion| insertion.shift);
+            let len = inserted.len();
+            if selectors.next_if_eq(&at).is_some() {
+                inserted.push(key.presentation.selector().unwrap());
+            }
+            let object_start = inserted.len();
+            while let Some((_, count)) = object_ends.next_if(|&(end, _)| end == at) {
+                inserted.extend(std::iter::repeat('\u{a0}').take(count));
+            }
+            let object_len = inserted.len() - object_start;
+            let spacer_len = if spacer_offsets.next_if_eq(&at).is_some() {
+                inserted.push_str(&spacer);
+                spacer.len()
+            } else {
+                0
+            };
+            insertions.push(Insertion {
+                at,
+                shift: shift + inserted.len() - len,
+                object_len,
+                spacer_len,
+            });
+        }
+        inserted.push_str(&text[start..]);
+        InsertedText {
+            key,
+            text: inserted.into(),
+            insertions: insertions.into(),
+        }
+    }
+
+    // Maps an offset in the original text to the inserted text, after the
+    // characters inserted there; those belong to the preceding character.
+    fn layout_offset(&self, offset: usize) -> usize {
+        let i = self
+            .insertions
+            .partition_point(|insertion| insertion.at <= offset);
+        offset + self.shift_before(i)
+    }
+
+    // Maps the end of an inline object to the inserted text, after the spaces
+    // padding it but before the letter spacing.
+    fn object_end(&self, end: usize) -> usize {
+        match self.insertion_at(end) {
+            Some(insertion) => end + insertion.shift - insertion.spacer_len,
+            None => self.layout_offset(end),
+        }
+    }
+
+    // The range of the inserted text holding the spaces padding the inline
+    // objects that end at `end`.
+    fn object_spacer_range(&self, end: usize) -> Option<Range<usize>> {
+        let insertion = self.insertion_at(end)?;
+        let spacers_end = end + insertion.shift - insertion.spacer_len;
+        (insertion.object_len > 0).then(|| spacers_end - insertion.object_len..spacers_end)
+    }
+
+    fn insertion_at(&self, offset: usize) -> Option<&Insertion> {
+        let i = self
+            .insertions
+            .binary_search_by_key(&offset, |insertion| insertion.at)
+            .ok()?;
+        Some(&self.insertions[i])
+    }
+
+    // Maps an offset in the inserted text to the original text. Offsets in
+    // inserted characters move to where they were inserted.
+    fn text_offset(&self, layout_offset: usize) -> usize {
+        let i = self
+            .insertions
+            .partition_point(|insertion| insertion.at + insertion.shift <= layout_offset);
+        let offset = layout_offset.saturating_sub(self.shift_before(i));
+        match self.insertions.get(i) {
+            Some(insertion) => offset.min(insertion.at),
+            None => offset,
+        }
+    }
+
+    // The total length of the first `i` insertions.
+    fn shift_before(&self, i: usize) -> usize {
+        i.checked_sub(1).map_or(0, |i| self.insertions[i].shift)
+    }
+
+    // The ranges of the inserted text holding the spaces for letter spacing.
+    fn spacer_ranges(&self) -> impl Iterator<Item = Range<usize>> + '_ {
+        self.insertions
+            .iter()
+            .filter(|insertion| insertion.spacer_len > 0)
+            .map(|insertion| {
+                let end = insertion.at + insertion.shift;
+                end - insertion.spacer_len..end
+            })
+    }
+}
+
+// The text given to the platform's layout.
+#[derive(Clone)]
+enum LayoutStorage<T> {
+    Text(T),
+    Inserted(ArcStr),
+    // Text with spaces replaced by line breaks.
+    Broken(String),
+}
+
+impl<T: TextStorage> crate::piet::TextStorage for LayoutStorage<T> {
+    fn as_str(&self) -> &str {
+        match self {
+            LayoutStorage::Text(text) => text.as_str(),
+            LayoutStorage::Inserted(text) => text,
+            LayoutStorage::Broken(text) => text,
         }
     }
 }
 
+/// Returns the text replaced by an inline object, if its range is valid.
+fn inline_object_run<'a>(text: &'a str, range: &Range<usize>) -> Option<&'a str> {
+    if range.is_empty() {
+        return None;
+    }
+    text.get(range.clone())
+}
+
+impl ShapeCacheStats {
+    fn add_shape(&mut self) {
+        if cfg!(debug_assertions) {
+            self.shapes += 1;
+        }
+    }
+
+    fn add_hit(&mut self) {
+        if cfg!(debug_assertions) {
+            self.hits += 1;
+        }
+    }
+
+    fn add_miss(&mut self) {
+        if cfg!(debug_assertions) {
+            self.misses += 1;
+        }
+    }
+}
+
+impl Add for ShapeCacheStats {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl AddAssign for ShapeCacheStats {
+    fn add_assign(&mut self, other: Self) {
+        self.shapes += other.shapes;
+        self.hits += other.hits;
+        self.misses += other.misses;
+    }
+}
+
+impl Sum for ShapeCacheStats {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
 impl<T> std::fmt::Debug for TextLayout<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.debug_struct("TextLayout")
@@ -429,3 +1709,139 @@ impl<T: TextStorage> Default for TextLayout<T> {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piet::{Device, TextStorage as PietTextStorage};
+    use crate::{Data, Selector};
+
+    #[derive(Clone)]
+    struct LinkedText {
+        text: &'static str,
+        links: Rc<[Link]>,
+    }
+
+    impl PietTextStorage for LinkedText {
+        fn as_str(&self) -> &str {
+            self.text
+        }
+    }
+
+    impl TextStorage for LinkedText {
+        fn links(&self) -> &[Link] {
+            &self.links
+        }
+    }
+
+    impl Data for LinkedText {
+        fn same(&self, other: &Self) -> bool {
+            self.text == other.text && Rc::ptr_eq(&self.links, &other.links)
+        }
+    }
+
+    #[test]
+    fn link_hit_tolerance() {
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(10, 10, 1.0).unwrap();
+        let mut piet = target.render_context();
+        let mut factory = piet.text().clone();
+        piet.finish().unwrap();
+
+        let mut layout = TextLayout::new();
+        layout.set_text(LinkedText {
+            text: "first second",
+            links: Rc::new([
+                Link::new(0..5, Selector::NOOP),
+                Link::new(6..12, Selector::NOOP),
+            ]),
+        });
+        layout.rebuild_if_needed(&mut factory, &Env::with_theme());
+
+        let first_box = layout.links[0].0;
+        let first_link = |link: Option<&Link>| link.map(Link::range) == Some(0..5);
+
+        // Just above the glyph box of the first link.
+        let above = Point::new(first_box.center().x, first_box.y0 - 2.0);
+        assert!(layout.link_for_pos(above).is_none());
+        assert!(layout.link_for_pos_with_tolerance(above, 1.0).is_none());
+        assert!(first_link(layout.link_for_pos_with_tolerance(above, 3.0)));
+
+        // In the gap between both links, closer to the first one.
+        let between = Point::new(first_box.x1 + 0.5, first_box.center().y);
+        assert!(first_link(
+            layout.link_for_pos_with_tolerance(between, 100.0)
+        ));
+    }
+
+    #[test]
+    fn hit_test_inside_cluster() {
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(10, 10, 1.0).unwrap();
+        let mut piet = target.render_context();
+        let mut factory = piet.text().clone();
+        piet.finish().unwrap();
+
+        // A family emoji: three code points joined by zero-width joiners.
+        let text = "a\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}b";
+        let emoji_end = text.len() - 1;
+        let mut layout = TextLayout::<crate::ArcStr>::from_text(text);
+        layout.rebuild_if_needed(&mut factory, &Env::with_theme());
+
+        let start_x = layout.point_for_text_position(1).x;
+        let end_x = layout.point_for_text_position(emoji_end).x;
+        for i in 0..=10 {
+            let x = start_x + (end_x - start_x) * i as f64 / 10.0;
+            let pos = layout.text_position_for_point(Point::new(x, 1.0));
+            assert!(pos == 1 || pos == emoji_end, "{x} -> {pos}");
+        }
+
+        // Offsets inside the cluster are placed at its start.
+        assert_eq!(layout.point_for_text_position(5).x, start_x);
+        assert_eq!(
+            layout.rects_for_range(5..6),
+            layout.rects_for_range(1..emoji_end)
+        );
+    }
+
+    #[test]
+    fn optimal_break_strategy() {
+        let mut device = Device::new().unwrap();
+        let mut target = device.bitmap_target(10, 10, 1.0).unwrap();
+        let mut piet = target.render_context();
+        let mut factory = piet.text().clone();
+        piet.finish().unwrap();
+        let env = Env::with_theme();
+
+        // Every word is made of the same letter, so that the first two words
+        // exactly fill a line.
+        let mut layout = TextLayout::<crate::ArcStr>::from_text("nnn nn nn nnnnn");
+        layout.rebuild_if_needed(&mut factory, &env);
+        let wrap_width = layout.point_for_text_position(6).x + 0.5;
+        layout.set_wrap_width(wrap_width);
+
+        let line_starts = |layout: &TextLayout<crate::ArcStr>| {
+            let layout = layout.layout.as_ref().unwrap();
+            (0..layout.line_count())
+                .map(|line| layout.line_metric(line).unwrap().start_offset)
+                .collect::<Vec<_>>()
+        };
+
+        layout.rebuild_if_needed(&mut factory, &env);
+        assert_eq!(line_starts(&layout), vec![0, 7, 10]);
+
+        // Moving "nn" down evens out the first two lines.
+        layout.set_break_strategy(BreakStrategy::Optimal);
+        layout.rebuild_if_needed(&mut factory, &env);
+        assert_eq!(line_starts(&layout), vec![0, 4, 10]);
+        assert_eq!(layout.text().unwrap().as_str(), "nnn nn nn nnnnn");
+
+        // Rewrapping the same text reuses the unwrapped layout.
+        layout.reset_shape_cache_stats();
+        layout.set_wrap_width(wrap_width + 1.0);
+        layout.rebuild_if_needed(&mut factory, &env);
+        assert_eq!(line_starts(&layout), vec![0, 4, 10]);
+        #[cfg(debug_assertions)]
+        assert_eq!(layout.shape_cache_stats().shapes, 1);
+    }
+}
@@ -9,8 +9,11 @@
 
 mod attribute;
 mod backspace;
+mod decoration;
 mod editable_text;
+mod emoji;
 mod font_descriptor;
+mod grapheme;
 
 mod input_component;
 mod input_methods;
@@ -31,8 +34,18 @@ pub use storage::{ArcStr, TextStorage};
 
 pub use self::attribute::{Attribute, AttributeSpans, Link};
 pub use self::backspace::offset_for_delete_backwards;
+pub(crate) use self::decoration::DecorationSpan;
+pub use self::decoration::{DecorationLineStyle, DecorationStyle};
 pub use self::editable_text::{EditableText, EditableTextCursor, StringCursor};
+pub use self::emoji::{
+    has_emoji_variations, EmojiPresentation, EMOJI_PRESENTATION_SELECTOR,
+    TEXT_PRESENTATION_SELECTOR,
+};
 pub use self::font_descriptor::FontDescriptor;
-pub use self::layout::{LayoutMetrics, TextLayout};
+pub use self::grapheme::{
+    grapheme_boundary_after, grapheme_boundary_before, grapheme_range, is_grapheme_boundary,
+    nearest_grapheme_boundary,
+};
+pub use self::layout::{BreakStrategy, InlineObject, LayoutMetrics, ShapeCacheStats, TextLayout};
 pub use self::movement::movement;
 pub use crate::piet::{FontFamily, FontStyle, FontWeight, TextAlignment};
@@ -10,10 +10,9 @@ use std::sync::Arc;
 use super::attribute::Link;
 use super::{Attribute, AttributeSpans, FontDescriptor, TextStorage};
 use crate::piet::{
-    util, Color, FontFamily, FontStyle, FontWeight, PietTextLayoutBuilder, TextLayoutBuilder,
-    TextStorage as PietTextStorage,
+    util, Color, FontFamily, FontStyle, FontWeight, TextAttribute, TextStorage as PietTextStorage,
 };
-use crate::{ArcStr, Data, Env, KeyOrValue};
+use crate::{ArcStr, Command, Data, Env, KeyOrValue};
 
 /// Text with optional style spans.
 #[derive(Clone, Debug)]
@@ -46,6 +45,11 @@ impl RichText {
         self
     }
 
+    /// The text, without its attributes.
+    pub fn text(&self) -> &ArcStr {
+        &self.buffer
+    }
+
     /// The length of the buffer, in utf8 code units.
     pub fn len(&self) -> usize {
         self.buffer.len()
@@ -56,6 +60,30 @@ impl RichText {
         self.buffer.is_empty()
     }
 
+    /// Returns `true` if the text has neither attributes nor links.
+    pub(crate) fn is_plain(&self) -> bool {
+        self.attrs.is_empty() && self.links.is_empty()
+    }
+
+    /// The first `len` bytes of the text followed by `suffix`, eg an ellipsis.
+    ///
+    /// Attributes are kept; the suffix takes the attributes of the text it
+    /// replaces. Links that don't fit entirely in the kept text are dropped.
+    pub(crate) fn truncated(&self, len: usize, suffix: &str) -> RichText {
+        let buffer = format!("{}{}", &self.buffer[..len], suffix);
+        let links: Vec<_> = self
+            .links
+            .iter()
+            .filter(|link| link.range().end <= len)
+            .cloned()
+            .collect();
+        RichText {
+            buffer: buffer.into(),
+            attrs: self.attrs.clone(),
+            links: links.into(),
+        }
+    }
+
     /// Add an [`Attribute`] to the provided range of text.
     ///
     /// [`Attribute`]: enum.Attribute.html
@@ -72,15 +100,8 @@ impl PietTextStorage for RichText {
 }
 
 impl TextStorage for RichText {
-    fn add_attributes(
-        &self,
-        mut builder: PietTextLayoutBuilder,
-        env: &Env,
-    ) -> PietTextLayoutBuilder {
-        for (range, attr) in self.attrs.to_piet_attrs(env) {
-            builder = builder.range_attribute(range, attr);
-        }
-        builder
+    fn attributes(&self, env: &Env) -> Vec<(Range<usize>, TextAttribute)> {
+        self.attrs.to_piet_attrs(env)
     }
 
     fn links(&self) -> &[Link] {
@@ -233,5 +254,10 @@ impl AttributesAdder<'_> {
         self
     }
 
-    //pub fn link(&mut self, command: impl Into<Command>) -> &mut Self;
+    /// Make the text a link, which submits `command` when clicked.
+    pub fn link(&mut self, command: impl Into<Command>) -> &mut Self {
+        let link = Link::new(self.range.clone(), command);
+        self.rich_text_builder.links.push(link);
+        self
+    }
 }
@@ -4,21 +4,43 @@
 
 //! Storing text.
 
+use std::ops::Range;
 use std::sync::Arc;
 
 use super::attribute::Link;
-use crate::piet::{PietTextLayoutBuilder, TextStorage as PietTextStorage};
+use crate::piet::{
+    PietTextLayoutBuilder, TextAttribute, TextLayoutBuilder as _, TextStorage as PietTextStorage,
+};
 use crate::{Data, Env};
 
 /// A type that represents text that can be displayed.
 pub trait TextStorage: PietTextStorage + Data {
     /// If this TextStorage object manages style spans, it should implement
     /// this method and update the provided builder with its spans, as required.
-    #[allow(unused_variables)]
-    fn add_attributes(&self, builder: PietTextLayoutBuilder, env: &Env) -> PietTextLayoutBuilder {
+    ///
+    /// The default implementation adds the spans returned by [`attributes`](Self::attributes).
+    fn add_attributes(
+        &self,
+        mut builder: PietTextLayoutBuilder,
+        env: &Env,
+    ) -> PietTextLayoutBuilder {
+        for (range, attr) in self.attributes(env) {
+            builder = builder.range_attribute(range, attr);
+        }
         builder
     }
 
+    /// The style spans of this text, as byte ranges and the attribute applied to them.
+    ///
+    /// When a [`TextLayout`](super::TextLayout) lays out the text with extra
+    /// characters, e.g. for letter spacing, it uses this method instead of
+    /// [`add_attributes`](Self::add_attributes) so that it can move the ranges
+    /// to match. Types that only override `add_attributes` are drawn unstyled then.
+    #[allow(unused_variables)]
+    fn attributes(&self, env: &Env) -> Vec<(Range<usize>, TextAttribute)> {
+        Vec::new()
+    }
+
     /// Any additional [`Link`] attributes on this text.
     ///
     /// If this `TextStorage` object manages link attributes, it should implement this
@@ -6,6 +6,8 @@
 
 #![allow(missing_docs)]
 
+use std::sync::Arc;
+
 use crate::piet::{Color, FontFamily, FontStyle, FontWeight};
 use crate::text::FontDescriptor;
 use crate::{Env, Insets, Key};
@@ -60,6 +62,40 @@ pub const UI_FONT_BOLD: Key<FontDescriptor> = Key::new("org.masonry.theme.ui-fon
 /// An Italic version of the default UI font.
 pub const UI_FONT_ITALIC: Key<FontDescriptor> = Key::new("org.masonry.theme.ui-font-italic");
 
+/// A factor applied to the size of all fonts, for UI density or zoom.
+///
+/// This is multiplied with [`TEXT_SCALE`].
+pub const FONT_SCALE: Key<f64> = Key::new("org.masonry.theme.font-scale");
+
+/// A factor applied to the size of all fonts, reflecting the user's
+/// accessibility preference for larger or smaller text.
+///
+/// This is multiplied with [`FONT_SCALE`].
+pub const TEXT_SCALE: Key<f64> = Key::new("org.masonry.theme.text-scale");
+
+/// How far, in pixels, a pointer may be from a link's text and still hit it.
+///
+/// `druid-shell` doesn't report whether a pointer event comes from touch or a mouse,
+/// so this defaults to zero; touch-first applications should raise it to a few pixels.
+pub const LINK_HIT_TOLERANCE: Key<f64> = Key::new("org.masonry.theme.link-hit-tolerance");
+
+/// The padding between the left and right edges of a label and its text.
+///
+/// Labels with a padding set with [`Label::with_padding`](crate::widget::Label::with_padding)
+/// ignore this.
+pub const LABEL_X_PADDING: Key<f64> = Key::new("org.masonry.theme.label-x-padding");
+
+/// How labels break lines that are too wide for them, unless they're given a
+/// [`LineBreaking`](crate::widget::LineBreaking) mode of their own.
+///
+/// This defaults to [`LineBreaking::Overflow`](crate::widget::LineBreaking::Overflow).
+pub const LABEL_LINE_BREAKING: Key<Arc<crate::widget::LineBreaking>> =
+    Key::new("org.masonry.theme.label-line-breaking");
+
+/// How long the pointer must stay over a widget, in milliseconds, before the
+/// widget receives [`Event::HoverStart`](crate::Event::HoverStart).
+pub const HOVER_DWELL_TIME: Key<u64> = Key::new("org.masonry.theme.hover-dwell-time");
+
 /// The default minimum width for a 'wide' widget; a textbox, slider, progress bar, etc.
 pub const WIDE_WIDGET_WIDTH: Key<f64> = Key::new("org.masonry.theme.long-widget-width");
 pub const BORDERED_WIDGET_HEIGHT: Key<f64> = Key::new("org.masonry.theme.bordered_widget_height");
@@ -89,46 +125,112 @@ pub const SCROLLBAR_EDGE_WIDTH: Key<f64> = Key::new("org.masonry.theme.scrollbar
 /// scrollbar's primary axis.
 pub const SCROLLBAR_MIN_SIZE: Key<f64> = Key::new("org.linebender.theme.scrollbar_min_size");
 
+/// The colors of the initial theme, as constants.
+///
+/// Applications setting up their own theme can start from these. [`Color::with_alpha`]
+/// gives translucent variants at runtime, and
+/// [`with_alpha8`](crate::theme::palette::with_alpha8) in `const` items:
+///
+/// ```
+/// use masonry::theme::palette;
+/// use masonry::Color;
+///
+/// const FADED_TEXT: Color = palette::with_alpha8(palette::TEXT, 0x80);
+/// assert_eq!(FADED_TEXT, palette::TEXT.with_alpha(0.5));
+/// ```
+///
+/// Like any [`Color`], they can be passed wherever a `KeyOrValue<Color>` is expected,
+/// eg to [`Label::with_text_color`](crate::widget::Label::with_text_color).
+pub mod palette {
+    use crate::piet::Color;
+
+    pub const WINDOW_BACKGROUND: Color = Color::grey8(0x29);
+    pub const TEXT: Color = Color::rgb8(0xf0, 0xf0, 0xea);
+    pub const DISABLED_TEXT: Color = Color::rgb8(0xa0, 0xa0, 0x9a);
+    pub const PLACEHOLDER: Color = Color::grey8(0x80);
+    pub const PRIMARY_LIGHT: Color = Color::rgb8(0x5c, 0xc4, 0xff);
+    pub const PRIMARY_DARK: Color = Color::rgb8(0x00, 0x8d, 0xdd);
+    pub const BACKGROUND_LIGHT: Color = Color::grey8(0x3a);
+    pub const BACKGROUND_DARK: Color = Color::grey8(0x31);
+    pub const FOREGROUND_LIGHT: Color = Color::grey8(0xf9);
+    pub const FOREGROUND_DARK: Color = Color::grey8(0xbf);
+    pub const DISABLED_FOREGROUND_LIGHT: Color = Color::grey8(0x89);
+    pub const DISABLED_FOREGROUND_DARK: Color = Color::grey8(0x6f);
+    pub const BUTTON_DARK: Color = Color::BLACK;
+    pub const BUTTON_LIGHT: Color = Color::grey8(0x21);
+    pub const DISABLED_BUTTON_DARK: Color = Color::grey8(0x28);
+    pub const DISABLED_BUTTON_LIGHT: Color = Color::grey8(0x38);
+    pub const BORDER_DARK: Color = Color::grey8(0x3a);
+    pub const BORDER_LIGHT: Color = Color::grey8(0xa1);
+    pub const SELECTION: Color = Color::rgb8(0x43, 0x70, 0xa8);
+    pub const SELECTION_INACTIVE: Color = Color::grey8(0x74);
+    pub const SELECTION_TEXT: Color = Color::BLACK;
+    pub const CURSOR: Color = Color::WHITE;
+    pub const SCROLLBAR: Color = Color::WHITE;
+    pub const SCROLLBAR_BORDER: Color = Color::grey8(0x77);
+
+    /// Return `color` with its alpha channel replaced by `alpha`.
+    ///
+    /// This is the `const` counterpart of [`Color::with_alpha`], taking an 8-bit alpha.
+    pub const fn with_alpha8(color: Color, alpha: u8) -> Color {
+        match color {
+            Color::Rgba32(rgba) => Color::from_rgba32_u32((rgba & !0xff) | alpha as u32),
+        }
+    }
+}
+
 /// An initial theme.
 pub(crate) fn add_to_env(env: Env) -> Env {
-    env.adding(WINDOW_BACKGROUND_COLOR, Color::rgb8(0x29, 0x29, 0x29))
-        .adding(TEXT_COLOR, Color::rgb8(0xf0, 0xf0, 0xea))
-        .adding(DISABLED_TEXT_COLOR, Color::rgb8(0xa0, 0xa0, 0x9a))
-        .adding(PLACEHOLDER_COLOR, Color::rgb8(0x80, 0x80, 0x80))
-        .adding(PRIMARY_LIGHT, Color::rgb8(0x5c, 0xc4, 0xff))
-        .adding(PRIMARY_DARK, Color::rgb8(0x00, 0x8d, 0xdd))
+    env.adding(WINDOW_BACKGROUND_COLOR, palette::WINDOW_BACKGROUND)
+        .adding(TEXT_COLOR, palette::TEXT)
+        .adding(DISABLED_TEXT_COLOR, palette::DISABLED_TEXT)
+        .adding(PLACEHOLDER_COLOR, palette::PLACEHOLDER)
+        .adding(PRIMARY_LIGHT, palette::PRIMARY_LIGHT)
+        .adding(PRIMARY_DARK, palette::PRIMARY_DARK)
         .adding(PROGRESS_BAR_RADIUS, 4.)
-        .adding(BACKGROUND_LIGHT, Color::rgb8(0x3a, 0x3a, 0x3a))
-        .adding(BACKGROUND_DARK, Color::rgb8(0x31, 0x31, 0x31))
-        .adding(FOREGROUND_LIGHT, Color::rgb8(0xf9, 0xf9, 0xf9))
-        .adding(FOREGROUND_DARK, Color::rgb8(0xbf, 0xbf, 0xbf))
-        .adding(DISABLED_FOREGROUND_LIGHT, Color::rgb8(0x89, 0x89, 0x89))
-        .adding(DISABLED_FOREGROUND_DARK, Color::rgb8(0x6f, 0x6f, 0x6f))
-        .adding(BUTTON_DARK, Color::BLACK)
-        .adding(BUTTON_LIGHT, Color::rgb8(0x21, 0x21, 0x21))
-        .adding(DISABLED_BUTTON_DARK, Color::grey8(0x28))
-        .adding(DISABLED_BUTTON_LIGHT, Color::grey8(0x38))
+        .adding(BACKGROUND_LIGHT, palette::BACKGROUND_LIGHT)
+        .adding(BACKGROUND_DARK, palette::BACKGROUND_DARK)
+        .adding(FOREGROUND_LIGHT, palette::FOREGROUND_LIGHT)
+        .adding(FOREGROUND_DARK, palette::FOREGROUND_DARK)
+        .adding(
+            DISABLED_FOREGROUND_LIGHT,
+            palette::DISABLED_FOREGROUND_LIGHT,
+        )
+        .adding(DISABLED_FOREGROUND_DARK, palette::DISABLED_FOREGROUND_DARK)
+        .adding(BUTTON_DARK, palette::BUTTON_DARK)
+        .adding(BUTTON_LIGHT, palette::BUTTON_LIGHT)
+        .adding(DISABLED_BUTTON_DARK, palette::DISABLED_BUTTON_DARK)
+        .adding(DISABLED_BUTTON_LIGHT, palette::DISABLED_BUTTON_LIGHT)
         .adding(BUTTON_BORDER_RADIUS, 4.)
         .adding(BUTTON_BORDER_WIDTH, 2.)
-        .adding(BORDER_DARK, Color::rgb8(0x3a, 0x3a, 0x3a))
-        .adding(BORDER_LIGHT, Color::rgb8(0xa1, 0xa1, 0xa1))
+        .adding(BORDER_DARK, palette::BORDER_DARK)
+        .adding(BORDER_LIGHT, palette::BORDER_LIGHT)
+        .adding(SELECTED_TEXT_BACKGROUND_COLOR, palette::SELECTION)
         .adding(
-            SELECTED_TEXT_BACKGROUND_COLOR,
-            Color::rgb8(0x43, 0x70, 0xA8),
+            SELECTED_TEXT_INACTIVE_BACKGROUND_COLOR,
+            palette::SELECTION_INACTIVE,
         )
-        .adding(SELECTED_TEXT_INACTIVE_BACKGROUND_COLOR, Color::grey8(0x74))
-        .adding(SELECTION_TEXT_COLOR, Color::rgb8(0x00, 0x00, 0x00))
-        .adding(CURSOR_COLOR, Color::WHITE)
+        .adding(SELECTION_TEXT_COLOR, palette::SELECTION_TEXT)
+        .adding(CURSOR_COLOR, palette::CURSOR)
         .adding(TEXT_SIZE_NORMAL, 15.0)
         .adding(TEXT_SIZE_LARGE, 24.0)
+        .adding(FONT_SCALE, 1.0)
+        .adding(TEXT_SCALE, 1.0)
+        .adding(LINK_HIT_TOLERANCE, 0.0)
+        .adding(LABEL_X_PADDING, 2.0)
+        .adding(
+            LABEL_LINE_BREAKING,
+            Arc::new(crate::widget::LineBreaking::Overflow),
+        )
+        .adding(HOVER_DWELL_TIME, 300u64)
         .adding(BASIC_WIDGET_HEIGHT, 18.0)
         .adding(WIDE_WIDGET_WIDTH, 100.)
         .adding(BORDERED_WIDGET_HEIGHT, 24.0)
         .adding(TEXTBOX_BORDER_RADIUS, 2.)
         .adding(TEXTBOX_BORDER_WIDTH, 1.)
         .adding(TEXTBOX_INSETS, Insets::new(4.0, 4.0, 4.0, 4.0))
-        .adding(SCROLLBAR_COLOR, Color::rgb8(0xff, 0xff, 0xff))
-        .adding(SCROLLBAR_BORDER_COLOR, Color::rgb8(0x77, 0x77, 0x77))
+        .adding(SCROLLBAR_COLOR, palette::SCROLLBAR)
+        .adding(SCROLLBAR_BORDER_COLOR, palette::SCROLLBAR_BORDER)
         .adding(SCROLLBAR_MAX_OPACITY, 0.7)
         .adding(SCROLLBAR_FADE_DELAY, 1500u64)
         .adding(SCROLLBAR_WIDTH, 8.)
@@ -156,3 +258,44 @@ pub(crate) fn add_to_env(env: Env) -> Env {
                 .with_size(15.0),
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KeyOrValue;
+
+    #[test]
+    fn with_alpha_endpoints() {
+        let color = palette::PRIMARY_DARK;
+        assert_eq!(color.with_alpha(0.0).as_rgba8(), (0x00, 0x8d, 0xdd, 0));
+        assert_eq!(color.with_alpha(1.0).as_rgba8(), (0x00, 0x8d, 0xdd, 0xff));
+        // Out-of-range alphas are clamped.
+        assert_eq!(color.with_alpha(-1.0), color.with_alpha(0.0));
+        assert_eq!(color.with_alpha(2.0), color);
+
+        assert_eq!(palette::with_alpha8(color, 0), color.with_alpha(0.0));
+        assert_eq!(palette::with_alpha8(color, 0xff), color);
+    }
+
+    #[test]
+    fn palette_channels() {
+        assert_eq!(
+            palette::WINDOW_BACKGROUND.as_rgba8(),
+            (0x29, 0x29, 0x29, 0xff)
+        );
+        assert_eq!(palette::TEXT.as_rgba8(), (0xf0, 0xf0, 0xea, 0xff));
+        assert_eq!(palette::PRIMARY_LIGHT.as_rgba8(), (0x5c, 0xc4, 0xff, 0xff));
+        assert_eq!(palette::SELECTION.as_rgba8(), (0x43, 0x70, 0xa8, 0xff));
+        assert_eq!(palette::BUTTON_DARK.as_rgba8(), (0, 0, 0, 0xff));
+
+        let env = Env::with_theme();
+        assert_eq!(env.get(TEXT_COLOR), palette::TEXT);
+        assert_eq!(env.get(SELECTED_TEXT_BACKGROUND_COLOR), palette::SELECTION);
+
+        let translucent: KeyOrValue<Color> = palette::TEXT.with_alpha(0.5).into();
+        assert_eq!(
+            translucent.resolve(&env).as_rgba8(),
+            (0xf0, 0xf0, 0xea, 0x80)
+        );
+    }
+}
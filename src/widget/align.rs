@@ -141,6 +141,10 @@ impl Widget for Align {
         self.child.paint(ctx, env);
     }
 
+    fn reset_interaction_state(&mut self) {
+        self.child.reset_interaction_state();
+    }
+
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
         smallvec![self.child.as_dyn()]
     }
@@ -8,10 +8,12 @@ use smallvec::SmallVec;
 use tracing::{trace, trace_span, Span};
 
 use crate::action::Action;
-use crate::widget::{Label, WidgetMut, WidgetPod, WidgetRef};
+use crate::gesture::{Gesture, GestureState};
+use crate::widget::{Label, LabelText, WidgetMut, WidgetPod, WidgetRef};
 use crate::{
-    theme, ArcStr, BoxConstraints, Env, Event, EventCtx, Insets, LayoutCtx, LifeCycle,
-    LifeCycleCtx, LinearGradient, PaintCtx, RenderContext, Size, StatusChange, UnitPoint, Widget,
+    theme, AccessCtx, ArcStr, BoxConstraints, Command, Env, Event, EventCtx, Insets, KeyOrValue,
+    LayoutCtx, LifeCycle, LifeCycleCtx, LinearGradient, PaintCtx, RenderContext, Role, Size,
+    StatusChange, UnitPoint, Widget,
 };
 
 // the minimum padding added to a button.
@@ -21,9 +23,13 @@ const LABEL_INSETS: Insets = Insets::uniform_xy(8., 2.);
 
 /// A button with a text label.
 ///
-/// Emits [`Action::ButtonPressed`] when pressed.
+/// Emits [`Action::ButtonPressed`] when clicked, and submits the command set with
+/// [`on_click`](Self::on_click), if any. A click is a [`Gesture::Tap`]: the press
+/// is cancelled if the pointer is dragged away or released outside the button.
 pub struct Button {
     label: WidgetPod<Label>,
+    gesture: GestureState,
+    on_click: Option<Command>,
 }
 
 crate::declare_widget!(ButtonMut, Button);
@@ -38,7 +44,7 @@ impl Button {
     ///
     /// let button = Button::new("Increment");
     /// ```
-    pub fn new(text: impl Into<ArcStr>) -> Button {
+    pub fn new(text: impl Into<LabelText>) -> Button {
         Button::from_label(Label::new(text))
     }
 
@@ -56,8 +62,27 @@ impl Button {
     pub fn from_label(label: Label) -> Button {
         Button {
             label: WidgetPod::new(label),
+            gesture: GestureState::new(),
+            on_click: None,
         }
     }
+
+    /// Builder-style method for setting the size of the label's text.
+    ///
+    /// The argument can be either an `f64` or a [`Key<f64>`](crate::Key).
+    pub fn with_text_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        // The label hasn't been added to the widget tree yet, so it can be rebuilt.
+        let label = std::mem::replace(self.label.widget_mut(), Label::empty());
+        self.label = WidgetPod::new_with_id(label.with_text_size(size), self.label.id());
+        self
+    }
+
+    /// Builder-style method for submitting `command` every time the button is
+    /// clicked, in addition to [`Action::ButtonPressed`].
+    pub fn on_click(mut self, command: impl Into<Command>) -> Self {
+        self.on_click = Some(command.into());
+        self
+    }
 }
 
 impl<'a, 'b> ButtonMut<'a, 'b> {
@@ -69,31 +94,38 @@ impl<'a, 'b> ButtonMut<'a, 'b> {
     pub fn label_mut(&mut self) -> WidgetMut<'_, 'b, Label> {
         self.ctx.get_mut(&mut self.widget.label)
     }
+
+    /// Set the command submitted when the button is clicked, or `None` to only
+    /// emit [`Action::ButtonPressed`].
+    pub fn set_on_click(&mut self, command: Option<Command>) {
+        self.widget.on_click = command;
+    }
 }
 
 impl Widget for Button {
     fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, _env: &Env) {
-        match event {
-            Event::MouseDown(_) => {
-                if !ctx.is_disabled() {
-                    ctx.set_active(true);
-                    ctx.request_paint();
-                    trace!("Button {:?} pressed", ctx.widget_id());
-                }
-            }
-            Event::MouseUp(_) => {
-                if ctx.is_active() && !ctx.is_disabled() {
-                    ctx.submit_action(Action::ButtonPressed);
-                    ctx.request_paint();
-                    trace!("Button {:?} released", ctx.widget_id());
-                }
-                ctx.set_active(false);
+        let was_pressed = self.gesture.is_pressed();
+        let gesture = self.gesture.on_mouse(ctx, event);
+        if self.gesture.is_pressed() != was_pressed {
+            ctx.request_paint();
+        }
+        if !was_pressed && self.gesture.is_pressed() {
+            trace!("Button {:?} pressed", ctx.widget_id());
+        }
+
+        if let Some(Gesture::Tap { .. }) = gesture {
+            ctx.submit_action(Action::ButtonPressed);
+            if let Some(command) = &self.on_click {
+                ctx.submit_command(command.clone());
             }
-            _ => (),
+            trace!("Button {:?} released", ctx.widget_id());
         }
     }
 
-    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        if let StatusChange::DisabledChanged(true) = event {
+            self.gesture.reset();
+        }
         ctx.request_paint();
     }
 
@@ -172,6 +204,11 @@ impl Widget for Button {
         self.label.paint(ctx, env);
     }
 
+    fn reset_interaction_state(&mut self) {
+        self.gesture.reset();
+        self.label.reset_interaction_state();
+    }
+
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
         SmallVec::new()
     }
@@ -180,6 +217,12 @@ impl Widget for Button {
         trace_span!("Button")
     }
 
+    fn accessibility(&self, ctx: &mut AccessCtx, env: &Env) {
+        ctx.set_role(Role::Button);
+        // The label isn't in `children`, so it isn't visited automatically.
+        self.label.accessibility(ctx, env);
+    }
+
     fn get_debug_text(&self) -> Option<String> {
         Some(self.label.as_ref().text().to_string())
     }
@@ -187,12 +230,16 @@ impl Widget for Button {
 
 #[cfg(test)]
 mod tests {
+    use druid_shell::MouseButton;
     use insta::assert_debug_snapshot;
 
     use super::*;
     use crate::assert_render_snapshot;
-    use crate::testing::{widget_ids, TestHarness, TestWidgetExt};
+    use crate::testing::{widget_ids, TestHarness};
     use crate::theme::PRIMARY_LIGHT;
+    use crate::widget::{Flex, IdentityWrapper, StoreInWidgetMut};
+    use crate::Selector;
+    use crate::WidgetExt as _;
 
     #[test]
     fn simple_button() {
@@ -213,6 +260,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn on_click() {
+        const CLICKED: Selector = Selector::new("masonry-test.button.clicked");
+
+        let [button_id] = widget_ids();
+        let button = Button::new("Hello").with_text_size(20.0).on_click(CLICKED);
+        let widget = Flex::column().with_child_id(button, button_id);
+
+        let mut harness = TestHarness::create(widget);
+        harness.start_recording_commands();
+        harness.mouse_click_on(button_id);
+
+        let clicks = harness
+            .take_recorded_commands()
+            .into_iter()
+            .filter(|command| command.selector == "masonry-test.button.clicked")
+            .count();
+        assert_eq!(clicks, 1);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ButtonPressed, button_id))
+        );
+        assert_eq!(harness.pop_action(), None);
+    }
+
     #[test]
     fn edit_button() {
         let image_1 = {
@@ -247,4 +319,36 @@ mod tests {
         // We don't use assert_eq because we don't want rich assert
         assert!(image_1 == image_2);
     }
+
+    #[test]
+    fn disabled_while_pressed() {
+        let [button_id] = widget_ids();
+        let widget = Button::new("Hello").with_id(button_id);
+        let mut harness = TestHarness::create(widget);
+
+        harness.mouse_move_to(button_id);
+        harness.mouse_button_press(MouseButton::Left);
+        assert!(harness.get_widget(button_id).state().is_active);
+
+        let set_disabled = |harness: &mut TestHarness, disabled: bool| {
+            harness.edit_root_widget(|mut wrapper, _| {
+                let mut wrapper = wrapper.downcast::<IdentityWrapper<Button>>().unwrap();
+                let mut button = wrapper.child_mut();
+                Button::get_ctx(&mut button.inner).set_disabled(disabled);
+            });
+        };
+        set_disabled(&mut harness, true);
+        harness.mouse_button_release(MouseButton::Left);
+
+        // The release doesn't click, but the button is no longer active.
+        assert!(!harness.get_widget(button_id).state().is_active);
+        assert_eq!(harness.pop_action(), None);
+
+        set_disabled(&mut harness, false);
+        harness.mouse_click_on(button_id);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::ButtonPressed, button_id))
+        );
+    }
 }
@@ -10,7 +10,7 @@ use tracing::{trace, trace_span, Span};
 use crate::action::Action;
 use crate::kurbo::{BezPath, Size};
 use crate::piet::{LineCap, LineJoin, LinearGradient, RenderContext, StrokeStyle, UnitPoint};
-use crate::widget::{Label, WidgetMut, WidgetRef};
+use crate::widget::{Label, LabelText, WidgetMut, WidgetRef};
 use crate::{
     theme, ArcStr, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx,
     PaintCtx, StatusChange, Widget, WidgetPod,
@@ -26,7 +26,7 @@ crate::declare_widget!(CheckboxMut, Checkbox);
 
 impl Checkbox {
     /// Create a new `Checkbox` with a text label.
-    pub fn new(checked: bool, text: impl Into<ArcStr>) -> Checkbox {
+    pub fn new(checked: bool, text: impl Into<LabelText>) -> Checkbox {
         Checkbox {
             checked,
             label: WidgetPod::new(Label::new(text)),
@@ -162,6 +162,10 @@ impl Widget for Checkbox {
         self.label.paint(ctx, env);
     }
 
+    fn reset_interaction_state(&mut self) {
+        self.label.reset_interaction_state();
+    }
+
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
         SmallVec::new()
     }
@@ -185,8 +189,9 @@ mod tests {
 
     use super::*;
     use crate::assert_render_snapshot;
-    use crate::testing::{widget_ids, TestHarness, TestWidgetExt};
+    use crate::testing::{widget_ids, TestHarness};
     use crate::theme::PRIMARY_LIGHT;
+    use crate::WidgetExt as _;
 
     #[test]
     fn simple_checkbox() {
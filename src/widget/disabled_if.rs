@@ -0,0 +1,182 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A widget that disables its child based on a predicate.
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+
+use crate::widget::{StoreInWidgetMut, WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point,
+    Size, StatusChange, Widget,
+};
+
+/// A widget that disables its child whenever a predicate on some data returns `true`.
+///
+/// The data is owned by this widget, and updated with [`DisabledIfMut::set_data`].
+/// When the predicate's result changes, the child is disabled or enabled, and it
+/// and its descendants receive [`LifeCycle::DisabledChanged`].
+///
+/// ## Nesting
+///
+/// A widget is disabled if it or any of its ancestors is disabled. Nested
+/// `DisabledIf` widgets therefore combine: if any of them disables its child,
+/// everything below it is disabled, regardless of what the inner ones decide.
+/// An inner `DisabledIf` can't re-enable a subtree disabled by an outer one.
+pub struct DisabledIf<T> {
+    child: WidgetPod<Box<dyn Widget>>,
+    data: T,
+    predicate: Box<dyn Fn(&T) -> bool>,
+}
+
+crate::declare_widget!(DisabledIfMut, DisabledIf<T: ('static)>);
+
+impl<T: 'static> DisabledIf<T> {
+    /// Create a new widget disabling `child` when `predicate(&data)` is `true`.
+    pub fn new(data: T, predicate: impl Fn(&T) -> bool + 'static, child: impl Widget) -> Self {
+        DisabledIf {
+            child: WidgetPod::new(child).boxed(),
+            data,
+            predicate: Box::new(predicate),
+        }
+    }
+
+    /// The data the predicate is evaluated on.
+    pub fn data(&self) -> &T {
+        &self.data
+    }
+
+    /// Returns `true` if the predicate currently disables the child.
+    pub fn disables_child(&self) -> bool {
+        (self.predicate)(&self.data)
+    }
+}
+
+impl<'a, 'b, T: 'static> DisabledIfMut<'a, 'b, T> {
+    /// Replace the data and re-evaluate the predicate.
+    pub fn set_data(&mut self, data: T) {
+        self.widget.data = data;
+        self.update_child();
+    }
+
+    /// Modify the data in place and re-evaluate the predicate.
+    pub fn update_data(&mut self, f: impl FnOnce(&mut T)) {
+        f(&mut self.widget.data);
+        self.update_child();
+    }
+
+    /// Get a [`WidgetMut`] to the child.
+    pub fn child_mut(&mut self) -> WidgetMut<'_, 'b, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+
+    fn update_child(&mut self) {
+        let disabled = self.widget.disables_child();
+        trace!("DisabledIf child disabled: {}", disabled);
+        let mut child = self.ctx.get_mut(&mut self.widget.child);
+        // The disabled state is propagated when the WidgetMut is dropped.
+        Box::<dyn Widget>::get_ctx(&mut child.inner).set_disabled(disabled);
+    }
+}
+
+impl<T: 'static> Widget for DisabledIf<T> {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.child.on_event(ctx, event, env);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.child.lifecycle(ctx, event, env);
+
+        if let LifeCycle::WidgetAdded = event {
+            let disabled = self.disables_child();
+            let mut child = ctx.get_mut(&mut self.child);
+            Box::<dyn Widget>::get_ctx(&mut child.inner).set_disabled(disabled);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, env);
+        ctx.place_child(&mut self.child, Point::ORIGIN, env);
+        ctx.set_paint_insets(self.child.paint_insets());
+        ctx.set_baseline_offset(self.child.baseline_offset());
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.child.paint(ctx, env);
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.child.reset_interaction_state();
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("DisabledIf")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::theme;
+    use crate::widget::Label;
+    use crate::WidgetExt as _;
+
+    fn set_data(harness: &mut TestHarness, data: bool) {
+        harness.edit_root_widget(|mut root, _| {
+            let mut root = root.downcast::<DisabledIf<bool>>().unwrap();
+            root.set_data(data);
+        });
+    }
+
+    #[test]
+    fn toggle_dims_label() {
+        let [label_id] = widget_ids();
+        let widget = DisabledIf::new(
+            false,
+            |disabled: &bool| *disabled,
+            Label::new("Hello").with_id(label_id),
+        );
+        let mut harness = TestHarness::create(widget);
+        let enabled_render = harness.render();
+        assert!(!harness.get_widget(label_id).state().is_disabled());
+
+        set_data(&mut harness, true);
+        assert!(harness.get_widget(label_id).state().is_disabled());
+        let dimmed =
+            TestHarness::create(Label::new("Hello").with_text_color(theme::DISABLED_TEXT_COLOR))
+                .render();
+        // We don't use assert_eq because we don't want rich assert
+        assert!(harness.render() == dimmed);
+
+        set_data(&mut harness, false);
+        assert!(!harness.get_widget(label_id).state().is_disabled());
+        assert!(harness.render() == enabled_render);
+    }
+
+    #[test]
+    fn outer_disable_wins() {
+        let [label_id] = widget_ids();
+        let inner = DisabledIf::new(
+            false,
+            |disabled: &bool| *disabled,
+            Label::new("Hello").with_id(label_id),
+        );
+        let widget = DisabledIf::new(true, |disabled: &bool| *disabled, inner);
+
+        let mut harness = TestHarness::create(widget);
+        assert!(harness.get_widget(label_id).state().is_disabled());
+
+        set_data(&mut harness, false);
+        assert!(!harness.get_widget(label_id).state().is_disabled());
+    }
+}
@@ -0,0 +1,143 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A widget that changes the environment of its child.
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace_span, Span};
+
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point,
+    Size, StatusChange, Widget,
+};
+
+/// A widget that changes the [`Env`] its child and descendants receive.
+///
+/// The function passed to [`new`](Self::new) is applied to a copy of the parent's
+/// environment every time the child is called, so the scope follows changes to
+/// the parent's environment. The parent and siblings are unaffected.
+///
+/// # Examples
+///
+/// ```
+/// use masonry::widget::{EnvScope, Label};
+/// use masonry::{theme, Color};
+///
+/// let green_label = EnvScope::new(
+///     |env| env.set(theme::TEXT_COLOR, Color::rgb8(0, 255, 0)),
+///     Label::new("Green"),
+/// );
+/// ```
+pub struct EnvScope {
+    child: WidgetPod<Box<dyn Widget>>,
+    f: Box<dyn Fn(&mut Env)>,
+}
+
+crate::declare_widget!(EnvScopeMut, EnvScope);
+
+impl EnvScope {
+    /// Create a widget that passes the parent's environment, modified by `f`, to `child`.
+    pub fn new(f: impl Fn(&mut Env) + 'static, child: impl Widget) -> Self {
+        EnvScope {
+            child: WidgetPod::new(child).boxed(),
+            f: Box::new(f),
+        }
+    }
+
+    fn child_env(&self, env: &Env) -> Env {
+        let mut env = env.clone();
+        (self.f)(&mut env);
+        env
+    }
+}
+
+impl<'a, 'b> EnvScopeMut<'a, 'b> {
+    /// Replace the function that modifies the child's environment.
+    pub fn set_env_fn(&mut self, f: impl Fn(&mut Env) + 'static) {
+        self.widget.f = Box::new(f);
+        self.ctx.request_layout();
+    }
+
+    /// Get a [`WidgetMut`] to the child.
+    pub fn child_mut(&mut self) -> WidgetMut<'_, 'b, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+impl Widget for EnvScope {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        let env = self.child_env(env);
+        self.child.on_event(ctx, event, &env);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        let env = self.child_env(env);
+        self.child.lifecycle(ctx, event, &env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let env = self.child_env(env);
+        let size = self.child.layout(ctx, bc, &env);
+        ctx.place_child(&mut self.child, Point::ORIGIN, &env);
+        ctx.set_paint_insets(self.child.paint_insets());
+        ctx.set_baseline_offset(self.child.baseline_offset());
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let env = self.child_env(env);
+        self.child.paint(ctx, &env);
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.child.reset_interaction_state();
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("EnvScope")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestHarness;
+    use crate::widget::{Flex, Label};
+    use crate::{theme, Color};
+
+    #[test]
+    fn text_color() {
+        let size = Size::new(100.0, 60.0);
+        let green = Color::rgb8(0, 255, 0);
+        let widget = Flex::column()
+            .with_child(EnvScope::new(
+                move |env| env.set(theme::TEXT_COLOR, green),
+                Label::new("Hello"),
+            ))
+            .with_child(Label::new("World"));
+        let mut harness = TestHarness::create_with_size(widget, size);
+
+        // Only the label in the scope is green.
+        let expected = Flex::column()
+            .with_child(Label::new("Hello").with_text_color(green))
+            .with_child(Label::new("World"));
+        let expected = TestHarness::create_with_size(expected, size).render();
+        // We don't use assert_eq because we don't want rich assert
+        assert!(harness.render() == expected);
+
+        let image = harness.render_to_pixels();
+        let has_green_glyphs = image
+            .pixel_colors()
+            .flatten()
+            .any(|color| color.as_rgba8() == (0, 255, 0, 255));
+        assert!(has_green_glyphs);
+    }
+}
@@ -0,0 +1,213 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A widget that animates changes to the environment of its child.
+
+use std::time::Duration;
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point,
+    Size, StatusChange, Widget,
+};
+
+/// A widget that replaces the [`Env`] of its child, and animates changes to it.
+///
+/// This can be used to fade between two themes, e.g. when switching from a
+/// light theme to a dark one. During a transition, the child receives an
+/// environment blended from the old and new ones (see [`Env::interpolate`]), and
+/// is laid out and painted again on every animation frame.
+///
+/// Until a first environment is set, the child receives the parent's environment.
+pub struct EnvTransition {
+    child: WidgetPod<Box<dyn Widget>>,
+    // The environment passed to the child, if it isn't the parent's.
+    current: Option<Env>,
+    transition: Option<Transition>,
+}
+
+struct Transition {
+    // `None` until the first animation frame if the transition started from the
+    // parent's environment.
+    from: Option<Env>,
+    to: Env,
+    duration: Duration,
+    elapsed: Duration,
+}
+
+crate::declare_widget!(EnvTransitionMut, EnvTransition);
+
+impl EnvTransition {
+    /// Create a new widget; its child receives the parent's environment.
+    pub fn new(child: impl Widget) -> Self {
+        EnvTransition {
+            child: WidgetPod::new(child).boxed(),
+            current: None,
+            transition: None,
+        }
+    }
+
+    /// Builder-style method for setting the initial environment of the child.
+    ///
+    /// This replaces the parent's environment entirely, so `env` should contain
+    /// every key the child needs.
+    pub fn with_env(mut self, env: Env) -> Self {
+        self.current = Some(env);
+        self
+    }
+
+    /// The environment currently passed to the child, or `None` if the child
+    /// receives the parent's environment.
+    pub fn current_env(&self) -> Option<&Env> {
+        self.current.as_ref()
+    }
+
+    /// Returns `true` while a transition is running.
+    pub fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
+}
+
+impl<'a, 'b> EnvTransitionMut<'a, 'b> {
+    /// Start animating the child's environment towards `env`, over `duration`.
+    ///
+    /// As with [`EnvTransition::with_env`], `env` replaces the parent's environment
+    /// entirely. If a transition is already running, the new one starts from
+    /// the current blended environment.
+    pub fn transition_to(&mut self, env: Env, duration: Duration) {
+        self.widget.transition = Some(Transition {
+            from: self.widget.current.clone(),
+            to: env,
+            duration,
+            elapsed: Duration::ZERO,
+        });
+        self.ctx.request_anim_frame();
+    }
+
+    /// Set the environment of the child immediately, without a transition.
+    pub fn set_env(&mut self, env: Env) {
+        self.widget.current = Some(env);
+        self.widget.transition = None;
+        self.ctx.request_layout();
+    }
+
+    /// Get a [`WidgetMut`] to the child.
+    pub fn child_mut(&mut self) -> WidgetMut<'_, 'b, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+impl EnvTransition {
+    fn child_env(&self, env: &Env) -> Env {
+        self.current.clone().unwrap_or_else(|| env.clone())
+    }
+}
+
+impl Widget for EnvTransition {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        if let (Event::AnimFrame(interval), Some(transition)) = (event, &mut self.transition) {
+            transition.elapsed += Duration::from_nanos(*interval);
+            let progress = if transition.duration.is_zero() {
+                1.0
+            } else {
+                (transition.elapsed.as_secs_f64() / transition.duration.as_secs_f64()).min(1.0)
+            };
+            trace!("Env transition progress: {}", progress);
+
+            let from = transition.from.get_or_insert_with(|| env.clone());
+            self.current = Some(from.interpolate(&transition.to, progress));
+            if progress < 1.0 {
+                ctx.request_anim_frame();
+            } else {
+                self.transition = None;
+            }
+            // Widgets re-resolve their env values during layout.
+            ctx.request_layout();
+        }
+
+        let env = self.child_env(env);
+        self.child.on_event(ctx, event, &env);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        let env = self.child_env(env);
+        self.child.lifecycle(ctx, event, &env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let env = self.child_env(env);
+        let size = self.child.layout(ctx, bc, &env);
+        ctx.place_child(&mut self.child, Point::ORIGIN, &env);
+        ctx.set_paint_insets(self.child.paint_insets());
+        ctx.set_baseline_offset(self.child.baseline_offset());
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let env = self.child_env(env);
+        self.child.paint(ctx, &env);
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.child.reset_interaction_state();
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("EnvTransition")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestHarness;
+    use crate::widget::Label;
+    use crate::{theme, Color};
+
+    #[test]
+    fn text_color_midpoint() {
+        let size = Size::new(100.0, 30.0);
+        let from = Env::with_theme().adding(theme::TEXT_COLOR, Color::BLACK);
+        let to = from.clone().adding(theme::TEXT_COLOR, Color::WHITE);
+        let widget = EnvTransition::new(Label::new("Hello")).with_env(from);
+        let mut harness = TestHarness::create_with_size(widget, size);
+
+        harness.edit_root_widget(|mut root, _| {
+            let mut root = root.downcast::<EnvTransition>().unwrap();
+            root.transition_to(to, Duration::from_millis(100));
+        });
+        harness.process_event(Event::AnimFrame(Duration::from_millis(50).as_nanos() as u64));
+
+        let midpoint = Color::rgb(0.5, 0.5, 0.5);
+        let root = harness.root_widget();
+        let root = root.downcast::<EnvTransition>().unwrap().deref();
+        assert!(root.is_transitioning());
+        assert_eq!(root.current_env().unwrap().get(theme::TEXT_COLOR), midpoint);
+
+        // The label was re-laid out with the blended color.
+        let expected =
+            TestHarness::create_with_size(Label::new("Hello").with_text_color(midpoint), size)
+                .render();
+        // We don't use assert_eq because we don't want rich assert
+        assert!(harness.render() == expected);
+
+        harness.process_event(Event::AnimFrame(Duration::from_millis(50).as_nanos() as u64));
+        let root = harness.root_widget();
+        let root = root.downcast::<EnvTransition>().unwrap().deref();
+        assert!(!root.is_transitioning());
+        assert_eq!(
+            root.current_env().unwrap().get(theme::TEXT_COLOR),
+            Color::WHITE
+        );
+    }
+}
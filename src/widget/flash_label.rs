@@ -0,0 +1,264 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A label that flashes its text when it changes.
+
+use std::time::Duration;
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+
+use crate::widget::{Label, LabelText, WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    theme, ArcStr, BoxConstraints, Color, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Point, Size, StatusChange, Value, ValueType, Widget,
+};
+
+/// A label that flashes its text when it changes.
+///
+/// Whenever the text changes, its color is set to the flash color, then animated
+/// back to the normal text color over the flash duration. This can be used to
+/// draw attention to a value that was just updated. Changing the text again
+/// during a flash restarts it.
+///
+/// Disabled labels don't flash.
+pub struct FlashLabel {
+    label: WidgetPod<Label>,
+    text_color: KeyOrValue<Color>,
+    flash_color: Color,
+    flash_duration: Duration,
+    // The text the label showed last time we checked, to tell when it changes.
+    shown_text: ArcStr,
+    // The time elapsed since the flash started, if one is running.
+    flash_elapsed: Option<Duration>,
+}
+
+crate::declare_widget!(FlashLabelMut, FlashLabel);
+
+impl FlashLabel {
+    /// Create a new label flashing in `color` for `duration` when its text changes.
+    pub fn new(text: impl Into<LabelText>, color: Color, duration: Duration) -> Self {
+        Self::from_label(Label::new(text), color, duration)
+    }
+
+    /// Create a new flashing label from the provided [`Label`].
+    ///
+    /// The label's text color is managed by this widget; set it with
+    /// [`with_text_color`](Self::with_text_color).
+    pub fn from_label(label: Label, color: Color, duration: Duration) -> Self {
+        FlashLabel {
+            shown_text: label.text(),
+            label: WidgetPod::new(label),
+            text_color: theme::TEXT_COLOR.into(),
+            flash_color: color,
+            flash_duration: duration,
+            flash_elapsed: None,
+        }
+    }
+
+    /// Builder-style method for setting the color the text settles to after a flash.
+    ///
+    /// The argument can be either a `Color` or a [`Key<Color>`].
+    ///
+    /// [`Key<Color>`]: crate::Key
+    pub fn with_text_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.text_color = color.into();
+        self
+    }
+
+    /// Returns `true` while the text is flashing after a change.
+    pub fn is_flashing(&self) -> bool {
+        self.flash_elapsed.is_some()
+    }
+
+    /// Returns `true` if the label's text changed since this was last called.
+    fn take_text_change(&mut self) -> bool {
+        let text = self.label.widget().text();
+        if text == self.shown_text {
+            return false;
+        }
+        self.shown_text = text;
+        true
+    }
+}
+
+impl<'a, 'b> FlashLabelMut<'a, 'b> {
+    /// Set the label's text, flashing it if it changed.
+    pub fn set_text(&mut self, new_text: impl Into<ArcStr>) {
+        self.label_mut().set_text(new_text.into());
+        if self.widget.take_text_change() && !self.ctx.is_disabled() {
+            self.widget.flash_elapsed = Some(Duration::ZERO);
+            let color = self.widget.flash_color;
+            self.label_mut().set_text_color(color);
+            self.ctx.request_anim_frame();
+        }
+    }
+
+    /// Set the color the text settles to after a flash.
+    ///
+    /// The argument can be either a `Color` or a [`Key<Color>`].
+    ///
+    /// [`Key<Color>`]: crate::Key
+    pub fn set_text_color(&mut self, color: impl Into<KeyOrValue<Color>>) {
+        self.widget.text_color = color.into();
+        if !self.widget.is_flashing() {
+            let color = self.widget.text_color.clone();
+            self.label_mut().set_text_color(color);
+        }
+    }
+
+    /// Set the highlight color and the duration of the flash.
+    ///
+    /// This takes effect starting with the next flash.
+    pub fn set_flash(&mut self, color: Color, duration: Duration) {
+        self.widget.flash_color = color;
+        self.widget.flash_duration = duration;
+    }
+
+    /// Get a [`WidgetMut`] to the label.
+    ///
+    /// The label's text color is managed by this widget. Text set through this
+    /// flashes on the next event the label receives.
+    pub fn label_mut(&mut self) -> WidgetMut<'_, 'b, Label> {
+        self.ctx.get_mut(&mut self.widget.label)
+    }
+}
+
+// Interpolates colors the same way `Env` transitions do.
+fn lerp_color(from: Color, to: Color, t: f64) -> Color {
+    let color = Value::Color(from).interpolate(&Value::Color(to), t);
+    Color::try_from_value(&color).unwrap()
+}
+
+impl Widget for FlashLabel {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.label.on_event(ctx, event, env);
+
+        // Catches text computed by the label itself, eg from a promise.
+        if self.take_text_change() && !ctx.is_disabled() {
+            self.flash_elapsed = Some(Duration::ZERO);
+            ctx.get_mut(&mut self.label)
+                .set_text_color(self.flash_color);
+            ctx.request_anim_frame();
+        } else if let Event::AnimFrame(interval) = event {
+            if let Some(elapsed) = &mut self.flash_elapsed {
+                *elapsed += Duration::from_nanos(*interval);
+                let progress = if self.flash_duration.is_zero() {
+                    1.0
+                } else {
+                    (elapsed.as_secs_f64() / self.flash_duration.as_secs_f64()).min(1.0)
+                };
+                trace!("Flash progress: {}", progress);
+
+                if progress < 1.0 && !ctx.is_disabled() {
+                    let base = self.text_color.resolve(env);
+                    ctx.get_mut(&mut self.label).set_text_color(lerp_color(
+                        self.flash_color,
+                        base,
+                        progress,
+                    ));
+                    ctx.request_anim_frame();
+                } else {
+                    self.flash_elapsed = None;
+                    ctx.get_mut(&mut self.label)
+                        .set_text_color(self.text_color.clone());
+                }
+            }
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.label.lifecycle(ctx, event, env);
+
+        if let LifeCycle::WidgetAdded = event {
+            ctx.get_mut(&mut self.label)
+                .set_text_color(self.text_color.clone());
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.label.layout(ctx, bc, env);
+        ctx.place_child(&mut self.label, Point::ORIGIN, env);
+        ctx.set_baseline_offset(self.label.baseline_offset());
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.label.paint(ctx, env);
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.label.reset_interaction_state();
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.label.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("FlashLabel")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestHarness;
+
+    #[test]
+    fn change_flash() {
+        const FRAME: Duration = Duration::from_millis(60);
+        fn is_flashing(harness: &TestHarness) -> bool {
+            let label = harness
+                .root_widget()
+                .downcast::<FlashLabel>()
+                .unwrap()
+                .deref();
+            label.is_flashing()
+        }
+
+        let size = Size::new(100.0, 30.0);
+        let label = FlashLabel::new("1.00", Color::rgb(0.0, 1.0, 0.0), FRAME * 2)
+            .with_text_color(Color::BLACK);
+        let mut harness = TestHarness::create_with_size(label, size);
+        assert!(!is_flashing(&harness));
+
+        harness.edit_root_widget(|mut root, _| {
+            let mut label = root.downcast::<FlashLabel>().unwrap();
+            label.set_text("1.05");
+        });
+        assert!(is_flashing(&harness));
+
+        let settled =
+            TestHarness::create_with_size(Label::new("1.05").with_text_color(Color::BLACK), size)
+                .render();
+        harness.process_event(Event::AnimFrame(FRAME.as_nanos() as u64));
+        assert!(is_flashing(&harness));
+        // We don't use assert_eq because we don't want rich assert
+        assert!(harness.render() != settled);
+
+        harness.process_event(Event::AnimFrame(FRAME.as_nanos() as u64));
+        assert!(!is_flashing(&harness));
+        assert!(harness.render() == settled);
+    }
+
+    #[test]
+    fn same_text_doesnt_flash() {
+        let label = FlashLabel::new("1.00", Color::WHITE, Duration::from_millis(100));
+        let mut harness = TestHarness::create(label);
+
+        harness.edit_root_widget(|mut root, _| {
+            let mut label = root.downcast::<FlashLabel>().unwrap();
+            label.set_text("1.00");
+        });
+        let label = harness
+            .root_widget()
+            .downcast::<FlashLabel>()
+            .unwrap()
+            .deref();
+        assert!(!label.is_flashing());
+    }
+}
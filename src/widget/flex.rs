@@ -694,7 +694,14 @@ impl Widget for Flex {
     }
 
     fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
-        for child in self.children.iter_mut().filter_map(|x| x.widget_mut()) {
+        let mut children: SmallVec<[_; 16]> = self
+            .children
+            .iter_mut()
+            .filter_map(|x| x.widget_mut())
+            .collect();
+        // The sort is stable, so children with the same z-order keep their logical order.
+        children.sort_by_key(|child| child.z_order());
+        for child in children {
             child.paint(ctx, env);
         }
 
@@ -708,6 +715,16 @@ impl Widget for Flex {
         }
     }
 
+    fn reset_interaction_state(&mut self) {
+        for child in self
+            .children
+            .iter_mut()
+            .filter_map(|child| child.widget_mut())
+        {
+            child.reset_interaction_state();
+        }
+    }
+
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
         self.children
             .iter()
@@ -1004,8 +1021,8 @@ impl Child {
 mod tests {
     use super::*;
     use crate::assert_render_snapshot;
-    use crate::testing::TestHarness;
-    use crate::widget::Label;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Label, SizedBox};
 
     #[test]
     #[allow(clippy::cognitive_complexity)]
@@ -1391,4 +1408,45 @@ mod tests {
 
         // TODO - test out-of-bounds access?
     }
+
+    #[test]
+    fn flex_distribution() {
+        let [fixed_id, one_id, two_id] = widget_ids();
+        let widget = Flex::row()
+            .with_child_id(SizedBox::empty().width(60.0), fixed_id)
+            .with_flex_child(
+                SizedBox::new_with_id(SizedBox::empty().expand_width(), one_id),
+                1.0,
+            )
+            .with_flex_child(
+                SizedBox::new_with_id(SizedBox::empty().expand_width(), two_id),
+                2.0,
+            );
+
+        let harness = TestHarness::create_with_size(widget, Size::new(300.0, 100.0));
+        let width = |id| harness.get_widget(id).state().layout_rect().width();
+        // The flex children share what the fixed child leaves, by their factors.
+        assert_eq!(width(fixed_id), 60.0);
+        assert_eq!(width(one_id), 80.0);
+        assert_eq!(width(two_id), 160.0);
+    }
+
+    #[test]
+    fn baseline_alignment() {
+        let [small_id, large_id] = widget_ids();
+        let widget = Flex::row()
+            .cross_axis_alignment(CrossAxisAlignment::Baseline)
+            .with_child_id(Label::new("Small").with_text_size(10.0), small_id)
+            .with_child_id(Label::new("Large").with_text_size(30.0), large_id);
+
+        let harness = TestHarness::create(widget);
+        let baseline = |id| {
+            let state = harness.get_widget(id).state();
+            state.window_layout_rect().y0 + state.first_baseline()
+        };
+        let small_height = harness.get_widget(small_id).state().layout_rect().height();
+        let large_height = harness.get_widget(large_id).state().layout_rect().height();
+        assert!(small_height < large_height);
+        assert!((baseline(small_id) - baseline(large_id)).abs() < 1e-6);
+    }
 }
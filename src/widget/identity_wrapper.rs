@@ -0,0 +1,90 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A widget that gives its child a known id.
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace_span, Span};
+
+use crate::widget::{StoreInWidgetMut, WidgetMut, WidgetRef};
+use crate::{
+    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point,
+    Size, StatusChange, Widget, WidgetId, WidgetPod,
+};
+
+/// A widget that wraps a child with a chosen [`WidgetId`].
+///
+/// The wrapper itself gets an automatic id and is otherwise invisible: it lays
+/// out its child with its own constraints and takes its size. Events, commands
+/// targeted at the id, [`children`](Widget::children) and
+/// [`TestHarness::get_widget`](crate::testing::TestHarness::get_widget) all see
+/// the child with the chosen id.
+///
+/// This is usually created with [`WidgetExt::with_id`](crate::widget::WidgetExt::with_id).
+pub struct IdentityWrapper<W: Widget> {
+    child: WidgetPod<W>,
+}
+
+crate::declare_widget!(IdentityWrapperMut, IdentityWrapper<W: (Widget)>);
+
+impl<W: Widget> IdentityWrapper<W> {
+    /// Wrap `child`, giving it the id `id`.
+    ///
+    /// As with any explicit id, `id` must not be used by another widget.
+    pub fn new(child: W, id: WidgetId) -> Self {
+        Self {
+            child: WidgetPod::new_with_id(child, id),
+        }
+    }
+
+    /// The id of the wrapped child.
+    pub fn child_id(&self) -> WidgetId {
+        self.child.id()
+    }
+}
+
+impl<'a, 'b, W: Widget> IdentityWrapperMut<'a, 'b, W> {
+    /// Get a [`WidgetMut`] to the child.
+    pub fn child_mut(&mut self) -> WidgetMut<'_, 'b, W>
+    where
+        W: StoreInWidgetMut,
+    {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+impl<W: Widget> Widget for IdentityWrapper<W> {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.child.on_event(ctx, event, env);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.child.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.child.layout(ctx, bc, env);
+        ctx.place_child(&mut self.child, Point::ORIGIN, env);
+        ctx.set_baseline_offset(self.child.baseline_offset());
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.child.paint(ctx, env);
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.child.reset_interaction_state();
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("IdentityWrapper")
+    }
+}
@@ -191,7 +191,7 @@ mod tests {
     use super::*;
     use crate::assert_render_snapshot;
     use crate::piet::ImageFormat;
-    use crate::testing::{widget_ids, TestHarness, TestWidgetExt};
+    use crate::testing::{widget_ids, TestHarness};
     use crate::theme::PRIMARY_LIGHT;
 
     /// Painting an empty image shouldn't crash.
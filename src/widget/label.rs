@@ -8,33 +8,139 @@
 // - set text
 // - set text attributes
 
-use druid_shell::Cursor;
+use std::ops::Range;
+
+use druid_shell::{Application, Cursor, HotKey, SysMods};
 use smallvec::SmallVec;
-use tracing::{trace, trace_span, Span};
+use tracing::{trace, trace_span, warn, Span};
 
-use crate::kurbo::Vec2;
-use crate::text::{FontDescriptor, TextAlignment, TextLayout};
+use crate::kurbo::Line;
+use crate::piet::{InterpolationMode, PietImage, PietText, TextLayout as _, TextStorage as _};
+use crate::promise::PromiseToken;
+use crate::text::{
+    grapheme_boundary_before, nearest_grapheme_boundary, BreakStrategy, DecorationSpan,
+    DecorationStyle, EmojiPresentation, FontDescriptor, FontStyle, InlineObject, LayoutMetrics,
+    RichText, Selection, ShapeCacheStats, TextAlignment, TextLayout,
+};
 use crate::widget::WidgetRef;
 use crate::{
-    ArcStr, BoxConstraints, Color, Data, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle,
-    LifeCycleCtx, PaintCtx, Point, RenderContext, Size, StatusChange, Widget,
+    command, theme, AccessCtx, Affine, ArcStr, BoxConstraints, Color, Command, ContextMenu, Data,
+    Env, Event, EventCtx, ImageBuf, Insets, KeyOrValue, LayoutCtx, LifeCycle, LifeCycleCtx,
+    MenuItem, PaintCtx, Point, Rect, RenderContext, Role, Size, StatusChange, Target, Vec2, Widget,
 };
 
-// added padding between the edges of the widget and the text.
-const LABEL_X_PADDING: f64 = 2.0;
+// appended to text cut short by `LineBreaking::WordWrapEllipsis`.
+const ELLIPSIS: &str = "\u{2026}";
+// How many layouts are tried when shrinking text to fit.
+const FIT_SEARCH_STEPS: usize = 8;
+// the size of ruby annotations relative to the label's text.
+const RUBY_SIZE_RATIO: f64 = 0.5;
+// The default smallest font size text is displayed at, in logical pixels.
+const DEFAULT_MIN_READABLE_FONT_SIZE: f64 = 6.0;
 
 /// A widget displaying non-editable text.
 pub struct Label {
-    current_text: ArcStr,
-    text_layout: TextLayout<ArcStr>,
-    line_break_mode: LineBreaking,
+    text: LabelText,
+    // shown when dynamic text fails to resolve.
+    fallback_text: ArcStr,
+    text_layout: TextLayout<RichText>,
+    // the text cut short with an ellipsis, painted instead of `text_layout`
+    // when it wraps to too many lines.
+    ellipsized_layout: Option<TextLayout<RichText>>,
+    // the generation of `text_layout` and the maximum number of lines that
+    // `ellipsized_layout` was computed for.
+    ellipsis_key: Option<(u64, usize)>,
+    placeholder_layout: TextLayout<ArcStr>,
+    // the font set with `set_font`, which the placeholder uses in italics.
+    // When unset, the placeholder uses `theme::UI_FONT_ITALIC`.
+    placeholder_font: Option<KeyOrValue<FontDescriptor>>,
+    ruby: Vec<RubyAnnotation>,
+    // where the text is drawn, in the label's coordinate space.
+    text_origin: Point,
+    // the metrics of the displayed text, from the last layout pass.
+    layout_metrics: LayoutMetrics,
+    // added padding between the left and right edges of the widget and the text.
+    x_padding: KeyOrValue<f64>,
+    // if set, the distance from the top of the widget to the first baseline.
+    first_baseline_to_top: Option<f64>,
+    // `None` uses `theme::LABEL_LINE_BREAKING`.
+    line_break_mode: Option<LineBreaking>,
+    fixed_wrap_width: Option<f64>,
+    // the smallest and largest width the label reports, whatever its text.
+    width_range: Option<(f64, f64)>,
+    fit_strategy: Option<FitStrategy>,
+    line_alignment_overrides: Vec<(usize, TextAlignment)>,
+    // the alignment set by the user, before it is mirrored for RTL layouts.
+    text_alignment: TextAlignment,
+    layout_direction: LayoutDirection,
+    content_role: ContentRole,
+    underline: bool,
+    strikethrough: bool,
+    decoration_style: DecorationStyle,
+    // relative to the text origin; computed during layout.
+    decoration_spans: Vec<(DecorationSpan, DecorationKind)>,
+    clip_overflow: bool,
+    clip_height: bool,
+    selection: Selection,
+    select_on_focus: bool,
+    selectable: bool,
+    pending_text: Option<Box<dyn FnOnce() -> ArcStr + Send>>,
+    text_promise: Option<PromiseToken<ArcStr>>,
+    is_overflowing: bool,
+    on_overflow: Option<Box<dyn FnMut(bool)>>,
+    on_measure: Option<Box<dyn FnMut(Size)>>,
+    min_readable_font_size: f64,
+    font_size_clamped: bool,
+    link_hit_tolerance: KeyOrValue<f64>,
+    // the range of the link the mouse was pressed on.
+    pressed_link: Option<Range<usize>>,
+    hit_area_insets: Insets,
+    // The width and color of the outline drawn around the glyphs.
+    outline: Option<(f64, Color)>,
+    // The outline of the displayed text, kept while the text doesn't change.
+    outline_cache: Option<OutlineCache>,
 
     disabled: bool,
     default_text_color: KeyOrValue<Color>,
 }
 
+// The outline of a label's text.
+struct OutlineCache {
+    // The generation of `text_layout` and the ellipsis key the outline was made
+    // for, and its color.
+    key: (u64, Option<(u64, usize)>, Color),
+    // A copy of the displayed layout in the outline color.
+    layout: TextLayout<RichText>,
+    // The outline rendered by drawing `layout` offset in every direction, with
+    // the rect it covers, the scale and text origin it was rendered at, and the
+    // line alignment overrides it was drawn with.
+    image: Option<(PietImage, Rect, f64, Point, Vec<(usize, TextAlignment)>)>,
+}
+
 crate::declare_widget!(LabelMut, Label);
 
+/// A ruby annotation, such as furigana, shown above a range of a label's text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RubyRun {
+    /// The byte range of the annotated ("base") text.
+    pub base: Range<usize>,
+    /// The annotation.
+    pub text: ArcStr,
+}
+
+struct RubyAnnotation {
+    run: RubyRun,
+    layout: TextLayout<ArcStr>,
+    // relative to the text origin; `None` if the base range isn't valid.
+    origin: Option<Point>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DecorationKind {
+    Underline,
+    Strikethrough,
+}
+
 /// Options for handling lines that are too wide for the label.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum LineBreaking {
@@ -44,40 +150,344 @@ pub enum LineBreaking {
     Clip,
     /// Lines overflow the label.
     Overflow,
+    /// Lines are broken at word boundaries, and text that takes more than
+    /// `max_lines` lines is cut short, with an ellipsis at the end of the last
+    /// line shown.
+    ///
+    /// The label is only as tall as the lines it shows.
+    WordWrapEllipsis {
+        /// The maximum number of lines shown; zero is treated as one.
+        max_lines: usize,
+    },
+}
+
+/// The semantics of a label's text, as reported to assistive technologies.
+///
+/// This doesn't change how the label looks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ContentRole {
+    /// The heading of a section, with a level from 1 to 6.
+    Heading(u8),
+    /// Text that names or describes another element, eg a text field.
+    #[default]
+    Label,
+    /// Text that stands on its own, eg a paragraph.
+    Static,
+}
+
+impl ContentRole {
+    fn assert_valid(self) {
+        if let ContentRole::Heading(level) = self {
+            assert!(
+                (1..=6).contains(&level),
+                "heading level must be between 1 and 6, got {level}"
+            );
+        }
+    }
+
+    fn role(self) -> Role {
+        match self {
+            ContentRole::Heading(level) => Role::Heading { level },
+            ContentRole::Label => Role::Label,
+            ContentRole::Static => Role::StaticText,
+        }
+    }
+}
+
+/// The direction of the layout a label is placed in.
+///
+/// This is distinct from the direction of the text itself, which is determined
+/// by its script: it controls which side of the label its padding and
+/// start-aligned text go to, so that a label matches the rest of a mirrored UI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LayoutDirection {
+    /// Start is on the left.
+    #[default]
+    Ltr,
+    /// Start is on the right.
+    Rtl,
+    /// Follows the direction of the text: right-to-left if its first strong
+    /// character, in the sense of the Unicode bidirectional algorithm, is RTL.
+    ///
+    /// This suits labels showing user content in an unknown script.
+    Auto,
+}
+
+/// How a label fits its text in the available space, by wrapping and then shrinking it.
+///
+/// The text is first wrapped at its normal size. If it takes more than
+/// `max_lines` lines, or is taller than the label's maximum height, the font
+/// size is reduced until the wrapped text fits both limits, but not below
+/// `min_font_size`. If the text still doesn't fit at `min_font_size`, it overflows.
+///
+/// Setting a fit strategy makes the label wrap at word boundaries, regardless
+/// of its [`LineBreaking`] mode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FitStrategy {
+    /// The maximum number of lines the text wraps to before it's shrunk.
+    pub max_lines: usize,
+    /// The smallest font size the text is shrunk to, before the [`Env`]'s font scale is applied.
+    pub min_font_size: f64,
+}
+
+/// The text of a [`Label`]: either a fixed string, or one computed from the [`Env`].
+///
+/// Labels are usually created from a string, or from a closure for dynamic text:
+///
+/// ```ignore
+/// let label = Label::new(|env: &Env| format!("Text size: {}", env.get(theme::TEXT_SIZE_NORMAL)));
+/// ```
+pub enum LabelText {
+    /// A string that doesn't change.
+    Static(ArcStr),
+    /// A string produced by a closure.
+    Dynamic(DynamicText),
+}
+
+/// Text computed from the [`Env`] by a closure, along with its last result.
+///
+/// The closure may fail (see [`fallible`](Self::fallible)), eg when a value it
+/// reads is missing from the `Env`; the label then shows its fallback text.
+pub struct DynamicText {
+    f: Box<dyn Fn(&Env) -> Result<ArcStr, String>>,
+    resolved: ArcStr,
+    // The error from the last resolution, if it failed, so that a persisting
+    // failure is only logged once.
+    error: Option<String>,
 }
 
 // --- METHODS ---
 
-impl Label {
-    /// Create a new label.
-    pub fn new(text: impl Into<ArcStr>) -> Self {
-        let current_text = text.into();
-        let mut text_layout = TextLayout::new();
-        text_layout.set_text(current_text.clone());
+impl LabelText {
+    /// Return the text currently displayed.
+    ///
+    /// For dynamic text, this is the result of the last call to [`resolve`](Self::resolve),
+    /// or an empty string if it hasn't been resolved yet.
+    pub fn display_text(&self) -> ArcStr {
+        match self {
+            LabelText::Static(text) => text.clone(),
+            LabelText::Dynamic(text) => text.resolved.clone(),
+        }
+    }
 
-        Self {
-            current_text,
-            text_layout,
-            line_break_mode: LineBreaking::Overflow,
-            disabled: false,
-            default_text_color: crate::theme::TEXT_COLOR.into(),
+    /// Call `f` with the text currently displayed, without cloning it.
+    pub fn with_display_text<V>(&self, f: impl FnOnce(&str) -> V) -> V {
+        match self {
+            LabelText::Static(text) => f(text),
+            LabelText::Dynamic(text) => f(&text.resolved),
+        }
+    }
+
+    /// Recompute dynamic text from `env`.
+    ///
+    /// Returns `true` if the displayed text changed. Static text never does.
+    ///
+    /// If the text can't be computed, it's replaced with an empty string; see
+    /// [`resolve_with_fallback`](Self::resolve_with_fallback).
+    pub fn resolve(&mut self, env: &Env) -> bool {
+        self.resolve_with_fallback(env, "")
+    }
+
+    /// Recompute dynamic text from `env`, displaying `fallback` if that fails.
+    ///
+    /// Returns `true` if the displayed text changed. A warning is logged when
+    /// resolution fails, but not again until it fails with a different error.
+    pub fn resolve_with_fallback(&mut self, env: &Env, fallback: &str) -> bool {
+        let text = match self {
+            LabelText::Static(_) => return false,
+            LabelText::Dynamic(text) => text,
+        };
+        match (text.f)(env) {
+            Ok(new_text) => {
+                text.error = None;
+                let changed = new_text != text.resolved;
+                text.resolved = new_text;
+                changed
+            }
+            Err(error) => {
+                if text.error.as_ref() != Some(&error) {
+                    warn!(
+                        "Failed to resolve label text ({}), showing {:?} instead",
+                        error, fallback
+                    );
+                    text.error = Some(error);
+                }
+                let changed = &*text.resolved != fallback;
+                if changed {
+                    text.resolved = fallback.into();
+                }
+                changed
+            }
+        }
+    }
+}
+
+impl DynamicText {
+    /// Create dynamic text from a closure.
+    pub fn new<R: Into<ArcStr>>(f: impl Fn(&Env) -> R + 'static) -> Self {
+        Self::fallible(move |env| Ok::<_, std::convert::Infallible>(f(env)))
+    }
+
+    /// Create dynamic text from a closure that may fail.
+    ///
+    /// When the closure returns an error, the label shows its fallback text
+    /// (see [`Label::with_fallback_text`]) and the error is logged.
+    pub fn fallible<R, E>(f: impl Fn(&Env) -> Result<R, E> + 'static) -> Self
+    where
+        R: Into<ArcStr>,
+        E: std::fmt::Display,
+    {
+        DynamicText {
+            f: Box::new(move |env| f(env).map(Into::into).map_err(|error| error.to_string())),
+            resolved: "".into(),
+            error: None,
         }
     }
+}
+
+impl From<&str> for LabelText {
+    fn from(text: &str) -> Self {
+        LabelText::Static(text.into())
+    }
+}
+
+impl From<String> for LabelText {
+    fn from(text: String) -> Self {
+        LabelText::Static(text.into())
+    }
+}
+
+impl From<ArcStr> for LabelText {
+    fn from(text: ArcStr) -> Self {
+        LabelText::Static(text)
+    }
+}
+
+impl From<DynamicText> for LabelText {
+    fn from(text: DynamicText) -> Self {
+        LabelText::Dynamic(text)
+    }
+}
+
+impl<F, R> From<F> for LabelText
+where
+    F: Fn(&Env) -> R + 'static,
+    R: Into<ArcStr>,
+{
+    fn from(f: F) -> Self {
+        LabelText::Dynamic(DynamicText::new(f))
+    }
+}
+
+impl Label {
+    /// Create a new label.
+    ///
+    /// `text` can be a string, or a closure computing the text from the [`Env`];
+    /// see [`LabelText`].
+    pub fn new(text: impl Into<LabelText>) -> Self {
+        let text = text.into();
+        let mut label = Self::empty();
+        label
+            .text_layout
+            .set_text(RichText::new(text.display_text()));
+        label.text = text;
+        label
+    }
 
     /// Create a label with empty text.
     pub fn empty() -> Self {
         Self {
-            current_text: "".into(),
+            text: LabelText::Static("".into()),
+            fallback_text: "".into(),
             text_layout: TextLayout::new(),
-            line_break_mode: LineBreaking::Overflow,
+            placeholder_layout: new_placeholder_layout(),
+            placeholder_font: None,
+            ruby: Vec::new(),
+            text_origin: Point::ORIGIN,
+            layout_metrics: LayoutMetrics::default(),
+            ellipsized_layout: None,
+            ellipsis_key: None,
+            line_break_mode: None,
+            fixed_wrap_width: None,
+            width_range: None,
+            fit_strategy: None,
+            line_alignment_overrides: Vec::new(),
+            text_alignment: TextAlignment::Start,
+            layout_direction: LayoutDirection::Ltr,
+            content_role: ContentRole::Label,
+            underline: false,
+            strikethrough: false,
+            decoration_style: DecorationStyle::default(),
+            decoration_spans: Vec::new(),
+            clip_overflow: false,
+            clip_height: false,
+            selection: Selection::caret(0),
+            select_on_focus: false,
+            selectable: false,
+            pending_text: None,
+            text_promise: None,
+            is_overflowing: false,
+            on_overflow: None,
+            on_measure: None,
+            min_readable_font_size: DEFAULT_MIN_READABLE_FONT_SIZE,
+            font_size_clamped: false,
+            link_hit_tolerance: theme::LINK_HIT_TOLERANCE.into(),
+            pressed_link: None,
+            x_padding: theme::LABEL_X_PADDING.into(),
+            first_baseline_to_top: None,
+            hit_area_insets: Insets::ZERO,
+            outline: None,
+            outline_cache: None,
             disabled: false,
             default_text_color: crate::theme::TEXT_COLOR.into(),
         }
     }
 
+    /// Create a label that shows `placeholder` until `compute_text` returns.
+    ///
+    /// `compute_text` is run on a background thread once the label is added to the
+    /// widget tree (see [`LifeCycleCtx::compute_in_background`]). Its result is
+    /// delivered on the UI thread as an [`Event::PromiseResult`], at which point
+    /// the label replaces the placeholder and requests a layout.
+    ///
+    /// If the label is removed from the tree, or its text is set explicitly before
+    /// the result arrives, the result is dropped.
+    pub fn pending(
+        placeholder: impl Into<LabelText>,
+        compute_text: impl FnOnce() -> ArcStr + Send + 'static,
+    ) -> Self {
+        let mut label = Self::new(placeholder);
+        label.pending_text = Some(Box::new(compute_text));
+        label
+    }
+
     /// Builder-style method for setting the text string.
-    pub fn with_text(mut self, new_text: impl Into<ArcStr>) -> Self {
-        self.text_layout.set_text(new_text.into());
+    pub fn with_text(mut self, new_text: impl Into<LabelText>) -> Self {
+        self.text = new_text.into();
+        self.text_layout
+            .set_text(RichText::new(self.text.display_text()));
+        self
+    }
+
+    /// Builder-style method for setting the text shown when dynamic text can't
+    /// be computed.
+    ///
+    /// This is empty by default. See [`DynamicText::fallible`].
+    pub fn with_fallback_text(mut self, text: impl Into<ArcStr>) -> Self {
+        self.fallback_text = text.into();
+        self
+    }
+
+    /// Builder-style method for setting text with styled ranges.
+    ///
+    /// The attributes of each range (eg [`Attribute::TextColor`] or
+    /// [`Attribute::Weight`]) take precedence over the label's own text color and font.
+    ///
+    /// [`Attribute::TextColor`]: crate::text::Attribute::TextColor
+    /// [`Attribute::Weight`]: crate::text::Attribute::Weight
+    pub fn with_attributed_text(mut self, text: RichText) -> Self {
+        self.text = LabelText::Static(text.text().clone());
+        self.text_layout.set_text(text);
         self
     }
 
@@ -101,6 +511,8 @@ impl Label {
     ///
     /// [`Key<f64>`]: ../struct.Key.html
     pub fn with_text_size(mut self, size: impl Into<KeyOrValue<f64>>) -> Self {
+        let size = size.into();
+        self.placeholder_layout.set_text_size(size.clone());
         self.text_layout.set_text_size(size);
         self
     }
@@ -114,295 +526,3488 @@ impl Label {
     ///
     /// [`Key<FontDescriptor>`]: ../struct.Key.html
     pub fn with_font(mut self, font: impl Into<KeyOrValue<FontDescriptor>>) -> Self {
+        let font = font.into();
+        self.placeholder_font = Some(font.clone());
         self.text_layout.set_font(font);
         self
     }
 
-    /// Builder-style method to set the [`LineBreaking`] behaviour.
-    pub fn with_line_break_mode(mut self, mode: LineBreaking) -> Self {
-        self.line_break_mode = mode;
+    /// Builder-style method to set how wrapped lines are broken.
+    ///
+    /// See [`LabelMut::set_break_strategy`] for details.
+    pub fn with_break_strategy(mut self, strategy: BreakStrategy) -> Self {
+        self.text_layout.set_break_strategy(strategy);
         self
     }
 
-    /// Builder-style method to set the [`TextAlignment`].
-    pub fn with_text_alignment(mut self, alignment: TextAlignment) -> Self {
-        self.text_layout.set_text_alignment(alignment);
+    /// Builder-style method for setting extra spacing between words.
+    ///
+    /// See [`LabelMut::set_word_spacing`] for details.
+    pub fn with_word_spacing(mut self, spacing: f64) -> Self {
+        self.text_layout.set_word_spacing(spacing);
         self
     }
 
-    /// Return the current value of the label's text.
-    pub fn text(&self) -> ArcStr {
-        self.current_text.clone()
+    /// Builder-style method for setting extra spacing between characters.
+    ///
+    /// See [`LabelMut::set_letter_spacing`] for details.
+    pub fn with_letter_spacing(mut self, spacing: f64) -> Self {
+        self.text_layout.set_letter_spacing(spacing);
+        self
     }
 
-    /// Return the offset of the first baseline relative to the bottom of the widget.
-    pub fn baseline_offset(&self) -> f64 {
-        let text_metrics = self.text_layout.layout_metrics();
-        text_metrics.size.height - text_metrics.first_baseline
+    /// Builder-style method for setting the height of each line, as a multiple
+    /// of the font size.
+    ///
+    /// See [`LabelMut::set_line_height`] for details.
+    pub fn with_line_height(mut self, line_height: f64) -> Self {
+        self.text_layout.set_line_height(Some(line_height));
+        self.placeholder_layout.set_line_height(Some(line_height));
+        self
     }
 
-    /// Draw this label's text at the provided `Point`, without internal padding.
+    /// Builder-style method for setting objects drawn inline with the text.
     ///
-    /// This is a convenience for widgets that want to use Label as a way
-    /// of managing a dynamic or localized string, but want finer control
-    /// over where the text is drawn.
-    pub fn draw_at(&self, ctx: &mut PaintCtx, origin: impl Into<Point>) {
-        self.text_layout.draw(ctx, origin)
+    /// See [`LabelMut::set_inline_objects`] for details.
+    pub fn with_inline_objects(mut self, objects: Vec<InlineObject>) -> Self {
+        self.text_layout.set_inline_objects(objects);
+        self
     }
-}
 
-impl LabelMut<'_, '_> {
-    /// Set the text.
-    pub fn set_text(&mut self, new_text: impl Into<ArcStr>) {
-        self.widget.text_layout.set_text(new_text.into());
-        self.ctx.request_layout();
+    /// Builder-style method to underline the text.
+    pub fn with_underline(mut self, underline: bool) -> Self {
+        self.underline = underline;
+        self
     }
 
-    /// Set the text color.
+    /// Builder-style method to strike through the text.
+    pub fn with_strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = strikethrough;
+        self
+    }
+
+    /// Builder-style method for setting how the underline and strikethrough are drawn.
     ///
-    /// The argument can be either a `Color` or a [`Key<Color>`].
-    /// [`Key<Color>`]: ../struct.Key.html
-    pub fn set_text_color(&mut self, color: impl Into<KeyOrValue<Color>>) {
-        let color = color.into();
-        if !self.widget.disabled {
-            self.widget.text_layout.set_text_color(color.clone());
-        }
-        self.widget.default_text_color = color;
-        self.ctx.request_layout();
+    /// See [`LabelMut::set_decoration_style`] for details.
+    pub fn with_decoration_style(mut self, style: DecorationStyle) -> Self {
+        self.decoration_style = style;
+        self
     }
 
-    /// Set the text size.
+    /// Builder-style method for setting ruby annotations.
     ///
-    /// The argument can be either an `f64` or a [`Key<f64>`].
+    /// See [`LabelMut::set_ruby`] for details.
+    pub fn with_ruby(mut self, runs: Vec<RubyRun>) -> Self {
+        self.ruby = Self::ruby_annotations(runs);
+        self
+    }
+
+    /// Builder-style method for setting the placeholder text.
     ///
-    /// [`Key<f64>`]: ../struct.Key.html
-    pub fn set_text_size(&mut self, size: impl Into<KeyOrValue<f64>>) {
-        self.widget.text_layout.set_text_size(size);
-        self.ctx.request_layout();
+    /// See [`LabelMut::set_placeholder`] for details.
+    pub fn with_placeholder(mut self, placeholder: impl Into<ArcStr>) -> Self {
+        self.placeholder_layout.set_text(placeholder.into());
+        self
     }
 
-    /// Set the font.
+    /// Builder-style method to set the [`LineBreaking`] behaviour.
     ///
-    /// The argument can be a [`FontDescriptor`] or a [`Key<FontDescriptor>`]
-    /// that refers to a font defined in the [`Env`].
+    /// Labels without one use [`theme::LABEL_LINE_BREAKING`].
+    pub fn with_line_break_mode(mut self, mode: LineBreaking) -> Self {
+        self.line_break_mode = Some(mode);
+        self
+    }
+
+    /// Builder-style method for wrapping the text at a fixed width, in pixels,
+    /// rather than at the width the label's parent allows.
     ///
-    /// [`Key<FontDescriptor>`]: ../struct.Key.html
-    pub fn set_font(&mut self, font: impl Into<KeyOrValue<FontDescriptor>>) {
-        self.widget.text_layout.set_font(font);
-        self.ctx.request_layout();
+    /// This applies whatever the [`LineBreaking`] mode is; the label is still
+    /// constrained by its parent, so text wider than the constraints overflows.
+    /// With `None`, the default, the wrap width depends on the line breaking mode.
+    pub fn with_fixed_wrap_width(mut self, width: Option<f64>) -> Self {
+        self.fixed_wrap_width = width;
+        self
     }
 
-    /// Set the [`LineBreaking`] behaviour.
-    pub fn set_line_break_mode(&mut self, mode: LineBreaking) {
-        self.widget.line_break_mode = mode;
-        self.ctx.request_layout();
+    /// Builder-style method for keeping the label's width between `min` and `max`.
+    ///
+    /// Labels whose text changes often otherwise change width with it, which
+    /// shifts everything laid out after them. With a range, text narrower than
+    /// `min` still takes up `min`, and text wraps (or is clipped, with
+    /// [`LineBreaking::Clip`]) at `max`. The parent's constraints still apply on
+    /// top of the range. If `min` is larger than `max`, `max` wins.
+    pub fn with_width_range(mut self, min: f64, max: f64) -> Self {
+        self.width_range = Some((min, max));
+        self
     }
 
-    /// Set the [`TextAlignment`] for this layout.
-    pub fn set_text_alignment(&mut self, alignment: TextAlignment) {
-        self.widget.text_layout.set_text_alignment(alignment);
-        self.ctx.request_layout();
+    /// Builder-style method for choosing whether characters like ☎ or ❤ are
+    /// drawn as text or as emoji.
+    ///
+    /// This only applies to characters without a variation selector; explicit
+    /// selectors in the text always win. The selectors are only added to the
+    /// text that is laid out: [`text`](Self::text), copied text and offsets
+    /// into the text are unaffected.
+    ///
+    /// The default is [`EmojiPresentation::Auto`], which leaves the choice to the font.
+    pub fn with_emoji_presentation(mut self, presentation: EmojiPresentation) -> Self {
+        self.text_layout.set_emoji_presentation(presentation);
+        self
     }
-}
 
-// --- TRAIT IMPLS ---
+    /// Builder-style method to clip overflowing text to the label's bounds.
+    ///
+    /// This only affects [`LineBreaking::Overflow`]: the label is still measured
+    /// with its natural, unwrapped width, but nothing is painted outside of the
+    /// layout rect its parent assigned it. Any clip set by ancestors still applies.
+    pub fn with_clip_overflow_to_parent(mut self, clip: bool) -> Self {
+        self.clip_overflow = clip;
+        self
+    }
 
-impl Widget for Label {
-    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, _env: &Env) {
-        match event {
-            Event::MouseUp(event) => {
-                // Account for the padding
-                let pos = event.pos - Vec2::new(LABEL_X_PADDING, 0.0);
-                if let Some(_link) = self.text_layout.link_for_pos(pos) {
-                    todo!();
-                    //ctx.submit_command(link.command.clone());
-                    // See issue #21
-                }
-            }
-            Event::MouseMove(event) => {
-                // Account for the padding
-                let pos = event.pos - Vec2::new(LABEL_X_PADDING, 0.0);
+    /// Builder-style method to clip text that is taller than the label.
+    ///
+    /// The label is as tall as its text, unless its height is constrained below it,
+    /// eg inside a fixed-height row. In that case the label reports the
+    /// constrained height, and by default its extra lines are painted below it.
+    /// With this set, they are clipped at the label's bottom edge instead.
+    ///
+    /// Only the height is clipped; use [`LineBreaking::Clip`] to clip the width too.
+    pub fn with_height_clip(mut self, clip: bool) -> Self {
+        self.clip_height = clip;
+        self
+    }
 
-                if self.text_layout.link_for_pos(pos).is_some() {
-                    ctx.set_cursor(&Cursor::Pointer);
-                } else {
-                    ctx.clear_cursor();
-                }
-            }
-            _ => {}
-        }
+    /// Builder-style method for setting the smallest font size the text is displayed at.
+    ///
+    /// See [`LabelMut::set_min_font_size_for_readability`] for details.
+    pub fn with_min_font_size_for_readability(mut self, size: f64) -> Self {
+        self.min_readable_font_size = size;
+        self
     }
 
-    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+    /// Builder-style method to set a callback fired when the text starts or stops
+    /// overflowing the label.
+    ///
+    /// See [`is_overflowing`](Self::is_overflowing) for details.
+    pub fn with_on_overflow(mut self, on_overflow: impl FnMut(bool) + 'static) -> Self {
+        self.on_overflow = Some(Box::new(on_overflow));
+        self
+    }
 
-    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _env: &Env) {
-        match event {
-            LifeCycle::DisabledChanged(disabled) => {
-                let color = if *disabled {
-                    KeyOrValue::Key(crate::theme::DISABLED_TEXT_COLOR)
-                } else {
-                    self.default_text_color.clone()
-                };
-                self.text_layout.set_text_color(color);
-                ctx.request_layout();
-            }
-            _ => {}
-        }
+    /// Builder-style method to set a callback fired with the label's size
+    /// every time it is laid out.
+    ///
+    /// See [`LabelMut::set_on_measure`] for details.
+    pub fn with_on_measure(mut self, on_measure: impl FnMut(Size) + 'static) -> Self {
+        self.on_measure = Some(Box::new(on_measure));
+        self
     }
 
-    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
-        let width = match self.line_break_mode {
-            LineBreaking::WordWrap => bc.max().width - LABEL_X_PADDING * 2.0,
-            _ => f64::INFINITY,
-        };
+    /// Builder-style method to set the [`TextAlignment`].
+    ///
+    /// [`TextAlignment::Justified`] only affects wrapped text: each line but the
+    /// last of a paragraph is stretched to the wrap width, which then becomes
+    /// the text's measured width.
+    pub fn with_text_alignment(mut self, alignment: TextAlignment) -> Self {
+        self.text_alignment = alignment;
+        self
+    }
 
-        self.text_layout.set_wrap_width(width);
-        self.text_layout.rebuild_if_needed(ctx.text(), env);
+    /// Builder-style method to set the [`LayoutDirection`].
+    ///
+    /// See [`LabelMut::set_layout_direction`] for details.
+    pub fn with_layout_direction(mut self, direction: LayoutDirection) -> Self {
+        self.layout_direction = direction;
+        self
+    }
 
-        let text_metrics = self.text_layout.layout_metrics();
-        ctx.set_baseline_offset(text_metrics.size.height - text_metrics.first_baseline);
-        let size = bc.constrain(Size::new(
-            text_metrics.size.width + 2. * LABEL_X_PADDING,
-            text_metrics.size.height,
-        ));
-        trace!("Computed size: {}", size);
-        size
+    /// Builder-style method to set the [`ContentRole`].
+    ///
+    /// See [`LabelMut::set_content_role`] for details.
+    pub fn with_content_role(mut self, role: ContentRole) -> Self {
+        role.assert_valid();
+        self.content_role = role;
+        self
     }
 
-    fn paint(&mut self, ctx: &mut PaintCtx, _env: &Env) {
-        let origin = Point::new(LABEL_X_PADDING, 0.0);
-        let label_size = ctx.size();
+    /// Builder-style method for wrapping and then shrinking the text to fit.
+    ///
+    /// See [`FitStrategy`] for details.
+    pub fn with_fit_strategy(mut self, fit: FitStrategy) -> Self {
+        self.fit_strategy = Some(fit);
+        self
+    }
 
-        if self.line_break_mode == LineBreaking::Clip {
-            ctx.clip(label_size.to_rect());
-        }
-        self.draw_at(ctx, origin)
+    /// Builder-style method to override the alignment of specific visual lines.
+    ///
+    /// See [`LabelMut::set_line_alignment_overrides`] for details.
+    pub fn with_line_alignment_overrides(mut self, overrides: Vec<(usize, TextAlignment)>) -> Self {
+        self.line_alignment_overrides = overrides;
+        self
     }
 
-    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
-        SmallVec::new()
+    /// Builder-style method for setting how far from a link the pointer may be and
+    /// still hit it.
+    ///
+    /// The argument can be either an `f64` or a [`Key<f64>`]; it defaults to
+    /// [`theme::LINK_HIT_TOLERANCE`]. See [`TextLayout::link_for_pos_with_tolerance`]
+    /// for how overlapping links are resolved.
+    ///
+    /// [`Key<f64>`]: crate::Key
+    pub fn with_link_hit_tolerance(mut self, tolerance: impl Into<KeyOrValue<f64>>) -> Self {
+        self.link_hit_tolerance = tolerance.into();
+        self
     }
 
-    fn make_trace_span(&self) -> Span {
-        trace_span!("Label")
+    /// Builder-style method for setting the padding between the left and right
+    /// edges of the label and its text.
+    ///
+    /// The argument can be either an `f64` or a [`Key<f64>`]; it defaults to
+    /// [`theme::LABEL_X_PADDING`], so that labels follow the theme until they're
+    /// given their own padding.
+    ///
+    /// [`Key<f64>`]: crate::Key
+    pub fn with_padding(mut self, padding: impl Into<KeyOrValue<f64>>) -> Self {
+        self.x_padding = padding.into();
+        self
     }
 
-    fn get_debug_text(&self) -> Option<String> {
-        Some(self.current_text.to_string())
+    /// Builder-style method for placing the first baseline at a fixed distance
+    /// from the top of the label.
+    ///
+    /// By default, the text sits at the top of the label, so the first baseline
+    /// is as far down as the ascent of the first line. With this set, the label
+    /// adds padding above the text so that the baseline is `offset` pixels from
+    /// its top edge; the padding counts towards the label's height, and the
+    /// baselines it reports take it into account.
+    ///
+    /// If `offset` is smaller than the natural position of the baseline (including
+    /// room for ruby annotations), no padding is added.
+    pub fn with_first_baseline_to_top(mut self, offset: f64) -> Self {
+        self.first_baseline_to_top = Some(offset);
+        self
     }
-}
 
-impl Data for LineBreaking {
-    fn same(&self, other: &Self) -> bool {
-        self == other
+    /// Builder-style method for extending the area in which the label receives
+    /// pointer events past its layout rect.
+    ///
+    /// This makes small labels (eg a link on its own) easier to hit. See
+    /// [`Widget::allow_pointer_events_outside_bounds`] for how the extended area
+    /// interacts with other widgets.
+    pub fn with_hit_area_insets(mut self, insets: impl Into<Insets>) -> Self {
+        self.hit_area_insets = insets.into();
+        self
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::piet::FontFamily;
+    /// Builder-style method for drawing an outline of `width` around the glyphs.
+    ///
+    /// The outline is drawn in `color` below the text, which is still filled
+    /// with the text color; use a transparent text color for hollow text. The
+    /// outline keeps its color when the label is disabled, and isn't drawn
+    /// around the placeholder, decorations or ruby annotations.
+    ///
+    /// The label reports paint insets of `width`, so that the outline isn't clipped
+    /// by its parent. It is still clipped if the label clips its own text.
+    pub fn with_text_outline(mut self, width: f64, color: Color) -> Self {
+        self.outline = Some((width.max(0.0), color));
+        self
+    }
+
+    /// Builder-style method to select the whole text when the label gains focus.
+    ///
+    /// Setting this makes the label focusable. When it loses focus, the selection
+    /// collapses to a caret at its active end. An empty label has nothing to
+    /// select, so its selection stays an empty caret.
+    pub fn with_selection_on_focus(mut self, select_on_focus: bool) -> Self {
+        self.select_on_focus = select_on_focus;
+        self
+    }
+
+    /// Builder-style method to let the user select text with the mouse.
+    ///
+    /// Dragging over a selectable label selects the text under the pointer, and
+    /// Ctrl+C (Cmd+C on macOS) copies it to the clipboard. Setting this makes the
+    /// label focusable, since keyboard shortcuts go to the focused widget.
+    pub fn with_selectable(mut self, selectable: bool) -> Self {
+        self.selectable = selectable;
+        self
+    }
+
+    /// Return the text currently displayed by the label.
+    ///
+    /// This is the string held by the label's text layout, without its attributes.
+    /// Dynamic text (see [`LabelText`]) is only updated when the label is laid out.
+    pub fn text(&self) -> ArcStr {
+        self.text_layout
+            .text()
+            .map_or_else(|| "".into(), |text| text.text().clone())
+    }
+
+    fn text_str(&self) -> &str {
+        self.text_layout.text().map_or("", |text| text.as_str())
+    }
+
+    /// Return `true` if the text didn't fit in the label during the last layout pass.
+    ///
+    /// Text overflows when it's wider or taller than the size the label was given,
+    /// which means it is either clipped or painted outside of the label, depending
+    /// on the [`LineBreaking`] mode.
+    ///
+    /// The callback set with [`with_on_overflow`](Self::with_on_overflow) is called
+    /// from `layout` whenever this value changes, with the new value.
+    pub fn is_overflowing(&self) -> bool {
+        self.is_overflowing
+    }
+
+    /// Return `true` if the text was enlarged to the minimum readable font size
+    /// during the last layout pass.
+    ///
+    /// See [`LabelMut::set_min_font_size_for_readability`] for details.
+    pub fn is_font_size_clamped(&self) -> bool {
+        self.font_size_clamped
+    }
+
+    /// Return the currently selected range of text.
+    ///
+    /// If nothing is selected, this is a caret.
+    pub fn selection(&self) -> Selection {
+        self.selection
+    }
+
+    /// Return the selected text, if any.
+    ///
+    /// This is the text the `COPY` command puts on the clipboard.
+    pub fn selected_text(&self) -> Option<&str> {
+        self.text_str()
+            .get(self.selection.range())
+            .filter(|text| !text.is_empty())
+    }
+
+    // The grapheme boundary nearest to `pos`, in the text's coordinate space.
+    fn text_offset_for_point(&self, pos: Point) -> usize {
+        let offset = self.text_layout.text_position_for_point(pos);
+        nearest_grapheme_boundary(self.text_str(), offset)
+    }
+
+    /// Return the size and baselines of the text, as measured in the last layout pass.
+    ///
+    /// The metrics are relative to the text itself, without the label's padding;
+    /// this is what a widget drawing the text with [`draw_at`](Self::draw_at)
+    /// needs to align it. They're all zero before the label is laid out.
+    pub fn layout_metrics(&self) -> LayoutMetrics {
+        self.layout_metrics
+    }
+
+    /// Return the offset of the first baseline relative to the bottom of the widget.
+    pub fn baseline_offset(&self) -> f64 {
+        let text_metrics = self.text_layout.layout_metrics();
+        text_metrics.size.height - text_metrics.first_baseline
+    }
+
+    /// Returns the boxes covering a range of the text, one per line, in the label's
+    /// coordinate space.
+    ///
+    /// The first and last boxes only cover the part of their line that is in
+    /// the range. The range is clamped to the text and extended to whole grapheme
+    /// clusters. This is only meaningful after the label has been laid out.
+    pub fn text_range_rects(&self, range: Range<usize>) -> SmallVec<[Rect; 4]> {
+        let len = self.text_layout.text_len();
+        let end = range.end.min(len);
+        let range = range.start.min(end)..end;
+        let offset = self.text_origin.to_vec2();
+        self.text_layout
+            .rects_for_range(range)
+            .into_iter()
+            .map(|rect| rect + offset)
+            .collect()
+    }
+
+    /// Returns the box of the inline object at `index`, in the label's coordinate space.
+    ///
+    /// Returns `None` if there is no such object or if its range isn't valid for
+    /// the text.
+    pub fn inline_object_rect(&self, index: usize) -> Option<Rect> {
+        let rect = self.text_layout.inline_object_rect(index)?;
+        Some(rect + self.text_origin.to_vec2())
+    }
+
+    /// Returns how often the label's text layouts were rebuilt.
+    ///
+    /// This sums the counters of the text, the text cut short with an ellipsis,
+    /// the placeholder, and the ruby annotations. See [`ShapeCacheStats`] for
+    /// details.
+    pub fn shape_cache_stats(&self) -> ShapeCacheStats {
+        self.text_layout.shape_cache_stats()
+            + self
+                .ellipsized_layout
+                .as_ref()
+                .map_or(ShapeCacheStats::default(), |layout| {
+                    layout.shape_cache_stats()
+                })
+            + self.placeholder_layout.shape_cache_stats()
+            + self
+                .ruby
+                .iter()
+                .map(|annotation| annotation.layout.shape_cache_stats())
+                .sum()
+    }
+
+    fn text_color(&self) -> KeyOrValue<Color> {
+        if self.disabled {
+            KeyOrValue::Key(crate::theme::DISABLED_TEXT_COLOR)
+        } else {
+            self.default_text_color.clone()
+        }
+    }
+
+    fn ruby_annotations(runs: Vec<RubyRun>) -> Vec<RubyAnnotation> {
+        runs.into_iter()
+            .map(|run| {
+                let mut layout = TextLayout::new();
+                layout.set_text(run.text.clone());
+                RubyAnnotation {
+                    run,
+                    layout,
+                    origin: None,
+                }
+            })
+            .collect()
+    }
+
+    /// Finds the largest text size scale for which the text fits, as described
+    /// in [`FitStrategy`], and rebuilds the layout with it.
+    fn fit_text(&mut self, factory: &mut PietText, fit: FitStrategy, max_height: f64, env: &Env) {
+        let mut fits_at = |layout: &mut TextLayout<RichText>, scale: f64| {
+            layout.set_text_size_scale(scale);
+            layout.rebuild_if_needed(factory, env);
+            let line_count = layout.layout().map_or(0, |layout| layout.line_count());
+            // 1e-4 is an arbitrary small-enough value to ignore rounding errors
+            line_count <= fit.max_lines && layout.size().height <= max_height + 1e-4
+        };
+
+        if fits_at(&mut self.text_layout, 1.0) {
+            return;
+        }
+        let base_size = self.text_layout.text_size(env);
+        let min_scale = if base_size > 0.0 {
+            (fit.min_font_size / base_size).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        if !fits_at(&mut self.text_layout, min_scale) {
+            return;
+        }
+
+        // Binary search between a scale that fits and one that doesn't.
+        let (mut fitting, mut overflowing) = (min_scale, 1.0);
+        for _ in 0..FIT_SEARCH_STEPS {
+            let scale = (fitting + overflowing) / 2.0;
+            if fits_at(&mut self.text_layout, scale) {
+                fitting = scale;
+            } else {
+                overflowing = scale;
+            }
+        }
+        fits_at(&mut self.text_layout, fitting);
+    }
+
+    /// Sets `ellipsized_layout` to the text cut short with an ellipsis if it
+    /// takes more than `max_lines` lines, or to `None` if it doesn't.
+    ///
+    /// The result is kept until `text_layout` is rebuilt, e.g. because its text,
+    /// wrap width or style changed.
+    fn ellipsize(&mut self, factory: &mut PietText, max_lines: usize, env: &Env) {
+        let max_lines = max_lines.max(1);
+        let key = (self.text_layout.generation(), max_lines);
+        if self.ellipsis_key == Some(key) {
+            return;
+        }
+        self.ellipsis_key = Some(key);
+        self.ellipsized_layout = None;
+        if self.text_layout.line_count() <= max_lines {
+            return;
+        }
+        let Some(last_line) = self.text_layout.line_range(max_lines - 1) else {
+            return;
+        };
+        let Some(rich_text) = self.text_layout.text().cloned() else {
+            return;
+        };
+
+        // The places the text can be cut at, up to the end of the last line,
+        // without leaving whitespace before the ellipsis.
+        let text = rich_text.as_str();
+        let mut ends = Vec::new();
+        let mut end = text[..last_line.end].trim_end().len();
+        while end > 0 {
+            ends.push(end);
+            end = text[..grapheme_boundary_before(text, end - 1)]
+                .trim_end()
+                .len();
+        }
+        ends.push(0);
+        ends.reverse();
+
+        let mut layout = self.text_layout.clone();
+        layout.reset_shape_cache_stats();
+        let mut fits = |layout: &mut TextLayout<RichText>, end: usize| {
+            layout.set_text(rich_text.truncated(end, ELLIPSIS));
+            layout.rebuild_if_needed(factory, env);
+            layout.layout().map_or(0, |layout| layout.line_count()) <= max_lines
+        };
+
+        // Binary search for the longest text that fits with the ellipsis. Only
+        // the ellipsis is shown if nothing fits.
+        let (mut fitting, mut overflowing) = (0, ends.len());
+        let mut last_tried = None;
+        while overflowing - fitting > 1 {
+            let mid = (fitting + overflowing) / 2;
+            last_tried = Some(mid);
+            if fits(&mut layout, ends[mid]) {
+                fitting = mid;
+            } else {
+                overflowing = mid;
+            }
+        }
+        if last_tried != Some(fitting) {
+            fits(&mut layout, ends[fitting]);
+        }
+        self.ellipsized_layout = Some(layout);
+    }
+
+    // The label's line breaking mode, or the theme's if it doesn't have one.
+    fn line_break_mode(&self, env: &Env) -> LineBreaking {
+        self.line_break_mode
+            .unwrap_or_else(|| *env.get(theme::LABEL_LINE_BREAKING))
+    }
+
+    // The layout that is painted: the text, or the text cut short with an ellipsis.
+    fn displayed_layout(&self) -> &TextLayout<RichText> {
+        self.ellipsized_layout.as_ref().unwrap_or(&self.text_layout)
+    }
+
+    fn has_links(&self) -> bool {
+        self.displayed_layout()
+            .text()
+            .map_or(false, |text| !text.links().is_empty())
+    }
+
+    /// The lines drawn under the links of the displayed text, one per line of
+    /// text each link is on, relative to the text origin.
+    fn link_underlines(&self) -> Vec<Line> {
+        let text_layout = self.displayed_layout();
+        let Some(text) = text_layout.text() else {
+            return Vec::new();
+        };
+        let lines: Vec<_> = (0..text_layout.line_count())
+            .filter_map(|i| text_layout.line_range(i))
+            .collect();
+        text.links()
+            .iter()
+            .flat_map(|link| {
+                lines.iter().filter_map(move |line| {
+                    let start = link.range.start.max(line.start);
+                    let end = link.range.end.min(line.end);
+                    (start < end).then(|| start..end)
+                })
+            })
+            .map(|range| text_layout.underline_for_range(range))
+            .collect()
+    }
+
+    /// Returns the text size scale to use instead of `scale`, so that the text
+    /// isn't smaller than the minimum readable font size.
+    ///
+    /// Warns the first time the text is enlarged.
+    fn readable_text_size_scale(&mut self, scale: f64, env: &Env) -> f64 {
+        let font_scale = env.get(theme::FONT_SCALE) * env.get(theme::TEXT_SCALE);
+        let unscaled_size = self.text_layout.text_size(env) * font_scale;
+        let font_size = unscaled_size * scale;
+
+        let clamped = font_size > 0.0 && font_size < self.min_readable_font_size;
+        if clamped && !self.font_size_clamped {
+            warn!(
+                "Label text '{}' at font size {:.2} is clamped to the minimum readable size {}; it may overflow.",
+                self.text_str(), font_size, self.min_readable_font_size,
+            );
+        }
+        self.font_size_clamped = clamped;
+        if clamped {
+            self.min_readable_font_size / unscaled_size
+        } else {
+            scale
+        }
+    }
+
+    fn compute_decoration_spans(&mut self, env: &Env) {
+        self.decoration_spans.clear();
+        if !self.underline && !self.strikethrough {
+            return;
+        }
+        let text_layout = self.ellipsized_layout.as_ref().unwrap_or(&self.text_layout);
+        let Some(layout) = text_layout.layout() else {
+            return;
+        };
+
+        let font_scale = env.get(theme::FONT_SCALE) * env.get(theme::TEXT_SCALE);
+        let font_size = text_layout.text_size(env) * text_layout.text_size_scale() * font_scale;
+        let thickness = self.decoration_style.resolved_thickness(font_size);
+        let offset = self.decoration_style.offset.unwrap_or(0.0);
+
+        for (i, line) in (0..layout.line_count()).filter_map(|i| Some((i, layout.line_metric(i)?)))
+        {
+            let Some(range) = text_layout.line_range(i) else {
+                continue;
+            };
+            if range.is_empty() {
+                continue;
+            }
+            let rects = text_layout.rects_for_range(range);
+            let (Some(first), Some(last)) = (rects.first(), rects.last()) else {
+                continue;
+            };
+            let baseline = line.y_offset + line.baseline + text_layout.line_offset(i);
+            let descent = line.height - line.baseline;
+            let span = |y: f64| DecorationSpan {
+                x0: first.x0.min(last.x0),
+                x1: first.x1.max(last.x1),
+                y: y + offset,
+                thickness,
+            };
+            if self.underline {
+                let y = baseline + (descent / 2.0).max(thickness);
+                self.decoration_spans
+                    .push((span(y), DecorationKind::Underline));
+            }
+            if self.strikethrough {
+                let y = baseline - font_size * 0.3;
+                self.decoration_spans
+                    .push((span(y), DecorationKind::Strikethrough));
+            }
+        }
+    }
+
+    fn shows_placeholder(&self) -> bool {
+        self.text_layout.text_len() == 0 && self.placeholder_layout.text_len() != 0
+    }
+
+    // The direction the label is laid out in, with `Auto` resolved from the text.
+    fn resolved_direction(&self) -> LayoutDirection {
+        match self.layout_direction {
+            LayoutDirection::Auto if self.text_layout.text_is_rtl() => LayoutDirection::Rtl,
+            LayoutDirection::Auto => LayoutDirection::Ltr,
+            direction => direction,
+        }
+    }
+
+    // Edit commands are only for us if we're focused or they target us explicitly,
+    // so that eg a global `COPY` doesn't copy from every label in the window.
+    fn is_command_target(&self, ctx: &EventCtx, cmd: &Command) -> bool {
+        (self.select_on_focus || self.selectable)
+            && (ctx.is_focused() || cmd.target() == Target::Widget(ctx.widget_id()))
+    }
+
+    /// Draw this label's text at the provided `Point`, without internal padding.
+    ///
+    /// This is a convenience for widgets that want to use Label as a way
+    /// of managing a dynamic or localized string, but want finer control
+    /// over where the text is drawn.
+    pub fn draw_at(&self, ctx: &mut PaintCtx, origin: impl Into<Point>) {
+        self.displayed_layout().draw(ctx, origin)
+    }
+
+    /// Draw this label's text so that its first baseline sits at `baseline_origin`.
+    ///
+    /// This is [`draw_at`](Self::draw_at), moved up by the distance from the top
+    /// of the text to its first baseline, and is meant for aligning text with
+    /// icons or other text. It has the same relationship to the text's top as
+    /// [`baseline_offset`](Self::baseline_offset) has to its bottom: neither
+    /// includes the label's padding.
+    pub fn draw_at_baseline(&self, ctx: &mut PaintCtx, baseline_origin: impl Into<Point>) {
+        let first_baseline = self.displayed_layout().layout_metrics().first_baseline;
+        let origin = baseline_origin.into() - Vec2::new(0.0, first_baseline);
+        self.draw_at(ctx, origin);
+    }
+
+    /// Draw this label's text on a rounded background, like a tag or a chip,
+    /// and return the background's rect.
+    ///
+    /// The background's top-left corner is at `origin`. It is the size of the
+    /// text as measured in the last layout (see [`layout_metrics`](Self::layout_metrics)),
+    /// plus `padding`, and its corners are rounded with `radius`. The text is drawn
+    /// inside the padding with [`draw_at`](Self::draw_at), so the label's own
+    /// padding isn't included.
+    pub fn draw_pill(
+        &self,
+        ctx: &mut PaintCtx,
+        origin: impl Into<Point>,
+        background: Color,
+        radius: f64,
+        padding: impl Into<Insets>,
+    ) -> Rect {
+        let padding = padding.into();
+        let text_origin = origin.into() + Vec2::new(padding.x0, padding.y0);
+        let text_rect = self.layout_metrics().size.to_rect() + text_origin.to_vec2();
+        let pill = text_rect + padding;
+        ctx.fill(pill.to_rounded_rect(radius.max(0.0)), &background);
+        self.draw_at(ctx, text_origin);
+        pill
+    }
+}
+
+impl LabelMut<'_, '_> {
+    /// Set the text.
+    ///
+    /// This replaces dynamic text, if the label had any. Setting the plain text
+    /// the label already shows does nothing, and doesn't request a layout.
+    pub fn set_text(&mut self, new_text: impl Into<ArcStr>) {
+        let new_text = new_text.into();
+        // Labels refreshed on every update mostly get the text they already show;
+        // there's no need to rebuild their layout for it.
+        let shows_new_text = matches!(&self.widget.text, LabelText::Static(text) if *text == new_text)
+            && self.widget.text_promise.is_none()
+            && self
+                .widget
+                .text_layout
+                .text()
+                .map_or(false, RichText::is_plain);
+        if shows_new_text {
+            return;
+        }
+        self.set_text_and_attributes(new_text.clone(), RichText::new(new_text));
+    }
+
+    /// Set the text shown when dynamic text can't be computed.
+    ///
+    /// See [`Label::with_fallback_text`].
+    pub fn set_fallback_text(&mut self, text: impl Into<ArcStr>) {
+        self.widget.fallback_text = text.into();
+        self.ctx.request_layout();
+    }
+
+    /// Set text with styled ranges.
+    ///
+    /// See [`Label::with_attributed_text`] for details.
+    pub fn set_attributed_text(&mut self, text: RichText) {
+        self.set_text_and_attributes(text.as_str().into(), text);
+    }
+
+    fn set_text_and_attributes(&mut self, new_text: ArcStr, rich_text: RichText) {
+        self.widget.text = LabelText::Static(new_text.clone());
+        self.widget.text_promise = None;
+        self.widget.selection = self.widget.selection.constrained(&new_text);
+        self.widget.text_layout.set_text(rich_text);
+        self.ctx.request_layout();
+    }
+
+    /// Replace the text in `range` with `replacement`.
+    ///
+    /// This is meant for editors splicing in small edits. The selection is kept
+    /// pointing at the same text: offsets before `range` are unchanged, offsets after
+    /// it are shifted by the change in length, and offsets inside it move to the end
+    /// of the replacement.
+    ///
+    /// Note that the text layout is still rebuilt in full, since piet has no way
+    /// to reshape only part of a layout. Attributes set with
+    /// [`set_attributed_text`](Self::set_attributed_text) are cleared.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds or doesn't lie on `char` boundaries.
+    pub fn replace_range(&mut self, range: Range<usize>, replacement: &str) {
+        let old_text: ArcStr = self
+            .widget
+            .text_layout
+            .text()
+            .map_or("", |text| text.as_str())
+            .into();
+        assert!(
+            old_text.get(range.clone()).is_some(),
+            "replace_range: invalid range {:?} for text of length {}",
+            range,
+            old_text.len(),
+        );
+
+        let mut new_text = String::with_capacity(old_text.len() - range.len() + replacement.len());
+        new_text.push_str(&old_text[..range.start]);
+        new_text.push_str(replacement);
+        new_text.push_str(&old_text[range.end..]);
+        let new_text: ArcStr = new_text.into();
+
+        let shift = |offset: usize| {
+            if offset <= range.start {
+                offset
+            } else if offset >= range.end {
+                offset - range.len() + replacement.len()
+            } else {
+                range.start + replacement.len()
+            }
+        };
+        let selection = self.widget.selection;
+        self.widget.selection = Selection::new(shift(selection.anchor), shift(selection.active));
+
+        self.widget.text = LabelText::Static(new_text.clone());
+        self.widget.text_promise = None;
+        self.widget.text_layout.set_text(RichText::new(new_text));
+        self.ctx.request_layout();
+    }
+
+    /// Set the selection.
+    ///
+    /// The selection is constrained to the label's text, and its ends are moved
+    /// to the nearest grapheme cluster boundaries.
+    pub fn set_selection(&mut self, selection: Selection) {
+        let text = self.widget.text();
+        let selection = selection.constrained(&text);
+        self.widget.selection = Selection::new(
+            nearest_grapheme_boundary(&text, selection.anchor),
+            nearest_grapheme_boundary(&text, selection.active),
+        );
+        self.ctx.request_paint();
+    }
+
+    /// Set the text color.
+    ///
+    /// The argument can be either a `Color` or a [`Key<Color>`].
+    /// [`Key<Color>`]: ../struct.Key.html
+    pub fn set_text_color(&mut self, color: impl Into<KeyOrValue<Color>>) {
+        let color = color.into();
+        if !self.widget.disabled {
+            self.widget.text_layout.set_text_color(color.clone());
+        }
+        self.widget.default_text_color = color;
+        self.ctx.request_layout();
+    }
+
+    /// Set the text size.
+    ///
+    /// The argument can be either an `f64` or a [`Key<f64>`].
+    ///
+    /// [`Key<f64>`]: ../struct.Key.html
+    pub fn set_text_size(&mut self, size: impl Into<KeyOrValue<f64>>) {
+        let size = size.into();
+        self.widget.placeholder_layout.set_text_size(size.clone());
+        self.widget.text_layout.set_text_size(size);
+        self.ctx.request_layout();
+    }
+
+    /// Set the font.
+    ///
+    /// The argument can be a [`FontDescriptor`] or a [`Key<FontDescriptor>`]
+    /// that refers to a font defined in the [`Env`].
+    ///
+    /// The placeholder uses the same font, in italics.
+    ///
+    /// [`Key<FontDescriptor>`]: ../struct.Key.html
+    pub fn set_font(&mut self, font: impl Into<KeyOrValue<FontDescriptor>>) {
+        let font = font.into();
+        self.widget.placeholder_font = Some(font.clone());
+        self.widget.text_layout.set_font(font);
+        self.ctx.request_layout();
+    }
+
+    /// Set how lines are broken when the text wraps.
+    ///
+    /// With [`BreakStrategy::Optimal`], lines of a paragraph are kept close in
+    /// length, which avoids the ragged right edge greedy wrapping can leave, at
+    /// the cost of slower layouts. This only matters with [`LineBreaking::WordWrap`]
+    /// or a [`FitStrategy`].
+    pub fn set_break_strategy(&mut self, strategy: BreakStrategy) {
+        self.widget.text_layout.set_break_strategy(strategy);
+        self.ctx.request_layout();
+    }
+
+    /// Set extra spacing added to each space between words, in pixels.
+    ///
+    /// Only spaces are widened, so words keep their normal glyph spacing, and
+    /// lines may wrap at different points. Large values can make lines taller;
+    /// see [`TextLayout::set_word_spacing`].
+    pub fn set_word_spacing(&mut self, spacing: f64) {
+        self.widget.text_layout.set_word_spacing(spacing);
+        self.ctx.request_layout();
+    }
+
+    /// Set extra spacing added after each character, in pixels.
+    ///
+    /// This is the tracking of the text, e.g. for spaced-out headings. The
+    /// label's width includes it, and lines may wrap at different points.
+    /// Negative values are treated as zero; see [`TextLayout::set_letter_spacing`].
+    pub fn set_letter_spacing(&mut self, spacing: f64) {
+        self.widget.text_layout.set_letter_spacing(spacing);
+        self.ctx.request_layout();
+    }
+
+    /// Set the height of each line, as a multiple of the font size, or `None`
+    /// to use the font's line spacing.
+    ///
+    /// Each line is centered in a box of that height, so a label with `n` lines
+    /// is `n * line_height * font_size` tall, plus padding. A line with larger
+    /// attributed text uses its largest font size instead. This also applies to
+    /// the placeholder.
+    pub fn set_line_height(&mut self, line_height: Option<f64>) {
+        self.widget.text_layout.set_line_height(line_height);
+        self.widget.placeholder_layout.set_line_height(line_height);
+        self.ctx.request_layout();
+    }
+
+    /// Set objects, such as custom emoji, drawn inline with the text.
+    ///
+    /// Each object hides a run of the text and draws an image in its place,
+    /// sitting on the baseline; the rest of the text wraps around it. See
+    /// [`InlineObject`] for details. Object ranges are byte ranges of the text,
+    /// and are not adjusted when the text changes.
+    pub fn set_inline_objects(&mut self, objects: Vec<InlineObject>) {
+        self.widget.text_layout.set_inline_objects(objects);
+        self.ctx.request_layout();
+    }
+
+    /// Set whether the text is underlined.
+    pub fn set_underline(&mut self, underline: bool) {
+        self.widget.underline = underline;
+        self.ctx.request_layout();
+    }
+
+    /// Set whether the text is struck through.
+    pub fn set_strikethrough(&mut self, strikethrough: bool) {
+        self.widget.strikethrough = strikethrough;
+        self.ctx.request_layout();
+    }
+
+    /// Set how the underline and strikethrough are drawn.
+    ///
+    /// By default, decorations are solid lines whose thickness and position are
+    /// derived from the font size and line metrics, which may look too thin or
+    /// badly placed for heavy or large text. Decorations are drawn in the text
+    /// color, and follow the label's base alignment even where
+    /// [`set_line_alignment_overrides`](Self::set_line_alignment_overrides) moves lines.
+    pub fn set_decoration_style(&mut self, style: DecorationStyle) {
+        self.widget.decoration_style = style;
+        self.ctx.request_layout();
+    }
+
+    /// Set ruby annotations, drawn at a smaller size centered above their base text.
+    ///
+    /// The label grows to make room for the annotations: its first line is pushed
+    /// down by the height of the tallest annotation, and it is widened if an
+    /// annotation is wider than its base text at either end of the label.
+    /// Annotations whose base range is empty or doesn't fall on character boundaries
+    /// are ignored. A base range that wraps is annotated on its first line.
+    pub fn set_ruby(&mut self, runs: Vec<RubyRun>) {
+        self.widget.ruby = Label::ruby_annotations(runs);
+        self.ctx.request_layout();
+    }
+
+    /// Set the placeholder text, shown in place of the text while it is empty.
+    ///
+    /// The placeholder is drawn in italics with [`theme::PLACEHOLDER_COLOR`], in the
+    /// label's font and text size and with its alignment. The label is sized to fit
+    /// it so that an empty field doesn't collapse. It is purely visual: it can't be
+    /// selected or copied.
+    pub fn set_placeholder(&mut self, placeholder: impl Into<ArcStr>) {
+        self.widget.placeholder_layout.set_text(placeholder.into());
+        self.ctx.request_layout();
+    }
+
+    /// Set the [`LineBreaking`] behaviour.
+    ///
+    /// See [`Label::with_line_break_mode`] for details.
+    pub fn set_line_break_mode(&mut self, mode: LineBreaking) {
+        self.widget.line_break_mode = Some(mode);
+        self.ctx.request_layout();
+    }
+
+    /// Set a fixed width to wrap the text at, or `None` to wrap it according to
+    /// the line breaking mode.
+    ///
+    /// See [`Label::with_fixed_wrap_width`] for details.
+    pub fn set_fixed_wrap_width(&mut self, width: Option<f64>) {
+        self.widget.fixed_wrap_width = width;
+        self.ctx.request_layout();
+    }
+
+    /// Set the smallest and largest width of the label, or `None` to size it
+    /// after its text.
+    ///
+    /// See [`Label::with_width_range`] for details.
+    pub fn set_width_range(&mut self, range: Option<(f64, f64)>) {
+        self.widget.width_range = range;
+        self.ctx.request_layout();
+    }
+
+    /// Set whether characters with both presentations are drawn as text or as emoji.
+    ///
+    /// See [`Label::with_emoji_presentation`] for details.
+    pub fn set_emoji_presentation(&mut self, presentation: EmojiPresentation) {
+        self.widget.text_layout.set_emoji_presentation(presentation);
+        self.ctx.request_layout();
+    }
+
+    /// Set how the text is wrapped and shrunk to fit, or `None` to always use
+    /// the normal text size.
+    ///
+    /// See [`FitStrategy`] for details.
+    pub fn set_fit_strategy(&mut self, fit: Option<FitStrategy>) {
+        self.widget.fit_strategy = fit;
+        self.ctx.request_layout();
+    }
+
+    /// Reset the counters returned by [`Label::shape_cache_stats`].
+    pub fn reset_shape_cache_stats(&mut self) {
+        self.widget.text_layout.reset_shape_cache_stats();
+        if let Some(layout) = &mut self.widget.ellipsized_layout {
+            layout.reset_shape_cache_stats();
+        }
+        self.widget.placeholder_layout.reset_shape_cache_stats();
+        for annotation in &mut self.widget.ruby {
+            annotation.layout.reset_shape_cache_stats();
+        }
+    }
+
+    /// Set the [`TextAlignment`] for this layout.
+    ///
+    /// See [`Label::with_text_alignment`] for details.
+    pub fn set_text_alignment(&mut self, alignment: TextAlignment) {
+        self.widget.text_alignment = alignment;
+        self.ctx.request_layout();
+    }
+
+    /// Set the direction of the layout the label is placed in.
+    ///
+    /// In [`LayoutDirection::Rtl`], the text is placed against the label's right
+    /// edge, and `Start` and `End` alignments are swapped, including in line
+    /// alignment overrides. When the text is wider than the label and gets
+    /// clipped, it is its left side that is cut off.
+    ///
+    /// This doesn't change the order of characters, which follows their script.
+    /// With [`LayoutDirection::Auto`], the direction is picked from the text each
+    /// time it changes.
+    pub fn set_layout_direction(&mut self, direction: LayoutDirection) {
+        self.widget.layout_direction = direction;
+        self.ctx.request_layout();
+    }
+
+    /// Set what the text is to assistive technologies: a heading, a label, or
+    /// static text.
+    ///
+    /// The default is [`ContentRole::Label`]. This only changes the role of the
+    /// label's accessibility node, not how it looks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `role` is a heading whose level isn't between 1 and 6.
+    pub fn set_content_role(&mut self, role: ContentRole) {
+        role.assert_valid();
+        self.widget.content_role = role;
+    }
+
+    /// Override the alignment of specific visual lines.
+    ///
+    /// Each entry is a line index and the alignment to use for that line instead of
+    /// the label's [`TextAlignment`], eg to right-align the last line of an address.
+    /// Line indices count wrapped lines, so with [`LineBreaking::WordWrap`] which
+    /// line an index refers to depends on the label's width. Indices past the last
+    /// line are ignored.
+    ///
+    /// Overrides only change where lines are painted; selection highlights and
+    /// link hit-testing still follow the base alignment.
+    pub fn set_line_alignment_overrides(&mut self, overrides: Vec<(usize, TextAlignment)>) {
+        self.widget.line_alignment_overrides = overrides;
+        self.ctx.request_paint();
+    }
+
+    /// Set the smallest font size the text is displayed at, in logical pixels.
+    ///
+    /// The clamp is applied after everything else that scales the text: the
+    /// [`Env`]'s font scale and shrinking to fit with a [`FitStrategy`]. Text that
+    /// would be smaller is laid out at this size instead, even if it then
+    /// overflows the label, and a warning is logged when the clamping starts.
+    /// Use [`Label::is_font_size_clamped`] to check whether it applies.
+    ///
+    /// The default is 6 pixels. A size of zero disables the clamp.
+    pub fn set_min_font_size_for_readability(&mut self, size: f64) {
+        self.widget.min_readable_font_size = size;
+        self.ctx.request_layout();
+    }
+
+    /// Set a callback fired when the text starts or stops overflowing the label.
+    ///
+    /// See [`Label::is_overflowing`] for details.
+    pub fn set_on_overflow(&mut self, on_overflow: impl FnMut(bool) + 'static) {
+        self.widget.on_overflow = Some(Box::new(on_overflow));
+    }
+
+    /// Set a callback fired with the label's size every time it is laid out.
+    ///
+    /// The callback is called at the end of `layout`, with the size the label
+    /// returns to its parent, ie after it is constrained. It is called on every
+    /// layout pass, even when the size doesn't change. This lets a widget that
+    /// contains the label react to its size in the same pass, eg to size a
+    /// background drawn behind it.
+    pub fn set_on_measure(&mut self, on_measure: impl FnMut(Size) + 'static) {
+        self.widget.on_measure = Some(Box::new(on_measure));
+        self.ctx.request_layout();
+    }
+
+    /// Set whether overflowing text is clipped to the label's bounds.
+    ///
+    /// See [`Label::with_clip_overflow_to_parent`] for details.
+    pub fn set_clip_overflow_to_parent(&mut self, clip: bool) {
+        self.widget.clip_overflow = clip;
+        self.ctx.request_paint();
+    }
+
+    /// Set whether text taller than the label is clipped.
+    ///
+    /// See [`Label::with_height_clip`] for details.
+    pub fn set_height_clip(&mut self, clip: bool) {
+        self.widget.clip_height = clip;
+        self.ctx.request_paint();
+    }
+
+    /// Set how far from a link the pointer may be and still hit it.
+    ///
+    /// See [`Label::with_link_hit_tolerance`] for details.
+    pub fn set_link_hit_tolerance(&mut self, tolerance: impl Into<KeyOrValue<f64>>) {
+        self.widget.link_hit_tolerance = tolerance.into();
+    }
+
+    /// Set the padding between the left and right edges of the label and its text.
+    ///
+    /// See [`Label::with_padding`] for details.
+    pub fn set_padding(&mut self, padding: impl Into<KeyOrValue<f64>>) {
+        self.widget.x_padding = padding.into();
+        self.ctx.request_layout();
+    }
+
+    /// Set the distance from the top of the label to its first baseline, or
+    /// `None` to place the text at the top.
+    ///
+    /// See [`Label::with_first_baseline_to_top`] for details.
+    pub fn set_first_baseline_to_top(&mut self, offset: Option<f64>) {
+        self.widget.first_baseline_to_top = offset;
+        self.ctx.request_layout();
+    }
+
+    /// Set whether the whole text is selected when the label gains focus.
+    ///
+    /// See [`Label::with_selection_on_focus`] for details.
+    pub fn set_selection_on_focus(&mut self, select_on_focus: bool) {
+        self.widget.select_on_focus = select_on_focus;
+        // Rebuild the focus chain, since we may have become focusable.
+        self.ctx.children_changed();
+    }
+
+    /// Set whether the user can select text with the mouse.
+    ///
+    /// See [`Label::with_selectable`] for details. Making the label unselectable
+    /// clears its selection.
+    pub fn set_selectable(&mut self, selectable: bool) {
+        self.widget.selectable = selectable;
+        if !selectable {
+            self.widget.selection = Selection::caret(0);
+            self.ctx.request_paint();
+        }
+        self.ctx.children_changed();
+    }
+
+    /// Set the width and color of the outline drawn around the glyphs, or remove
+    /// it if `outline` is `None`.
+    ///
+    /// See [`Label::with_text_outline`] for details.
+    pub fn set_text_outline(&mut self, outline: Option<(f64, Color)>) {
+        self.widget.outline = outline.map(|(width, color)| (width.max(0.0), color));
+        self.ctx.request_layout();
+    }
+
+    /// Set how far past its layout rect the label receives pointer events.
+    ///
+    /// See [`Label::with_hit_area_insets`] for details.
+    pub fn set_hit_area_insets(&mut self, insets: impl Into<Insets>) {
+        self.widget.hit_area_insets = insets.into();
+    }
+}
+
+// Swap `Start` and `End` in RTL layouts.
+fn mirror_alignment(alignment: TextAlignment, direction: LayoutDirection) -> TextAlignment {
+    match (direction, alignment) {
+        (LayoutDirection::Rtl, TextAlignment::Start) => TextAlignment::End,
+        (LayoutDirection::Rtl, TextAlignment::End) => TextAlignment::Start,
+        _ => alignment,
+    }
+}
+
+// --- TRAIT IMPLS ---
+
+impl Widget for Label {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        let link_hit_tolerance = self.link_hit_tolerance.resolve(env);
+        match event {
+            Event::MouseDown(event) => {
+                // Account for the padding
+                let pos = event.pos - self.text_origin.to_vec2();
+                if let Some(link) = self
+                    .text_layout
+                    .link_for_pos_with_tolerance(pos, link_hit_tolerance)
+                {
+                    self.pressed_link = Some(link.range());
+                    ctx.set_active(true);
+                    ctx.set_handled();
+                } else if self.selectable && !self.shows_placeholder() {
+                    self.selection = Selection::caret(self.text_offset_for_point(pos));
+                    ctx.set_active(true);
+                    ctx.request_focus();
+                    ctx.request_paint();
+                    ctx.set_handled();
+                }
+            }
+            Event::MouseUp(event) => {
+                // A link only fires if the click started on it.
+                if let Some(pressed_link) = self.pressed_link.take() {
+                    ctx.set_active(false);
+                    let pos = event.pos - self.text_origin.to_vec2();
+                    if let Some(link) = self
+                        .text_layout
+                        .link_for_pos_with_tolerance(pos, link_hit_tolerance)
+                    {
+                        if link.range == pressed_link {
+                            ctx.submit_command(link.command.clone());
+                        }
+                    }
+                    ctx.set_handled();
+                } else if self.selectable && ctx.is_active() {
+                    ctx.set_active(false);
+                    ctx.set_handled();
+                }
+            }
+            Event::MouseMove(event) => {
+                // Account for the padding
+                let pos = event.pos - self.text_origin.to_vec2();
+
+                // Dragging extends the selection from where the mouse was pressed.
+                if self.selectable && ctx.is_active() && self.pressed_link.is_none() {
+                    let active = self.text_offset_for_point(pos);
+                    if active != self.selection.active {
+                        self.selection = Selection::new(self.selection.anchor, active);
+                        ctx.request_paint();
+                    }
+                }
+
+                if self
+                    .text_layout
+                    .link_for_pos_with_tolerance(pos, link_hit_tolerance)
+                    .is_some()
+                {
+                    ctx.set_cursor(&Cursor::Pointer);
+                } else if self.selectable {
+                    ctx.set_cursor(&Cursor::IBeam);
+                } else {
+                    ctx.clear_cursor();
+                }
+            }
+            Event::KeyDown(key)
+                if self.selectable && HotKey::new(SysMods::Cmd, "c").matches(key) =>
+            {
+                ctx.submit_command(command::COPY.to(ctx.widget_id()));
+                ctx.set_handled();
+            }
+            Event::ContextMenu { pos } if self.select_on_focus || self.selectable => {
+                let id = ctx.widget_id();
+                let menu = ContextMenu::new()
+                    .with_item(
+                        MenuItem::new("Copy", command::COPY.to(id))
+                            .enabled(!self.selection.is_caret()),
+                    )
+                    .with_item(MenuItem::new("Select All", command::SELECT_ALL.to(id)));
+                ctx.show_context_menu(menu, *pos);
+                ctx.set_handled();
+            }
+            Event::Command(cmd) if cmd.is(command::COPY) && self.is_command_target(ctx, cmd) => {
+                if let (Some(text), Some(app)) = (self.selected_text(), Application::try_global()) {
+                    app.clipboard().put_string(text);
+                }
+                ctx.set_handled();
+            }
+            Event::Command(cmd)
+                if cmd.is(command::SELECT_ALL) && self.is_command_target(ctx, cmd) =>
+            {
+                self.selection = Selection::new(0, self.text_layout.text_len());
+                ctx.request_paint();
+                ctx.set_handled();
+            }
+            Event::PromiseResult(result) => {
+                if let Some(token) = self.text_promise {
+                    if let Some(text) = result.try_get(token) {
+                        self.text_promise = None;
+                        self.selection = self.selection.constrained(&text);
+                        self.text = LabelText::Static(text.clone());
+                        self.text_layout.set_text(RichText::new(text));
+                        ctx.request_layout();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        match event {
+            StatusChange::FocusChanged(true) if self.select_on_focus => {
+                self.selection = Selection::new(0, self.text_layout.text_len());
+                ctx.request_paint();
+            }
+            StatusChange::FocusChanged(false) => {
+                self.selection = Selection::caret(self.selection.active);
+                ctx.request_paint();
+            }
+            StatusChange::DisabledChanged(disabled) => {
+                self.disabled = *disabled;
+                self.text_layout.set_text_color(self.text_color());
+                ctx.request_layout();
+            }
+            // Links are underlined while the label is hot.
+            StatusChange::HotChanged(_) if self.has_links() => {
+                ctx.request_paint();
+            }
+            _ => {}
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, _env: &Env) {
+        match event {
+            LifeCycle::WidgetAdded => {
+                if let Some(compute_text) = self.pending_text.take() {
+                    self.text_promise = Some(ctx.compute_in_background(move |_| compute_text()));
+                }
+            }
+            LifeCycle::BuildFocusChain => {
+                if self.select_on_focus || self.selectable {
+                    ctx.register_for_focus();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        if self.text.resolve_with_fallback(env, &self.fallback_text) {
+            let text = self.text.display_text();
+            self.selection = self.selection.constrained(&text);
+            self.text_layout.set_text(RichText::new(text));
+        }
+
+        let x_padding = self.x_padding.resolve(env);
+        let line_break_mode = self.line_break_mode(env);
+        let max_width = match self.width_range {
+            Some((_, max)) => bc.max().width.min(max),
+            None => bc.max().width,
+        };
+        let width = if let Some(width) = self.fixed_wrap_width {
+            width
+        } else {
+            match line_break_mode {
+                _ if self.fit_strategy.is_some() => max_width - x_padding * 2.0,
+                LineBreaking::WordWrap | LineBreaking::WordWrapEllipsis { .. } => {
+                    max_width - x_padding * 2.0
+                }
+                _ => f64::INFINITY,
+            }
+        };
+
+        let alignment = mirror_alignment(self.text_alignment, self.resolved_direction());
+        self.text_layout.set_wrap_width(width);
+        self.text_layout.set_text_alignment(alignment);
+        // The scale is clamped before building the layout, so that it's only
+        // rebuilt when something changed.
+        match self.fit_strategy {
+            Some(fit) if !self.shows_placeholder() => {
+                self.fit_text(ctx.text(), fit, bc.max().height, env);
+                let scale = self.text_layout.text_size_scale();
+                let readable_scale = self.readable_text_size_scale(scale, env);
+                self.text_layout.set_text_size_scale(readable_scale);
+            }
+            _ if !self.shows_placeholder() => {
+                let readable_scale = self.readable_text_size_scale(1.0, env);
+                self.text_layout.set_text_size_scale(readable_scale);
+            }
+            _ => self.text_layout.set_text_size_scale(1.0),
+        }
+        self.text_layout.rebuild_if_needed(ctx.text(), env);
+        match line_break_mode {
+            LineBreaking::WordWrapEllipsis { max_lines } if !self.shows_placeholder() => {
+                self.ellipsize(ctx.text(), max_lines, env);
+            }
+            _ => {
+                self.ellipsized_layout = None;
+                self.ellipsis_key = None;
+            }
+        }
+
+        let text_metrics = if self.shows_placeholder() {
+            if let Some(font) = &self.placeholder_font {
+                let font = font.resolve(env).with_style(FontStyle::Italic);
+                self.placeholder_layout.set_font(font);
+            }
+            self.placeholder_layout.set_wrap_width(width);
+            self.placeholder_layout.set_text_alignment(alignment);
+            self.placeholder_layout.rebuild_if_needed(ctx.text(), env);
+            self.placeholder_layout.layout_metrics()
+        } else {
+            self.displayed_layout().layout_metrics()
+        };
+        self.layout_metrics = text_metrics;
+
+        // Ruby annotations may stick out above the first line, and to the
+        // left or right of the text when they're wider than their base.
+        let ruby_size =
+            self.text_layout.text_size(env) * self.text_layout.text_size_scale() * RUBY_SIZE_RATIO;
+        let ruby_color = self.text_color();
+        let mut ruby_top = 0.0_f64;
+        let mut ruby_left = 0.0_f64;
+        let mut ruby_right = text_metrics.size.width;
+        if !self.shows_placeholder() {
+            let text = self.text_layout.text().cloned();
+            for annotation in &mut self.ruby {
+                annotation.layout.set_text_size(ruby_size);
+                annotation.layout.set_text_color(ruby_color.clone());
+                annotation.layout.rebuild_if_needed(ctx.text(), env);
+
+                let base_range = annotation.run.base.clone();
+                let base_text = text
+                    .as_ref()
+                    .and_then(|text| text.as_str().get(base_range.clone()));
+                let base_rect = match base_text {
+                    Some(base) if !base.is_empty() => self
+                        .text_layout
+                        .rects_for_range(base_range)
+                        .first()
+                        .copied(),
+                    _ => None,
+                };
+
+                let ruby = annotation.layout.size();
+                annotation.origin = base_rect.map(|base_rect| {
+                    Point::new(
+                        base_rect.center().x - ruby.width / 2.0,
+                        base_rect.y0 - ruby.height,
+                    )
+                });
+                if let Some(origin) = annotation.origin {
+                    ruby_top = ruby_top.max(-origin.y);
+                    ruby_left = ruby_left.max(-origin.x);
+                    ruby_right = ruby_right.max(origin.x + ruby.width);
+                }
+            }
+        }
+        // The text moves down past its natural position (below the annotations)
+        // to put the first baseline at the requested offset.
+        let text_top = match self.first_baseline_to_top {
+            Some(offset) => ruby_top.max(offset - text_metrics.first_baseline),
+            None => ruby_top,
+        };
+        self.text_origin = Point::new(x_padding + ruby_left, text_top);
+
+        match self.outline {
+            Some((width, color)) if width > 0.0 && !self.shows_placeholder() => {
+                let key = (self.text_layout.generation(), self.ellipsis_key, color);
+                if self
+                    .outline_cache
+                    .as_ref()
+                    .map_or(true, |cache| cache.key != key)
+                {
+                    let mut outline_layout = self.displayed_layout().clone();
+                    outline_layout.set_text_color(color);
+                    // Inline objects keep their space, but only glyphs are outlined.
+                    if !outline_layout.inline_objects().is_empty() {
+                        let objects = outline_layout
+                            .inline_objects()
+                            .iter()
+                            .map(|object| InlineObject {
+                                image: ImageBuf::empty(),
+                                ..object.clone()
+                            })
+                            .collect();
+                        outline_layout.set_inline_objects(objects);
+                    }
+                    outline_layout.rebuild_if_needed(ctx.text(), env);
+                    self.outline_cache = Some(OutlineCache {
+                        key,
+                        layout: outline_layout,
+                        image: None,
+                    });
+                }
+                ctx.set_paint_insets(Insets::uniform(width));
+            }
+            _ => {
+                self.outline_cache = None;
+                ctx.set_paint_insets(Insets::ZERO);
+            }
+        }
+
+        if self.shows_placeholder() {
+            self.decoration_spans.clear();
+        } else {
+            self.compute_decoration_spans(env);
+        }
+
+        let first_baseline = text_top + text_metrics.first_baseline;
+        let last_baseline = text_top + text_metrics.last_baseline;
+        let text_size = Size::new(
+            ruby_left + ruby_right + 2. * x_padding,
+            text_top + text_metrics.size.height,
+        );
+        ctx.set_baseline_offset(text_size.height - first_baseline);
+        ctx.set_baselines(first_baseline, last_baseline);
+        let size = match self.width_range {
+            Some((min, max)) => {
+                let width = text_size.width.max(min).min(max);
+                bc.constrain(Size::new(width, text_size.height))
+            }
+            None => bc.constrain(text_size),
+        };
+        if self.resolved_direction() == LayoutDirection::Rtl {
+            // Padding and annotations mirror: the text ends at the label's right edge.
+            self.text_origin.x = size.width - x_padding - ruby_right;
+        }
+
+        // 1e-4 is an arbitrary small-enough value to ignore rounding errors
+        let is_overflowing =
+            text_size.width - size.width > 1e-4 || text_size.height - size.height > 1e-4;
+        if is_overflowing != self.is_overflowing {
+            self.is_overflowing = is_overflowing;
+            if let Some(on_overflow) = &mut self.on_overflow {
+                on_overflow(is_overflowing);
+            }
+        }
+        if let Some(on_measure) = &mut self.on_measure {
+            on_measure(size);
+        }
+
+        trace!("Computed size: {}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let origin = self.text_origin;
+        let label_size = ctx.size();
+
+        let clip = match self.line_break_mode(env) {
+            LineBreaking::Clip => true,
+            LineBreaking::Overflow => self.clip_overflow,
+            LineBreaking::WordWrap | LineBreaking::WordWrapEllipsis { .. } => false,
+        };
+        if clip {
+            ctx.clip(label_size.to_rect());
+        } else if self.clip_height {
+            // Keep whatever overhangs horizontally, including the outline.
+            let text_rect = self.displayed_layout().size().to_rect() + origin.to_vec2();
+            let outline_width = self.outline.map_or(0.0, |(width, _)| width);
+            ctx.clip(Rect::new(
+                text_rect.x0.min(0.0) - outline_width,
+                0.0,
+                text_rect.x1.max(label_size.width) + outline_width,
+                label_size.height,
+            ));
+        }
+
+        if self.shows_placeholder() {
+            self.placeholder_layout.draw(ctx, origin);
+            return;
+        }
+
+        let direction = self.resolved_direction();
+        let line_alignment_overrides: Vec<_> = self
+            .line_alignment_overrides
+            .iter()
+            .map(|&(line, alignment)| (line, mirror_alignment(alignment, direction)))
+            .collect();
+        if !self.selection.is_caret() {
+            let selection_color = if ctx.is_focused() {
+                env.get(theme::SELECTED_TEXT_BACKGROUND_COLOR)
+            } else {
+                env.get(theme::SELECTED_TEXT_INACTIVE_BACKGROUND_COLOR)
+            };
+            for rect in self.text_range_rects(self.selection.range()) {
+                ctx.fill(rect, &selection_color);
+            }
+        }
+        if let (Some(cache), Some((width, _))) = (&mut self.outline_cache, self.outline) {
+            // Piet can't stroke glyph outlines, so we approximate a stroke by
+            // drawing the text offset in every direction. That's a lot of text
+            // to draw, so it's done once into an image.
+            let draw_outline = |ctx: &mut PaintCtx| {
+                let steps = if width > 2.0 { 16 } else { 8 };
+                for i in 0..steps {
+                    let angle = i as f64 * std::f64::consts::TAU / steps as f64;
+                    let offset = Vec2::from_angle(angle) * width;
+                    cache.layout.draw_with_line_alignments(
+                        ctx,
+                        origin + offset,
+                        &line_alignment_overrides,
+                    );
+                }
+            };
+            // Lines aligned by overrides stay within the label.
+            let rect = (cache.layout.size().to_rect() + origin.to_vec2())
+                .union(label_size.to_rect())
+                .inflate(width, width);
+            let scale = ctx.render_scale();
+            let is_cached = cache.image.as_ref().map_or(false, |image| {
+                image.1 == rect
+                    && image.2 == scale
+                    && image.3 == origin
+                    && image.4 == line_alignment_overrides
+            });
+            if !is_cached {
+                cache.image = None;
+                match ctx.paint_to_image(rect, scale, draw_outline) {
+                    Ok(Some(image)) => {
+                        let image = image.to_image(ctx.render_ctx);
+                        cache.image =
+                            Some((image, rect, scale, origin, line_alignment_overrides.clone()));
+                    }
+                    Ok(None) => {}
+                    Err(draw_outline) => draw_outline(ctx),
+                }
+            }
+            if let Some((image, rect, ..)) = &cache.image {
+                ctx.draw_image(image, *rect, InterpolationMode::Bilinear);
+            }
+        }
+        self.displayed_layout()
+            .draw_with_line_alignments(ctx, origin, &line_alignment_overrides);
+        if !self.decoration_spans.is_empty() {
+            let color = self.text_color().resolve(env);
+            let line_style = self.decoration_style.line_style;
+            ctx.with_save(|ctx| {
+                ctx.transform(Affine::translate(origin.to_vec2()));
+                for (span, _) in &self.decoration_spans {
+                    span.draw(ctx, line_style, &color);
+                }
+            });
+        }
+        if ctx.is_hot() && self.has_links() {
+            let color = self.text_color().resolve(env);
+            for underline in self.link_underlines() {
+                ctx.stroke(underline + origin.to_vec2(), &color, 1.0);
+            }
+        }
+        for annotation in &self.ruby {
+            if let Some(ruby_origin) = annotation.origin {
+                annotation.layout.draw(ctx, origin + ruby_origin.to_vec2());
+            }
+        }
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.selection = Selection::caret(0);
+        self.pressed_link = None;
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        SmallVec::new()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Label")
+    }
+
+    fn accessibility(&self, ctx: &mut AccessCtx, _env: &Env) {
+        ctx.set_role(self.content_role.role());
+        let text = self.text_str();
+        if !text.is_empty() {
+            ctx.set_name(text.to_string());
+        }
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        Some(self.text_str().to_string())
+    }
+
+    fn allow_pointer_events_outside_bounds(&self) -> Insets {
+        self.hit_area_insets
+    }
+}
+
+fn new_placeholder_layout() -> TextLayout<ArcStr> {
+    let mut layout = TextLayout::new();
+    layout.set_font(theme::UI_FONT_ITALIC);
+    layout.set_text_color(theme::PLACEHOLDER_COLOR);
+    layout
+}
+
+impl Data for LineBreaking {
+    fn same(&self, other: &Self) -> bool {
+        self == other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::piet::{FontFamily, ImageFormat, TextLayout as _};
+    use druid_shell::{KeyEvent, MouseButton, RawMods};
     use insta::assert_debug_snapshot;
+    use instant::Duration;
+
+    use super::*;
+    use crate::assert_render_snapshot;
+    use crate::testing::{widget_ids, RecordedCommand, TestHarness};
+    use crate::text::{Attribute, DecorationLineStyle, RichTextBuilder};
+    use crate::theme::{PRIMARY_DARK, PRIMARY_LIGHT};
+    use crate::widget::{Flex, SizedBox};
+    use crate::WidgetExt as _;
+    use crate::{Key, Selector, WidgetId};
+
+    // The default value of `theme::LABEL_X_PADDING`.
+    const LABEL_X_PADDING: f64 = 2.0;
+
+    #[test]
+    fn simple_label() {
+        let label = Label::new("Hello");
+
+        let mut harness = TestHarness::create(label);
+
+        assert_debug_snapshot!(harness.root_widget());
+        assert_render_snapshot!(harness, "hello");
+    }
+
+    #[test]
+    fn styled_label() {
+        let label = Label::new("The quick brown fox jumps over the lazy dog")
+            .with_text_color(PRIMARY_LIGHT)
+            .with_font(FontDescriptor::new(FontFamily::MONOSPACE))
+            .with_text_size(20.0)
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .with_text_alignment(TextAlignment::Center);
+
+        let mut harness = TestHarness::create_with_size(label, Size::new(200.0, 200.0));
+
+        assert_render_snapshot!(harness, "styled_label");
+    }
+
+    #[test]
+    fn line_break_modes() {
+        let widget = Flex::column()
+            .with_flex_spacer(1.0)
+            .with_child(
+                SizedBox::new(
+                    Label::new("The quick brown fox jumps over the lazy dog")
+                        .with_line_break_mode(LineBreaking::WordWrap),
+                )
+                .width(200.0),
+            )
+            .with_spacer(20.0)
+            .with_child(
+                SizedBox::new(
+                    Label::new("The quick brown fox jumps over the lazy dog")
+                        .with_line_break_mode(LineBreaking::Clip),
+                )
+                .width(200.0),
+            )
+            .with_spacer(20.0)
+            .with_child(
+                SizedBox::new(
+                    Label::new("The quick brown fox jumps over the lazy dog")
+                        .with_line_break_mode(LineBreaking::Overflow),
+                )
+                .width(200.0),
+            )
+            .with_flex_spacer(1.0);
+
+        let mut harness = TestHarness::create(widget);
+
+        assert_render_snapshot!(harness, "line_break_modes");
+    }
+
+    #[test]
+    fn edit_label() {
+        let image_1 = {
+            let label = Label::new("The quick brown fox jumps over the lazy dog")
+                .with_text_color(PRIMARY_LIGHT)
+                .with_font(FontDescriptor::new(FontFamily::MONOSPACE))
+                .with_text_size(20.0)
+                .with_line_break_mode(LineBreaking::WordWrap)
+                .with_text_alignment(TextAlignment::Center);
+
+            let mut harness = TestHarness::create_with_size(label, Size::new(50.0, 50.0));
+
+            harness.render()
+        };
+
+        let image_2 = {
+            let label = Label::new("Hello world")
+                .with_text_color(PRIMARY_DARK)
+                .with_text_size(40.0);
+
+            let mut harness = TestHarness::create_with_size(label, Size::new(50.0, 50.0));
+
+            harness.edit_root_widget(|mut label, _| {
+                let mut label = label.downcast::<Label>().unwrap();
+                label.set_text("The quick brown fox jumps over the lazy dog");
+                label.set_text_color(PRIMARY_LIGHT);
+                label.set_font(FontDescriptor::new(FontFamily::MONOSPACE));
+                label.set_text_size(20.0);
+                label.set_line_break_mode(LineBreaking::WordWrap);
+                label.set_text_alignment(TextAlignment::Center);
+            });
+
+            harness.render()
+        };
+
+        // We don't use assert_eq because we don't want rich assert
+        assert!(image_1 == image_2);
+    }
+
+    #[test]
+    fn select_all_on_focus() {
+        let [label_id] = widget_ids();
+        let label = Label::new("Hello")
+            .with_selection_on_focus(true)
+            .with_id(label_id);
+
+        let mut harness = TestHarness::create(label);
+        let selection = |harness: &TestHarness| {
+            let label = harness.get_widget(label_id);
+            label.downcast::<Label>().unwrap().deref().selection()
+        };
+        assert!(selection(&harness).is_caret());
+
+        harness.focus_on(Some(label_id));
+        assert_eq!(selection(&harness).range(), 0..5);
+
+        harness.focus_on(None);
+        assert!(selection(&harness).is_caret());
+    }
+
+    #[test]
+    fn recycling_clears_selection() {
+        let [label_id] = widget_ids();
+        let label = Label::new("First row").with_selection_on_focus(true);
+        let widget = Flex::row().with_child_id(label, label_id);
+
+        let mut harness = TestHarness::create(widget);
+        let selection = |harness: &TestHarness| {
+            let label = harness.get_widget(label_id);
+            label.downcast::<Label>().unwrap().deref().selection()
+        };
+        harness.focus_on(Some(label_id));
+        assert_eq!(selection(&harness).range(), 0..9);
+
+        // Bind the row to new data, the way a virtualized list would.
+        harness.edit_root_widget(|mut root, _| {
+            let mut flex = root.downcast::<Flex>().unwrap();
+            flex.reset_interaction_state();
+            let mut label = flex.child_mut(0).unwrap();
+            label.downcast::<Label>().unwrap().set_text("Second");
+        });
+        assert!(selection(&harness).is_caret());
+        assert_eq!(selection(&harness).range(), 0..0);
+    }
+
+    #[test]
+    fn select_all_on_focus_empty() {
+        let [label_id] = widget_ids();
+        let label = Label::empty()
+            .with_selection_on_focus(true)
+            .with_id(label_id);
+
+        let mut harness = TestHarness::create(label);
+
+        harness.focus_on(Some(label_id));
+        assert_eq!(harness.focused_widget().unwrap().id(), label_id);
+        let label = harness.get_widget(label_id).downcast::<Label>().unwrap();
+        assert!(label.deref().selection().is_caret());
+    }
+
+    #[test]
+    fn fixed_wrap_width() {
+        let [label_id] = widget_ids();
+        let label = Label::new("The quick brown fox jumps over the lazy dog")
+            .with_fixed_wrap_width(Some(100.0))
+            .with_id(label_id);
+        let mut harness = TestHarness::create_with_size(label, Size::new(1000.0, 400.0));
+        let line_count = |harness: &TestHarness| {
+            let label = harness.get_widget(label_id);
+            let label = label.downcast::<Label>().unwrap().deref();
+            label.text_layout.layout().unwrap().line_count()
+        };
+        assert!(line_count(&harness) > 1);
+
+        // Without the fixed width, unwrapped text stays on one line.
+        harness.edit_root_widget(|mut root, _| {
+            let mut label = root.downcast::<Label>().unwrap();
+            label.set_fixed_wrap_width(None);
+        });
+        assert_eq!(line_count(&harness), 1);
+    }
+
+    #[test]
+    fn set_same_text() {
+        let [label_id] = widget_ids();
+        let widget = Flex::column().with_child_id(Label::new("Hello"), label_id);
+        let mut harness = TestHarness::create(widget);
+        let _ = harness.simulate_frame();
+
+        let set_text = |harness: &mut TestHarness, text: &'static str| {
+            harness.edit_root_widget(|mut flex, _| {
+                let mut flex = flex.downcast::<Flex>().unwrap();
+                let mut label = flex.child_mut(0).unwrap();
+                label.downcast::<Label>().unwrap().set_text(text);
+            });
+            harness.simulate_frame().relaid_out.contains(&label_id)
+        };
+        assert!(set_text(&mut harness, "Goodbye"));
+        // The second time, the label already shows the text.
+        assert!(!set_text(&mut harness, "Goodbye"));
+        assert!(set_text(&mut harness, "Hello"));
+    }
+
+    #[test]
+    fn draw_at_baseline() {
+        use smallvec::smallvec;
+
+        use crate::testing::ModularWidget;
+        use crate::WidgetPod;
+
+        // A widget drawing a label's text itself, with `draw_text`.
+        fn render(
+            draw_text: impl Fn(&Label, &mut PaintCtx, f64) + 'static,
+        ) -> std::sync::Arc<[u8]> {
+            let label = WidgetPod::new(Label::new("Hello"));
+            let widget = ModularWidget::new(label)
+                .lifecycle_fn(|label, ctx, event, env| label.lifecycle(ctx, event, env))
+                .layout_fn(|label, ctx, bc, env| {
+                    label.layout(ctx, bc, env);
+                    ctx.place_child(label, Point::ORIGIN, env);
+                    bc.max()
+                })
+                .paint_fn(move |label, ctx, _| {
+                    let first_baseline = label.as_ref().deref().layout_metrics().first_baseline;
+                    draw_text(label.as_ref().deref(), ctx, first_baseline);
+                })
+                .children_fn(|label| smallvec![label.as_dyn()]);
+            TestHarness::create_with_size(widget, Size::new(100.0, 50.0)).render()
+        }
+
+        let top_left = render(|label, ctx, _| label.draw_at(ctx, (10.0, 10.0)));
+        let baseline = render(|label, ctx, first_baseline| {
+            label.draw_at_baseline(ctx, (10.0, 10.0 + first_baseline));
+        });
+        let unshifted = render(|label, ctx, _| label.draw_at_baseline(ctx, (10.0, 10.0)));
+        // We don't use assert_eq because we don't want rich assert
+        assert!(top_left == baseline);
+        assert!(top_left != unshifted);
+    }
+
+    #[test]
+    fn draw_text_pill() {
+        use smallvec::smallvec;
+
+        use crate::testing::ModularWidget;
+        use crate::WidgetPod;
+
+        let red = Color::rgb8(255, 0, 0);
+        let blue = Color::rgb8(0, 0, 255);
+        let label = Label::new("WW").with_text_size(30.0).with_text_color(red);
+        let pill_rect = Rc::new(RefCell::new(Rect::ZERO));
+        let painted_rect = pill_rect.clone();
+        let widget = ModularWidget::new(WidgetPod::new(label))
+            .lifecycle_fn(|label, ctx, event, env| label.lifecycle(ctx, event, env))
+            .layout_fn(|label, ctx, bc, env| {
+                label.layout(ctx, bc, env);
+                ctx.place_child(label, Point::ORIGIN, env);
+                bc.max()
+            })
+            .paint_fn(move |label, ctx, _| {
+                let rect = ctx.draw_text_pill(label.as_ref().deref(), (10.0, 10.0), blue, 8.0);
+                *painted_rect.borrow_mut() = rect;
+            })
+            .children_fn(|label| smallvec![label.as_dyn()]);
+        let mut harness = TestHarness::create_with_size(widget, Size::new(120.0, 80.0));
+
+        let image = harness.render_to_pixels();
+        let pixel = |x: f64, y: f64| {
+            let i = (y as usize * image.width() + x as usize) * 4;
+            &image.raw_pixels()[i..i + 4]
+        };
+        let rect = *pill_rect.borrow();
+        assert_eq!(rect.origin(), Point::new(10.0, 10.0));
+
+        // The padding left of the text is the background color.
+        assert_eq!(pixel(rect.x0 + 6.0, rect.center().y), [0, 0, 255, 255]);
+        let text_rect = rect - Insets::uniform_xy(8.0, 4.0);
+        let has_red_glyphs = (text_rect.y0 as usize..text_rect.y1 as usize).any(|y| {
+            (text_rect.x0 as usize..text_rect.x1 as usize)
+                .any(|x| pixel(x as f64, y as f64) == [255, 0, 0, 255])
+        });
+        assert!(has_red_glyphs);
+    }
+
+    #[test]
+    fn width_range() {
+        const SHORT: &str = "Hi";
+        const LONG: &str = "The quick brown fox jumps over the lazy dog";
+        let label_width = |label: Label| {
+            let [label_id] = widget_ids();
+            let widget = Flex::column().with_child_id(label, label_id);
+            let harness = TestHarness::create_with_size(widget, Size::new(1000.0, 400.0));
+            let label = harness.get_widget(label_id);
+            let width = label.state().layout_rect().width();
+            let is_overflowing = label.downcast::<Label>().unwrap().deref().is_overflowing();
+            (width, is_overflowing)
+        };
+        let (short_width, _) = label_width(Label::new(SHORT));
+        let (long_width, _) = label_width(Label::new(LONG));
+        assert!(short_width < 50.0);
+        assert!(long_width > 150.0);
+
+        // Narrower than min: the label keeps the minimum width.
+        let (width, _) = label_width(Label::new(SHORT).with_width_range(50.0, 150.0));
+        assert_eq!(width, 50.0);
+
+        // Within the range: the label is as wide as its text.
+        let (width, _) = label_width(Label::new(SHORT).with_width_range(10.0, 150.0));
+        assert_eq!(width, short_width);
+
+        // Wider than max: the text is clipped to the maximum width.
+        let (width, is_overflowing) = label_width(
+            Label::new(LONG)
+                .with_line_break_mode(LineBreaking::Clip)
+                .with_width_range(50.0, 150.0),
+        );
+        assert_eq!(width, 150.0);
+        assert!(is_overflowing);
+    }
+
+    #[test]
+    fn emoji_presentation() {
+        fn render(label: Label) -> std::sync::Arc<[u8]> {
+            TestHarness::create_with_size(label, Size::new(60.0, 30.0)).render()
+        }
+
+        let phone = "\u{260E}";
+        let as_text = render(Label::new(format!("{phone}\u{FE0E}")));
+        let as_emoji = render(Label::new(format!("{phone}\u{FE0F}")));
+        // Without fonts that draw both presentations, there's nothing to compare.
+        if as_text == as_emoji {
+            return;
+        }
+
+        // We don't use assert_eq because we don't want rich assert
+        let with_default =
+            |presentation| render(Label::new(phone).with_emoji_presentation(presentation));
+        assert!(with_default(EmojiPresentation::Text) == as_text);
+        assert!(with_default(EmojiPresentation::Emoji) == as_emoji);
+        assert!(with_default(EmojiPresentation::Text) != with_default(EmojiPresentation::Emoji));
+
+        // An explicit selector wins over the default.
+        let explicit = Label::new(format!("{phone}\u{FE0E}"))
+            .with_emoji_presentation(EmojiPresentation::Emoji);
+        assert!(render(explicit) == as_text);
+    }
+
+    #[test]
+    fn set_emoji_presentation() {
+        let text = "Call \u{260E}\u{FE0E} or \u{260E}";
+        let label = Label::new(text).with_emoji_presentation(EmojiPresentation::Emoji);
+        let mut harness = TestHarness::create(label);
+        fn laid_out(harness: &TestHarness) -> String {
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            label.text_layout.layout().unwrap().text().to_string()
+        }
+        let label_text = |harness: &TestHarness| {
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            label.text().to_string()
+        };
+
+        // The selector is only added to the laid-out text.
+        assert_eq!(laid_out(&harness), format!("{text}\u{FE0F}"));
+        assert_eq!(label_text(&harness), text);
+        {
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            let end = label.text_layout.point_for_text_position(text.len());
+            assert_eq!(label.text_layout.text_position_for_point(end), text.len());
+        }
+
+        // Laying out the same text again doesn't reshape it.
+        harness.edit_root_widget(|mut root, _| {
+            let mut label = root.downcast::<Label>().unwrap();
+            label.reset_shape_cache_stats();
+            label.ctx.request_layout();
+        });
+        {
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            assert_eq!(label.shape_cache_stats().misses, 0);
+        }
+
+        fn set_presentation(harness: &mut TestHarness, presentation: EmojiPresentation) {
+            harness.edit_root_widget(|mut root, _| {
+                let mut label = root.downcast::<Label>().unwrap();
+                label.set_emoji_presentation(presentation);
+            });
+        }
+        set_presentation(&mut harness, EmojiPresentation::Text);
+        assert_eq!(laid_out(&harness), format!("{text}\u{FE0E}"));
+        assert_eq!(label_text(&harness), text);
+        set_presentation(&mut harness, EmojiPresentation::Auto);
+        assert_eq!(laid_out(&harness), text);
+    }
+
+    #[test]
+    fn drag_to_select_and_copy() {
+        let [label_id] = widget_ids();
+        let label = Label::new("Hello world")
+            .with_selectable(true)
+            .with_id(label_id);
+
+        let mut harness = TestHarness::create(label);
+        let window_point = |harness: &TestHarness, offset: usize| {
+            let widget = harness.get_widget(label_id);
+            let origin = widget.state().window_layout_rect().origin();
+            let label = widget.downcast::<Label>().unwrap().deref();
+            origin
+                + label.text_origin.to_vec2()
+                + label.text_layout.point_for_text_position(offset).to_vec2()
+        };
+
+        let start = window_point(&harness, 2);
+        let end = window_point(&harness, 5);
+        harness.mouse_move(start);
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_move(end);
+        harness.mouse_button_release(MouseButton::Left);
+
+        assert_eq!(harness.focused_widget().unwrap().id(), label_id);
+        let selection = |harness: &TestHarness| {
+            let label = harness.get_widget(label_id);
+            label.downcast::<Label>().unwrap().deref().selection()
+        };
+        assert_eq!(selection(&harness), Selection::new(2, 5));
+
+        let cmd = if cfg!(target_os = "macos") {
+            RawMods::Meta
+        } else {
+            RawMods::Ctrl
+        };
+        harness.start_recording_commands();
+        harness.process_event(Event::KeyDown(KeyEvent::for_test(cmd, "c")));
+        assert_eq!(
+            harness.take_recorded_commands(),
+            vec![RecordedCommand::new(
+                Some(label_id),
+                command::COPY,
+                Target::Widget(label_id)
+            )]
+        );
+        assert_eq!(selection(&harness), Selection::new(2, 5));
+        let label = harness.get_widget(label_id).downcast::<Label>().unwrap();
+        assert_eq!(label.deref().selected_text(), Some("llo"));
+    }
+
+    #[test]
+    fn pending_label() {
+        let [label_id] = widget_ids();
+        let (sender, receiver) = std::sync::mpsc::channel::<()>();
+        let label = Label::pending("Loading...", move || {
+            receiver.recv().unwrap();
+            "Done".into()
+        })
+        .with_id(label_id);
+
+        let mut harness = TestHarness::create(label);
+        let text = |harness: &TestHarness| {
+            let label = harness.get_widget(label_id).downcast::<Label>().unwrap();
+            label.deref().text()
+        };
+        assert_eq!(&*text(&harness), "Loading...");
+
+        sender.send(()).unwrap();
+        harness.wait_for_ext_events(Duration::from_secs(5));
+        assert_eq!(&*text(&harness), "Done");
+    }
+
+    #[test]
+    fn clip_overflow_to_parent() {
+        fn render_label(label: Label) -> std::sync::Arc<[u8]> {
+            let widget = Flex::row()
+                .with_child(SizedBox::new(label).width(50.0))
+                .with_flex_spacer(1.0);
+            let mut harness = TestHarness::create_with_size(widget, Size::new(200.0, 50.0));
+            harness.render()
+        }
+
+        const TEXT: &str = "The quick brown fox jumps over the lazy dog";
+
+        let clipped = render_label(Label::new(TEXT).with_line_break_mode(LineBreaking::Clip));
+        let overflowing = render_label(Label::new(TEXT));
+        let clipped_overflow = render_label(Label::new(TEXT).with_clip_overflow_to_parent(true));
+
+        // We don't use assert_eq because we don't want rich assert
+        assert!(clipped_overflow == clipped);
+        assert!(clipped_overflow != overflowing);
+    }
+
+    #[test]
+    fn tight_height() {
+        const TEXT: &str = "First line\nSecond line\nThird line";
+        let [label_id] = widget_ids();
+
+        fn render_label(label: Label, id: WidgetId) -> (Size, std::sync::Arc<[u8]>) {
+            let widget = Flex::column()
+                .with_child(SizedBox::new_with_id(label, id).height(20.0))
+                .with_flex_spacer(1.0);
+            let mut harness = TestHarness::create_with_size(widget, Size::new(200.0, 100.0));
+            let size = harness.get_widget(id).state().layout_rect().size();
+            (size, harness.render())
+        }
+
+        let (size, clipped) = render_label(Label::new(TEXT).with_height_clip(true), label_id);
+        assert_eq!(size.height, 20.0);
+        let (_, overflowing) = render_label(Label::new(TEXT), label_id);
+        let (_, fully_clipped) = render_label(
+            Label::new(TEXT).with_line_break_mode(LineBreaking::Clip),
+            label_id,
+        );
+
+        // The lines are short enough that only the height needs clipping.
+        // We don't use assert_eq because we don't want rich assert
+        assert!(clipped == fully_clipped);
+        assert!(clipped != overflowing);
+
+        // With a loose height, the label is as tall as its text.
+        let widget =
+            Flex::column().with_child_id(Label::new(TEXT).with_height_clip(true), label_id);
+        let harness = TestHarness::create(widget);
+        let natural_height = harness.get_widget(label_id).state().layout_rect().height();
+        assert!(natural_height > 20.0, "{natural_height}");
+    }
+
+    #[test]
+    fn rtl_layout_direction() {
+        let label_width = 200.0;
+        let text_rect = |label: Label| {
+            let [label_id] = widget_ids();
+            let widget = Flex::column()
+                .with_child(SizedBox::new_with_id(label, label_id).width(label_width));
+            let harness = TestHarness::create(widget);
+            let label = harness.get_widget(label_id);
+            let label = label.downcast::<Label>().unwrap();
+            label.text_layout.size().to_rect() + label.text_origin.to_vec2()
+        };
+
+        let ltr = text_rect(Label::new("Hello"));
+        assert_eq!(ltr.x0, LABEL_X_PADDING);
+
+        // The padding and start-aligned text go to the right edge.
+        let rtl = text_rect(Label::new("Hello").with_layout_direction(LayoutDirection::Rtl));
+        assert_eq!(rtl.x1, label_width - LABEL_X_PADDING);
+        assert_eq!(rtl.width(), ltr.width());
+
+        // With `Auto`, only right-to-left text is mirrored.
+        let auto_ltr = text_rect(Label::new("Hello").with_layout_direction(LayoutDirection::Auto));
+        assert_eq!(auto_ltr.x0, LABEL_X_PADDING);
+        let auto_rtl = text_rect(
+            Label::new("\u{5E9}\u{5DC}\u{5D5}\u{5DD}").with_layout_direction(LayoutDirection::Auto),
+        );
+        assert_eq!(auto_rtl.x1, label_width - LABEL_X_PADDING);
+    }
+
+    #[test]
+    fn themed_padding() {
+        let [themed_id, padded_id] = widget_ids();
+        let widget = Flex::column()
+            .with_child_id(Label::new("Hello"), themed_id)
+            .with_child_id(Label::new("Hello").with_padding(5.0), padded_id);
+        let mut harness = TestHarness::create(widget);
+
+        let text_x = |harness: &TestHarness, id| {
+            let label = harness.get_widget(id);
+            label.downcast::<Label>().unwrap().text_origin.x
+        };
+        let width =
+            |harness: &TestHarness, id| harness.get_widget(id).state().layout_rect().width();
+        assert_eq!(text_x(&harness, themed_id), LABEL_X_PADDING);
+        assert_eq!(text_x(&harness, padded_id), 5.0);
+        let themed_width = width(&harness, themed_id);
+        let padded_width = width(&harness, padded_id);
+
+        harness.edit_env(|env| env.set(theme::LABEL_X_PADDING, 10.0));
+        assert_eq!(text_x(&harness, themed_id), 10.0);
+        assert_eq!(width(&harness, themed_id), themed_width + 16.0);
+        // The label's own padding wins over the theme.
+        assert_eq!(text_x(&harness, padded_id), 5.0);
+        assert_eq!(width(&harness, padded_id), padded_width);
+
+        harness.edit_root_widget(|mut flex, _| {
+            let mut flex = flex.downcast::<Flex>().unwrap();
+            let mut label = flex.child_mut(1).unwrap();
+            let mut label = label.downcast::<Label>().unwrap();
+            label.set_padding(theme::LABEL_X_PADDING);
+        });
+        assert_eq!(text_x(&harness, padded_id), 10.0);
+    }
+
+    #[test]
+    fn dynamic_text() {
+        let [label_id] = widget_ids();
+        let label = Label::new(|env: &Env| format!("Scale: {}", env.get(theme::FONT_SCALE)));
+        let widget = Flex::column().with_child_id(label, label_id);
+
+        let mut harness = TestHarness::create(widget);
+        let text = |harness: &TestHarness| {
+            let label = harness.get_widget(label_id);
+            label.downcast::<Label>().unwrap().deref().text()
+        };
+        assert_eq!(&*text(&harness), "Scale: 1");
+
+        harness.edit_env(|env| env.set(theme::FONT_SCALE, 2.0));
+        assert_eq!(&*text(&harness), "Scale: 2");
+
+        // Setting the text explicitly replaces the closure.
+        harness.edit_root_widget(|mut flex, _| {
+            let mut flex = flex.downcast::<Flex>().unwrap();
+            let mut label = flex.child_mut(0).unwrap();
+            label.downcast::<Label>().unwrap().set_text("Fixed");
+        });
+        harness.edit_env(|env| env.set(theme::FONT_SCALE, 3.0));
+        assert_eq!(&*text(&harness), "Fixed");
+    }
+
+    #[test]
+    fn themed_line_breaking() {
+        const TEXT: &str = "The quick brown fox jumps over the lazy dog";
+        let [themed_id, explicit_id] = widget_ids();
+        let widget = Flex::column()
+            .with_child_id(Label::new(TEXT), themed_id)
+            .with_child_id(
+                Label::new(TEXT).with_line_break_mode(LineBreaking::Overflow),
+                explicit_id,
+            );
+        let mut harness = TestHarness::create_with_size(widget, Size::new(150.0, 400.0));
+        let line_count = |harness: &TestHarness, id| {
+            let label = harness.get_widget(id);
+            let label = label.downcast::<Label>().unwrap().deref();
+            label.text_layout.layout().unwrap().line_count()
+        };
+        assert_eq!(line_count(&harness, themed_id), 1);
+
+        harness
+            .edit_env(|env| env.set(theme::LABEL_LINE_BREAKING, Arc::new(LineBreaking::WordWrap)));
+        assert!(line_count(&harness, themed_id) > 1);
+        // The label's own mode wins over the theme.
+        assert_eq!(line_count(&harness, explicit_id), 1);
+    }
+
+    #[test]
+    fn resolve_reports_changes() {
+        let mut env = Env::empty();
+        env.set(theme::FONT_SCALE, 1.0);
+        let mut text = LabelText::from(|env: &Env| {
+            if env.get(theme::FONT_SCALE) > 1.0 {
+                "large"
+            } else {
+                "normal"
+            }
+        });
+        assert_eq!(&*text.display_text(), "");
+        assert!(text.resolve(&env));
+        assert!(!text.resolve(&env));
+        text.with_display_text(|text| assert_eq!(text, "normal"));
+
+        env.set(theme::FONT_SCALE, 2.0);
+        assert!(text.resolve(&env));
+        assert_eq!(&*text.display_text(), "large");
+
+        let mut fixed = LabelText::from("fixed");
+        assert!(!fixed.resolve(&env));
+    }
+
+    // Counts the warnings logged by this module.
+    struct WarningCounter(Arc<AtomicUsize>);
+
+    impl tracing::Subscriber for WarningCounter {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            tracing::span::Id::from_u64(1)
+        }
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+        fn event(&self, event: &tracing::Event<'_>) {
+            let metadata = event.metadata();
+            if *metadata.level() == tracing::Level::WARN
+                && metadata.target() == "masonry::widget::label"
+            {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+        fn enter(&self, _: &tracing::span::Id) {}
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[test]
+    fn fallback_text() {
+        const GREETING: Key<ArcStr> = Key::new("masonry-test.label.greeting");
+
+        let warnings = Arc::new(AtomicUsize::new(0));
+        let subscriber = WarningCounter(warnings.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let label = Label::new(DynamicText::fallible(|env: &Env| env.try_get(GREETING)))
+                .with_fallback_text("Hello");
+            let mut harness = TestHarness::create(label);
+            let text = |harness: &TestHarness| {
+                let label = harness.root_widget();
+                label.downcast::<Label>().unwrap().deref().text()
+            };
+            assert_eq!(&*text(&harness), "Hello");
+
+            // Relayouts with the key still missing don't log again.
+            harness.edit_env(|env| env.set(theme::FONT_SCALE, 2.0));
+            assert_eq!(&*text(&harness), "Hello");
+            assert_eq!(warnings.load(Ordering::SeqCst), 1);
+
+            harness.edit_env(|env| env.set(GREETING, ArcStr::from("Bonjour")));
+            assert_eq!(&*text(&harness), "Bonjour");
+            assert_eq!(warnings.load(Ordering::SeqCst), 1);
+        });
+    }
+
+    #[test]
+    fn font_scale() {
+        let [label_id] = widget_ids();
+        let widget = Flex::column().with_child_id(Label::new("Hello world"), label_id);
+
+        let mut harness = TestHarness::create(widget);
+        let text_size = |harness: &TestHarness| {
+            let size = harness.get_widget(label_id).state().layout_rect().size();
+            Size::new(size.width - 2.0 * LABEL_X_PADDING, size.height)
+        };
+        let assert_scaled = |size: Size, base: Size, factor: f64| {
+            let width_ratio = size.width / base.width;
+            let height_ratio = size.height / base.height;
+            assert!((width_ratio - factor).abs() < 0.1 * factor, "{width_ratio}");
+            assert!(
+                (height_ratio - factor).abs() < 0.1 * factor,
+                "{height_ratio}"
+            );
+        };
+        let base_size = text_size(&harness);
+
+        harness.edit_env(|env| env.set(theme::FONT_SCALE, 2.0));
+        assert_scaled(text_size(&harness), base_size, 2.0);
+
+        // The accessibility text scale multiplies with the font scale
+        harness.edit_env(|env| env.set(theme::TEXT_SCALE, 1.5));
+        assert_scaled(text_size(&harness), base_size, 3.0);
+    }
+
+    #[test]
+    fn overflow_callback() {
+        let overflow_changes: Rc<RefCell<Vec<bool>>> = Default::default();
+        let label = Label::new("Hello world").with_on_overflow({
+            let overflow_changes = overflow_changes.clone();
+            move |overflowing| overflow_changes.borrow_mut().push(overflowing)
+        });
+
+        let mut harness = TestHarness::create_with_size(label, Size::new(200.0, 50.0));
+        assert!(overflow_changes.borrow().is_empty());
+
+        harness.process_event(Event::WindowSize(Size::new(20.0, 50.0)));
+        assert_eq!(*overflow_changes.borrow(), [true]);
+
+        // Staying truncated doesn't fire the callback again
+        harness.process_event(Event::WindowSize(Size::new(15.0, 50.0)));
+        assert_eq!(*overflow_changes.borrow(), [true]);
+
+        harness.process_event(Event::WindowSize(Size::new(200.0, 50.0)));
+        assert_eq!(*overflow_changes.borrow(), [true, false]);
+    }
+
+    #[test]
+    fn measure_callback() {
+        let sizes: Rc<RefCell<Vec<Size>>> = Default::default();
+        let label = Label::new("Hello world")
+            .with_line_break_mode(LineBreaking::WordWrap)
+            .with_on_measure({
+                let sizes = sizes.clone();
+                move |size| sizes.borrow_mut().push(size)
+            });
+        let widget = Flex::column().with_child(SizedBox::new(label).width(200.0));
+
+        let mut harness = TestHarness::create(widget);
+        let wide = *sizes.borrow().last().unwrap();
+        assert_eq!(wide.width, 200.0);
+
+        harness.edit_root_widget(|mut flex, _| {
+            let mut flex = flex.downcast::<Flex>().unwrap();
+            let mut sized_box = flex.child_mut(0).unwrap();
+            sized_box.downcast::<SizedBox>().unwrap().set_width(30.0);
+        });
+        let narrow = *sizes.borrow().last().unwrap();
+        assert_eq!(narrow.width, 30.0);
+        // The text wraps, so the label gets taller.
+        assert!(narrow.height > wide.height);
+    }
 
-    use super::*;
-    use crate::assert_render_snapshot;
-    use crate::testing::TestHarness;
-    use crate::theme::{PRIMARY_DARK, PRIMARY_LIGHT};
-    use crate::widget::{Flex, SizedBox};
+    #[test]
+    fn line_alignment_override() {
+        const TEXT: &str = "short\nmiddle line\nthe longest line of them all";
+        let window_size = Size::new(300.0, 100.0);
+
+        let render = |label: Label| {
+            let mut harness = TestHarness::create_with_size(label, window_size);
+            let image = harness.render();
+            let label = harness.root_widget().downcast::<Label>().unwrap();
+            let line = label.text_layout.layout().unwrap().line_metric(1).unwrap();
+            (image, line.y_offset, line.y_offset + line.height)
+        };
+
+        let (base, top, bottom) = render(Label::new(TEXT));
+        // Index 5 is past the last line and should be ignored.
+        let (overridden, _, _) = render(Label::new(TEXT).with_line_alignment_overrides(vec![
+            (1, TextAlignment::End),
+            (5, TextAlignment::Center),
+        ]));
+
+        let row_len = window_size.width as usize * 4;
+        let rows = base.chunks(row_len).zip(overridden.chunks(row_len));
+        let mut line_1_changed = false;
+        for (y, (base_row, overridden_row)) in rows.enumerate() {
+            let y = y as f64;
+            if y + 1.0 <= top || y >= bottom {
+                assert!(
+                    base_row == overridden_row,
+                    "row {} outside line 1 changed",
+                    y
+                );
+            } else if y >= top.ceil() && y + 1.0 <= bottom.floor() {
+                line_1_changed |= base_row != overridden_row;
+            }
+        }
+        assert!(line_1_changed);
+    }
 
     #[test]
-    fn simple_label() {
-        let label = Label::new("Hello");
+    fn first_and_last_baselines() {
+        let [label_id] = widget_ids();
+        let widget = Flex::column().with_child_id(Label::new("First line\nSecond line"), label_id);
 
-        let mut harness = TestHarness::create(label);
+        let harness = TestHarness::create(widget);
+        let state = harness.get_widget(label_id).state();
 
-        assert_debug_snapshot!(harness.root_widget());
-        assert_render_snapshot!(harness, "hello");
+        let line_height = state.layout_rect().height() / 2.0;
+        assert!(state.first_baseline() > 0.0);
+        assert!(state.first_baseline() < line_height);
+        assert!(state.last_baseline() > line_height);
+        assert!(state.last_baseline() < state.layout_rect().height());
+        // The baseline offset still points at the first line.
+        let height = state.layout_rect().height();
+        assert_eq!(state.first_baseline(), height - state.baseline_offset);
     }
 
     #[test]
-    fn styled_label() {
-        let label = Label::new("The quick brown fox jumps over the lazy dog")
-            .with_text_color(PRIMARY_LIGHT)
-            .with_font(FontDescriptor::new(FontFamily::MONOSPACE))
-            .with_text_size(20.0)
-            .with_line_break_mode(LineBreaking::WordWrap)
-            .with_text_alignment(TextAlignment::Center);
+    fn first_baseline_to_top() {
+        let [natural_id, offset_id] = widget_ids();
+        let widget = Flex::column()
+            .with_child_id(Label::new("Hello\nWorld"), natural_id)
+            .with_child_id(
+                Label::new("Hello\nWorld").with_first_baseline_to_top(40.0),
+                offset_id,
+            );
+        let mut harness = TestHarness::create(widget);
 
-        let mut harness = TestHarness::create_with_size(label, Size::new(200.0, 200.0));
+        let natural = harness.get_widget(natural_id).state();
+        let offset = harness.get_widget(offset_id).state();
+        assert!(natural.first_baseline() < 40.0);
+        assert!((offset.first_baseline() - 40.0).abs() < 1e-9);
+        let padding = 40.0 - natural.first_baseline();
+        let height = natural.layout_rect().height() + padding;
+        assert!((offset.layout_rect().height() - height).abs() < 1e-9);
+        let last_baseline = natural.last_baseline() + padding;
+        assert!((offset.last_baseline() - last_baseline).abs() < 1e-9);
 
-        assert_render_snapshot!(harness, "styled_label");
+        // An offset above the natural baseline leaves the text at the top.
+        harness.edit_root_widget(|mut flex, _| {
+            let mut flex = flex.downcast::<Flex>().unwrap();
+            let mut label = flex.child_mut(1).unwrap();
+            let mut label = label.downcast::<Label>().unwrap();
+            label.set_first_baseline_to_top(Some(1.0));
+        });
+        let natural = harness.get_widget(natural_id).state();
+        let offset = harness.get_widget(offset_id).state();
+        assert_eq!(offset.first_baseline(), natural.first_baseline());
+        assert_eq!(
+            offset.layout_rect().height(),
+            natural.layout_rect().height()
+        );
     }
 
     #[test]
-    fn line_break_modes() {
+    fn context_menu_offers_copy() {
+        let [selectable_id, plain_id] = widget_ids();
         let widget = Flex::column()
-            .with_flex_spacer(1.0)
-            .with_child(
-                SizedBox::new(
-                    Label::new("The quick brown fox jumps over the lazy dog")
-                        .with_line_break_mode(LineBreaking::WordWrap),
-                )
-                .width(200.0),
-            )
-            .with_spacer(20.0)
-            .with_child(
-                SizedBox::new(
-                    Label::new("The quick brown fox jumps over the lazy dog")
-                        .with_line_break_mode(LineBreaking::Clip),
-                )
-                .width(200.0),
+            .with_child_id(
+                Label::new("Selectable").with_selection_on_focus(true),
+                selectable_id,
             )
-            .with_spacer(20.0)
-            .with_child(
-                SizedBox::new(
-                    Label::new("The quick brown fox jumps over the lazy dog")
-                        .with_line_break_mode(LineBreaking::Overflow),
-                )
-                .width(200.0),
+            .with_child_id(Label::new("Plain"), plain_id);
+
+        let mut harness = TestHarness::create(widget);
+
+        harness.mouse_move_to(plain_id);
+        harness.mouse_button_press(MouseButton::Right);
+        harness.mouse_button_release(MouseButton::Right);
+        assert!(harness.pop_context_menu().is_none());
+
+        harness.mouse_move_to(selectable_id);
+        harness.mouse_button_press(MouseButton::Right);
+        harness.mouse_button_release(MouseButton::Right);
+        let (menu, pos) = harness.pop_context_menu().unwrap();
+        let copy_item = menu
+            .items()
+            .iter()
+            .find(|item| item.command().is(command::COPY))
+            .unwrap();
+        assert_eq!(copy_item.command().target(), Target::Widget(selectable_id));
+        assert_eq!(
+            pos,
+            harness
+                .get_widget(selectable_id)
+                .state()
+                .window_layout_rect()
+                .center()
+        );
+    }
+
+    #[test]
+    fn placeholder_shown_when_empty() {
+        fn render(label: Label) -> std::sync::Arc<[u8]> {
+            TestHarness::create_with_size(label, Size::new(100.0, 30.0)).render()
+        }
+
+        // We don't use assert_eq because we don't want rich assert
+        assert!(render(Label::new("")) != render(Label::new("").with_placeholder("Name")));
+        assert!(
+            render(Label::new("Alice")) == render(Label::new("Alice").with_placeholder("Name"))
+        );
+
+        // The label doesn't collapse while showing its placeholder.
+        let [label_id] = widget_ids();
+        let widget =
+            Flex::column().with_child_id(Label::new("").with_placeholder("Name"), label_id);
+        let mut harness = TestHarness::create(widget);
+        let placeholder_width = harness.get_widget(label_id).state().layout_rect().width();
+        assert!(placeholder_width > 2.0 * LABEL_X_PADDING);
+
+        harness.edit_root_widget(|mut flex, _| {
+            let mut flex = flex.downcast::<Flex>().unwrap();
+            let mut label = flex.child_mut(0).unwrap();
+            let mut label = label.downcast::<Label>().unwrap();
+            label.set_placeholder("A much longer placeholder");
+        });
+        let longer_width = harness.get_widget(label_id).state().layout_rect().width();
+        assert!(longer_width > placeholder_width);
+    }
+
+    #[test]
+    fn placeholder_follows_alignment_and_font() {
+        fn render(label: Label) -> std::sync::Arc<[u8]> {
+            let label = label
+                .with_placeholder("Name")
+                .with_line_break_mode(LineBreaking::WordWrap);
+            TestHarness::create_with_size(label, Size::new(200.0, 30.0)).render()
+        }
+
+        // We don't use assert_eq because we don't want rich assert
+        assert!(
+            render(Label::new(""))
+                != render(Label::new("").with_text_alignment(TextAlignment::End))
+        );
+        assert!(
+            render(Label::new(""))
+                != render(Label::new("").with_font(FontDescriptor::new(FontFamily::MONOSPACE)))
+        );
+    }
+
+    #[test]
+    fn ruby_annotations() {
+        let [plain_id, ruby_id] = widget_ids();
+        let ruby = vec![RubyRun {
+            base: 6..11,
+            text: "wo".into(),
+        }];
+        let widget = Flex::column()
+            .with_child_id(Label::new("Hello world"), plain_id)
+            .with_child_id(Label::new("Hello world").with_ruby(ruby), ruby_id);
+
+        let harness = TestHarness::create(widget);
+        let plain_rect = harness.get_widget(plain_id).state().layout_rect();
+        let ruby_state = harness.get_widget(ruby_id).state();
+        let ruby_rect = ruby_state.layout_rect();
+
+        // The annotation adds room above the line without changing the line itself.
+        assert!(ruby_rect.height() > plain_rect.height());
+        assert!(
+            ruby_state.first_baseline() > harness.get_widget(plain_id).state().first_baseline()
+        );
+        // A narrow annotation in the middle of the text doesn't widen the label.
+        assert!((ruby_rect.width() - plain_rect.width()).abs() < 1e-4);
+
+        let label = harness.get_widget(ruby_id);
+        let label = label.downcast::<Label>().unwrap().deref();
+        let annotation = &label.ruby[0];
+        let ruby_center = annotation.origin.unwrap().x + annotation.layout.size().width / 2.0;
+        let base_rect = label.text_layout.rects_for_range(6..11)[0];
+        assert!((ruby_center - base_rect.center().x).abs() < 1e-4);
+        assert!(annotation.origin.unwrap().y + annotation.layout.size().height <= base_rect.y0);
+    }
+
+    #[test]
+    fn wide_ruby_widens_label() {
+        let [plain_id, ruby_id] = widget_ids();
+        let ruby = vec![RubyRun {
+            base: 0..1,
+            text: "a very long annotation".into(),
+        }];
+        let widget = Flex::column()
+            .with_child_id(Label::new("Hi"), plain_id)
+            .with_child_id(Label::new("Hi").with_ruby(ruby), ruby_id);
+
+        let harness = TestHarness::create(widget);
+        let plain_width = harness.get_widget(plain_id).state().layout_rect().width();
+        let ruby_width = harness.get_widget(ruby_id).state().layout_rect().width();
+        assert!(ruby_width > plain_width);
+    }
+
+    #[test]
+    fn text_after_set_text() {
+        let mut harness = TestHarness::create(Label::new("Hello"));
+        harness.edit_root_widget(|mut label, _| {
+            label.downcast::<Label>().unwrap().set_text("Goodbye");
+        });
+
+        let label = harness.root_widget();
+        assert_eq!(label.deref().get_debug_text().as_deref(), Some("Goodbye"));
+        assert_eq!(
+            &*label.downcast::<Label>().unwrap().deref().text(),
+            "Goodbye"
+        );
+    }
+
+    #[test]
+    fn text_range_rects_across_lines() {
+        let [label_id] = widget_ids();
+        let label = Label::new("aaaa bbbb").with_line_break_mode(LineBreaking::WordWrap);
+        let widget = Flex::column().with_child(SizedBox::new_with_id(label, label_id).width(50.0));
+
+        let harness = TestHarness::create(widget);
+        let label = harness.get_widget(label_id);
+        let label = label.downcast::<Label>().unwrap().deref();
+        assert_eq!(label.text_layout.layout().unwrap().line_count(), 2);
+
+        let x = |offset: usize| {
+            label.text_origin.x + label.text_layout.point_for_text_position(offset).x
+        };
+        let rects = label.text_range_rects(2..7);
+        assert_eq!(rects.len(), 2);
+        // The first line is covered from the start of the range to its end, the
+        // second from its start to the end of the range.
+        assert!((rects[0].x0 - x(2)).abs() < 1e-6);
+        assert!(rects[0].x1 > rects[0].x0);
+        assert!((rects[1].x0 - label.text_origin.x).abs() < 1e-6);
+        assert!((rects[1].x1 - x(7)).abs() < 1e-6);
+        assert!(rects[1].y0 >= rects[0].y1 - 1e-6);
+
+        // The range is clamped to the text.
+        assert_eq!(
+            label.text_range_rects(7..100)[..],
+            label.text_range_rects(7..9)[..]
+        );
+        assert!(label
+            .text_range_rects(50..100)
+            .iter()
+            .all(|rect| rect.width() == 0.0));
+    }
+
+    #[test]
+    fn word_wrap_ellipsis() {
+        const PARAGRAPH: &str = "The quick brown fox jumps over the lazy dog, \
+            then runs off into the woods and is never seen again by anyone.";
+
+        let [label_id] = widget_ids();
+        let label = Label::new(PARAGRAPH)
+            .with_line_break_mode(LineBreaking::WordWrapEllipsis { max_lines: 2 });
+        let widget = Flex::column().with_child(SizedBox::new_with_id(label, label_id).width(120.0));
+
+        let harness = TestHarness::create(widget);
+        let label = harness.get_widget(label_id);
+        let height = label.state().layout_rect().height();
+        let label = label.downcast::<Label>().unwrap().deref();
+
+        let full_layout = label.text_layout.layout().unwrap();
+        assert!(full_layout.line_count() > 2);
+        let line_heights: f64 = (0..2)
+            .map(|i| full_layout.line_metric(i).unwrap().height)
+            .sum();
+        assert!((height - line_heights).abs() < 1e-6, "{height}");
+
+        let ellipsized = label.ellipsized_layout.as_ref().unwrap();
+        assert_eq!(ellipsized.layout().unwrap().line_count(), 2);
+        let shown = ellipsized.text().unwrap().as_str();
+        assert!(shown.ends_with(ELLIPSIS), "{shown}");
+        // The label still holds the whole text.
+        assert_eq!(&*label.text(), PARAGRAPH);
+    }
+
+    #[test]
+    fn ellipsis_is_cached() {
+        const PARAGRAPH: &str = "The quick brown fox jumps over the lazy dog, \
+            then runs off into the woods and is never seen again by anyone.";
+
+        let label = Label::new(PARAGRAPH)
+            .with_line_break_mode(LineBreaking::WordWrapEllipsis { max_lines: 2 });
+        let mut harness = TestHarness::create_with_size(label, Size::new(120.0, 400.0));
+        let shown = |harness: &TestHarness| {
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            let layout = label.ellipsized_layout.as_ref().unwrap();
+            layout.text().unwrap().as_str().to_string()
+        };
+        let before = shown(&harness);
+
+        // Laying out the same text again doesn't search for the cut again.
+        harness.edit_root_widget(|mut root, _| {
+            let mut label = root.downcast::<Label>().unwrap();
+            label.reset_shape_cache_stats();
+            label.ctx.request_layout();
+        });
+        let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+        assert_eq!(label.shape_cache_stats().misses, 0);
+        assert_eq!(shown(&harness), before);
+
+        // Allowing more lines shows more of the text.
+        harness.edit_root_widget(|mut root, _| {
+            let mut label = root.downcast::<Label>().unwrap();
+            label.set_line_break_mode(LineBreaking::WordWrapEllipsis { max_lines: 3 });
+        });
+        let after = shown(&harness);
+        assert!(after.len() > before.len(), "{after}");
+        assert!(after.ends_with(ELLIPSIS), "{after}");
+    }
+
+    #[test]
+    fn layout_metrics() {
+        let [small_id, large_id] = widget_ids();
+        let widget = Flex::row()
+            .with_child_id(Label::new("Hello").with_text_size(12.0), small_id)
+            .with_child_id(Label::new("Hello").with_text_size(24.0), large_id);
+
+        let harness = TestHarness::create(widget);
+        let metrics = |id| {
+            let label = harness.get_widget(id);
+            label.downcast::<Label>().unwrap().deref().layout_metrics()
+        };
+        let small = metrics(small_id);
+        let large = metrics(large_id);
+
+        assert!(small.first_baseline > 0.0);
+        let ratio = large.first_baseline / small.first_baseline;
+        assert!((ratio - 2.0).abs() < 0.2, "{ratio}");
+        assert!(large.size.height > small.size.height);
+        // Single lines have the same first and last baseline.
+        assert_eq!(small.first_baseline, small.last_baseline);
+        // The metrics don't include the padding.
+        let small_width = harness.get_widget(small_id).state().layout_rect().width();
+        assert!((small.size.width - (small_width - 2.0 * LABEL_X_PADDING)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn replace_range() {
+        let label = Label::new("Hello world!");
+        let mut harness = TestHarness::create(label);
+
+        harness.edit_root_widget(|mut label, _| {
+            let mut label = label.downcast::<Label>().unwrap();
+            label.set_selection(Selection::caret(11));
+            label.replace_range(6..11, "brave new world");
+        });
+
+        let label = harness.root_widget();
+        let label = label.downcast::<Label>().unwrap().deref();
+        assert_eq!(&*label.text(), "Hello brave new world!");
+        // The caret was after the replaced range, before the '!'.
+        assert_eq!(label.selection(), Selection::caret(21));
+    }
+
+    #[test]
+    fn attributed_text_colors() {
+        let size = Size::new(120.0, 30.0);
+        let text = RichText::new("MMMMMMMM".into())
+            .with_attribute(0..4, Attribute::TextColor(Color::RED.into()))
+            .with_attribute(4..8, Attribute::TextColor(Color::BLUE.into()));
+        let mut harness = TestHarness::create_with_size(Label::empty(), size);
+        harness.edit_root_widget(|mut label, _| {
+            let mut label = label.downcast::<Label>().unwrap();
+            label.set_attributed_text(text);
+        });
+
+        let image = harness.render();
+        let columns = |is_color: fn(&[u8]) -> bool| -> Vec<usize> {
+            image
+                .chunks(4)
+                .enumerate()
+                .filter(|(_, pixel)| is_color(pixel))
+                .map(|(i, _)| i % size.width as usize)
+                .collect()
+        };
+        let red = columns(|p| p[0] > 128 && p[1] < 64 && p[2] < 64);
+        let blue = columns(|p| p[2] > 128 && p[0] < 64 && p[1] < 64);
+        assert!(!red.is_empty() && !blue.is_empty());
+        // The first span is drawn entirely before the second.
+        assert!(red.iter().max() < blue.iter().min());
+    }
+
+    #[test]
+    fn link_click() {
+        const HOME: Selector = Selector::new("masonry-test.label.home");
+
+        let [label_id] = widget_ids();
+        let mut builder = RichTextBuilder::new();
+        builder.push("home").link(HOME);
+        builder.push(" sweet home");
+        let label = Label::empty().with_attributed_text(builder.build());
+        let widget = Flex::column().with_child_id(label, label_id);
+
+        let mut harness = TestHarness::create(widget);
+        let (link_rect, plain_rect) = {
+            let label = harness.get_widget(label_id);
+            let origin = label.state().window_layout_rect().origin().to_vec2();
+            let label = label.downcast::<Label>().unwrap().deref();
+            let text_origin = label.text_origin.to_vec2() + origin;
+            let rect = |range| label.text_layout.rects_for_range(range)[0] + text_origin;
+            (rect(0..4), rect(5..10))
+        };
+        let in_link = Point::new(link_rect.x0 + 1.0, link_rect.center().y);
+        // Left of the text, in the label's padding.
+        let in_padding = Point::new(link_rect.x0 - 1.0, link_rect.center().y);
+
+        harness
+            .script()
+            .click(link_rect.center())
+            .expect_command(HOME)
+            .click(in_link)
+            .expect_command(HOME)
+            .click(in_padding)
+            .click(plain_rect.center())
+            .expect_no_command()
+            // Dragging onto the link from elsewhere isn't a click on it, nor is
+            // dragging off it.
+            .mouse_down(plain_rect.center())
+            .mouse_up(link_rect.center())
+            .mouse_down(link_rect.center())
+            .mouse_up(plain_rect.center())
+            .expect_no_command();
+    }
+
+    #[test]
+    fn link_hover_underline() {
+        const HOME: Selector = Selector::new("masonry-test.label.hover-home");
+
+        let [link_label_id, plain_label_id] = widget_ids();
+        let mut builder = RichTextBuilder::new();
+        builder.push("home").link(HOME);
+        let widget = Flex::column()
+            .with_child_id(
+                Label::empty().with_attributed_text(builder.build()),
+                link_label_id,
             )
-            .with_flex_spacer(1.0);
+            .with_child_id(Label::new("plain"), plain_label_id);
 
         let mut harness = TestHarness::create(widget);
+        let _ = harness.simulate_frame();
+        let link_rect = harness
+            .get_widget(link_label_id)
+            .state()
+            .window_layout_rect();
+        let plain_rect = harness
+            .get_widget(plain_label_id)
+            .state()
+            .window_layout_rect();
+        let not_hot = harness.render();
 
-        assert_render_snapshot!(harness, "line_break_modes");
+        harness.mouse_move(link_rect.center());
+        assert!(harness.get_widget(link_label_id).state().is_hot);
+        let summary = harness.simulate_frame();
+        assert!(summary
+            .invalid
+            .rects()
+            .iter()
+            .any(|rect| rect.contains(link_rect.center())));
+        assert!(harness.render() != not_hot);
+
+        // Labels without links don't repaint when hovered.
+        harness.mouse_move(plain_rect.center());
+        let summary = harness.simulate_frame();
+        assert!(summary
+            .invalid
+            .rects()
+            .iter()
+            .any(|rect| rect.contains(link_rect.center())));
+        assert!(!summary
+            .invalid
+            .rects()
+            .iter()
+            .any(|rect| rect.contains(plain_rect.center())));
+        assert!(harness.render() == not_hot);
     }
 
     #[test]
-    fn edit_label() {
-        let image_1 = {
+    fn decoration_style() {
+        fn underlines(harness: &TestHarness) -> Vec<DecorationSpan> {
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            label
+                .decoration_spans
+                .iter()
+                .filter(|(_, kind)| *kind == DecorationKind::Underline)
+                .map(|(span, _)| *span)
+                .collect()
+        }
+
+        let size = Size::new(100.0, 30.0);
+        let mut harness =
+            TestHarness::create_with_size(Label::new("Hello").with_underline(true), size);
+        let [default_span] = underlines(&harness)[..] else {
+            panic!("expected a single underline");
+        };
+        let default_render = harness.render();
+
+        harness.edit_root_widget(|mut label, _| {
+            let mut label = label.downcast::<Label>().unwrap();
+            label.set_decoration_style(DecorationStyle {
+                thickness: Some(4.0),
+                offset: Some(2.0),
+                line_style: DecorationLineStyle::Solid,
+            });
+        });
+        let [span] = underlines(&harness)[..] else {
+            panic!("expected a single underline");
+        };
+        assert_eq!(span.thickness, 4.0);
+        assert_eq!(span.y, default_span.y + 2.0);
+        assert_eq!((span.x0, span.x1), (default_span.x0, default_span.x1));
+        let thick_render = harness.render();
+        assert!(thick_render != default_render);
+
+        harness.edit_root_widget(|mut label, _| {
+            let mut label = label.downcast::<Label>().unwrap();
+            label.set_decoration_style(DecorationStyle {
+                thickness: Some(4.0),
+                offset: Some(2.0),
+                line_style: DecorationLineStyle::Wavy,
+            });
+        });
+        assert!(harness.render() != thick_render);
+    }
+
+    #[test]
+    fn word_spacing() {
+        let [plain_id, spaced_id] = widget_ids();
+        let text = "one two three four";
+        let widget = Flex::column()
+            .with_child_id(Label::new(text), plain_id)
+            .with_child_id(Label::new(text).with_word_spacing(10.0), spaced_id);
+
+        let harness = TestHarness::create(widget);
+        let plain_width = harness.get_widget(plain_id).state().layout_rect().width();
+        let spaced_width = harness.get_widget(spaced_id).state().layout_rect().width();
+        // Three spaces, each 10px wider.
+        let extra_width = spaced_width - plain_width;
+        assert!((extra_width - 30.0).abs() < 1.5, "{extra_width}");
+    }
+
+    #[test]
+    fn letter_spacing() {
+        fn text_width(spacing: f64) -> f64 {
+            let harness = TestHarness::create(Label::new("Tracking").with_letter_spacing(spacing));
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            label.text_layout.size().width
+        }
+
+        // Seven gaps between eight letters, each 4px wider; none after the last.
+        let extra_width = text_width(4.0) - text_width(0.0);
+        assert!((extra_width - 28.0).abs() < 1.5, "{extra_width}");
+    }
+
+    #[test]
+    fn letter_spacing_offsets() {
+        let label = Label::new("ab cd").with_letter_spacing(6.0);
+        let harness = TestHarness::create(label);
+        let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+        let layout = &label.text_layout;
+
+        // Offsets are into the text, not into the text with the spacing added.
+        assert_eq!(layout.line_range(0), Some(0..5));
+        let b = layout.point_for_text_position(1);
+        assert_eq!(layout.text_position_for_point(b + Vec2::new(1.0, 0.0)), 1);
+        // The spacing after a character belongs to it.
+        let space = layout.point_for_text_position(2);
+        assert_eq!(
+            layout.text_position_for_point(space - Vec2::new(1.0, 0.0)),
+            2
+        );
+        let end = layout.point_for_text_position(5).x;
+        assert!((end - layout.size().width).abs() < 0.5, "{end}");
+    }
+
+    #[test]
+    fn line_height() {
+        fn text_height(label: Label) -> f64 {
+            let harness = TestHarness::create(label);
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            label.text_layout.size().height
+        }
+
+        let label = || Label::new("One\nTwo\nThree").with_text_size(10.0);
+        let natural = text_height(label());
+        let spaced = text_height(label().with_line_height(1.5));
+        let double_spaced = text_height(label().with_line_height(3.0));
+        assert_eq!(spaced, 45.0);
+        assert_eq!(double_spaced, 2.0 * spaced);
+        assert!(natural < double_spaced);
+
+        let single_line = text_height(Label::new("One").with_text_size(10.0).with_line_height(2.0));
+        assert_eq!(single_line, 20.0);
+
+        // A line with larger text gets a taller box.
+        let text =
+            RichText::new("One\nTwo\nThree".into()).with_attribute(4..7, Attribute::size(20.0));
+        let mixed = text_height(
+            Label::empty()
+                .with_attributed_text(text)
+                .with_text_size(10.0)
+                .with_line_height(1.5),
+        );
+        assert_eq!(mixed, 60.0);
+    }
+
+    #[test]
+    fn justified() {
+        let wrap_width = 150.0;
+        let label = Label::new(
+            "The quick brown fox jumps over the lazy dog while the cat watches from the fence",
+        )
+        .with_text_alignment(TextAlignment::Justified)
+        .with_fixed_wrap_width(Some(wrap_width));
+        let harness = TestHarness::create_with_size(label, Size::new(400.0, 400.0));
+        let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+        let layout = label.text_layout.layout().unwrap();
+
+        let line_count = layout.line_count();
+        assert!(line_count >= 3, "{line_count}");
+        let line_widths: Vec<f64> = (0..line_count)
+            .map(|line| {
+                let metric = layout.line_metric(line).unwrap();
+                let end = metric.end_offset - metric.trailing_whitespace;
+                let rects = label.text_layout.rects_for_range(metric.start_offset..end);
+                let x0 = rects
+                    .iter()
+                    .map(|rect| rect.x0)
+                    .fold(f64::INFINITY, f64::min);
+                let x1 = rects.iter().map(|rect| rect.x1).fold(0.0, f64::max);
+                x1 - x0
+            })
+            .collect();
+
+        let (last, lines) = line_widths.split_last().unwrap();
+        for width in lines {
+            assert!((width - wrap_width).abs() < 1.0, "{line_widths:?}");
+        }
+        assert!(*last < wrap_width - 1.0, "{line_widths:?}");
+        assert!((label.text_layout.size().width - wrap_width).abs() < 1.0);
+    }
+
+    #[test]
+    fn fit_strategy() {
+        fn fitted(harness: &TestHarness) -> (usize, f64, Size) {
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            let line_count = label.text_layout.layout().unwrap().line_count();
+            (
+                line_count,
+                label.text_layout.text_size_scale(),
+                label.text_layout.size(),
+            )
+        }
+
+        let fit = FitStrategy {
+            max_lines: 2,
+            min_font_size: 4.0,
+        };
+        let label = || {
+            Label::new("The quick brown fox jumps over the lazy dog")
+                .with_text_size(20.0)
+                .with_fit_strategy(fit)
+        };
+
+        // Plenty of height: the text only wraps.
+        let harness = TestHarness::create_with_size(label(), Size::new(300.0, 200.0));
+        let (line_count, scale, _) = fitted(&harness);
+        assert_eq!(line_count, 2);
+        assert_eq!(scale, 1.0);
+
+        // Too narrow for two lines at the base size, and too short: the text
+        // wraps to at most two lines, then shrinks until it fits the height.
+        let harness = TestHarness::create_with_size(label(), Size::new(150.0, 30.0));
+        let (line_count, scale, size) = fitted(&harness);
+        assert!(line_count <= 2, "{line_count}");
+        assert!(scale < 1.0 && scale >= 4.0 / 20.0, "{scale}");
+        assert!(size.height <= 30.0, "{size}");
+    }
+
+    #[test]
+    fn min_readable_font_size() {
+        fn font_size(harness: &TestHarness) -> (f64, bool) {
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            let size = label.text_layout.text_size(&Env::with_theme())
+                * label.text_layout.text_size_scale();
+            (size, label.is_font_size_clamped())
+        }
+
+        let warnings = Arc::new(AtomicUsize::new(0));
+        let subscriber = WarningCounter(warnings.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            // Shrinking to fit would go down to 2px, but the clamp stops it at 10px.
+            let fit = FitStrategy {
+                max_lines: 1,
+                min_font_size: 2.0,
+            };
             let label = Label::new("The quick brown fox jumps over the lazy dog")
-                .with_text_color(PRIMARY_LIGHT)
-                .with_font(FontDescriptor::new(FontFamily::MONOSPACE))
                 .with_text_size(20.0)
-                .with_line_break_mode(LineBreaking::WordWrap)
-                .with_text_alignment(TextAlignment::Center);
+                .with_fit_strategy(fit)
+                .with_min_font_size_for_readability(10.0);
+            let mut harness = TestHarness::create_with_size(label, Size::new(60.0, 30.0));
+            let (size, clamped) = font_size(&harness);
+            assert!((size - 10.0).abs() < 1e-9, "{size}");
+            assert!(clamped);
+            assert!(harness
+                .root_widget()
+                .downcast::<Label>()
+                .unwrap()
+                .deref()
+                .is_overflowing());
+            assert_eq!(warnings.load(Ordering::SeqCst), 1);
 
-            let mut harness = TestHarness::create_with_size(label, Size::new(50.0, 50.0));
+            // With the clamp disabled, the text shrinks below it.
+            harness.edit_root_widget(|mut root, _| {
+                let mut label = root.downcast::<Label>().unwrap();
+                label.set_min_font_size_for_readability(0.0);
+            });
+            let (size, clamped) = font_size(&harness);
+            assert!(size < 10.0, "{size}");
+            assert!(!clamped);
+            assert_eq!(warnings.load(Ordering::SeqCst), 1);
+        });
+    }
 
-            harness.render()
+    #[test]
+    fn min_readable_font_size_without_fit() {
+        let warnings = Arc::new(AtomicUsize::new(0));
+        let subscriber = WarningCounter(warnings.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let label = Label::new("Tiny")
+                .with_text_size(4.0)
+                .with_min_font_size_for_readability(10.0);
+            let mut harness = TestHarness::create(label);
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            assert!(label.is_font_size_clamped());
+            assert_eq!(warnings.load(Ordering::SeqCst), 1);
+
+            // Laying out again neither reshapes the text nor warns again.
+            harness.edit_root_widget(|mut root, _| {
+                let mut label = root.downcast::<Label>().unwrap();
+                label.reset_shape_cache_stats();
+                label.ctx.request_layout();
+            });
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            assert_eq!(label.shape_cache_stats().misses, 0);
+            assert_eq!(warnings.load(Ordering::SeqCst), 1);
+        });
+    }
+
+    // The stats are only counted in debug builds.
+    #[cfg(debug_assertions)]
+    #[test]
+    fn shape_cache_stats() {
+        fn stats(harness: &TestHarness) -> ShapeCacheStats {
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            label.shape_cache_stats()
+        }
+
+        let mut harness = TestHarness::create(Label::new("Hello"));
+        // Lay the label out again without changing it.
+        harness.edit_root_widget(|mut root, _| {
+            let mut label = root.downcast::<Label>().unwrap();
+            label.ctx.request_layout();
+        });
+
+        assert_eq!(
+            stats(&harness),
+            ShapeCacheStats {
+                shapes: 1,
+                hits: 1,
+                misses: 1,
+            }
+        );
+
+        harness.edit_root_widget(|mut root, _| {
+            let mut label = root.downcast::<Label>().unwrap();
+            label.reset_shape_cache_stats();
+        });
+        assert_eq!(stats(&harness), ShapeCacheStats::default());
+    }
+
+    #[test]
+    fn text_outline() {
+        fn paint_insets(harness: &TestHarness) -> Insets {
+            harness.root_widget().state().paint_insets
+        }
+
+        let size = Size::new(100.0, 40.0);
+        let outlined = Label::new("Hello").with_text_outline(2.0, Color::BLACK);
+        let mut harness = TestHarness::create_with_size(outlined, size);
+        assert_eq!(paint_insets(&harness), Insets::uniform(2.0));
+
+        let mut plain_harness = TestHarness::create_with_size(Label::new("Hello"), size);
+        // We don't use assert_eq because we don't want rich assert
+        let outlined_image = harness.render();
+        assert!(outlined_image != plain_harness.render());
+
+        // Laying out again keeps the outline, and painting again reuses its image.
+        let outline_generation = |harness: &TestHarness| {
+            let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+            label.outline_cache.as_ref().unwrap().layout.generation()
         };
+        let generation = outline_generation(&harness);
+        harness.edit_root_widget(|mut root, _| {
+            let mut label = root.downcast::<Label>().unwrap();
+            label.ctx.request_layout();
+        });
+        assert_eq!(outline_generation(&harness), generation);
+        assert!(harness.render() == outlined_image);
+        let label = harness.root_widget().downcast::<Label>().unwrap().deref();
+        assert!(label.outline_cache.as_ref().unwrap().image.is_some());
 
-        let image_2 = {
-            let label = Label::new("Hello world")
-                .with_text_color(PRIMARY_DARK)
-                .with_text_size(40.0);
+        // Removing the outline removes the insets and the stroke pass.
+        harness.edit_root_widget(|mut root, _| {
+            let mut label = root.downcast::<Label>().unwrap();
+            label.set_text_outline(None);
+        });
+        assert_eq!(paint_insets(&harness), Insets::ZERO);
+        assert!(harness.render() == plain_harness.render());
+    }
 
-            let mut harness = TestHarness::create_with_size(label, Size::new(50.0, 50.0));
+    #[test]
+    fn inline_object() {
+        let size = Size::new(100.0, 40.0);
+        let red = [255, 0, 0, 255].repeat(10 * 10);
+        let object = InlineObject {
+            // U+FFFC is three bytes long.
+            range: 1..4,
+            size: Size::new(12.0, 10.0),
+            image: ImageBuf::from_raw(red, ImageFormat::RgbaSeparate, 10, 10),
+        };
+        let label = Label::new("a\u{FFFC}b").with_inline_objects(vec![object]);
+        let mut harness = TestHarness::create_with_size(label, size);
+        let plain_harness = TestHarness::create_with_size(Label::new("ab"), size);
 
-            harness.edit_root_widget(|mut label, _| {
-                let mut label = label.downcast::<Label>().unwrap();
-                label.set_text("The quick brown fox jumps over the lazy dog");
-                label.set_text_color(PRIMARY_LIGHT);
-                label.set_font(FontDescriptor::new(FontFamily::MONOSPACE));
-                label.set_text_size(20.0);
-                label.set_line_break_mode(LineBreaking::WordWrap);
-                label.set_text_alignment(TextAlignment::Center);
-            });
+        let plain = plain_harness.root_widget();
+        let plain = plain.downcast::<Label>().unwrap().deref();
+        let root = harness.root_widget();
+        let label = root.downcast::<Label>().unwrap().deref();
 
-            harness.render()
+        // The object's box is added to the line's width.
+        let width_increase = label.text_layout.size().width - plain.text_layout.size().width;
+        assert!((width_increase - 12.0).abs() < 0.5, "{width_increase}");
+
+        // The object follows the first glyph, and sits on the baseline.
+        let rect = label.inline_object_rect(0).unwrap();
+        let expected_x = plain.text_origin.x + plain.text_layout.point_for_text_position(1).x;
+        let baseline = label.text_origin.y + label.text_layout.layout_metrics().first_baseline;
+        assert!((rect.x0 - expected_x).abs() < 0.5, "{rect:?}");
+        assert_eq!(rect.y1, baseline);
+        assert_eq!(rect.size(), Size::new(12.0, 10.0));
+
+        // The image is painted in the box.
+        let center = rect.center();
+        let pixel = (center.y as usize * size.width as usize + center.x as usize) * 4;
+        assert_eq!(&harness.render()[pixel..pixel + 4], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn wide_inline_object_keeps_line_height() {
+        let object = InlineObject {
+            range: 1..4,
+            size: Size::new(60.0, 10.0),
+            image: ImageBuf::from_raw([0, 0, 255, 255], ImageFormat::RgbaSeparate, 1, 1),
         };
+        let label = Label::new("a\u{FFFC}b").with_inline_objects(vec![object]);
+        let harness = TestHarness::create_with_size(label, Size::new(200.0, 40.0));
+        let plain_harness = TestHarness::create_with_size(Label::new("ab"), Size::new(200.0, 40.0));
 
-        // We don't use assert_eq because we don't want rich assert
-        assert!(image_1 == image_2);
+        let plain = plain_harness.root_widget();
+        let plain = plain.downcast::<Label>().unwrap().deref();
+        let root = harness.root_widget();
+        let label = root.downcast::<Label>().unwrap().deref();
+
+        // The run is padded instead of enlarged, so the line doesn't get taller.
+        let (size, plain_size) = (label.text_layout.size(), plain.text_layout.size());
+        assert_eq!(size.height, plain_size.height);
+        let width_increase = size.width - plain_size.width;
+        assert!((width_increase - 60.0).abs() < 0.5, "{width_increase}");
+
+        // The padding is part of the object, not of the label's text.
+        let rect = label.inline_object_rect(0).unwrap();
+        assert_eq!(rect.size(), Size::new(60.0, 10.0));
+        assert_eq!(&*label.text(), "a\u{FFFC}b");
+        let end = Point::new(size.width, rect.center().y - label.text_origin.y);
+        assert_eq!(label.text_layout.text_position_for_point(end), 5);
+    }
+
+    #[test]
+    fn render_to_pixels() {
+        let [label_id] = widget_ids();
+        let label = Label::new("WW")
+            .with_text_size(40.0)
+            .with_text_color(Color::rgb8(255, 0, 0));
+        let widget = SizedBox::new_with_id(label, label_id).background(Color::WHITE);
+        let mut harness = TestHarness::create_with_size(widget, Size::new(120.0, 80.0));
+
+        let image = harness.render_to_pixels();
+        assert_eq!((image.width(), image.height()), (120, 80));
+        let pixel = |x: usize, y: usize| {
+            let i = (y * image.width() + x) * 4;
+            &image.raw_pixels()[i..i + 4]
+        };
+
+        let rect = harness.get_widget(label_id).state().layout_rect();
+        let rows = rect.y0 as usize..rect.y1 as usize;
+        let text_columns = LABEL_X_PADDING as usize..rect.x1 as usize - LABEL_X_PADDING as usize;
+        let red_pixels = rows
+            .clone()
+            .flat_map(|y| text_columns.clone().map(move |x| (x, y)))
+            .filter(|&(x, y)| pixel(x, y) == [255, 0, 0, 255])
+            .count();
+        assert!(red_pixels > 0);
+
+        // The horizontal padding is left to the white background.
+        for y in rows {
+            for x in 0..LABEL_X_PADDING as usize {
+                assert_eq!(pixel(x, y), [255, 255, 255, 255], "({x}, {y})");
+            }
+        }
     }
 }
@@ -0,0 +1,289 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A widget that shows a spinner while loading, then fades to a label.
+
+use std::time::Duration;
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+
+use crate::widget::{Label, LabelText, Spinner, WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    theme, ArcStr, BoxConstraints, Color, Env, Event, EventCtx, KeyOrValue, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Size, StatusChange, Widget,
+};
+
+const DEFAULT_FADE_DURATION: Duration = Duration::from_millis(250);
+
+/// A widget that shows a [`Spinner`] while loading, and cross-fades to a [`Label`]
+/// once loading is done.
+///
+/// Both children are drawn on top of each other, centered, and the widget is
+/// large enough to fit either of them.
+pub struct LoadingLabel {
+    spinner: WidgetPod<Spinner>,
+    label: WidgetPod<Label>,
+    color: KeyOrValue<Color>,
+    loading: bool,
+    fade_duration: Duration,
+    // 0.0 when only the spinner is visible, 1.0 when only the label is.
+    label_opacity: f64,
+    // The label's opacity when the current fade started.
+    fade_start: f64,
+    fade_elapsed: Duration,
+}
+
+crate::declare_widget!(LoadingLabelMut, LoadingLabel);
+
+impl LoadingLabel {
+    /// Create a new widget in the loading state, which will show `text` once loaded.
+    pub fn new(text: impl Into<LabelText>) -> Self {
+        LoadingLabel {
+            spinner: WidgetPod::new(Spinner::new()),
+            label: WidgetPod::new(Label::new(text)),
+            color: theme::TEXT_COLOR.into(),
+            loading: true,
+            fade_duration: DEFAULT_FADE_DURATION,
+            label_opacity: 0.0,
+            fade_start: 0.0,
+            fade_elapsed: Duration::ZERO,
+        }
+    }
+
+    /// Builder-style method for setting the initial loading state.
+    ///
+    /// Unlike [`LoadingLabelMut::set_loading`], this doesn't fade.
+    pub fn with_loading(mut self, loading: bool) -> Self {
+        self.loading = loading;
+        self.label_opacity = if loading { 0.0 } else { 1.0 };
+        self.fade_start = self.label_opacity;
+        self
+    }
+
+    /// Builder-style method for setting how long the cross-fade lasts.
+    ///
+    /// The default is 250 milliseconds.
+    pub fn with_fade_duration(mut self, duration: Duration) -> Self {
+        self.fade_duration = duration;
+        self
+    }
+
+    /// Returns `true` if the spinner is (or is fading towards being) shown.
+    pub fn is_loading(&self) -> bool {
+        self.loading
+    }
+
+    /// The current opacity of the label, from 0.0 (only the spinner is visible)
+    /// to 1.0 (only the label is visible).
+    pub fn label_opacity(&self) -> f64 {
+        self.label_opacity
+    }
+
+    fn target_opacity(&self) -> f64 {
+        if self.loading {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns the colors of the label and the spinner for the current opacity.
+    fn faded_colors(&self, env: &Env) -> (KeyOrValue<Color>, KeyOrValue<Color>) {
+        let faded = |opacity: f64| -> KeyOrValue<Color> {
+            if opacity >= 1.0 {
+                self.color.clone()
+            } else {
+                let (r, g, b, a) = self.color.resolve(env).as_rgba();
+                Color::rgba(r, g, b, a * opacity).into()
+            }
+        };
+        (faded(self.label_opacity), faded(1.0 - self.label_opacity))
+    }
+}
+
+impl<'a, 'b> LoadingLabelMut<'a, 'b> {
+    /// Set whether the spinner or the label should be shown.
+    ///
+    /// Changing this starts a cross-fade. If a fade is already running, it is
+    /// restarted from the current opacity, so that rapid toggles reverse smoothly
+    /// instead of jumping; the new fade always lasts the full fade duration.
+    pub fn set_loading(&mut self, loading: bool) {
+        if self.widget.loading == loading {
+            return;
+        }
+        self.widget.loading = loading;
+        self.widget.fade_start = self.widget.label_opacity;
+        self.widget.fade_elapsed = Duration::ZERO;
+        self.ctx.request_anim_frame();
+    }
+
+    /// Set how long the cross-fade lasts.
+    ///
+    /// This takes effect starting with the next call to [`set_loading`](Self::set_loading).
+    pub fn set_fade_duration(&mut self, duration: Duration) {
+        self.widget.fade_duration = duration;
+    }
+
+    /// Set the label's text.
+    pub fn set_text(&mut self, new_text: impl Into<ArcStr>) {
+        self.label_mut().set_text(new_text.into());
+    }
+
+    /// Get a [`WidgetMut`] to the label.
+    ///
+    /// The label's text color is managed by this widget.
+    pub fn label_mut(&mut self) -> WidgetMut<'_, 'b, Label> {
+        self.ctx.get_mut(&mut self.widget.label)
+    }
+}
+
+impl Widget for LoadingLabel {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.spinner.on_event(ctx, event, env);
+        self.label.on_event(ctx, event, env);
+
+        if let Event::AnimFrame(interval) = event {
+            let target = self.target_opacity();
+            if self.label_opacity != target {
+                self.fade_elapsed += Duration::from_nanos(*interval);
+                let progress = if self.fade_duration.is_zero() {
+                    1.0
+                } else {
+                    (self.fade_elapsed.as_secs_f64() / self.fade_duration.as_secs_f64()).min(1.0)
+                };
+                self.label_opacity = self.fade_start + (target - self.fade_start) * progress;
+                trace!("Label opacity: {}", self.label_opacity);
+
+                let (label_color, spinner_color) = self.faded_colors(env);
+                ctx.get_mut(&mut self.label).set_text_color(label_color);
+                ctx.get_mut(&mut self.spinner).set_color(spinner_color);
+                if progress < 1.0 {
+                    ctx.request_anim_frame();
+                }
+                ctx.request_paint();
+            }
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.spinner.lifecycle(ctx, event, env);
+        self.label.lifecycle(ctx, event, env);
+
+        if let LifeCycle::WidgetAdded = event {
+            let (label_color, spinner_color) = self.faded_colors(env);
+            ctx.get_mut(&mut self.label).set_text_color(label_color);
+            ctx.get_mut(&mut self.spinner).set_color(spinner_color);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let child_bc = bc.loosen();
+        let label_size = self.label.layout(ctx, &child_bc, env);
+        let spinner_size = self.spinner.layout(ctx, &child_bc, env);
+
+        let size = bc.constrain(Size::new(
+            label_size.width.max(spinner_size.width),
+            label_size.height.max(spinner_size.height),
+        ));
+
+        let label_offset = (size.to_vec2() - label_size.to_vec2()) / 2.0;
+        let spinner_offset = (size.to_vec2() - spinner_size.to_vec2()) / 2.0;
+        ctx.place_child(&mut self.label, label_offset.to_point(), env);
+        ctx.place_child(&mut self.spinner, spinner_offset.to_point(), env);
+        ctx.set_baseline_offset(
+            size.height - label_offset.y - label_size.height + self.label.baseline_offset(),
+        );
+
+        trace!("Computed size: {}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.spinner.paint(ctx, env);
+        self.label.paint(ctx, env);
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.label.reset_interaction_state();
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.spinner.as_dyn(), self.label.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("LoadingLabel")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestHarness;
+
+    const FRAME: Duration = Duration::from_millis(50);
+
+    fn label_opacity(harness: &TestHarness) -> f64 {
+        harness
+            .root_widget()
+            .downcast::<LoadingLabel>()
+            .unwrap()
+            .deref()
+            .label_opacity()
+    }
+
+    fn set_loading(harness: &mut TestHarness, loading: bool) {
+        harness.edit_root_widget(|mut root, _| {
+            let mut root = root.downcast::<LoadingLabel>().unwrap();
+            root.set_loading(loading);
+        });
+    }
+
+    fn anim_frame(harness: &mut TestHarness, interval: Duration) {
+        harness.process_event(Event::AnimFrame(interval.as_nanos() as u64));
+    }
+
+    #[test]
+    fn fade_to_label() {
+        let widget = LoadingLabel::new("Loaded").with_fade_duration(FRAME * 2);
+        let mut harness = TestHarness::create(widget);
+        assert_eq!(label_opacity(&harness), 0.0);
+
+        set_loading(&mut harness, false);
+        anim_frame(&mut harness, FRAME);
+        let opacity = label_opacity(&harness);
+        assert!(opacity > 0.0 && opacity < 1.0, "{opacity}");
+
+        anim_frame(&mut harness, FRAME);
+        assert_eq!(label_opacity(&harness), 1.0);
+
+        // Nothing changes once the fade is over.
+        anim_frame(&mut harness, FRAME);
+        assert_eq!(label_opacity(&harness), 1.0);
+    }
+
+    #[test]
+    fn toggle_restarts_fade() {
+        let widget = LoadingLabel::new("Loaded").with_fade_duration(FRAME * 2);
+        let mut harness = TestHarness::create(widget);
+
+        set_loading(&mut harness, false);
+        anim_frame(&mut harness, FRAME);
+        let halfway = label_opacity(&harness);
+
+        // Toggling mid-fade reverses from the current opacity...
+        set_loading(&mut harness, true);
+        assert_eq!(label_opacity(&harness), halfway);
+        anim_frame(&mut harness, FRAME);
+        let opacity = label_opacity(&harness);
+        assert!(opacity > 0.0 && opacity < halfway, "{opacity}");
+
+        // ...and takes the full fade duration.
+        anim_frame(&mut harness, FRAME);
+        assert_eq!(label_opacity(&harness), 0.0);
+    }
+}
@@ -6,6 +6,7 @@
 
 #[allow(clippy::module_inception)]
 mod widget;
+mod widget_ext;
 mod widget_mut;
 mod widget_pod;
 mod widget_ref;
@@ -17,36 +18,62 @@ mod tests;
 mod align;
 mod button;
 mod checkbox;
+mod disabled_if;
+mod env_scope;
+mod env_transition;
+mod flash_label;
 mod flex;
+mod identity_wrapper;
 mod image;
 mod label;
+mod loading_label;
+mod padding;
 mod portal;
+mod relative_size;
 mod scroll_bar;
+mod scrollable_label;
+mod segmented_control;
 mod sized_box;
 mod spinner;
 mod split;
+mod tabs;
 mod textbox;
+mod value_label;
 
 pub use align::Align;
 pub use button::Button;
 pub use checkbox::Checkbox;
+pub use disabled_if::DisabledIf;
+pub use env_scope::EnvScope;
+pub use env_transition::EnvTransition;
+pub use flash_label::FlashLabel;
 pub use flex::{Axis, CrossAxisAlignment, Flex, FlexParams, MainAxisAlignment};
-pub use label::{Label, LineBreaking};
+pub use identity_wrapper::IdentityWrapper;
+pub use label::{
+    ContentRole, DynamicText, FitStrategy, Label, LabelText, LayoutDirection, LineBreaking, RubyRun,
+};
+pub use loading_label::LoadingLabel;
+pub use padding::Padding;
 pub use portal::Portal;
+pub use relative_size::RelativeSize;
 pub use scroll_bar::ScrollBar;
+pub use scrollable_label::ScrollableLabel;
+pub use segmented_control::{SegmentSizing, SegmentedControl};
 pub use sized_box::SizedBox;
 pub use spinner::Spinner;
 pub use split::Split;
+pub use tabs::Tabs;
 pub use textbox::TextBox;
+pub use value_label::ValueLabel;
 pub use widget::StoreInWidgetMut;
 #[doc(hidden)]
-pub use widget::{Widget, WidgetId};
-//#[doc(hidden)]
-//pub use widget_ext::WidgetExt;
+pub use widget::{Widget, WidgetId, WidgetIdLease, WidgetIdPool};
+pub use widget_ext::WidgetExt;
 //pub use widget_wrapper::WidgetWrapper;
 pub use widget_mut::WidgetMut;
 pub use widget_pod::WidgetPod;
 pub use widget_ref::WidgetRef;
+pub(crate) use widget_state::PaintCache;
 pub use widget_state::WidgetState;
 
 pub use self::image::Image;
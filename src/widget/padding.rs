@@ -0,0 +1,137 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A widget that adds space around its child.
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    BoxConstraints, Env, Event, EventCtx, Insets, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
+    Point, Size, StatusChange, Widget,
+};
+
+/// A widget that adds empty space around its child.
+///
+/// The child is laid out with the parent's constraints, minus the insets, and the
+/// padding reports the child's size plus the insets.
+pub struct Padding {
+    child: WidgetPod<Box<dyn Widget>>,
+    insets: Insets,
+}
+
+crate::declare_widget!(PaddingMut, Padding);
+
+impl Padding {
+    /// Create a new widget with `insets` of space around `child`.
+    ///
+    /// Negative insets are treated as zero.
+    pub fn new(insets: impl Into<Insets>, child: impl Widget) -> Self {
+        Self {
+            child: WidgetPod::new(child).boxed(),
+            insets: non_negative(insets.into()),
+        }
+    }
+
+    /// Builder-style method for putting the same amount of space on every side.
+    pub fn with_uniform(mut self, padding: f64) -> Self {
+        self.insets = non_negative(Insets::uniform(padding));
+        self
+    }
+
+    /// The space around the child.
+    pub fn insets(&self) -> Insets {
+        self.insets
+    }
+}
+
+impl<'a, 'b> PaddingMut<'a, 'b> {
+    /// Set the space around the child.
+    pub fn set_insets(&mut self, insets: impl Into<Insets>) {
+        self.widget.insets = non_negative(insets.into());
+        self.ctx.request_layout();
+    }
+
+    /// Get a [`WidgetMut`] to the child.
+    pub fn child_mut(&mut self) -> WidgetMut<'_, 'b, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+fn non_negative(insets: Insets) -> Insets {
+    Insets::new(
+        insets.x0.max(0.0),
+        insets.y0.max(0.0),
+        insets.x1.max(0.0),
+        insets.y1.max(0.0),
+    )
+}
+
+impl Widget for Padding {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.child.on_event(ctx, event, env);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.child.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let insets = self.insets;
+        let extra = Size::new(insets.x_value(), insets.y_value());
+        let child_bc = bc.shrink(extra);
+        let child_size = self.child.layout(ctx, &child_bc, env);
+        ctx.place_child(&mut self.child, Point::new(insets.x0, insets.y0), env);
+        ctx.set_baseline_offset(self.child.baseline_offset() + insets.y1);
+
+        let size = bc.constrain(Size::new(
+            child_size.width + extra.width,
+            child_size.height + extra.height,
+        ));
+        trace!("Computed size: {}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.child.paint(ctx, env);
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.child.reset_interaction_state();
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Padding")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Flex, SizedBox};
+    use crate::Rect;
+
+    #[test]
+    fn uniform_padding() {
+        let [padding_id, child_id] = widget_ids();
+        let child = SizedBox::empty().width(50.0).height(20.0);
+        let padding =
+            Padding::new(Insets::ZERO, SizedBox::new_with_id(child, child_id)).with_uniform(10.0);
+        let widget = Flex::column().with_child_id(padding, padding_id);
+
+        let harness = TestHarness::create(widget);
+        let padding_rect = harness.get_widget(padding_id).state().layout_rect();
+        let child_rect = harness.get_widget(child_id).state().layout_rect();
+        assert_eq!(padding_rect.size(), Size::new(70.0, 40.0));
+        assert_eq!(child_rect, Rect::new(10.0, 10.0, 60.0, 30.0));
+    }
+}
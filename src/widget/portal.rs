@@ -11,7 +11,7 @@ use tracing::{trace_span, Span};
 
 use crate::kurbo::{Point, Rect, Size, Vec2};
 use crate::widget::scroll_bar::SCROLLBAR_MOVED;
-use crate::widget::{Axis, ScrollBar, StoreInWidgetMut, WidgetMut, WidgetRef};
+use crate::widget::{Axis, ScrollBar, StoreInWidgetMut, WidgetMut, WidgetRef, WidgetState};
 use crate::{
     BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
     RenderContext, StatusChange, Widget, WidgetPod,
@@ -130,6 +130,53 @@ fn compute_pan_range(mut viewport: Range<f64>, target: Range<f64>) -> Range<f64>
 }
 
 impl<W: Widget> Portal<W> {
+    /// Scroll to show a rect requested with [`EventCtx::scroll_to_visible`] by a
+    /// descendant, and forward the visible part of it to our ancestors.
+    ///
+    /// Returns `true` if the viewport moved.
+    fn handle_scroll_request(&mut self, state: &mut WidgetState) -> bool {
+        let Some(target) = state.scroll_to_visible.take() else {
+            return false;
+        };
+        let portal_size = state.size;
+        let content_size = self.child.layout_rect().size();
+
+        // The request is in our coordinate space; move it to the child's.
+        let target = target - self.child.layout_rect().origin().to_vec2();
+        let viewport = Rect::from_origin_size(self.viewport_pos, portal_size);
+        let new_pos = Point::new(
+            compute_pan_range(
+                viewport.min_x()..viewport.max_x(),
+                target.min_x()..target.max_x(),
+            )
+            .start,
+            compute_pan_range(
+                viewport.min_y()..viewport.max_y(),
+                target.min_y()..target.max_y(),
+            )
+            .start,
+        );
+        let moved = self.set_viewport_pos_raw(portal_size, content_size, new_pos);
+
+        // Our ancestors only need to show the part of the target we now show.
+        let child_origin = -self.viewport_pos.to_vec2();
+        let visible = (target + child_origin).intersect(portal_size.to_rect());
+        state.scroll_to_visible = Some(visible);
+        moved
+    }
+
+    /// Scrollbar cursor progress along each axis for the current viewport position.
+    ///
+    /// An axis whose content fits in the portal has nothing to scroll and reports `0.0`.
+    fn scrollbar_progress(&self, portal_size: Size, content_size: Size) -> (f64, f64) {
+        let range = content_size - portal_size;
+        let progress = |pos: f64, range: f64| if range <= 0.0 { 0.0 } else { pos / range };
+        (
+            progress(self.viewport_pos.x, range.width),
+            progress(self.viewport_pos.y, range.height),
+        )
+    }
+
     // TODO - rename
     fn set_viewport_pos_raw(&mut self, portal_size: Size, content_size: Size, pos: Point) -> bool {
         let viewport_max_pos =
@@ -204,10 +251,10 @@ impl<'a, 'b, W: Widget> PortalMut<'a, 'b, W> {
             .widget
             .set_viewport_pos_raw(portal_size, content_size, position);
         if pos_changed {
-            let progress_x = self.widget.viewport_pos.x / (content_size - portal_size).width;
+            let (progress_x, progress_y) =
+                self.widget.scrollbar_progress(portal_size, content_size);
             self.horizontal_scrollbar_mut()
                 .set_cursor_progress(progress_x);
-            let progress_y = self.widget.viewport_pos.y / (content_size - portal_size).height;
             self.vertical_scrollbar_mut()
                 .set_cursor_progress(progress_y);
             self.ctx.request_layout();
@@ -252,8 +299,9 @@ impl<W: Widget> Widget for Portal<W> {
                     self.viewport_pos + wheel_event.wheel_delta,
                 );
                 // TODO - horizontal scrolling?
+                let (_, progress_y) = self.scrollbar_progress(portal_size, content_size);
                 ctx.get_mut(&mut self.scrollbar_vertical)
-                    .set_cursor_progress(self.viewport_pos.y / (content_size - portal_size).height);
+                    .set_cursor_progress(progress_y);
             }
             Event::Notification(notif) => {
                 if let Some((axis, progress)) = notif.try_get(SCROLLBAR_MOVED) {
@@ -272,6 +320,13 @@ impl<W: Widget> Widget for Portal<W> {
         self.child.on_event(ctx, event, env);
         self.scrollbar_horizontal.on_event(ctx, event, env);
         self.scrollbar_vertical.on_event(ctx, event, env);
+        if self.handle_scroll_request(ctx.widget_state) {
+            let (progress_x, progress_y) = self.scrollbar_progress(portal_size, content_size);
+            ctx.get_mut(&mut self.scrollbar_horizontal)
+                .set_cursor_progress(progress_x);
+            ctx.get_mut(&mut self.scrollbar_vertical)
+                .set_cursor_progress(progress_y);
+        }
         ctx.request_layout();
     }
 
@@ -282,14 +337,22 @@ impl<W: Widget> Widget for Portal<W> {
             LifeCycle::WidgetAdded => {
                 ctx.register_as_portal();
             }
-            //TODO
-            //LifeCycle::RequestPanToChild(target_rect) => {}
             _ => {}
         }
 
         self.child.lifecycle(ctx, event, env);
         self.scrollbar_horizontal.lifecycle(ctx, event, env);
         self.scrollbar_vertical.lifecycle(ctx, event, env);
+        if self.handle_scroll_request(ctx.widget_state) {
+            let portal_size = ctx.size();
+            let content_size = self.child.layout_rect().size();
+            let (progress_x, progress_y) = self.scrollbar_progress(portal_size, content_size);
+            ctx.get_mut(&mut self.scrollbar_horizontal)
+                .set_cursor_progress(progress_x);
+            ctx.get_mut(&mut self.scrollbar_vertical)
+                .set_cursor_progress(progress_y);
+            ctx.request_layout();
+        }
     }
 
     fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
@@ -312,7 +375,11 @@ impl<W: Widget> Widget for Portal<W> {
         self.set_viewport_pos_raw(portal_size, content_size, self.viewport_pos);
         // TODO - recompute portal progress
 
-        ctx.place_child(&mut self.child, Point::new(0.0, -self.viewport_pos.y), env);
+        ctx.place_child(
+            &mut self.child,
+            Point::ORIGIN - self.viewport_pos.to_vec2(),
+            env,
+        );
 
         self.scrollbar_horizontal_visible =
             !self.constrain_horizontal && portal_size.width < content_size.width;
@@ -366,6 +433,10 @@ impl<W: Widget> Widget for Portal<W> {
         }
     }
 
+    fn reset_interaction_state(&mut self) {
+        self.child.reset_interaction_state();
+    }
+
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
         smallvec![self.child.as_dyn()]
     }
@@ -382,7 +453,7 @@ mod tests {
     use super::*;
     use crate::assert_render_snapshot;
     use crate::testing::{widget_ids, TestHarness};
-    use crate::widget::{Button, Flex, SizedBox};
+    use crate::widget::{Button, Flex, Label, SizedBox};
 
     fn button(text: &str) -> impl Widget {
         SizedBox::new(Button::new(text)).width(70.0).height(40.0)
@@ -453,6 +524,36 @@ mod tests {
         assert_render_snapshot!(harness, "button_list_scroll_to_item_13");
     }
 
+    #[test]
+    fn wheel_offset_clamps() {
+        let mut column = Flex::column();
+        for index in 0..50 {
+            column = column.with_child(Label::new(format!("Line {index}")));
+        }
+        let mut harness = TestHarness::create_with_size(Portal::new(column), Size::new(200., 200.));
+        let viewport_y = |harness: &TestHarness| {
+            let portal = harness.root_widget();
+            let portal = portal.downcast::<Portal<Flex>>().unwrap();
+            portal.deref().get_viewport_pos().y
+        };
+        let content_height = harness.root_widget().children()[0]
+            .state()
+            .layout_rect()
+            .height();
+        assert!(content_height > 200.0);
+
+        harness.mouse_move(Point::new(100.0, 100.0));
+        harness.mouse_wheel(Vec2::new(0.0, 50.0));
+        assert_eq!(viewport_y(&harness), 50.0);
+
+        // Scrolling past the end stops at the bottom of the content.
+        harness.mouse_wheel(Vec2::new(0.0, 100_000.0));
+        assert_eq!(viewport_y(&harness), content_height - 200.0);
+
+        harness.mouse_wheel(Vec2::new(0.0, -100_000.0));
+        assert_eq!(viewport_y(&harness), 0.0);
+    }
+
     // Helper function for panning tests
     fn make_range(repr: &str) -> Range<f64> {
         let repr = &repr[repr.find('_').unwrap()..];
@@ -465,6 +566,82 @@ mod tests {
         (start as f64)..(end as f64)
     }
 
+    #[test]
+    fn focus_scrolls_to_visible() {
+        let [label_id] = widget_ids();
+
+        let widget = Portal::new(
+            Flex::column()
+                .with_spacer(1000.0)
+                .with_child_id(Label::new("Focus me"), label_id),
+        );
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400.0, 400.0));
+        let label_rect = harness.get_widget(label_id).state().window_layout_rect();
+        assert!(label_rect.min_y() > 400.0);
+
+        harness.focus_on(Some(label_id));
+
+        let portal = harness.root_widget();
+        let portal = portal.downcast::<Portal<Flex>>().unwrap();
+        assert!(portal.deref().viewport_pos.y > 0.0);
+        let label_rect = harness.get_widget(label_id).state().window_layout_rect();
+        assert!(label_rect.min_y() >= 0.0, "{label_rect:?}");
+        assert!(label_rect.max_y() <= 400.0, "{label_rect:?}");
+    }
+
+    #[test]
+    fn focus_scrolls_horizontally() {
+        let [label_id] = widget_ids();
+
+        let widget = Portal::new(
+            Flex::row()
+                .with_spacer(1000.0)
+                .with_child_id(Label::new("Focus me"), label_id),
+        );
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400.0, 400.0));
+
+        harness.focus_on(Some(label_id));
+
+        let portal = harness.root_widget();
+        let portal = portal.downcast::<Portal<Flex>>().unwrap();
+        assert!(portal.deref().viewport_pos.x > 0.0);
+        // The child is moved by the horizontal scroll too.
+        let label_rect = harness.get_widget(label_id).state().window_layout_rect();
+        assert!(label_rect.min_x() >= 0.0, "{label_rect:?}");
+        assert!(label_rect.max_x() <= 400.0, "{label_rect:?}");
+        // The content fits vertically, so there is no vertical progress to report.
+        let progress_y = portal.deref().scrollbar_vertical.widget().cursor_progress();
+        assert_eq!(progress_y, 0.0);
+    }
+
+    #[test]
+    fn nested_scroll_to_visible() {
+        let [inner_id, label_id] = widget_ids();
+
+        let inner = Portal::new(
+            Flex::column()
+                .with_spacer(1000.0)
+                .with_child_id(Label::new("Focus me"), label_id),
+        );
+        let widget = Portal::new(
+            Flex::column()
+                .with_spacer(1000.0)
+                .with_child_id(SizedBox::new(inner).height(200.0), inner_id),
+        );
+        let mut harness = TestHarness::create_with_size(widget, Size::new(400.0, 400.0));
+
+        harness.focus_on(Some(label_id));
+
+        // The inner portal shows the label, and the outer one shows the part of
+        // the inner portal containing the label.
+        let inner_rect = harness.get_widget(inner_id).state().window_layout_rect();
+        let label_rect = harness.get_widget(label_id).state().window_layout_rect();
+        assert!(label_rect.min_y() >= inner_rect.min_y(), "{label_rect:?}");
+        assert!(label_rect.max_y() <= inner_rect.max_y(), "{label_rect:?}");
+        assert!(label_rect.min_y() >= 0.0, "{label_rect:?}");
+        assert!(label_rect.max_y() <= 400.0, "{label_rect:?}");
+    }
+
     #[test]
     fn test_pan_to_same() {
         let initial_range = make_range("_______xxxx_____");
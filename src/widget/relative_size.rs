@@ -0,0 +1,209 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A widget that sizes its child relative to the available space.
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, warn, Span};
+
+use crate::widget::{WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point,
+    Size, StatusChange, Widget,
+};
+
+/// A widget that makes its child take a fraction of the available space.
+///
+/// For each axis with a factor set, the child is given a tight constraint equal
+/// to that fraction of the parent's maximum size, clamped to the parent's
+/// constraints. This means that under tight constraints, the factor has no effect.
+///
+/// If the parent's maximum is infinite on an axis, there is no space to take a
+/// fraction of; the child is laid out with the parent's constraints on that axis,
+/// and keeps its intrinsic size.
+///
+/// Axes without a factor are passed through unchanged.
+pub struct RelativeSize {
+    child: WidgetPod<Box<dyn Widget>>,
+    width_factor: Option<f64>,
+    height_factor: Option<f64>,
+}
+
+crate::declare_widget!(RelativeSizeMut, RelativeSize);
+
+impl RelativeSize {
+    /// Create a new widget wrapping `child`, with no factor set on either axis.
+    pub fn new(child: impl Widget) -> Self {
+        Self {
+            child: WidgetPod::new(child).boxed(),
+            width_factor: None,
+            height_factor: None,
+        }
+    }
+
+    /// Builder-style method for setting the fraction of the available width taken
+    /// by the child.
+    ///
+    /// The factor is clamped to `[0, 1]`.
+    pub fn width_factor(mut self, factor: f64) -> Self {
+        self.width_factor = Some(clamp_factor(factor));
+        self
+    }
+
+    /// Builder-style method for setting the fraction of the available height taken
+    /// by the child.
+    ///
+    /// The factor is clamped to `[0, 1]`.
+    pub fn height_factor(mut self, factor: f64) -> Self {
+        self.height_factor = Some(clamp_factor(factor));
+        self
+    }
+}
+
+impl<'a, 'b> RelativeSizeMut<'a, 'b> {
+    /// Set the fraction of the available width taken by the child.
+    ///
+    /// The factor is clamped to `[0, 1]`. `None` passes the width constraints through.
+    pub fn set_width_factor(&mut self, factor: Option<f64>) {
+        self.widget.width_factor = factor.map(clamp_factor);
+        self.ctx.request_layout();
+    }
+
+    /// Set the fraction of the available height taken by the child.
+    ///
+    /// The factor is clamped to `[0, 1]`. `None` passes the height constraints through.
+    pub fn set_height_factor(&mut self, factor: Option<f64>) {
+        self.widget.height_factor = factor.map(clamp_factor);
+        self.ctx.request_layout();
+    }
+
+    /// Get a [`WidgetMut`] to the child.
+    pub fn child_mut(&mut self) -> WidgetMut<'_, 'b, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.child)
+    }
+}
+
+fn clamp_factor(factor: f64) -> f64 {
+    if !(0.0..=1.0).contains(&factor) {
+        warn!("RelativeSize factor {} is outside of [0, 1]", factor);
+    }
+    // NaN is treated as zero.
+    factor.max(0.0).min(1.0)
+}
+
+impl RelativeSize {
+    fn child_constraints(&self, bc: &BoxConstraints) -> BoxConstraints {
+        let axis = |factor: Option<f64>, min: f64, max: f64| match factor {
+            Some(factor) if max.is_finite() => {
+                let value = (max * factor).max(min).min(max);
+                (value, value)
+            }
+            _ => (min, max),
+        };
+        let (min_width, max_width) = axis(self.width_factor, bc.min().width, bc.max().width);
+        let (min_height, max_height) = axis(self.height_factor, bc.min().height, bc.max().height);
+
+        BoxConstraints::new(
+            Size::new(min_width, min_height),
+            Size::new(max_width, max_height),
+        )
+    }
+}
+
+impl Widget for RelativeSize {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.child.on_event(ctx, event, env);
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.child.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let child_bc = self.child_constraints(bc);
+        let size = self.child.layout(ctx, &child_bc, env);
+        ctx.place_child(&mut self.child, Point::ORIGIN, env);
+        ctx.set_baseline_offset(self.child.baseline_offset());
+
+        let size = bc.constrain(size);
+        trace!("Computed size: {}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.child.paint(ctx, env);
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.child.reset_interaction_state();
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.child.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("RelativeSize")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Flex, Label, SizedBox};
+
+    #[test]
+    fn half_width() {
+        let [label_id] = widget_ids();
+        let widget = RelativeSize::new(SizedBox::new_with_id(Label::new("Hello"), label_id))
+            .width_factor(0.5);
+
+        let harness = TestHarness::create_with_size(widget, Size::new(400.0, 100.0));
+        let label_size = harness.get_widget(label_id).state().layout_rect().size();
+        assert_eq!(label_size.width, 200.0);
+        // The height has no factor, so the tight constraint is passed through.
+        assert_eq!(label_size.height, 100.0);
+    }
+
+    #[test]
+    fn child_constraints() {
+        let widget = RelativeSize::new(Label::new("Hello"))
+            .width_factor(0.5)
+            .height_factor(2.0);
+
+        let bc = BoxConstraints::new(Size::new(250.0, 0.0), Size::new(400.0, 100.0));
+        let child_bc = widget.child_constraints(&bc);
+        // Clamped to the parent's minimum width.
+        assert_eq!(child_bc.min().width, 250.0);
+        assert_eq!(child_bc.max().width, 250.0);
+        // The factor is clamped to 1.0.
+        assert_eq!(child_bc.min().height, 100.0);
+        assert_eq!(child_bc.max().height, 100.0);
+    }
+
+    #[test]
+    fn unbounded_constraints() {
+        let [plain_id, relative_id] = widget_ids();
+        // A horizontal flex gives its non-flex children an unbounded width.
+        let widget = Flex::row()
+            .with_child_id(Label::new("Hello"), plain_id)
+            .with_child_id(
+                RelativeSize::new(Label::new("Hello")).width_factor(0.5),
+                relative_id,
+            );
+
+        let harness = TestHarness::create(widget);
+        let plain_width = harness.get_widget(plain_id).state().layout_rect().width();
+        let relative_width = harness
+            .get_widget(relative_id)
+            .state()
+            .layout_rect()
+            .width();
+        assert!(plain_width.is_finite());
+        assert_eq!(relative_width, plain_width);
+    }
+}
@@ -229,7 +229,8 @@ mod tests {
 
     use super::*;
     use crate::assert_render_snapshot;
-    use crate::testing::{widget_ids, TestHarness, TestWidgetExt};
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::WidgetExt as _;
 
     #[test]
     fn simple_scrollbar() {
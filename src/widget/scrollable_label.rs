@@ -0,0 +1,331 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A single-line label that scrolls its text when it doesn't fit.
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+
+use crate::widget::{Label, LabelText, WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    theme, Affine, ArcStr, BoxConstraints, Color, Env, Event, EventCtx, KeyOrValue, LayoutCtx,
+    LifeCycle, LifeCycleCtx, LinearGradient, PaintCtx, Point, Rect, RenderContext, Size,
+    StatusChange, UnitPoint, Widget,
+};
+
+// In pixels per second.
+const DEFAULT_SCROLL_SPEED: f64 = 30.0;
+const DEFAULT_LOOP_GAP: f64 = 40.0;
+const DEFAULT_FADE_WIDTH: f64 = 12.0;
+
+/// A single-line label that scrolls its text horizontally, in a loop, when it's
+/// too long for the available width.
+///
+/// This is the "marquee" of news tickers and media players. Text that fits
+/// stays still. Scrolling pauses while the pointer is over the widget, so that
+/// the text can be read.
+///
+/// While scrolling, the text fades out at both edges, into the color set with
+/// [`with_fade_color`](Self::with_fade_color).
+pub struct ScrollableLabel {
+    label: WidgetPod<Label>,
+    // How far the text has scrolled left, between zero and the loop period.
+    offset: f64,
+    scroll_speed: f64,
+    loop_gap: f64,
+    fade_width: f64,
+    fade_color: KeyOrValue<Color>,
+    is_overflowing: bool,
+}
+
+crate::declare_widget!(ScrollableLabelMut, ScrollableLabel);
+
+impl ScrollableLabel {
+    /// Create a new scrolling label.
+    pub fn new(text: impl Into<LabelText>) -> Self {
+        Self::from_label(Label::new(text))
+    }
+
+    /// Create a new scrolling label from the provided [`Label`].
+    ///
+    /// The label is laid out on a single line, however wide its text is.
+    pub fn from_label(label: Label) -> Self {
+        ScrollableLabel {
+            label: WidgetPod::new(label),
+            offset: 0.0,
+            scroll_speed: DEFAULT_SCROLL_SPEED,
+            loop_gap: DEFAULT_LOOP_GAP,
+            fade_width: DEFAULT_FADE_WIDTH,
+            fade_color: theme::WINDOW_BACKGROUND_COLOR.into(),
+            is_overflowing: false,
+        }
+    }
+
+    /// Builder-style method for setting how fast the text scrolls, in pixels per second.
+    ///
+    /// The default is 30.
+    pub fn with_scroll_speed(mut self, speed: f64) -> Self {
+        self.scroll_speed = speed;
+        self
+    }
+
+    /// Builder-style method for setting the space between the end of the text
+    /// and its start coming around again.
+    ///
+    /// The default is 40 pixels.
+    pub fn with_loop_gap(mut self, gap: f64) -> Self {
+        self.loop_gap = gap;
+        self
+    }
+
+    /// Builder-style method for setting the width of the fades at the edges of
+    /// scrolling text.
+    ///
+    /// The default is 12 pixels; zero disables the fades.
+    pub fn with_fade_width(mut self, width: f64) -> Self {
+        self.fade_width = width;
+        self
+    }
+
+    /// Builder-style method for setting the color the text fades into at the
+    /// edges, which should be the color behind the widget.
+    ///
+    /// The argument can be either a `Color` or a [`Key<Color>`]; it defaults to
+    /// [`theme::WINDOW_BACKGROUND_COLOR`].
+    ///
+    /// [`Key<Color>`]: crate::Key
+    pub fn with_fade_color(mut self, color: impl Into<KeyOrValue<Color>>) -> Self {
+        self.fade_color = color.into();
+        self
+    }
+
+    /// How far the text has scrolled to the left, in pixels.
+    ///
+    /// This wraps around to zero each time the text has gone around the loop.
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    /// Returns `true` if the text is too long for the widget, and scrolls.
+    pub fn is_overflowing(&self) -> bool {
+        self.is_overflowing
+    }
+
+    // The distance the text travels before it's back at its starting point.
+    fn loop_period(&self) -> f64 {
+        self.label.layout_rect().width() + self.loop_gap
+    }
+}
+
+impl<'a, 'b> ScrollableLabelMut<'a, 'b> {
+    /// Set the text.
+    ///
+    /// This doesn't reset the scroll offset.
+    pub fn set_text(&mut self, new_text: impl Into<ArcStr>) {
+        self.label_mut().set_text(new_text.into());
+    }
+
+    /// Set how fast the text scrolls, in pixels per second.
+    pub fn set_scroll_speed(&mut self, speed: f64) {
+        self.widget.scroll_speed = speed;
+    }
+
+    /// Set the space between the end of the text and its start coming around again.
+    pub fn set_loop_gap(&mut self, gap: f64) {
+        self.widget.loop_gap = gap;
+        self.ctx.request_layout();
+    }
+
+    /// Get a [`WidgetMut`] to the label.
+    pub fn label_mut(&mut self) -> WidgetMut<'_, 'b, Label> {
+        self.ctx.get_mut(&mut self.widget.label)
+    }
+}
+
+impl Widget for ScrollableLabel {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.label.on_event(ctx, event, env);
+
+        if let Event::AnimFrame(interval) = event {
+            // Scrolling stops while hovered, and resumes when the pointer leaves.
+            if self.is_overflowing && !ctx.is_hot() {
+                let seconds = *interval as f64 * 1e-9;
+                self.offset = (self.offset + self.scroll_speed * seconds) % self.loop_period();
+                trace!("Scroll offset: {}", self.offset);
+                ctx.request_paint();
+                ctx.request_anim_frame();
+            }
+        }
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        if let StatusChange::HotChanged(false) = event {
+            if self.is_overflowing {
+                ctx.request_anim_frame();
+            }
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.label.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        // The label gets all the width it wants; we show as much of it as fits.
+        let label_bc = BoxConstraints::new(
+            Size::new(0.0, bc.min().height),
+            Size::new(f64::INFINITY, bc.max().height),
+        );
+        let label_size = self.label.layout(ctx, &label_bc, env);
+        ctx.place_child(&mut self.label, Point::ORIGIN, env);
+
+        let size = bc.constrain(label_size);
+        ctx.set_baseline_offset(size.height - label_size.height + self.label.baseline_offset());
+
+        // 1e-4 is an arbitrary small-enough value to ignore rounding errors
+        self.is_overflowing = label_size.width - size.width > 1e-4;
+        if self.is_overflowing {
+            self.offset %= self.loop_period();
+            ctx.request_anim_frame();
+        } else {
+            self.offset = 0.0;
+        }
+
+        trace!("Computed size: {}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let size = ctx.size();
+        if !self.is_overflowing {
+            self.label.paint(ctx, env);
+            return;
+        }
+
+        let offset = self.offset;
+        let period = self.loop_period();
+        let label = &mut self.label;
+        ctx.with_save(|ctx| {
+            ctx.clip(size.to_rect());
+            ctx.transform(Affine::translate((-offset, 0.0)));
+            label.paint(ctx, env);
+            // The start of the text, coming around again after the gap.
+            ctx.transform(Affine::translate((period, 0.0)));
+            label.paint(ctx, env);
+        });
+
+        let fade_width = self.fade_width.min(size.width / 2.0);
+        if fade_width > 0.0 {
+            let color = self.fade_color.resolve(env);
+            let transparent = color.with_alpha(0.0);
+            let left = Rect::new(0.0, 0.0, fade_width, size.height);
+            let left_fade =
+                LinearGradient::new(UnitPoint::LEFT, UnitPoint::RIGHT, (color, transparent));
+            ctx.fill(left, &left_fade);
+            let right = Rect::new(size.width - fade_width, 0.0, size.width, size.height);
+            let right_fade =
+                LinearGradient::new(UnitPoint::LEFT, UnitPoint::RIGHT, (transparent, color));
+            ctx.fill(right, &right_fade);
+        }
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.offset = 0.0;
+        self.label.reset_interaction_state();
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.label.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("ScrollableLabel")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        Some(self.label.as_ref().text().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Flex, SizedBox};
+    use crate::WidgetId;
+
+    const FRAME: Duration = Duration::from_millis(100);
+
+    fn offset(harness: &TestHarness, id: WidgetId) -> f64 {
+        let widget = harness.get_widget(id);
+        widget
+            .downcast::<ScrollableLabel>()
+            .unwrap()
+            .deref()
+            .offset()
+    }
+
+    #[test]
+    fn only_overflowing_text_scrolls() {
+        let [long_id, short_id] = widget_ids();
+        let long = ScrollableLabel::new("Now playing: a song with a very long title");
+        let short = ScrollableLabel::new("Hi");
+        let widget = Flex::column()
+            .with_child(SizedBox::new_with_id(long, long_id).width(80.0))
+            .with_child(SizedBox::new_with_id(short, short_id).width(80.0));
+        let mut harness = TestHarness::create(widget);
+        assert!(harness.window().wants_animation_frame());
+
+        harness.process_event(Event::AnimFrame(FRAME.as_nanos() as u64));
+        assert!((offset(&harness, long_id) - DEFAULT_SCROLL_SPEED * 0.1).abs() < 1e-9);
+        assert_eq!(offset(&harness, short_id), 0.0);
+        // The long label keeps going, the short one never asked for frames.
+        assert!(harness.window().wants_animation_frame());
+
+        harness.process_event(Event::AnimFrame(FRAME.as_nanos() as u64));
+        assert!((offset(&harness, long_id) - DEFAULT_SCROLL_SPEED * 0.2).abs() < 1e-9);
+        assert_eq!(offset(&harness, short_id), 0.0);
+    }
+
+    #[test]
+    fn hover_pauses_scrolling() {
+        let [label_id] = widget_ids();
+        let label = ScrollableLabel::new("Now playing: a song with a very long title");
+        let widget = Flex::column().with_child(SizedBox::new_with_id(label, label_id).width(80.0));
+        let mut harness = TestHarness::create(widget);
+
+        harness.mouse_move_to(label_id);
+        harness.process_event(Event::AnimFrame(FRAME.as_nanos() as u64));
+        assert_eq!(offset(&harness, label_id), 0.0);
+        assert!(!harness.window().wants_animation_frame());
+
+        harness.mouse_move(Point::new(500.0, 500.0));
+        assert!(harness.window().wants_animation_frame());
+        harness.process_event(Event::AnimFrame(FRAME.as_nanos() as u64));
+        assert!(offset(&harness, label_id) > 0.0);
+    }
+
+    #[test]
+    fn offset_loops() {
+        let [label_id] = widget_ids();
+        let label = ScrollableLabel::new("Now playing: a song with a very long title")
+            .with_scroll_speed(1000.0)
+            .with_loop_gap(20.0);
+        let widget = Flex::column().with_child(SizedBox::new_with_id(label, label_id).width(80.0));
+        let mut harness = TestHarness::create(widget);
+
+        let text_width = harness.get_widget(label_id).children()[0]
+            .state()
+            .layout_rect()
+            .width();
+        let period = text_width + 20.0;
+        assert!(period < 1000.0);
+
+        harness.process_event(Event::AnimFrame(Duration::from_secs(1).as_nanos() as u64));
+        let offset = offset(&harness, label_id);
+        assert!((offset - 1000.0 % period).abs() < 1e-6, "{offset}");
+    }
+}
@@ -0,0 +1,484 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A row of segments, one of which is selected.
+
+use druid_shell::KbKey;
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+
+use crate::action::Action;
+use crate::kurbo::{Line, RoundedRectRadii};
+use crate::widget::{Label, LabelText, StoreInWidgetMut, WidgetMut, WidgetRef};
+use crate::{
+    theme, BoxConstraints, Env, Event, EventCtx, Insets, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, Rect, RenderContext, Size, StatusChange, Widget, WidgetPod,
+};
+
+// The padding around each segment's label.
+const SEGMENT_INSETS: Insets = Insets::uniform_xy(10., 4.);
+
+/// How the segments of a [`SegmentedControl`] are sized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SegmentSizing {
+    /// Every segment is as wide as the widest one.
+    ///
+    /// If the control is given a minimum width, it is shared equally between segments.
+    #[default]
+    Equal,
+    /// Each segment is as wide as its label.
+    Auto,
+}
+
+/// A horizontal row of segments, exactly one of which is selected.
+///
+/// Each segment shows a label. Clicking a segment selects it; when the control
+/// has focus, the left and right arrow keys select the previous and next
+/// segment. Either way, [`Action::SegmentSelected`] is emitted with the new index.
+///
+/// Disabled segments are dimmed and can't be selected by the user; the arrow
+/// keys skip over them.
+pub struct SegmentedControl {
+    segments: Vec<WidgetPod<Label>>,
+    disabled: Vec<bool>,
+    selected: usize,
+    sizing: SegmentSizing,
+    // The bounds of each segment, set during layout.
+    segment_rects: Vec<Rect>,
+}
+
+crate::declare_widget!(SegmentedControlMut, SegmentedControl);
+
+impl SegmentedControl {
+    /// Create a new `SegmentedControl` with no segments.
+    pub fn new() -> Self {
+        SegmentedControl {
+            segments: Vec::new(),
+            disabled: Vec::new(),
+            selected: 0,
+            sizing: SegmentSizing::default(),
+            segment_rects: Vec::new(),
+        }
+    }
+
+    /// Builder-style method to add a segment.
+    ///
+    /// The first segment added is selected by default.
+    pub fn with_segment(mut self, text: impl Into<LabelText>) -> Self {
+        self.segments.push(WidgetPod::new(Label::new(text)));
+        self.disabled.push(false);
+        self
+    }
+
+    /// Builder-style method to set the initially selected segment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn with_selected(mut self, index: usize) -> Self {
+        assert!(index < self.segments.len(), "segment index out of bounds");
+        self.selected = index;
+        self
+    }
+
+    /// Builder-style method to set how segments are sized.
+    pub fn with_sizing(mut self, sizing: SegmentSizing) -> Self {
+        self.sizing = sizing;
+        self
+    }
+
+    /// Builder-style method to disable a segment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn with_segment_disabled(mut self, index: usize, disabled: bool) -> Self {
+        assert!(index < self.segments.len(), "segment index out of bounds");
+        self.disabled[index] = disabled;
+        self
+    }
+
+    /// Return the number of segments.
+    pub fn len(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Return `true` if there are no segments.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Return the index of the selected segment.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Return `true` if the segment at `index` is disabled.
+    pub fn is_segment_disabled(&self, index: usize) -> bool {
+        self.disabled.get(index).copied().unwrap_or(false)
+    }
+
+    fn select(&mut self, ctx: &mut EventCtx, index: usize) {
+        if index == self.selected || self.disabled.get(index) != Some(&false) {
+            return;
+        }
+        self.selected = index;
+        ctx.submit_action(Action::SegmentSelected(index));
+        ctx.request_paint();
+        trace!(
+            "SegmentedControl {:?} selected segment {}",
+            ctx.widget_id(),
+            index
+        );
+    }
+}
+
+impl Default for SegmentedControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, 'b> SegmentedControlMut<'a, 'b> {
+    /// Add a segment after the existing ones.
+    pub fn add_segment(&mut self, text: impl Into<LabelText>) {
+        self.widget.segments.push(WidgetPod::new(Label::new(text)));
+        self.widget.disabled.push(false);
+        self.ctx.children_changed();
+        self.ctx.request_layout();
+    }
+
+    /// Select the segment at `index`.
+    ///
+    /// Unlike selecting a segment by clicking it, this doesn't emit an action,
+    /// and can select a disabled segment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set_selected(&mut self, index: usize) {
+        assert!(
+            index < self.widget.segments.len(),
+            "segment index out of bounds"
+        );
+        self.widget.selected = index;
+        self.ctx.request_paint();
+    }
+
+    /// Set how segments are sized.
+    pub fn set_sizing(&mut self, sizing: SegmentSizing) {
+        self.widget.sizing = sizing;
+        self.ctx.request_layout();
+    }
+
+    /// Enable or disable the segment at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set_segment_disabled(&mut self, index: usize, disabled: bool) {
+        self.widget.disabled[index] = disabled;
+        let mut segment = self.ctx.get_mut(&mut self.widget.segments[index]);
+        // The disabled state is propagated when the WidgetMut is dropped.
+        Label::get_ctx(&mut segment.inner).set_disabled(disabled);
+    }
+
+    /// Return a mutable reference to the label of the segment at `index`.
+    pub fn segment_mut(&mut self, index: usize) -> WidgetMut<'_, 'b, Label> {
+        self.ctx.get_mut(&mut self.widget.segments[index])
+    }
+}
+
+impl Widget for SegmentedControl {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        for segment in &mut self.segments {
+            segment.on_event(ctx, event, env);
+        }
+        if ctx.is_handled() || ctx.is_disabled() {
+            return;
+        }
+
+        match event {
+            Event::MouseDown(mouse) => {
+                let clicked = self
+                    .segment_rects
+                    .iter()
+                    .position(|rect| rect.contains(mouse.pos));
+                if let Some(index) = clicked {
+                    ctx.request_focus();
+                    self.select(ctx, index);
+                    ctx.set_handled();
+                }
+            }
+            Event::KeyDown(key) if ctx.is_focused() => {
+                let target = match key.key {
+                    KbKey::ArrowLeft => (0..self.selected).rev().find(|&i| !self.disabled[i]),
+                    KbKey::ArrowRight => {
+                        (self.selected + 1..self.segments.len()).find(|&i| !self.disabled[i])
+                    }
+                    _ => return,
+                };
+                if let Some(index) = target {
+                    self.select(ctx, index);
+                }
+                ctx.set_handled();
+            }
+            _ => {}
+        }
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        if let StatusChange::FocusChanged(_) = event {
+            ctx.request_paint();
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        if let LifeCycle::BuildFocusChain = event {
+            if !self.segments.is_empty() {
+                ctx.register_for_focus();
+            }
+        }
+
+        for segment in &mut self.segments {
+            segment.lifecycle(ctx, event, env);
+        }
+
+        if let LifeCycle::WidgetAdded = event {
+            for (segment, disabled) in self.segments.iter_mut().zip(&self.disabled) {
+                if *disabled {
+                    let mut segment = ctx.get_mut(segment);
+                    Label::get_ctx(&mut segment.inner).set_disabled(true);
+                }
+            }
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        // Labels take the width of their text, whatever our constraints.
+        let label_bc = BoxConstraints::new(Size::ZERO, Size::new(f64::INFINITY, bc.max().height));
+        let label_sizes: Vec<Size> = self
+            .segments
+            .iter_mut()
+            .map(|segment| segment.layout(ctx, &label_bc, env))
+            .collect();
+
+        let height = label_sizes
+            .iter()
+            .map(|size| size.height)
+            .fold(0.0, f64::max)
+            + SEGMENT_INSETS.y_value();
+        let natural_widths = label_sizes
+            .iter()
+            .map(|size| size.width + SEGMENT_INSETS.x_value());
+        let widths: Vec<f64> = match self.sizing {
+            SegmentSizing::Equal if !self.segments.is_empty() => {
+                let shared_width = bc.min().width / self.segments.len() as f64;
+                let width = natural_widths.fold(shared_width, f64::max);
+                vec![width; self.segments.len()]
+            }
+            _ => natural_widths.collect(),
+        };
+
+        let mut x = 0.0;
+        self.segment_rects.clear();
+        for ((segment, label_size), width) in self.segments.iter_mut().zip(label_sizes).zip(widths)
+        {
+            // Labels are centered in their segment.
+            let origin = Point::new(
+                x + (width - label_size.width) / 2.0,
+                (height - label_size.height) / 2.0,
+            );
+            ctx.place_child(segment, origin, env);
+            self.segment_rects
+                .push(Rect::new(x, 0.0, x + width, height));
+            x += width;
+        }
+
+        let size = bc.constrain(Size::new(x, height));
+        trace!("Computed size: {}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        let stroke_width = env.get(theme::BUTTON_BORDER_WIDTH);
+        let radius = env.get(theme::BUTTON_BORDER_RADIUS);
+        let outline = ctx
+            .size()
+            .to_rect()
+            .inset(-stroke_width / 2.0)
+            .to_rounded_rect(radius);
+
+        let border_color = if ctx.is_disabled() {
+            env.get(theme::DISABLED_TEXT_COLOR)
+        } else if ctx.is_focused() {
+            env.get(theme::PRIMARY_LIGHT)
+        } else {
+            env.get(theme::BORDER_DARK)
+        };
+
+        ctx.fill(outline, &env.get(theme::BACKGROUND_DARK));
+
+        if let Some(rect) = self.segment_rects.get(self.selected) {
+            // Only the outer corners of the first and last segments are rounded.
+            let last = self.segment_rects.len() - 1;
+            let left = if self.selected == 0 { radius } else { 0.0 };
+            let right = if self.selected == last { radius } else { 0.0 };
+            let background = if ctx.is_disabled() {
+                env.get(theme::DISABLED_BUTTON_LIGHT)
+            } else {
+                env.get(theme::BUTTON_LIGHT)
+            };
+            ctx.fill(
+                rect.inset(-stroke_width / 2.0)
+                    .to_rounded_rect(RoundedRectRadii::new(left, right, right, left)),
+                &background,
+            );
+        }
+
+        for rect in self.segment_rects.iter().skip(1) {
+            ctx.stroke(
+                Line::new((rect.x0, rect.y0), (rect.x0, rect.y1)),
+                &border_color,
+                stroke_width,
+            );
+        }
+        ctx.stroke(outline, &border_color, stroke_width);
+
+        for segment in &mut self.segments {
+            segment.paint(ctx, env);
+        }
+    }
+
+    fn reset_interaction_state(&mut self) {
+        for segment in self.segments.iter_mut() {
+            segment.reset_interaction_state();
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.segments
+            .iter()
+            .map(|segment| segment.as_dyn())
+            .collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("SegmentedControl")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        self.segments
+            .get(self.selected)
+            .map(|segment| segment.as_ref().text().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::{KeyEvent, MouseButton, RawMods};
+
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::Flex;
+    use crate::WidgetId;
+
+    fn make_control(control_id: WidgetId, sizing: SegmentSizing) -> Flex {
+        let control = SegmentedControl::new()
+            .with_segment("Day")
+            .with_segment("Week")
+            .with_segment("Whole month")
+            .with_segment_disabled(2, true)
+            .with_sizing(sizing);
+        Flex::column().with_child_id(control, control_id)
+    }
+
+    fn segment_rects(harness: &TestHarness, control_id: WidgetId) -> Vec<Rect> {
+        let control = harness.get_widget(control_id);
+        let origin = control.state().window_layout_rect().origin().to_vec2();
+        let control = control.downcast::<SegmentedControl>().unwrap();
+        control
+            .segment_rects
+            .iter()
+            .map(|rect| *rect + origin)
+            .collect()
+    }
+
+    fn selected(harness: &TestHarness, control_id: WidgetId) -> usize {
+        let control = harness.get_widget(control_id);
+        control.downcast::<SegmentedControl>().unwrap().selected()
+    }
+
+    #[test]
+    fn click_selects_segment() {
+        let [control_id] = widget_ids();
+        let mut harness = TestHarness::create(make_control(control_id, SegmentSizing::Equal));
+        let rects = segment_rects(&harness, control_id);
+        assert_eq!(selected(&harness, control_id), 0);
+
+        harness.mouse_move(rects[1].center());
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert_eq!(selected(&harness, control_id), 1);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::SegmentSelected(1), control_id))
+        );
+
+        // Disabled segments can't be selected.
+        harness.mouse_move(rects[2].center());
+        harness.mouse_button_press(MouseButton::Left);
+        harness.mouse_button_release(MouseButton::Left);
+        assert_eq!(selected(&harness, control_id), 1);
+        assert_eq!(harness.pop_action(), None);
+    }
+
+    #[test]
+    fn arrow_keys_skip_disabled_segments() {
+        let [control_id] = widget_ids();
+        let mut harness = TestHarness::create(make_control(control_id, SegmentSizing::Equal));
+
+        harness.focus_on(Some(control_id));
+        let arrow_right = KeyEvent::for_test(RawMods::None, KbKey::ArrowRight);
+        harness.process_event(Event::KeyDown(arrow_right.clone()));
+        harness.process_event(Event::KeyDown(arrow_right));
+        assert_eq!(selected(&harness, control_id), 1);
+
+        let arrow_left = KeyEvent::for_test(RawMods::None, KbKey::ArrowLeft);
+        harness.process_event(Event::KeyDown(arrow_left));
+        assert_eq!(selected(&harness, control_id), 0);
+    }
+
+    #[test]
+    fn segment_sizing() {
+        let [control_id] = widget_ids();
+
+        let harness = TestHarness::create(make_control(control_id, SegmentSizing::Equal));
+        let widths: Vec<f64> = segment_rects(&harness, control_id)
+            .iter()
+            .map(|rect| rect.width())
+            .collect();
+        assert_eq!(widths[0], widths[1]);
+        assert_eq!(widths[1], widths[2]);
+
+        let harness = TestHarness::create(make_control(control_id, SegmentSizing::Auto));
+        let auto_widths: Vec<f64> = segment_rects(&harness, control_id)
+            .iter()
+            .map(|rect| rect.width())
+            .collect();
+        assert!(auto_widths[0] < auto_widths[2]);
+        // Equal segments are as wide as the widest label.
+        assert_eq!(widths[0], auto_widths[2]);
+    }
+
+    #[test]
+    fn disabled_segment_is_dimmed() {
+        let [control_id] = widget_ids();
+        let harness = TestHarness::create(make_control(control_id, SegmentSizing::Equal));
+        let control = harness.get_widget(control_id);
+        let segments = control.children();
+        assert!(!segments[0].state().is_disabled());
+        assert!(segments[2].state().is_disabled());
+    }
+}
@@ -380,6 +380,12 @@ impl Widget for SizedBox {
         }
     }
 
+    fn reset_interaction_state(&mut self) {
+        if let Some(child) = &mut self.child {
+            child.reset_interaction_state();
+        }
+    }
+
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
         if let Some(child) = &self.child {
             smallvec![child.as_dyn()]
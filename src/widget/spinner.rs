@@ -19,13 +19,19 @@ use crate::{
 // TODO - Set color
 /// An animated spinner widget for showing a loading state.
 ///
-/// To customize the spinner's size, you can place it inside a [`SizedBox`]
+/// To customize the spinner's size, you can set its radius with
+/// [`with_radius`](Self::with_radius), or place it inside a [`SizedBox`]
 /// that has a fixed width and height.
 ///
+/// The spinner turns once per second, driven by animation frames. It only asks
+/// for another frame when it receives one, so it stops animating once it's
+/// removed from the widget tree.
+///
 /// [`SizedBox`]: struct.SizedBox.html
 pub struct Spinner {
     t: f64,
     color: KeyOrValue<Color>,
+    radius: Option<f64>,
 }
 
 crate::declare_widget!(SpinnerMut, Spinner);
@@ -45,6 +51,20 @@ impl Spinner {
         self.color = color.into();
         self
     }
+
+    /// Builder-style method for setting the outer radius of the spinner.
+    ///
+    /// The spinner then asks for a size of twice the radius, instead of filling
+    /// the available space.
+    pub fn with_radius(mut self, radius: f64) -> Self {
+        self.radius = Some(radius.max(0.0));
+        self
+    }
+
+    /// How far the spinner is through its current turn, from `0.0` to `1.0`.
+    pub fn phase(&self) -> f64 {
+        self.t
+    }
 }
 
 impl SpinnerMut<'_, '_> {
@@ -57,6 +77,12 @@ impl SpinnerMut<'_, '_> {
         self.widget.color = color.into();
         self.ctx.request_paint();
     }
+
+    /// Set the outer radius of the spinner, or `None` to fill the available space.
+    pub fn set_radius(&mut self, radius: Option<f64>) {
+        self.widget.radius = radius.map(|radius| radius.max(0.0));
+        self.ctx.request_layout();
+    }
 }
 
 impl Default for Spinner {
@@ -64,6 +90,7 @@ impl Default for Spinner {
         Spinner {
             t: 0.0,
             color: theme::TEXT_COLOR.into(),
+            radius: None,
         }
     }
 }
@@ -72,9 +99,8 @@ impl Widget for Spinner {
     fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, _env: &Env) {
         if let Event::AnimFrame(interval) = event {
             self.t += (*interval as f64) * 1e-9;
-            if self.t >= 1.0 {
-                self.t = 0.0;
-            }
+            // Keep the time past a full turn, so the speed stays constant.
+            self.t = self.t.fract();
             ctx.request_anim_frame();
             ctx.request_paint();
         }
@@ -90,7 +116,9 @@ impl Widget for Spinner {
     }
 
     fn layout(&mut self, _ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
-        let size = if bc.is_width_bounded() && bc.is_height_bounded() {
+        let size = if let Some(radius) = self.radius {
+            bc.constrain(Size::new(2.0 * radius, 2.0 * radius))
+        } else if bc.is_width_bounded() && bc.is_height_bounded() {
             bc.max()
         } else {
             bc.constrain(Size::new(
@@ -108,7 +136,8 @@ impl Widget for Spinner {
         let (width, height) = (ctx.size().width, ctx.size().height);
         let center = Point::new(width / 2.0, height / 2.0);
         let (r, g, b, original_alpha) = Color::as_rgba(self.color.resolve(env));
-        let scale_factor = width.min(height) / 40.0;
+        let radius = self.radius.unwrap_or(f64::INFINITY);
+        let scale_factor = width.min(height).min(2.0 * radius) / 40.0;
 
         for step in 1..=12 {
             let step = f64::from(step);
@@ -136,8 +165,9 @@ impl Widget for Spinner {
 mod tests {
     use super::*;
     use crate::assert_render_snapshot;
-    use crate::testing::TestHarness;
-    //use instant::Duration;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::Flex;
+    use instant::Duration;
 
     #[test]
     fn simple_spinner() {
@@ -151,6 +181,44 @@ mod tests {
         //assert_render_snapshot!(harness, "spinner_700ms");
     }
 
+    #[test]
+    fn phase_advances() {
+        fn phase(harness: &TestHarness) -> f64 {
+            let spinner = harness.root_widget();
+            spinner.downcast::<Spinner>().unwrap().deref().phase()
+        }
+        fn frame(harness: &mut TestHarness, millis: u64) {
+            let interval = Duration::from_millis(millis).as_nanos() as u64;
+            harness.process_event(Event::AnimFrame(interval));
+        }
+
+        let mut harness = TestHarness::create(Spinner::new());
+        assert_eq!(phase(&harness), 0.0);
+        for expected in [0.1, 0.2, 0.3] {
+            frame(&mut harness, 100);
+            assert!(
+                (phase(&harness) - expected).abs() < 1e-9,
+                "{}",
+                phase(&harness)
+            );
+        }
+        frame(&mut harness, 250);
+        assert!((phase(&harness) - 0.55).abs() < 1e-9, "{}", phase(&harness));
+        // The spinner wraps around after a full turn.
+        frame(&mut harness, 500);
+        assert!((phase(&harness) - 0.05).abs() < 1e-9, "{}", phase(&harness));
+    }
+
+    #[test]
+    fn radius() {
+        let [spinner_id] = widget_ids();
+        let widget = Flex::column().with_child_id(Spinner::new().with_radius(12.0), spinner_id);
+
+        let harness = TestHarness::create(widget);
+        let size = harness.get_widget(spinner_id).state().layout_rect().size();
+        assert_eq!(size, Size::new(24.0, 24.0));
+    }
+
     #[test]
     fn edit_spinner() {
         let image_1 = {
@@ -562,6 +562,11 @@ impl Widget for Split {
         self.child2.paint(ctx, env);
     }
 
+    fn reset_interaction_state(&mut self) {
+        self.child1.reset_interaction_state();
+        self.child2.reset_interaction_state();
+    }
+
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
         smallvec![self.child1.as_dyn(), self.child2.as_dyn()]
     }
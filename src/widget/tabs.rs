@@ -0,0 +1,398 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A tab bar with switchable content.
+
+use druid_shell::KbKey;
+use smallvec::SmallVec;
+use tracing::{trace, trace_span, Span};
+
+use crate::action::Action;
+use crate::kurbo::Line;
+use crate::widget::{Label, LabelText, WidgetMut, WidgetRef};
+use crate::{
+    theme, BoxConstraints, Env, Event, EventCtx, Insets, LayoutCtx, LifeCycle, LifeCycleCtx,
+    PaintCtx, Point, Rect, RenderContext, Size, StatusChange, Widget, WidgetId, WidgetPod,
+};
+
+// The padding around each header's label.
+const HEADER_INSETS: Insets = Insets::uniform_xy(10., 4.);
+// The height of the line under the selected header.
+const SELECTED_LINE_WIDTH: f64 = 2.0;
+
+/// A row of tab headers above a content area showing the selected tab.
+///
+/// Headers are labels sized to their text, laid out left to right. Clicking a
+/// header selects its tab; when the tab bar has focus, the left and right arrow
+/// keys select the previous and next tab. Either way, [`Action::TabSelected`]
+/// is emitted with the new index.
+///
+/// All tab contents stay in the widget tree, but only the selected one is laid
+/// out and painted; the others are stashed.
+pub struct Tabs {
+    headers: Vec<WidgetPod<Label>>,
+    contents: Vec<WidgetPod<Box<dyn Widget>>>,
+    selected: usize,
+    // The bounds of each header, set during layout.
+    header_rects: Vec<Rect>,
+}
+
+crate::declare_widget!(TabsMut, Tabs);
+
+impl Tabs {
+    /// Create a new `Tabs` widget with no tabs.
+    pub fn new() -> Self {
+        Tabs {
+            headers: Vec::new(),
+            contents: Vec::new(),
+            selected: 0,
+            header_rects: Vec::new(),
+        }
+    }
+
+    /// Builder-style method to add a tab.
+    ///
+    /// The first tab added is selected by default.
+    pub fn with_tab(self, title: impl Into<LabelText>, content: impl Widget) -> Self {
+        self.with_tab_id(title, content, WidgetId::next())
+    }
+
+    /// Builder-style method to add a tab whose content has the given id.
+    ///
+    /// See also [`with_tab`](Self::with_tab).
+    pub fn with_tab_id(
+        mut self,
+        title: impl Into<LabelText>,
+        content: impl Widget,
+        id: WidgetId,
+    ) -> Self {
+        let mut content = WidgetPod::new_with_id(Box::new(content) as Box<dyn Widget>, id);
+        content.state.is_stashed = !self.contents.is_empty();
+        self.headers.push(WidgetPod::new(Label::new(title)));
+        self.contents.push(content);
+        self
+    }
+
+    /// Builder-style method to set the initially selected tab.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn with_selected(mut self, index: usize) -> Self {
+        assert!(index < self.contents.len(), "tab index out of bounds");
+        for (i, content) in self.contents.iter_mut().enumerate() {
+            content.state.is_stashed = i != index;
+        }
+        self.selected = index;
+        self
+    }
+
+    /// Return the number of tabs.
+    pub fn len(&self) -> usize {
+        self.contents.len()
+    }
+
+    /// Return `true` if there are no tabs.
+    pub fn is_empty(&self) -> bool {
+        self.contents.is_empty()
+    }
+
+    /// Return the index of the selected tab.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    fn select(&mut self, ctx: &mut EventCtx, index: usize) {
+        if index == self.selected || index >= self.contents.len() {
+            return;
+        }
+        ctx.set_stashed(&mut self.contents[self.selected], true);
+        ctx.set_stashed(&mut self.contents[index], false);
+        self.selected = index;
+        ctx.submit_action(Action::TabSelected(index));
+        ctx.request_layout();
+        trace!("Tabs {:?} selected tab {}", ctx.widget_id(), index);
+    }
+}
+
+impl Default for Tabs {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, 'b> TabsMut<'a, 'b> {
+    /// Add a tab after the existing ones.
+    pub fn add_tab(&mut self, title: impl Into<LabelText>, content: impl Widget) {
+        let mut content = WidgetPod::new(Box::new(content) as Box<dyn Widget>);
+        content.state.is_stashed = !self.widget.contents.is_empty();
+        self.widget.headers.push(WidgetPod::new(Label::new(title)));
+        self.widget.contents.push(content);
+        self.ctx.children_changed();
+        self.ctx.request_layout();
+    }
+
+    /// Select the tab at `index`.
+    ///
+    /// Unlike selecting a tab by clicking it, this doesn't emit an action.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn set_selected(&mut self, index: usize) {
+        assert!(
+            index < self.widget.contents.len(),
+            "tab index out of bounds"
+        );
+        let previous = self.widget.selected;
+        if index == previous {
+            return;
+        }
+        self.ctx
+            .set_stashed(&mut self.widget.contents[previous], true);
+        self.ctx
+            .set_stashed(&mut self.widget.contents[index], false);
+        self.widget.selected = index;
+        self.ctx.request_layout();
+    }
+
+    /// Return a mutable reference to the header label of the tab at `index`.
+    pub fn header_mut(&mut self, index: usize) -> WidgetMut<'_, 'b, Label> {
+        self.ctx.get_mut(&mut self.widget.headers[index])
+    }
+
+    /// Return a mutable reference to the content of the tab at `index`.
+    pub fn content_mut(&mut self, index: usize) -> WidgetMut<'_, 'b, Box<dyn Widget>> {
+        self.ctx.get_mut(&mut self.widget.contents[index])
+    }
+}
+
+impl Widget for Tabs {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        // Children see the event before the selection changes, so a newly
+        // selected content doesn't get events before it has been laid out.
+        for header in &mut self.headers {
+            header.on_event(ctx, event, env);
+        }
+        if let Some(content) = self.contents.get_mut(self.selected) {
+            content.on_event(ctx, event, env);
+        }
+        if ctx.is_handled() || ctx.is_disabled() {
+            return;
+        }
+
+        match event {
+            Event::MouseDown(mouse) => {
+                let clicked = self
+                    .header_rects
+                    .iter()
+                    .position(|rect| rect.contains(mouse.pos));
+                if let Some(index) = clicked {
+                    ctx.request_focus();
+                    self.select(ctx, index);
+                    ctx.set_handled();
+                }
+            }
+            Event::KeyDown(key) if ctx.is_focused() => match key.key {
+                KbKey::ArrowLeft if self.selected > 0 => {
+                    self.select(ctx, self.selected - 1);
+                    ctx.set_handled();
+                }
+                KbKey::ArrowRight => {
+                    self.select(ctx, self.selected + 1);
+                    ctx.set_handled();
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, _env: &Env) {
+        if let StatusChange::FocusChanged(_) = event {
+            ctx.request_paint();
+        }
+    }
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        if let LifeCycle::BuildFocusChain = event {
+            if !self.contents.is_empty() {
+                ctx.register_for_focus();
+            }
+        }
+
+        for header in &mut self.headers {
+            header.lifecycle(ctx, event, env);
+        }
+        for content in &mut self.contents {
+            content.lifecycle(ctx, event, env);
+        }
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        // Headers take the width of their text, whatever our constraints.
+        let header_bc = BoxConstraints::new(Size::ZERO, Size::new(f64::INFINITY, bc.max().height));
+        let mut header_x = 0.0;
+        let mut header_bar_height: f64 = 0.0;
+        self.header_rects.clear();
+        for header in &mut self.headers {
+            let label_size = header.layout(ctx, &header_bc, env);
+            let origin = Point::new(header_x + HEADER_INSETS.x0, HEADER_INSETS.y0);
+            ctx.place_child(header, origin, env);
+
+            let header_size = Size::new(
+                label_size.width + HEADER_INSETS.x_value(),
+                label_size.height + HEADER_INSETS.y_value() + SELECTED_LINE_WIDTH,
+            );
+            self.header_rects
+                .push(Rect::from_origin_size((header_x, 0.0), header_size));
+            header_x += header_size.width;
+            header_bar_height = header_bar_height.max(header_size.height);
+        }
+        // Headers of different heights all get the height of the tallest one.
+        for rect in &mut self.header_rects {
+            rect.y1 = header_bar_height;
+        }
+
+        let content_bc = bc.shrink((0.0, header_bar_height)).loosen();
+        let content_size = match self.contents.get_mut(self.selected) {
+            Some(content) => {
+                let size = content.layout(ctx, &content_bc, env);
+                ctx.place_child(content, Point::new(0.0, header_bar_height), env);
+                size
+            }
+            None => Size::ZERO,
+        };
+
+        let size = bc.constrain(Size::new(
+            header_x.max(content_size.width),
+            header_bar_height + content_size.height,
+        ));
+        trace!("Computed size: {}", size);
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        if let Some(rect) = self.header_rects.get(self.selected) {
+            ctx.fill(*rect, &env.get(theme::BACKGROUND_LIGHT));
+
+            let line_color = if ctx.is_disabled() {
+                env.get(theme::DISABLED_TEXT_COLOR)
+            } else if ctx.is_focused() {
+                env.get(theme::PRIMARY_LIGHT)
+            } else {
+                env.get(theme::BORDER_LIGHT)
+            };
+            let line_y = rect.y1 - SELECTED_LINE_WIDTH / 2.0;
+            ctx.stroke(
+                Line::new((rect.x0, line_y), (rect.x1, line_y)),
+                &line_color,
+                SELECTED_LINE_WIDTH,
+            );
+        }
+
+        for header in &mut self.headers {
+            header.paint(ctx, env);
+        }
+        if let Some(content) = self.contents.get_mut(self.selected) {
+            content.paint(ctx, env);
+        }
+    }
+
+    fn reset_interaction_state(&mut self) {
+        for child in self.headers.iter_mut() {
+            child.reset_interaction_state();
+        }
+        for child in self.contents.iter_mut() {
+            child.reset_interaction_state();
+        }
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        self.headers
+            .iter()
+            .map(|header| header.as_dyn())
+            .chain(self.contents.iter().map(|content| content.as_dyn()))
+            .collect()
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("Tabs")
+    }
+
+    fn get_debug_text(&self) -> Option<String> {
+        self.headers
+            .get(self.selected)
+            .map(|header| header.as_ref().text().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use druid_shell::{KeyEvent, MouseButton, RawMods};
+
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::Flex;
+
+    fn make_tabs(tabs_id: WidgetId, content_ids: [WidgetId; 3]) -> Flex {
+        let tabs = Tabs::new()
+            .with_tab_id("General", Label::new("General settings"), content_ids[0])
+            .with_tab_id("Advanced", Label::new("Advanced settings"), content_ids[1])
+            .with_tab_id("About", Label::new("About this app"), content_ids[2]);
+        Flex::column().with_child_id(tabs, tabs_id)
+    }
+
+    #[test]
+    fn click_selects_tab() {
+        let [tabs_id, general_id, advanced_id, about_id] = widget_ids();
+        let widget = make_tabs(tabs_id, [general_id, advanced_id, about_id]);
+
+        let mut harness = TestHarness::create(widget);
+        let tabs = harness.get_widget(tabs_id);
+        let tabs_origin = tabs.state().window_layout_rect().origin();
+        let tabs = tabs.downcast::<Tabs>().unwrap();
+        assert_eq!(tabs.selected(), 0);
+        let advanced_header = tabs.header_rects[1];
+
+        harness.mouse_move(tabs_origin + advanced_header.center().to_vec2());
+        harness.mouse_button_press(MouseButton::Left);
+
+        let tabs = harness.get_widget(tabs_id);
+        assert_eq!(tabs.downcast::<Tabs>().unwrap().selected(), 1);
+        assert_eq!(
+            harness.pop_action(),
+            Some((Action::TabSelected(1), tabs_id))
+        );
+    }
+
+    #[test]
+    fn content_matches_selected_tab() {
+        let [tabs_id, general_id, advanced_id, about_id] = widget_ids();
+        let widget = make_tabs(tabs_id, [general_id, advanced_id, about_id]);
+
+        let mut harness = TestHarness::create(widget);
+        let is_shown = |harness: &TestHarness, id| !harness.get_widget(id).state().is_stashed;
+        assert!(is_shown(&harness, general_id));
+        assert!(!is_shown(&harness, advanced_id));
+        assert!(!is_shown(&harness, about_id));
+
+        harness.focus_on(Some(tabs_id));
+        let arrow_right = KeyEvent::for_test(RawMods::None, KbKey::ArrowRight);
+        harness.process_event(Event::KeyDown(arrow_right.clone()));
+        harness.process_event(Event::KeyDown(arrow_right));
+        assert!(!is_shown(&harness, general_id));
+        assert!(!is_shown(&harness, advanced_id));
+        assert!(is_shown(&harness, about_id));
+
+        harness.edit_root_widget(|mut root, _| {
+            let mut flex = root.downcast::<Flex>().unwrap();
+            let mut tabs = flex.child_mut(0).unwrap();
+            let mut tabs = tabs.downcast::<Tabs>().unwrap();
+            tabs.set_selected(0);
+        });
+        assert!(is_shown(&harness, general_id));
+        assert!(!is_shown(&harness, about_id));
+    }
+}
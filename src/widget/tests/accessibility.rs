@@ -0,0 +1,91 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+use crate::testing::{widget_ids, TestHarness};
+use crate::widget::{Button, ContentRole, Flex, Label, Portal};
+use crate::*;
+
+#[test]
+fn button_contains_label_node() {
+    let [button_id, label_id] = widget_ids();
+    let widget = Flex::column()
+        .with_child_id(Button::new("Increment"), button_id)
+        .with_child_id(Label::new("Count: 0"), label_id);
+    let mut harness = TestHarness::create(widget);
+
+    let tree = harness.accessibility_tree();
+    // Flex has no role, so its children's nodes are top-level.
+    assert_eq!(tree.nodes.len(), 2);
+    assert_eq!(tree.focus, None);
+
+    let button = &tree.nodes[0];
+    assert_eq!(button.id, button_id);
+    assert_eq!(button.role, Role::Button);
+    assert_eq!(button.name.as_deref(), Some("Increment"));
+    assert_eq!(
+        button.bounds,
+        harness.get_widget(button_id).state().window_layout_rect()
+    );
+    assert_eq!(button.children.len(), 1);
+    let button_label = &button.children[0];
+    assert_eq!(button_label.role, Role::Label);
+    assert_eq!(button_label.name.as_deref(), Some("Increment"));
+    assert!(button.bounds.contains(button_label.bounds.center()));
+
+    let label = tree.get(label_id).unwrap();
+    assert_eq!(label.role, Role::Label);
+    assert_eq!(label.name.as_deref(), Some("Count: 0"));
+    assert!(!label.is_focused);
+
+    harness.focus_on(Some(label_id));
+    let tree = harness.accessibility_tree();
+    assert_eq!(tree.focus, Some(label_id));
+    assert!(tree.focused_node().unwrap().is_focused);
+    assert!(!tree.get(button_id).unwrap().is_focused);
+}
+
+#[test]
+fn scrolled_out_nodes_are_hidden() {
+    let [top_id, bottom_id] = widget_ids();
+    let widget = Portal::new(
+        Flex::column()
+            .with_child_id(Label::new("Top"), top_id)
+            .with_spacer(1000.0)
+            .with_child_id(Label::new("Bottom"), bottom_id),
+    );
+    let harness = TestHarness::create_with_size(widget, Size::new(400.0, 400.0));
+
+    let tree = harness.accessibility_tree();
+    assert!(!tree.get(top_id).unwrap().is_hidden);
+    assert!(tree.get(bottom_id).unwrap().is_hidden);
+}
+
+#[test]
+fn label_content_role() {
+    let [heading_id, paragraph_id, label_id] = widget_ids();
+    let widget = Flex::column()
+        .with_child_id(
+            Label::new("Settings").with_content_role(ContentRole::Heading(2)),
+            heading_id,
+        )
+        .with_child_id(
+            Label::new("Nothing to see here.").with_content_role(ContentRole::Static),
+            paragraph_id,
+        )
+        .with_child_id(Label::new("Name:"), label_id);
+    let harness = TestHarness::create(widget);
+
+    let tree = harness.accessibility_tree();
+    let heading = tree.get(heading_id).unwrap();
+    assert_eq!(heading.role, Role::Heading { level: 2 });
+    assert_eq!(heading.name.as_deref(), Some("Settings"));
+    assert_eq!(tree.get(paragraph_id).unwrap().role, Role::StaticText);
+    assert_eq!(tree.get(label_id).unwrap().role, Role::Label);
+}
+
+#[test]
+#[should_panic(expected = "heading level must be between 1 and 6")]
+fn invalid_heading_level() {
+    let _ = Label::new("Title").with_content_role(ContentRole::Heading(7));
+}
@@ -2,8 +2,10 @@
 // "as-is" basis without warranties of any kind. See the LICENSE file for
 // details.
 
-use crate::testing::{ModularWidget, Record, Recording, TestHarness, TestWidgetExt as _};
-use crate::widget::{Flex, SizedBox};
+use crate::testing::{
+    widget_ids, ModularWidget, Record, RecordedCommand, Recording, TestHarness, TestWidgetExt as _,
+};
+use crate::widget::{Flex, Label, SizedBox};
 use crate::*;
 
 /// Ensure that notifications are delivered to ancestors, but not siblings.
@@ -39,3 +41,124 @@ fn notifications() {
     assert!(saw_notification(&parent_rec));
     assert!(saw_notification(&grandparent_rec));
 }
+
+/// Ensure that commands sent to subscribers skip widgets that didn't subscribe.
+#[test]
+fn subscribed_commands() {
+    const THEME_CHANGED: Selector = Selector::new("masonry-test.theme-changed");
+
+    let subscriber = ModularWidget::new(()).lifecycle_fn(|_, ctx, event, _| {
+        if matches!(event, LifeCycle::WidgetAdded) {
+            ctx.subscribe_to(THEME_CHANGED);
+        }
+    });
+
+    let subscriber_rec = Recording::default();
+    let sibling_rec = Recording::default();
+    let parent_rec = Recording::default();
+
+    let tree = Flex::row()
+        .with_child(subscriber.record(&subscriber_rec))
+        .with_child(Label::new("Not subscribed").record(&sibling_rec))
+        .record(&parent_rec);
+
+    let saw_command = |rec: &Recording| {
+        rec.drain()
+            .iter()
+            .any(|ev| matches!(ev, Record::E(Event::Command(cmd)) if cmd.is(THEME_CHANGED)))
+    };
+
+    let mut harness = TestHarness::create(tree);
+    harness.submit_command(Command::from(THEME_CHANGED).to_subscribers());
+
+    assert!(saw_command(&subscriber_rec));
+    assert!(!saw_command(&sibling_rec));
+    // The parent only routes the command.
+    assert!(!saw_command(&parent_rec));
+
+    // Without `to_subscribers`, the command is broadcast as usual.
+    harness.submit_command(THEME_CHANGED);
+    assert!(saw_command(&subscriber_rec));
+    assert!(saw_command(&sibling_rec));
+    assert!(saw_command(&parent_rec));
+}
+
+/// Ensure the harness records commands with the widget that sent them.
+#[test]
+fn record_commands() {
+    const CLICKED: Selector = Selector::new("masonry-test.clicked");
+
+    let [sender_id, receiver_id] = widget_ids();
+    let sender = ModularWidget::new(()).event_fn(move |_, ctx, event, _| {
+        if matches!(event, Event::MouseDown(_)) {
+            ctx.submit_command(CLICKED.to(receiver_id));
+        }
+    });
+
+    let tree = Flex::row()
+        .with_child_id(sender, sender_id)
+        .with_child_id(SizedBox::empty(), receiver_id);
+    let mut harness = TestHarness::create(tree);
+
+    harness.mouse_click_on(sender_id);
+    assert!(harness.take_recorded_commands().is_empty());
+
+    harness.start_recording_commands();
+    harness.mouse_click_on(sender_id);
+    assert_eq!(
+        harness.take_recorded_commands(),
+        vec![RecordedCommand::new(
+            Some(sender_id),
+            CLICKED,
+            Target::Widget(receiver_id)
+        )]
+    );
+    assert!(harness.take_recorded_commands().is_empty());
+}
+
+/// Ensure that commands sent to a widget are only delivered to that widget.
+#[test]
+fn targeted_commands() {
+    const PING: Selector = Selector::new("masonry-test.ping");
+
+    let [sender_id, target_id] = widget_ids();
+    let sender = ModularWidget::new(()).event_fn(move |_, ctx, event, _| {
+        if matches!(event, Event::MouseDown(_)) {
+            ctx.submit_command(PING.to(target_id));
+        }
+    });
+
+    let sender_rec = Recording::default();
+    let target_rec = Recording::default();
+    let other_rec = Recording::default();
+    let parent_rec = Recording::default();
+
+    let tree = Flex::row()
+        .with_child_id(sender.record(&sender_rec), sender_id)
+        .with_child_id(Label::new("Target").record(&target_rec), target_id)
+        .with_child(Label::new("Other").record(&other_rec))
+        .record(&parent_rec);
+
+    let saw_command = |rec: &Recording| {
+        rec.drain()
+            .iter()
+            .any(|ev| matches!(ev, Record::E(Event::Command(cmd)) if cmd.is(PING)))
+    };
+
+    let mut harness = TestHarness::create(tree);
+    harness.start_recording_commands();
+    harness.mouse_click_on(sender_id);
+
+    assert!(saw_command(&target_rec));
+    assert!(!saw_command(&sender_rec));
+    assert!(!saw_command(&other_rec));
+    assert!(!saw_command(&parent_rec));
+    assert_eq!(
+        harness.take_recorded_commands(),
+        vec![RecordedCommand::new(
+            Some(sender_id),
+            PING,
+            Target::Widget(target_id)
+        )]
+    );
+}
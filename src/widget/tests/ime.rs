@@ -0,0 +1,130 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::widget::Flex;
+use crate::*;
+
+const REQUEST_FOCUS: Selector<()> = Selector::new("masonry-test.request-focus");
+
+#[derive(Default)]
+struct EditorState {
+    committed: String,
+    preedit: String,
+}
+
+fn editor(state: Rc<RefCell<EditorState>>) -> impl Widget {
+    ModularWidget::new(state)
+        .event_fn(|state, ctx, event, _env| match event {
+            Event::Command(cmd) if cmd.is(REQUEST_FOCUS) => {
+                ctx.request_focus();
+            }
+            Event::Ime(ImeEvent::Preedit(text)) => {
+                state.borrow_mut().preedit = text.clone();
+                ctx.set_ime_caret_rect(Rect::new(10.0, 0.0, 11.0, 20.0));
+                ctx.request_paint();
+            }
+            Event::Ime(ImeEvent::Commit(text)) => {
+                let mut state = state.borrow_mut();
+                state.preedit.clear();
+                state.committed.push_str(text);
+                ctx.request_paint();
+            }
+            _ => {}
+        })
+        .lifecycle_fn(|_state, ctx, event, _env| {
+            if let LifeCycle::BuildFocusChain = event {
+                ctx.register_for_focus();
+            }
+        })
+}
+
+#[test]
+fn preedit_and_commit() {
+    let [editor_id] = widget_ids();
+    let state: Rc<RefCell<EditorState>> = Default::default();
+
+    let widget = editor(state.clone()).with_id(editor_id);
+    let mut harness = TestHarness::create(widget);
+
+    // IME events are only delivered to the focused widget
+    harness.process_event(Event::Ime(ImeEvent::Commit("lost".into())));
+    assert_eq!(state.borrow().committed, "");
+
+    harness.submit_command(REQUEST_FOCUS);
+    harness.process_event(Event::Ime(ImeEvent::Commit("ab".into())));
+    assert_eq!(state.borrow().committed, "ab");
+
+    // Preedit text is displayed, but isn't committed
+    harness.process_event(Event::Ime(ImeEvent::Preedit("c".into())));
+    assert_eq!(state.borrow().committed, "ab");
+    assert_eq!(state.borrow().preedit, "c");
+
+    harness.process_event(Event::Ime(ImeEvent::Preedit("cd".into())));
+    assert_eq!(state.borrow().committed, "ab");
+    assert_eq!(state.borrow().preedit, "cd");
+
+    harness.process_event(Event::Ime(ImeEvent::Commit("CD".into())));
+    assert_eq!(state.borrow().committed, "abCD");
+    assert_eq!(state.borrow().preedit, "");
+}
+
+#[test]
+fn report_caret_rect() {
+    let [editor_id] = widget_ids();
+    let state: Rc<RefCell<EditorState>> = Default::default();
+
+    let widget = Flex::column()
+        .with_spacer(50.0)
+        .with_child_id(editor(state), editor_id);
+    let mut harness = TestHarness::create(widget);
+
+    assert_eq!(harness.window().ime_caret_rect(), None);
+
+    harness.submit_command(REQUEST_FOCUS);
+    harness.process_event(Event::Ime(ImeEvent::Preedit("a".into())));
+
+    let editor_origin = harness
+        .get_widget(editor_id)
+        .state()
+        .window_layout_rect()
+        .origin();
+    assert_eq!(
+        harness.window().ime_caret_rect(),
+        Some(Rect::new(10.0, 0.0, 11.0, 20.0) + editor_origin.to_vec2())
+    );
+}
+
+#[test]
+fn nested_caret_rect() {
+    let [row_id, editor_id] = widget_ids();
+    let state: Rc<RefCell<EditorState>> = Default::default();
+
+    // Our widget hierarchy is:
+    // - column, at the window origin
+    //  - row, below a spacer
+    //   - editor, after a spacer
+    let widget = Flex::column().with_spacer(50.0).with_child_id(
+        Flex::row()
+            .with_spacer(30.0)
+            .with_child_id(editor(state), editor_id),
+        row_id,
+    );
+    let mut harness = TestHarness::create(widget);
+
+    harness.submit_command(REQUEST_FOCUS);
+    harness.process_event(Event::Ime(ImeEvent::Preedit("a".into())));
+
+    let row_origin = harness.get_widget(row_id).state().layout_rect().origin();
+    let editor_origin = harness.get_widget(editor_id).state().layout_rect().origin();
+    assert!(row_origin.y >= 50.0);
+    assert!(editor_origin.x >= 30.0);
+    assert_eq!(
+        harness.window().ime_caret_rect(),
+        Some(Rect::new(10.0, 0.0, 11.0, 20.0) + row_origin.to_vec2() + editor_origin.to_vec2())
+    );
+}
@@ -4,8 +4,14 @@
 
 //! Tests related to propagation of invalid rects.
 
-use crate::testing::{widget_ids, TestHarness};
-use crate::widget::{Button, Flex};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use smallvec::smallvec;
+
+use crate::testing::{widget_ids, ModularWidget, TestHarness, HARNESS_DEFAULT_SIZE};
+use crate::widget::{Button, Flex, Label};
+use crate::*;
 
 #[test]
 fn invalidate_union() {
@@ -38,3 +44,131 @@ fn invalidate_union() {
 }
 
 // TODO: Add a test with scrolling/viewport
+
+#[test]
+fn merge_child_paint_request() {
+    const PAINT_ME: Selector = Selector::new("masonry-test.paint-me");
+
+    let child = ModularWidget::new(()).event_fn(|_, ctx, event, _| {
+        if let Event::Command(cmd) = event {
+            if cmd.is(PAINT_ME) {
+                ctx.request_paint();
+            }
+        }
+    });
+
+    // Whether the parent needed a paint before and after merging the child's state.
+    let parent_needed_paint: Rc<Cell<Option<(bool, bool)>>> = Default::default();
+    let parent_state = (WidgetPod::new(child), parent_needed_paint.clone());
+    let parent = ModularWidget::new(parent_state)
+        .event_fn(|(child, needed_paint), ctx, event, env| {
+            let before_merge = ctx.widget_state.needs_paint();
+            child.on_event(ctx, event, env);
+            ctx.merge_child_state(child);
+            if let Event::Command(cmd) = event {
+                if cmd.is(PAINT_ME) {
+                    needed_paint.set(Some((before_merge, ctx.widget_state.needs_paint())));
+                }
+            }
+        })
+        .lifecycle_fn(|(child, _), ctx, event, env| child.lifecycle(ctx, event, env))
+        .layout_fn(|(child, _), ctx, bc, env| {
+            let size = child.layout(ctx, bc, env);
+            ctx.place_child(child, Point::ORIGIN, env);
+            size
+        })
+        .paint_fn(|(child, _), ctx, env| child.paint(ctx, env))
+        .children_fn(|(child, _)| smallvec![child.as_dyn()]);
+
+    let mut harness = TestHarness::create(parent);
+    let _ = harness.render();
+
+    harness.submit_command(PAINT_ME);
+    assert_eq!(parent_needed_paint.get(), Some((false, true)));
+}
+
+#[test]
+fn paint_cache_replays_until_paint_request() {
+    const PAINT_ME: Selector = Selector::new("masonry-test.paint-me");
+    const CHANGE_KEY: Selector = Selector::new("masonry-test.change-key");
+
+    // How many times the cached closure ran, and the key it's cached with.
+    let paint_count: Rc<Cell<u32>> = Default::default();
+    let widget = ModularWidget::new((paint_count.clone(), 0))
+        .event_fn(|(_, key), ctx, event, _| {
+            if let Event::Command(cmd) = event {
+                if cmd.is(PAINT_ME) {
+                    ctx.request_paint();
+                }
+                // Changes what's painted without requesting a paint.
+                if cmd.is(CHANGE_KEY) {
+                    *key += 1;
+                }
+            }
+        })
+        .layout_fn(|_, _, _, _| Size::new(20.0, 20.0))
+        .paint_fn(|(paint_count, key), ctx, _| {
+            ctx.with_cache(*key, |ctx| {
+                paint_count.set(paint_count.get() + 1);
+                let rect = ctx.size().to_rect();
+                ctx.fill(rect, &Color::RED);
+            });
+        });
+
+    let mut harness = TestHarness::create_with_size(widget, Size::new(20.0, 20.0));
+    let red_pixel = |pixels: &[u8]| pixels[..4] == [255, 0, 0, 255];
+
+    assert!(red_pixel(&harness.render()));
+    assert_eq!(paint_count.get(), 1);
+    // The cached image is drawn instead.
+    harness.invalidate_window();
+    assert!(red_pixel(&harness.render()));
+    assert_eq!(paint_count.get(), 1);
+
+    harness.submit_command(PAINT_ME);
+    assert!(red_pixel(&harness.render()));
+    assert_eq!(paint_count.get(), 2);
+
+    harness.submit_command(CHANGE_KEY);
+    harness.invalidate_window();
+    let _ = harness.render();
+    assert_eq!(paint_count.get(), 3);
+}
+
+#[test]
+fn simulate_frame_after_text_change() {
+    let [label_id, sibling_id] = widget_ids();
+
+    let widget = Flex::column()
+        .with_child_id(Label::new("Hello"), label_id)
+        .with_child_id(Label::new("World"), sibling_id);
+
+    let mut harness = TestHarness::create(widget);
+    // Flush everything that happened while the window was created.
+    let _ = harness.simulate_frame();
+    let old_rect = harness.get_widget(label_id).state().layout_rect();
+
+    harness.edit_root_widget(|mut root, _| {
+        let mut flex = root.downcast::<Flex>().unwrap();
+        let mut label = flex.child_mut(0).unwrap();
+        label.downcast::<Label>().unwrap().set_text("Hello again");
+    });
+    let summary = harness.simulate_frame();
+
+    assert!(summary.relaid_out.contains(&label_id));
+    assert!(!summary.relaid_out.contains(&sibling_id));
+
+    // The harness repaints the whole window after a layout pass.
+    let new_rect = harness.get_widget(label_id).state().layout_rect();
+    assert_ne!(new_rect, old_rect);
+    let window_rect = HARNESS_DEFAULT_SIZE.to_rect();
+    assert_eq!(summary.invalid.bounding_box(), window_rect);
+
+    assert!(!summary.wants_animation_frame);
+    assert!(summary.commands.is_empty());
+
+    // Nothing happens in a frame where nothing changed.
+    let summary = harness.simulate_frame();
+    assert!(summary.relaid_out.is_empty());
+    assert!(summary.invalid.is_empty());
+}
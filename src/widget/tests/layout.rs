@@ -8,8 +8,10 @@
 
 use druid_shell::kurbo::{Insets, Size};
 
-use crate::testing::{widget_ids, ModularWidget, TestHarness, TestWidgetExt};
-use crate::widget::{Flex, SizedBox};
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
+use crate::widget::{Flex, Label, SizedBox};
+use crate::WidgetExt as _;
+use crate::{BoxConstraints, WidgetPod};
 
 #[test]
 fn layout_simple() {
@@ -75,3 +77,23 @@ fn layout_insets() {
 // TODO - insets + flex
 // TODO - viewport
 // TODO - insets + viewport
+
+#[test]
+fn measure_offscreen() {
+    let [label_id] = widget_ids();
+    let widget = Flex::column().with_child_id(Label::new("Hello world"), label_id);
+
+    let mut harness = TestHarness::create(widget);
+    let on_tree_size = harness.get_widget(label_id).state().layout_rect().size();
+
+    let mut offscreen_label = WidgetPod::new(Label::new("Hello world"));
+    let bc = BoxConstraints::new(Size::ZERO, Size::new(400., 400.));
+    let offscreen_size = harness.measure_offscreen(&mut offscreen_label, bc);
+
+    assert_eq!(offscreen_size, on_tree_size);
+    assert!(offscreen_label.is_initialized());
+    // It will be laid out again once it's in a tree.
+    assert!(offscreen_label.state().needs_layout);
+    // The measured widget isn't part of the tree.
+    assert!(harness.try_get_widget(offscreen_label.id()).is_none());
+}
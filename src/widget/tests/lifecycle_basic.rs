@@ -5,7 +5,8 @@
 use insta::assert_debug_snapshot;
 
 use crate::testing::{
-    widget_ids, Record, Recording, ReplaceChild, TestHarness, TestWidgetExt as _, REPLACE_CHILD,
+    widget_ids, ModularWidget, Record, Recording, ReplaceChild, TestHarness, TestWidgetExt as _,
+    REPLACE_CHILD,
 };
 use crate::widget::{Flex, Label, SizedBox};
 use crate::*;
@@ -116,3 +117,69 @@ fn register_after_adding_child() {
     assert!(root_state.children.may_contain(&id_2));
     assert!(root_state.children.may_contain(&id_3));
 }
+
+#[test]
+fn widget_id_round_trips_through_u64() {
+    let [label_id] = widget_ids();
+    let harness = TestHarness::create(Flex::column().with_child_id(Label::new("Hi"), label_id));
+
+    let raw = label_id.as_u64();
+    let id = WidgetId::from_u64(raw).unwrap();
+    assert_eq!(id, label_id);
+    assert_eq!(harness.get_widget(id).id(), label_id);
+    assert_eq!(WidgetId::from_u64(0), None);
+}
+
+/// Test that a pooled id is only handed out again once its widget is removed.
+#[test]
+fn recycle_pooled_id() {
+    let pool = WidgetIdPool::new();
+    let lease = pool.lease();
+    let pooled_id = lease.id();
+    let child = WidgetPod::new_with_lease(Label::new("pooled"), lease);
+
+    let parent = ModularWidget::new(Some(child))
+        .event_fn(|child, ctx, event, env| {
+            if let Event::Command(cmd) = event {
+                if cmd.is(REPLACE_CHILD) {
+                    *child = None;
+                    ctx.children_changed();
+                    return;
+                }
+            }
+            if let Some(child) = child {
+                child.on_event(ctx, event, env);
+            }
+        })
+        .lifecycle_fn(|child, ctx, event, env| {
+            if let Some(child) = child {
+                child.lifecycle(ctx, event, env);
+            }
+        })
+        .layout_fn(|child, ctx, bc, env| {
+            if let Some(child) = child {
+                child.layout(ctx, bc, env);
+                ctx.place_child(child, Point::ORIGIN, env);
+            }
+            bc.max()
+        })
+        .paint_fn(|child, ctx, env| {
+            if let Some(child) = child {
+                child.paint(ctx, env);
+            }
+        })
+        .children_fn(|child| child.iter().map(|child| child.as_dyn()).collect());
+
+    let mut harness = TestHarness::create(parent);
+    assert!(harness.try_get_widget(pooled_id).is_some());
+
+    let other_lease = pool.lease();
+    assert_ne!(other_lease.id(), pooled_id);
+    assert_eq!(pool.free_count(), 0);
+
+    harness.submit_command(REPLACE_CHILD);
+    assert!(harness.try_get_widget(pooled_id).is_none());
+    assert_eq!(pool.free_count(), 1);
+
+    assert_eq!(pool.lease().id(), pooled_id);
+}
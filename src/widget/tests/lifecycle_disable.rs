@@ -7,7 +7,7 @@ use std::rc::Rc;
 
 use smallvec::smallvec;
 
-use crate::testing::{widget_ids, ModularWidget, TestHarness, TestWidgetExt as _};
+use crate::testing::{widget_ids, ModularWidget, TestHarness};
 use crate::widget::Flex;
 use crate::*;
 
@@ -5,11 +5,10 @@
 use std::cell::Cell;
 use std::rc::Rc;
 
+use druid_shell::{KbKey, KeyEvent, RawMods};
 use smallvec::smallvec;
 
-use crate::testing::{
-    widget_ids, ModularWidget, ReplaceChild, TestHarness, TestWidgetExt as _, REPLACE_CHILD,
-};
+use crate::testing::{widget_ids, ModularWidget, ReplaceChild, TestHarness, REPLACE_CHILD};
 use crate::widget::Flex;
 use crate::*;
 
@@ -232,3 +231,49 @@ fn resign_focus_on_disable() {
     assert_eq!(harness.window().focus_chain(), &[focus_2]);
     assert_eq!(harness.window().focus, None);
 }
+
+/// Check that Tab and Shift+Tab move the focus along the focus chain, wrapping around,
+/// and that Tab with other modifiers doesn't.
+#[test]
+fn tab_cycles_focus() {
+    let [id_1, id_2, id_3] = widget_ids();
+    let focus_3: Rc<Cell<bool>> = Default::default();
+
+    let widget = Flex::column()
+        .with_child_id(FocusTaker::new(), id_1)
+        .with_child_id(FocusTaker::new(), id_2)
+        .with_child_id(FocusTaker::track(focus_3.clone()), id_3);
+
+    let mut harness = TestHarness::create(widget);
+    let tab = || Event::KeyDown(KeyEvent::for_test(RawMods::None, KbKey::Tab));
+    let shift_tab = || Event::KeyDown(KeyEvent::for_test(RawMods::Shift, KbKey::Tab));
+
+    assert_eq!(harness.window().focus, None);
+
+    harness.process_event(tab());
+    assert_eq!(harness.window().focus, Some(id_1));
+    harness.process_event(tab());
+    assert_eq!(harness.window().focus, Some(id_2));
+    harness.process_event(tab());
+    assert_eq!(harness.window().focus, Some(id_3));
+    assert_eq!(focus_3.get(), true);
+
+    harness.process_event(tab());
+    assert_eq!(harness.window().focus, Some(id_1));
+    assert_eq!(focus_3.get(), false);
+
+    harness.process_event(shift_tab());
+    assert_eq!(harness.window().focus, Some(id_3));
+    harness.process_event(shift_tab());
+    assert_eq!(harness.window().focus, Some(id_2));
+
+    for mods in [
+        RawMods::Ctrl,
+        RawMods::Alt,
+        RawMods::CtrlShift,
+        RawMods::Meta,
+    ] {
+        harness.process_event(Event::KeyDown(KeyEvent::for_test(mods, KbKey::Tab)));
+        assert_eq!(harness.window().focus, Some(id_2));
+    }
+}
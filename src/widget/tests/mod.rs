@@ -2,8 +2,10 @@
 // "as-is" basis without warranties of any kind. See the LICENSE file for
 // details.
 
+mod accessibility;
 mod aspect_ratio;
 mod event_notification;
+mod ime;
 mod invalidation;
 mod layout;
 mod lifecycle_basic;
@@ -12,6 +14,7 @@ mod lifecycle_focus;
 mod safety_rails;
 mod status_change;
 mod timers;
+mod z_order;
 
 // TODO
 // - InternalLifeCycle::RouteDisabledChanged
@@ -86,6 +86,85 @@ fn check_forget_to_call_place_child() {
     let _harness = TestHarness::create(widget);
 }
 
+#[cfg(feature = "debug_layout")]
+#[should_panic(expected = "child widget 'ModularWidget' #")]
+#[test]
+fn check_child_size_outside_constraints() {
+    // The child ignores its constraints.
+    let child = ModularWidget::new(()).layout_fn(|_, _, _, _| Size::new(50.0, 50.0));
+    let widget = make_parent_widget(child).layout_fn(|child, ctx, _, env| {
+        let size = Size::new(10.0, 10.0);
+        child.layout(ctx, &BoxConstraints::tight(size), env);
+        ctx.place_child(child, Point::ZERO, env);
+        size
+    });
+
+    let _harness = TestHarness::create(widget);
+}
+
+#[should_panic(expected = "needs layout, but its parent doesn't")]
+#[test]
+fn check_skip_child_needing_layout() {
+    const RELAYOUT: Selector = Selector::new("masonry-test.relayout");
+
+    let child = ModularWidget::new(()).event_fn(|_, ctx, event, _| {
+        if let Event::Command(command) = event {
+            if command.is(RELAYOUT) {
+                ctx.request_layout();
+            }
+        }
+    });
+    let mut is_first_layout = true;
+    let widget = make_parent_widget(child).layout_fn(move |child, ctx, bc, env| {
+        if is_first_layout {
+            is_first_layout = false;
+            let size = child.layout(ctx, bc, env);
+            ctx.place_child(child, Point::ZERO, env);
+            size
+        } else {
+            // We skip the child, even though it asked to be laid out again.
+            ctx.skip_child(child);
+            child.layout_rect().size()
+        }
+    });
+
+    let mut harness = TestHarness::create(widget);
+    harness.submit_command(RELAYOUT);
+}
+
+#[should_panic(expected = "layout returned invalid size")]
+#[test]
+fn check_layout_returns_nan_size() {
+    let widget = make_parent_widget(Flex::row()).layout_fn(|child, ctx, bc, env| {
+        child.layout(ctx, bc, env);
+        ctx.place_child(child, Point::ZERO, env);
+        Size::new(f64::NAN, 0.)
+    });
+
+    let _harness = TestHarness::create(widget);
+}
+
+#[should_panic(expected = "layout returned invalid size")]
+#[test]
+fn check_layout_returns_negative_size() {
+    let widget = ModularWidget::new(()).layout_fn(|_, _, _, _| Size::new(10., -5.));
+
+    let _harness = TestHarness::create(make_parent_widget(widget));
+}
+
+#[test]
+fn allow_layout_returning_infinite_size() {
+    let widget = ModularWidget::new(()).layout_fn(|_, _, bc, _| bc.max());
+    let widget = make_parent_widget(widget).layout_fn(|child, ctx, _, env| {
+        let size = child.layout(ctx, &BoxConstraints::UNBOUNDED, env);
+        ctx.place_child(child, Point::ZERO, env);
+        assert_eq!(size, Size::new(f64::INFINITY, f64::INFINITY));
+        Size::new(10., 10.)
+    });
+
+    let _harness = TestHarness::create(widget);
+}
+
 #[should_panic(expected = "not visited in method paint")]
 #[test]
 fn check_forget_to_recurse_paint() {
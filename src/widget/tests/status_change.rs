@@ -2,12 +2,14 @@
 // "as-is" basis without warranties of any kind. See the LICENSE file for
 // details.
 
+use assert_matches::assert_matches;
 use druid_shell::MouseButton;
+use instant::Duration;
 
 use crate::testing::{
     widget_ids, ModularWidget, Record, Recording, TestHarness, TestWidgetExt as _,
 };
-use crate::widget::{Button, Flex, Label, SizedBox};
+use crate::widget::{Button, DisabledIf, Flex, Label, SizedBox};
 use crate::*;
 
 fn is_hot(harness: &TestHarness, id: WidgetId) -> bool {
@@ -306,3 +308,134 @@ fn get_mouse_events_while_active() {
     harness.mouse_move_to(empty_2);
     assert!(next_mouse_event(&button_rec).is_none());
 }
+
+fn next_hover_event(recording: &Recording) -> Option<Event> {
+    loop {
+        match recording.next() {
+            Record::E(event @ (Event::HoverStart | Event::HoverEnd { .. } | Event::Timer(_))) => {
+                return Some(event);
+            }
+            Record::None => return None,
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn hover_start_and_end() {
+    let [label_id] = widget_ids();
+    let label_rec = Recording::default();
+
+    let widget = Flex::column()
+        .with_child_id(Label::new("hello").record(&label_rec), label_id)
+        .with_spacer(100.0);
+
+    let mut harness = TestHarness::create(widget);
+    label_rec.clear();
+
+    harness.mouse_move_to(label_id);
+    harness.move_timers_forward(Duration::from_millis(100));
+    assert_matches!(next_hover_event(&label_rec), None);
+
+    // Moving within the widget doesn't restart the dwell time.
+    harness.mouse_move_to(label_id);
+    harness.move_timers_forward(Duration::from_millis(200));
+    assert_matches!(next_hover_event(&label_rec), Some(Event::HoverStart));
+
+    harness.move_timers_forward(Duration::from_millis(200));
+    harness.mouse_move((5.0, 300.0));
+    assert_matches!(
+        next_hover_event(&label_rec),
+        Some(Event::HoverEnd { duration }) if duration == Duration::from_millis(500)
+    );
+    assert_matches!(next_hover_event(&label_rec), None);
+}
+
+#[test]
+fn hover_cancelled_on_pass_through() {
+    let [label_id] = widget_ids();
+    let label_rec = Recording::default();
+
+    let widget = Flex::column()
+        .with_child_id(Label::new("hello").record(&label_rec), label_id)
+        .with_spacer(100.0);
+
+    let mut harness = TestHarness::create(widget);
+    label_rec.clear();
+
+    harness.mouse_move_to(label_id);
+    harness.move_timers_forward(Duration::from_millis(100));
+    harness.mouse_move((5.0, 300.0));
+    harness.move_timers_forward(Duration::from_secs(1));
+
+    // Neither a hover event nor the internal timer reach the widget.
+    assert_matches!(next_hover_event(&label_rec), None);
+}
+
+#[test]
+fn expanded_hit_area() {
+    let [label_id, other_id] = widget_ids();
+    let recording = Recording::default();
+
+    let widget = Flex::column()
+        .with_child_id(
+            Label::new("Hello")
+                .with_hit_area_insets(Insets::uniform(10.0))
+                .record(&recording),
+            label_id,
+        )
+        .with_child_id(Label::new("World"), other_id);
+
+    let mut harness = TestHarness::create(widget);
+    let label_rect = harness.get_widget(label_id).state().window_layout_rect();
+    let other_rect = harness.get_widget(other_id).state().window_layout_rect();
+
+    // Just right of the label, in its expanded hit area.
+    harness.mouse_move((label_rect.x1 + 5.0, label_rect.center().y));
+    assert!(is_hot(&harness, label_id));
+    recording.clear();
+    harness.mouse_button_press(MouseButton::Left);
+    harness.mouse_button_release(MouseButton::Left);
+    assert!(recording
+        .drain()
+        .iter()
+        .any(|record| matches!(record, Record::E(Event::MouseDown(_)))));
+
+    // Past the expanded hit area.
+    harness.mouse_move((label_rect.x1 + 15.0, label_rect.center().y));
+    assert!(!is_hot(&harness, label_id));
+
+    // The expanded hit area doesn't take events from the other label.
+    assert!(other_rect.y0 < label_rect.y1 + 10.0);
+    harness.mouse_move((other_rect.center().x, other_rect.y0 + 1.0));
+    assert!(!is_hot(&harness, label_id));
+    assert!(is_hot(&harness, other_id));
+}
+
+#[test]
+fn disabled_label_recolors() {
+    let recording = Recording::default();
+    let widget = DisabledIf::new(
+        false,
+        |disabled: &bool| *disabled,
+        Label::new("Hello").record(&recording),
+    );
+
+    let mut harness = TestHarness::create(widget);
+    recording.clear();
+
+    harness.edit_root_widget(|mut root, _| {
+        let mut root = root.downcast::<DisabledIf<bool>>().unwrap();
+        root.set_data(true);
+    });
+    assert!(recording
+        .drain()
+        .iter()
+        .any(|record| matches!(record, Record::SC(StatusChange::DisabledChanged(true)))));
+
+    let dimmed =
+        TestHarness::create(Label::new("Hello").with_text_color(theme::DISABLED_TEXT_COLOR))
+            .render();
+    // We don't use assert_eq because we don't want rich assert
+    assert!(harness.render() == dimmed);
+}
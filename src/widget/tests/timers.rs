@@ -2,7 +2,7 @@
 // "as-is" basis without warranties of any kind. See the LICENSE file for
 // details.
 
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
 use instant::Duration;
@@ -39,3 +39,40 @@ fn basic_timer() {
     harness.move_timers_forward(Duration::from_secs(2));
     assert_eq!(timer_handled.get(), true);
 }
+
+#[test]
+fn animating_widgets_share_frame_interval() {
+    type Intervals = Rc<RefCell<Vec<u64>>>;
+
+    fn animated_widget(intervals: Intervals) -> ModularWidget<Intervals> {
+        ModularWidget::new(intervals)
+            .lifecycle_fn(|_, ctx, event, _| {
+                if let LifeCycle::WidgetAdded = event {
+                    ctx.request_anim_frame();
+                }
+            })
+            .event_fn(|intervals, ctx, event, _| {
+                if let Event::AnimFrame(interval) = event {
+                    intervals.borrow_mut().push(*interval);
+                    ctx.request_anim_frame();
+                }
+            })
+    }
+
+    let first = Intervals::default();
+    let second = Intervals::default();
+    let widget = widget::Flex::row()
+        .with_child(animated_widget(first.clone()))
+        .with_child(animated_widget(second.clone()));
+    let mut harness = TestHarness::create(widget);
+
+    harness.animate_frame();
+    std::thread::sleep(Duration::from_millis(2));
+    harness.animate_frame();
+
+    assert_eq!(first.borrow().len(), 2);
+    assert_eq!(*first.borrow(), *second.borrow());
+    assert_eq!(first.borrow()[0], 0);
+    assert!(first.borrow()[1] > 0);
+    assert!(harness.window().last_anim_frame_duration().is_some());
+}
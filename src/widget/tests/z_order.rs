@@ -0,0 +1,54 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::testing::{ModularWidget, TestHarness};
+use crate::widget::Flex;
+use crate::*;
+
+type CallLog = Rc<RefCell<Vec<(&'static str, usize)>>>;
+
+fn logging_widget(index: usize, log: CallLog) -> ModularWidget<CallLog> {
+    ModularWidget::new(log)
+        .event_fn(move |log, _, event, _| {
+            if let Event::WindowSize(_) = event {
+                log.borrow_mut().push(("event", index));
+            }
+        })
+        .layout_fn(|_, _, _, _| Size::new(10.0, 10.0))
+        .paint_fn(move |log, _, _| log.borrow_mut().push(("paint", index)))
+}
+
+#[test]
+fn higher_z_order_paints_last() {
+    let log = CallLog::default();
+    let widget = Flex::row()
+        .with_child(logging_widget(0, log.clone()))
+        .with_child(logging_widget(1, log.clone()))
+        .with_child(logging_widget(2, log.clone()));
+    let mut harness = TestHarness::create(widget);
+
+    harness.edit_root_widget(|mut flex, _| {
+        let mut flex = flex.downcast::<Flex>().unwrap();
+        flex.child_mut(0).unwrap().set_z_order(1);
+    });
+    assert_eq!(harness.root_widget().children()[0].state().z_order(), 1);
+
+    log.borrow_mut().clear();
+    harness.render();
+    assert_eq!(*log.borrow(), [("paint", 1), ("paint", 2), ("paint", 0)]);
+
+    // Events still go through the children in their logical order.
+    log.borrow_mut().clear();
+    harness.process_event(Event::WindowSize(Size::new(400.0, 400.0)));
+    let events: Vec<_> = log
+        .borrow()
+        .iter()
+        .filter(|(kind, _)| *kind == "event")
+        .copied()
+        .collect();
+    assert_eq!(events, [("event", 0), ("event", 1), ("event", 2)]);
+}
@@ -708,6 +708,17 @@ impl Widget for TextBox {
         ctx.stroke(clip_rect, &border_color, border_width);
     }
 
+    fn reset_interaction_state(&mut self) {
+        self.was_focused_from_click = false;
+        let _ = self
+            .inner
+            .as_ref()
+            .child()
+            .borrow_mut()
+            .set_selection(Selection::caret(0));
+        self.inner.reset_interaction_state();
+    }
+
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
         smallvec![self.inner.as_dyn()]
     }
@@ -732,7 +743,8 @@ mod tests {
     use super::*;
     use crate::action::Action;
     use crate::assert_render_snapshot;
-    use crate::testing::{widget_ids, TestHarness, TestWidgetExt as _};
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::WidgetExt as _;
 
     #[test]
     fn simple_textbox() {
@@ -775,6 +787,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn reset_clears_selection() {
+        let [textbox_id] = widget_ids();
+        let textbox = TextBox::new("").with_id(textbox_id);
+        let mut harness = TestHarness::create(textbox);
+        let selection = |harness: &TestHarness| {
+            harness
+                .get_widget(textbox_id)
+                .downcast::<TextBox>()
+                .unwrap()
+                .inner
+                .as_ref()
+                .child()
+                .borrow()
+                .selection()
+        };
+
+        harness.mouse_click_on(textbox_id);
+        harness.keyboard_type_chars("abc");
+        assert_eq!(selection(&harness), Selection::caret(3));
+
+        harness.edit_root_widget(|mut root, _| root.reset_interaction_state());
+        assert_eq!(selection(&harness), Selection::caret(0));
+    }
+
     #[test]
     fn simple_textbox_placeholder() {
         let textbox = TextBox::new("").with_placeholder("placeholder text");
@@ -0,0 +1,223 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! A label showing a number, which counts to new values.
+
+use std::time::Duration;
+
+use smallvec::{smallvec, SmallVec};
+use tracing::{trace, trace_span, Span};
+
+use crate::widget::{Label, WidgetMut, WidgetPod, WidgetRef};
+use crate::{
+    BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx, Point,
+    Size, StatusChange, Widget,
+};
+
+const DEFAULT_TWEEN_DURATION: Duration = Duration::from_millis(300);
+
+/// A label showing a number, which counts to new values.
+///
+/// When the value changes, the text counts from the old value to the new one
+/// over the tween duration, formatting each intermediate value with the
+/// widget's format function.
+pub struct ValueLabel {
+    label: WidgetPod<Label>,
+    format: Box<dyn Fn(f64) -> String>,
+    tween_duration: Duration,
+    // The number the text currently shows, once a value was set.
+    shown: Option<f64>,
+    from: f64,
+    to: f64,
+    // The time elapsed since the tween started, if one is running.
+    elapsed: Option<Duration>,
+}
+
+crate::declare_widget!(ValueLabelMut, ValueLabel);
+
+impl ValueLabel {
+    /// Create a new label formatting its values with `format`.
+    ///
+    /// The label is empty until a value is set with [`ValueLabelMut::set_value`].
+    pub fn new(format: impl Fn(f64) -> String + 'static) -> Self {
+        Self::from_label(Label::empty(), format)
+    }
+
+    /// Create a new value label from the provided [`Label`].
+    ///
+    /// The label's text is managed by this widget.
+    pub fn from_label(label: Label, format: impl Fn(f64) -> String + 'static) -> Self {
+        ValueLabel {
+            label: WidgetPod::new(label),
+            format: Box::new(format),
+            tween_duration: DEFAULT_TWEEN_DURATION,
+            shown: None,
+            from: 0.0,
+            to: 0.0,
+            elapsed: None,
+        }
+    }
+
+    /// Builder-style method for setting how long the text takes to count to a new value.
+    ///
+    /// The default is 300 milliseconds.
+    pub fn with_tween_duration(mut self, duration: Duration) -> Self {
+        self.tween_duration = duration;
+        self
+    }
+
+    /// The value the label shows, or is counting to.
+    ///
+    /// Returns `None` until a value is set.
+    pub fn value(&self) -> Option<f64> {
+        self.shown.map(|_| self.to)
+    }
+
+    /// Returns `true` while the text is counting to a new value.
+    pub fn is_animating(&self) -> bool {
+        self.elapsed.is_some()
+    }
+}
+
+impl<'a, 'b> ValueLabelMut<'a, 'b> {
+    /// Show a number.
+    ///
+    /// The first value is shown immediately. After that, the text counts
+    /// linearly from the number it shows to `value` over the tween duration.
+    /// A value set while a tween is running starts a new one from the number
+    /// shown at that moment, so the text never jumps.
+    pub fn set_value(&mut self, value: f64) {
+        self.widget.to = value;
+        match self.widget.shown {
+            Some(shown) if shown != value && !self.widget.tween_duration.is_zero() => {
+                self.widget.from = shown;
+                self.widget.elapsed = Some(Duration::ZERO);
+                self.ctx.request_anim_frame();
+            }
+            _ => {
+                self.widget.elapsed = None;
+                self.show_value(value);
+            }
+        }
+    }
+
+    /// Set how long the text takes to count to a new value.
+    ///
+    /// This takes effect starting with the next call to [`set_value`](Self::set_value).
+    pub fn set_tween_duration(&mut self, duration: Duration) {
+        self.widget.tween_duration = duration;
+    }
+
+    /// Get a [`WidgetMut`] to the label.
+    ///
+    /// The label's text is managed by this widget.
+    pub fn label_mut(&mut self) -> WidgetMut<'_, 'b, Label> {
+        self.ctx.get_mut(&mut self.widget.label)
+    }
+
+    fn show_value(&mut self, value: f64) {
+        self.widget.shown = Some(value);
+        let text = (self.widget.format)(value);
+        self.label_mut().set_text(text);
+    }
+}
+
+impl Widget for ValueLabel {
+    fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env) {
+        self.label.on_event(ctx, event, env);
+
+        if let Event::AnimFrame(interval) = event {
+            if let Some(elapsed) = &mut self.elapsed {
+                *elapsed += Duration::from_nanos(*interval);
+                let progress = (elapsed.as_secs_f64() / self.tween_duration.as_secs_f64()).min(1.0);
+                let value = self.from + (self.to - self.from) * progress;
+                trace!("Shown value: {}", value);
+                if progress < 1.0 {
+                    ctx.request_anim_frame();
+                } else {
+                    self.elapsed = None;
+                }
+                self.shown = Some(value);
+                ctx.get_mut(&mut self.label).set_text((self.format)(value));
+            }
+        }
+    }
+
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
+
+    fn lifecycle(&mut self, ctx: &mut LifeCycleCtx, event: &LifeCycle, env: &Env) {
+        self.label.lifecycle(ctx, event, env);
+    }
+
+    fn layout(&mut self, ctx: &mut LayoutCtx, bc: &BoxConstraints, env: &Env) -> Size {
+        let size = self.label.layout(ctx, bc, env);
+        ctx.place_child(&mut self.label, Point::ORIGIN, env);
+        ctx.set_baseline_offset(self.label.baseline_offset());
+        size
+    }
+
+    fn paint(&mut self, ctx: &mut PaintCtx, env: &Env) {
+        self.label.paint(ctx, env);
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.label.reset_interaction_state();
+    }
+
+    fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
+        smallvec![self.label.as_dyn()]
+    }
+
+    fn make_trace_span(&self) -> Span {
+        trace_span!("ValueLabel")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestHarness;
+
+    #[test]
+    fn value_tween() {
+        const FRAME: Duration = Duration::from_millis(20);
+        fn text(harness: &TestHarness) -> String {
+            let root = harness.root_widget();
+            let label = root.children()[0].downcast::<Label>().unwrap().deref();
+            label.text().to_string()
+        }
+        fn set_value(harness: &mut TestHarness, value: f64) {
+            harness.edit_root_widget(|mut root, _| {
+                root.downcast::<ValueLabel>().unwrap().set_value(value);
+            });
+        }
+
+        let label = ValueLabel::new(|value| format!("{value:.0}")).with_tween_duration(FRAME * 5);
+        let mut harness = TestHarness::create(label);
+
+        // The first value has nothing to animate from.
+        set_value(&mut harness, 0.0);
+        assert_eq!(text(&harness), "0");
+        assert!(!harness.window().wants_animation_frame());
+
+        set_value(&mut harness, 100.0);
+        assert_eq!(text(&harness), "0");
+        harness.process_event(Event::AnimFrame(FRAME.as_nanos() as u64));
+        assert_eq!(text(&harness), "20");
+        harness.process_event(Event::AnimFrame(FRAME.as_nanos() as u64));
+        assert_eq!(text(&harness), "40");
+
+        // Changing the value mid-tween animates from the value shown.
+        set_value(&mut harness, 0.0);
+        assert_eq!(text(&harness), "40");
+        harness.process_event(Event::AnimFrame(FRAME.as_nanos() as u64));
+        assert_eq!(text(&harness), "32");
+        for _ in 0..4 {
+            assert!(harness.window().wants_animation_frame());
+            harness.process_event(Event::AnimFrame(FRAME.as_nanos() as u64));
+        }
+        assert_eq!(text(&harness), "0");
+        assert!(!harness.window().wants_animation_frame());
+    }
+}
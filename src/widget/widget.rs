@@ -3,8 +3,10 @@
 // details.
 
 use std::any::Any;
+use std::collections::HashSet;
 use std::num::NonZeroU64;
 use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Mutex};
 
 use smallvec::SmallVec;
 use tracing::{trace_span, Span};
@@ -12,8 +14,8 @@ use tracing::{trace_span, Span};
 use crate::event::StatusChange;
 use crate::widget::WidgetRef;
 use crate::{
-    AsAny, BoxConstraints, Env, Event, EventCtx, LayoutCtx, LifeCycle, LifeCycleCtx, PaintCtx,
-    Point, Size, WidgetCtx,
+    AccessCtx, AsAny, BoxConstraints, Env, Event, EventCtx, Insets, LayoutCtx, LifeCycle,
+    LifeCycleCtx, PaintCtx, Point, Size, WidgetCtx,
 };
 
 /// A unique identifier for a single [`Widget`].
@@ -33,10 +35,14 @@ use crate::{
 /// Sometimes, you may want to construct a widget, in a way that lets you know its id,
 /// so you can refer to the widget later. You can use [`WidgetPod::new_with_id`](crate::WidgetPod::new_with_id) to pass
 /// an id to the WidgetPod you're creating; various widgets which have methods to create
-/// children may have variants taking ids as parameters.
+/// children may have variants taking ids as parameters. Any other widget can be given
+/// an id with [`WidgetExt::with_id`](crate::widget::WidgetExt::with_id).
 ///
 /// If you set a `WidgetId` directly, you are resposible for ensuring that it
 /// is unique. Two widgets must not be created with the same id.
+///
+/// Ids are never reused by default; see [`WidgetIdPool`] for widgets that want
+/// their id recycled once they're removed.
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub struct WidgetId(NonZeroU64);
 
@@ -71,8 +77,11 @@ pub trait Widget: AsAny {
     /// a [`Command`](crate::Command).
     fn on_event(&mut self, ctx: &mut EventCtx, event: &Event, env: &Env);
 
-    #[allow(missing_docs)]
-    fn on_status_change(&mut self, ctx: &mut LifeCycleCtx, event: &StatusChange, env: &Env);
+    /// Handle a change of the widget's status, such as whether it is hot or focused.
+    ///
+    /// See [`StatusChange`] for the possible changes. The default implementation
+    /// does nothing.
+    fn on_status_change(&mut self, _ctx: &mut LifeCycleCtx, _event: &StatusChange, _env: &Env) {}
 
     /// Handle a lifecycle notification.
     ///
@@ -133,6 +142,32 @@ pub trait Widget: AsAny {
         trace_span!("Widget", r#type = self.short_type_name())
     }
 
+    /// Describe the widget to assistive technologies.
+    ///
+    /// Widgets set the role and name of their node on the [`AccessCtx`]. A widget
+    /// that doesn't set a role gets no node of its own; its descendants' nodes are
+    /// attached to its closest ancestor's.
+    ///
+    /// The children returned by [`children`](Self::children) add their nodes
+    /// automatically after this method returns. Widgets only need to call
+    /// [`WidgetPod::accessibility`](crate::WidgetPod::accessibility) for children
+    /// they don't return there.
+    #[allow(unused_variables)]
+    fn accessibility(&self, ctx: &mut AccessCtx, env: &Env) {}
+
+    /// Clear transient interaction state, such as a text selection or an
+    /// animation started by user input.
+    ///
+    /// This is called through [`WidgetMut::reset_interaction_state`](crate::widget::WidgetMut::reset_interaction_state)
+    /// when a widget is reused to show different content, eg a row of a
+    /// virtualized list being bound to another item, so that state from the old
+    /// content doesn't carry over. Hot and focus state are tracked by the
+    /// framework and don't need to be reset here.
+    ///
+    /// Container widgets should forward the call to their children with
+    /// [`WidgetPod::reset_interaction_state`](crate::WidgetPod::reset_interaction_state).
+    fn reset_interaction_state(&mut self) {}
+
     /// Return a small string representing important info about this widget instance.
     ///
     /// When using [`WidgetRef`]'s [Debug](std::fmt::Debug) implementation, widgets
@@ -143,6 +178,24 @@ pub trait Widget: AsAny {
         None
     }
 
+    /// Return by how much the area in which the widget receives pointer events
+    /// extends past its layout rect.
+    ///
+    /// This is useful for small widgets that should be easy to hit, eg a slider
+    /// thumb. The expanded area is only used by this widget's own hit test: events
+    /// must still be routed to it by its parent, so the area doesn't extend past
+    /// the parent's layout rect.
+    ///
+    /// The expanded area never takes events from other widgets: the widget only
+    /// receives a pointer event outside its layout rect if the pointer isn't over
+    /// any other widget than its parent (eg a sibling, or a sibling's child).
+    ///
+    /// Outside of pointer events (eg when the widget moves during layout), a widget
+    /// stays hot while the pointer is in its expanded area, but doesn't become hot.
+    fn allow_pointer_events_outside_bounds(&self) -> Insets {
+        Insets::ZERO
+    }
+
     // --- Auto-generated implementations ---
 
     /// Return which child, if any, has the given `pos` in its layout rect.
@@ -335,6 +388,8 @@ impl WidgetId {
     /// The actual inner representation of the returned `WidgetId` will not
     /// be the same as the raw value that is passed in; it will be
     /// `u64::max_value() - raw`.
+    ///
+    /// `u16::MAX` is reserved by Masonry itself and shouldn't be used.
     #[allow(unsafe_code)]
     pub const fn reserved(raw: u16) -> WidgetId {
         let id = u64::max_value() - raw as u64;
@@ -342,9 +397,120 @@ impl WidgetId {
         WidgetId(unsafe { std::num::NonZeroU64::new_unchecked(id) })
     }
 
-    pub(crate) fn to_raw(self) -> u64 {
+    /// The id of the throwaway parent used by [`LayoutCtx::measure_offscreen`].
+    ///
+    /// [`LayoutCtx::measure_offscreen`]: crate::LayoutCtx::measure_offscreen
+    pub(crate) const OFFSCREEN_PARENT: WidgetId = WidgetId::reserved(u16::MAX);
+
+    /// Return the id as a plain integer, eg to pass it across an FFI boundary.
+    ///
+    /// [`from_u64`](Self::from_u64) turns the integer back into the same id.
+    pub fn as_u64(self) -> u64 {
         self.0.into()
     }
+
+    /// Turn an integer returned by [`as_u64`](Self::as_u64) back into a `WidgetId`.
+    ///
+    /// Returns `None` if `raw` is zero, which is never a valid id.
+    ///
+    /// As with [`reserved`](Self::reserved), the caller is responsible for the
+    /// id referring to a single widget at any time: an id made up rather than
+    /// returned by `as_u64` may collide with one [`next`](Self::next) hands out.
+    pub fn from_u64(raw: u64) -> Option<WidgetId> {
+        NonZeroU64::new(raw).map(WidgetId)
+    }
+
+    pub(crate) fn to_raw(self) -> u64 {
+        self.as_u64()
+    }
+}
+
+/// A pool of [`WidgetId`]s that are handed back when their widget is removed.
+///
+/// [`WidgetId::next`] never reuses ids, so a long-running app that keeps creating
+/// and discarding widgets will keep growing the id counter. Widgets that opt in
+/// by being created with [`WidgetPod::new_with_lease`](crate::WidgetPod::new_with_lease)
+/// return their id to the pool when their `WidgetPod` is dropped, and the pool
+/// hands it out again to the next widget that asks for one.
+///
+/// ## Safety contract
+///
+/// A pooled id is only returned to the pool when the [`WidgetIdLease`] holding it
+/// is dropped, so an id can't be handed out while the widget using it is still in
+/// the tree. This holds as long as the id isn't copied out of the lease and given
+/// to another widget (eg with [`WidgetPod::new_with_id`](crate::WidgetPod::new_with_id)),
+/// and as long as nothing keeps the id around after its widget is removed
+/// expecting it to still refer to that widget. Commands targeting a recycled id
+/// will be delivered to whichever widget holds it now.
+///
+/// Cloning a `WidgetIdPool` gives another handle to the same pool.
+#[derive(Clone, Debug, Default)]
+pub struct WidgetIdPool {
+    inner: Arc<Mutex<IdPoolInner>>,
+}
+
+#[derive(Debug, Default)]
+struct IdPoolInner {
+    free: Vec<WidgetId>,
+    live: HashSet<WidgetId>,
+}
+
+/// An id borrowed from a [`WidgetIdPool`].
+///
+/// The id goes back to the pool when the lease is dropped.
+#[derive(Debug)]
+pub struct WidgetIdLease {
+    id: WidgetId,
+    pool: WidgetIdPool,
+}
+
+impl WidgetIdPool {
+    /// Create an empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Take an id from the pool, or allocate a new one with [`WidgetId::next`]
+    /// if none are free.
+    pub fn lease(&self) -> WidgetIdLease {
+        let mut inner = self.inner.lock().unwrap();
+        let id = inner.free.pop().unwrap_or_else(WidgetId::next);
+        let newly_live = inner.live.insert(id);
+        debug_assert!(newly_live, "WidgetIdPool handed out live id {:?}", id);
+        WidgetIdLease {
+            id,
+            pool: self.clone(),
+        }
+    }
+
+    /// The number of ids waiting to be reused.
+    pub fn free_count(&self) -> usize {
+        self.inner.lock().unwrap().free.len()
+    }
+
+    fn release(&self, id: WidgetId) {
+        let mut inner = self.inner.lock().unwrap();
+        // Ids that aren't live were never ours or were already returned;
+        // pushing them would let two widgets share an id.
+        if inner.live.remove(&id) {
+            inner.free.push(id);
+        } else {
+            debug_panic!("WidgetIdPool: released id {:?} which isn't live", id);
+        }
+    }
+}
+
+impl WidgetIdLease {
+    /// The leased id.
+    pub fn id(&self) -> WidgetId {
+        self.id
+    }
+}
+
+impl Drop for WidgetIdLease {
+    fn drop(&mut self) {
+        self.pool.release(self.id);
+    }
 }
 
 // TODO - remove
@@ -373,6 +539,10 @@ impl Widget for Box<dyn Widget> {
         self.deref().type_name()
     }
 
+    fn short_type_name(&self) -> &'static str {
+        self.deref().short_type_name()
+    }
+
     fn children(&self) -> SmallVec<[WidgetRef<'_, dyn Widget>; 16]> {
         self.deref().children()
     }
@@ -381,16 +551,34 @@ impl Widget for Box<dyn Widget> {
         self.deref().make_trace_span()
     }
 
+    fn accessibility(&self, ctx: &mut AccessCtx, env: &Env) {
+        self.deref().accessibility(ctx, env)
+    }
+
+    fn reset_interaction_state(&mut self) {
+        self.deref_mut().reset_interaction_state()
+    }
+
     fn get_debug_text(&self) -> Option<String> {
         self.deref().get_debug_text()
     }
 
+    fn allow_pointer_events_outside_bounds(&self) -> Insets {
+        self.deref().allow_pointer_events_outside_bounds()
+    }
+
+    fn get_child_at_pos(&self, pos: Point) -> Option<WidgetRef<'_, dyn Widget>> {
+        self.deref().get_child_at_pos(pos)
+    }
+
+    // These forward to the inner widget's `as_any` rather than `as_dyn_any`, so
+    // that a box nested in another box still downcasts to the widget inside.
     fn as_any(&self) -> &dyn Any {
-        self.deref().as_dyn_any()
+        self.deref().as_any()
     }
 
     fn as_mut_any(&mut self) -> &mut dyn Any {
-        self.deref_mut().as_mut_dyn_any()
+        self.deref_mut().as_mut_any()
     }
 }
 
@@ -0,0 +1,113 @@
+// This software is licensed under Apache License 2.0 and distributed on an
+// "as-is" basis without warranties of any kind. See the LICENSE file for
+// details.
+
+//! Convenience methods for widgets.
+
+use super::{IdentityWrapper, Widget, WidgetId};
+
+/// A trait that provides extra methods for combining [`Widget`]s.
+///
+/// It is implemented for every widget.
+pub trait WidgetExt: Widget + Sized + 'static {
+    /// Box the widget, erasing its type.
+    ///
+    /// This is how to put widgets of different types in the same container,
+    /// or in a `WidgetPod<Box<dyn Widget>>` field. The boxed widget behaves
+    /// exactly like the original: every `Widget` method is forwarded to it,
+    /// including [`type_name`](Widget::type_name) and `short_type_name`, so
+    /// traces and debug output show the inner widget's name. To edit it, get a
+    /// [`WidgetMut`](crate::widget::WidgetMut) to the box and
+    /// [`downcast`](crate::widget::WidgetMut::downcast) it to the original type.
+    fn boxed(self) -> Box<dyn Widget> {
+        Box::new(self)
+    }
+
+    /// Wrap the widget in an [`IdentityWrapper`], giving it the id `id`.
+    ///
+    /// This is how to refer to a widget later, eg to send it a command or find it
+    /// in tests, when the container it goes in doesn't take ids for its children.
+    fn with_id(self, id: WidgetId) -> IdentityWrapper<Self> {
+        IdentityWrapper::new(self, id)
+    }
+}
+
+impl<W: Widget + 'static> WidgetExt for W {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Flex, Label};
+
+    #[test]
+    fn boxed_label() {
+        let [boxed_id, plain_id] = widget_ids();
+        let widget = Flex::column()
+            .with_child_id(Label::new("Hello").boxed(), boxed_id)
+            .with_child_id(Label::new("Hello"), plain_id);
+        let mut harness = TestHarness::create(widget);
+
+        let boxed = harness.get_widget(boxed_id);
+        let plain = harness.get_widget(plain_id);
+        assert_eq!(
+            boxed.state().layout_rect().size(),
+            plain.state().layout_rect().size()
+        );
+        assert_eq!(boxed.deref().short_type_name(), "Label");
+        assert_eq!(boxed.deref().type_name(), plain.deref().type_name());
+
+        harness.edit_root_widget(|mut flex, _| {
+            let mut flex = flex.downcast::<Flex>().unwrap();
+            let mut boxed = flex.child_mut(0).unwrap();
+            boxed.downcast::<Label>().unwrap().set_text("Goodbye");
+        });
+        let boxed = harness.get_widget(boxed_id);
+        assert_eq!(boxed.deref().get_debug_text().as_deref(), Some("Goodbye"));
+    }
+
+    #[test]
+    fn boxed_container_children() {
+        let [label_id] = widget_ids();
+        let inner = Flex::row().with_child_id(Label::new("Inside"), label_id);
+        let widget = Flex::column().with_child(inner.boxed());
+        let harness = TestHarness::create(widget);
+
+        // The label is found by walking through the boxed flex's children.
+        let boxed = harness.root_widget().children()[0];
+        assert_eq!(boxed.children()[0].id(), label_id);
+        assert!(harness.root_widget().find_widget_by_id(label_id).is_some());
+    }
+
+    #[test]
+    fn with_id() {
+        let [first_id, second_id] = widget_ids();
+        let widget = Flex::row()
+            .with_child(Label::new("First").with_id(first_id))
+            .with_child(Label::new("Second").with_id(second_id));
+        let mut harness = TestHarness::create(widget);
+
+        let text = |harness: &TestHarness, id| {
+            let label = harness.get_widget(id);
+            label.deref().get_debug_text()
+        };
+        assert_eq!(text(&harness, first_id).as_deref(), Some("First"));
+        assert_eq!(text(&harness, second_id).as_deref(), Some("Second"));
+
+        // The wrappers report the chosen ids as their children's.
+        let wrappers = harness.root_widget().children();
+        let child_ids: Vec<_> = wrappers
+            .iter()
+            .map(|wrapper| wrapper.children()[0].id())
+            .collect();
+        assert_eq!(child_ids, vec![first_id, second_id]);
+
+        harness.edit_root_widget(|mut flex, _| {
+            let mut flex = flex.downcast::<Flex>().unwrap();
+            let mut wrapper = flex.child_mut(1).unwrap();
+            let mut wrapper = wrapper.downcast::<IdentityWrapper<Label>>().unwrap();
+            wrapper.child_mut().set_text("Changed");
+        });
+        assert_eq!(text(&harness, second_id).as_deref(), Some("Changed"));
+    }
+}
@@ -83,6 +83,25 @@ impl<W: StoreInWidgetMut> WidgetMut<'_, '_, W> {
     pub fn id(&mut self) -> WidgetId {
         W::get_ctx(&mut self.inner).widget_state.id
     }
+
+    /// Set the paint order of the widget among its siblings.
+    ///
+    /// See [`WidgetPod::z_order`](crate::WidgetPod::z_order).
+    pub fn set_z_order(&mut self, z_order: i32) {
+        let ctx = W::get_ctx(&mut self.inner);
+        ctx.widget_state.z_order = z_order;
+        ctx.request_paint();
+    }
+
+    /// Clear the transient interaction state of the widget and its descendants,
+    /// eg before reusing it to show different content.
+    ///
+    /// See [`Widget::reset_interaction_state`].
+    pub fn reset_interaction_state(&mut self) {
+        let (widget, ctx) = W::get_widget_and_ctx(&mut self.inner);
+        widget.reset_interaction_state();
+        ctx.request_layout();
+    }
 }
 
 // TODO - unit tests
@@ -3,17 +3,19 @@
 // details.
 
 use std::collections::VecDeque;
+use std::time::Duration;
 
 use tracing::{info_span, trace, warn};
 
+use crate::accessibility;
 use crate::contexts::GlobalPassCtx;
 use crate::kurbo::{Affine, Insets, Point, Rect, Shape, Size};
 use crate::text::TextLayout;
-use crate::widget::{FocusChange, WidgetRef, WidgetState};
+use crate::widget::{FocusChange, WidgetIdLease, WidgetRef, WidgetState};
 use crate::{
-    ArcStr, BoxConstraints, Color, Env, Event, EventCtx, InternalEvent, InternalLifeCycle,
-    LayoutCtx, LifeCycle, LifeCycleCtx, Notification, PaintCtx, RenderContext, StatusChange,
-    Target, Widget, WidgetId,
+    theme, AccessCtx, ArcStr, BoxConstraints, Color, Command, Env, Event, EventCtx, InternalEvent,
+    InternalLifeCycle, LayoutCtx, LifeCycle, LifeCycleCtx, Notification, PaintCtx, RenderContext,
+    StatusChange, Target, Widget, WidgetId,
 };
 
 // TODO - rewrite links in doc
@@ -32,6 +34,8 @@ pub struct WidgetPod<W> {
     pub(crate) env: Option<Env>,
     // stashed layout so we don't recompute this when debugging
     pub(crate) debug_widget_text: TextLayout<ArcStr>,
+    // Returns a pooled id when the pod is dropped.
+    id_lease: Option<WidgetIdLease>,
 }
 
 // ---
@@ -56,9 +60,21 @@ impl<W: Widget> WidgetPod<W> {
             inner,
             env: None,
             debug_widget_text: TextLayout::new(),
+            id_lease: None,
         }
     }
 
+    /// Create a new widget pod whose id is taken from a [`WidgetIdPool`].
+    ///
+    /// The id is returned to the pool when this pod is dropped.
+    ///
+    /// [`WidgetIdPool`]: crate::WidgetIdPool
+    pub fn new_with_lease(inner: W, lease: WidgetIdLease) -> WidgetPod<W> {
+        let mut pod = Self::new_with_id(inner, lease.id());
+        pod.id_lease = Some(lease);
+        pod
+    }
+
     /// Read-only access to state. We don't mark the field as `pub` because
     /// we want to control mutation.
     pub(crate) fn state(&self) -> &WidgetState {
@@ -181,11 +197,42 @@ impl<W: Widget> WidgetPod<W> {
         union_pant_rect - parent_bounds
     }
 
+    /// The paint order of this widget among its siblings.
+    ///
+    /// Containers that support it paint children with a higher z-order after,
+    /// and so on top of, those with a lower one; children with the same z-order
+    /// are painted in their logical order. The z-order doesn't affect the order
+    /// in which children receive events or focus. It defaults to zero.
+    pub fn z_order(&self) -> i32 {
+        self.state.z_order
+    }
+
+    /// Set the paint order of this widget among its siblings.
+    ///
+    /// See [`z_order`](Self::z_order).
+    pub fn set_z_order(&mut self, z_order: i32) {
+        self.state.z_order = z_order;
+    }
+
     /// The distance from the bottom of this widget to the baseline.
     pub fn baseline_offset(&self) -> f64 {
         self.state.baseline_offset
     }
 
+    /// The y position of the first baseline, in this widget's coordinate space.
+    ///
+    /// See [`WidgetState::first_baseline`].
+    pub fn first_baseline(&self) -> f64 {
+        self.state.first_baseline()
+    }
+
+    /// The y position of the last baseline, in this widget's coordinate space.
+    ///
+    /// See [`WidgetState::last_baseline`].
+    pub fn last_baseline(&self) -> f64 {
+        self.state.last_baseline()
+    }
+
     // FIXME - Remove
     /// Return a mutable reference to the inner widget.
     pub(crate) fn widget_mut(&mut self) -> &mut W {
@@ -224,8 +271,11 @@ impl<W: Widget> WidgetPod<W> {
     // - A concept of "cursor moved to inner widget" (though I think's that's not super useful outside the browser).
     // - Multiple pointers handling.
 
-    /// Determines if the provided `mouse_pos` is inside `rect`
-    /// and if so updates the hot state and sends `LifeCycle::HotChanged`.
+    /// Determines if the provided `mouse_pos` is inside `rect`, or inside the area
+    /// added by [`Widget::allow_pointer_events_outside_bounds`], and if so updates
+    /// the hot state and sends `LifeCycle::HotChanged`.
+    ///
+    /// `parent_id` is the id of the widget calling this method.
     ///
     /// Return `true` if the hot state changed.
     ///
@@ -235,16 +285,43 @@ impl<W: Widget> WidgetPod<W> {
         inner_state: &mut WidgetState,
         global_state: &mut GlobalPassCtx,
         rect: Rect,
+        parent_id: WidgetId,
         mouse_pos: Option<Point>,
         env: &Env,
     ) -> bool {
         let had_hot = inner_state.is_hot;
+        let hit_rect = rect + inner.allow_pointer_events_outside_bounds();
         inner_state.is_hot = match mouse_pos {
-            Some(pos) => rect.winding(pos) != 0,
-            None => false,
+            Some(pos) if rect.winding(pos) != 0 => true,
+            // The expanded area only applies if the pointer isn't over another
+            // widget. Outside of pointer events, we don't know what the pointer
+            // is over, so we only keep the current state.
+            Some(pos) if hit_rect.winding(pos) != 0 => match global_state.pointer_hit_target {
+                Some(target) => target == parent_id,
+                None => had_hot,
+            },
+            _ => false,
         };
         // FIXME - don't send event, update flags instead
         if had_hot != inner_state.is_hot {
+            let now = global_state.now();
+            if inner_state.is_hot {
+                inner_state.hover_start = Some(now);
+                // If a timer is still pending from a quick pass-through, it
+                // checks the new start time when it fires.
+                if inner_state.hover_timer.is_none() {
+                    let dwell = Duration::from_millis(env.get(theme::HOVER_DWELL_TIME));
+                    let token = global_state.request_timer(dwell, inner_state.id);
+                    inner_state.hover_timer = Some(token);
+                }
+            } else {
+                if let (true, Some(start)) = (inner_state.is_hovering, inner_state.hover_start) {
+                    inner_state.pending_hover_end = Some(now.saturating_duration_since(start));
+                }
+                inner_state.is_hovering = false;
+                inner_state.hover_start = None;
+            }
+
             trace!(
                 "Widget '{}' #{}: set hot state to {}",
                 inner.short_type_name(),
@@ -319,6 +396,56 @@ impl<W: Widget> WidgetPod<W> {
         return_value
     }
 
+    /// Check that the flags of this widget agree with those of its children.
+    ///
+    /// A child that needs layout, a new window origin, an animation frame or a
+    /// repaint must have passed that request on to its parent; otherwise the
+    /// request is lost, and the child is never updated. This happens when a
+    /// container skips a child (eg with `skip_child`) in the pass that would have
+    /// cleared the request.
+    ///
+    /// This is called by `WidgetPod` after each event, lifecycle and layout pass.
+    /// It does nothing in release builds; in debug builds, it panics with a
+    /// description of the first inconsistency found.
+    pub fn debug_assert_consistent_state(&self) {
+        if cfg!(not(debug_assertions)) {
+            return;
+        }
+
+        let state = &self.state;
+        for child in self.inner.children() {
+            let child_state = child.state();
+            // Stashed children are skipped by every pass, and new children
+            // haven't been merged into their parent yet.
+            if child_state.is_stashed || child_state.is_new {
+                continue;
+            }
+
+            let inconsistency = if child_state.needs_layout && !state.needs_layout {
+                Some("needs layout")
+            } else if child_state.needs_window_origin && !state.needs_window_origin {
+                Some("needs its window origin updated")
+            } else if child_state.request_anim && !state.request_anim {
+                Some("requested an animation frame")
+            } else if !child_state.invalid.is_empty() && state.invalid.is_empty() {
+                Some("needs paint")
+            } else {
+                None
+            };
+
+            if let Some(inconsistency) = inconsistency {
+                debug_panic!(
+                    "Error in '{}' #{}: child widget '{}' #{} {}, but its parent doesn't. Containers must visit children with pending requests instead of skipping them.",
+                    self.inner.short_type_name(),
+                    state.id.to_raw(),
+                    child.deref().short_type_name(),
+                    child_state.id.to_raw(),
+                    inconsistency,
+                );
+            }
+        }
+    }
+
     fn check_initialized(&self, method_name: &str) {
         if !self.is_initialized() {
             debug_panic!(
@@ -329,6 +456,24 @@ impl<W: Widget> WidgetPod<W> {
             );
         }
     }
+
+    /// Decide how a command sent with [`Command::to_subscribers`] reaches this widget.
+    ///
+    /// Subscribed widgets receive it as a normal command. Widgets with subscribed
+    /// descendants receive it as an internal event, which they forward to their
+    /// children without handling it.
+    fn route_subscribed_command(&self, cmd: &Command, modified_event: &mut Option<Event>) -> bool {
+        let symbol = cmd.selector_name();
+        if self.state.subscribed_selectors.contains(&symbol) {
+            *modified_event = Some(Event::Command(cmd.clone()));
+            true
+        } else if self.state.subscriptions.may_contain(&symbol) {
+            *modified_event = Some(Event::Internal(InternalEvent::TargetedCommand(cmd.clone())));
+            true
+        } else {
+            false
+        }
+    }
 }
 
 impl<W: Widget + 'static> WidgetPod<W> {
@@ -336,8 +481,10 @@ impl<W: Widget + 'static> WidgetPod<W> {
     ///
     /// Convert a `WidgetPod` containing a widget of a specific concrete type
     /// into a dynamically boxed widget.
-    pub fn boxed(self) -> WidgetPod<Box<dyn Widget>> {
-        WidgetPod::new_with_id(Box::new(self.inner), self.state.id)
+    pub fn boxed(mut self) -> WidgetPod<Box<dyn Widget>> {
+        let mut pod = WidgetPod::new_with_id(Box::new(self.inner), self.state.id);
+        pod.id_lease = self.id_lease.take();
+        pod
     }
 }
 
@@ -392,6 +539,7 @@ impl<W: Widget> WidgetPod<W> {
                         &mut self.state,
                         parent_ctx.global_state,
                         rect,
+                        parent_ctx.widget_state.id,
                         None,
                         env,
                     );
@@ -408,6 +556,9 @@ impl<W: Widget> WidgetPod<W> {
                             // The bloom filter we're checking can return false positives.
                             self.state.children.may_contain(&id)
                         }
+                        Target::Global | Target::Window(_) if cmd.is_for_subscribers() => {
+                            self.route_subscribed_command(cmd, &mut modified_event)
+                        }
                         Target::Global | Target::Window(_) => {
                             modified_event = Some(Event::Command(cmd.clone()));
                             true
@@ -416,7 +567,31 @@ impl<W: Widget> WidgetPod<W> {
                     }
                 }
                 InternalEvent::RouteTimer(token, widget_id) => {
-                    if *widget_id == self.id() {
+                    if *widget_id == self.id() && self.state.hover_timer == Some(*token) {
+                        self.state.hover_timer = None;
+                        match self.state.hover_start {
+                            Some(start) if self.state.is_hot && !self.state.is_stashed => {
+                                let dwell = Duration::from_millis(env.get(theme::HOVER_DWELL_TIME));
+                                let elapsed = parent_ctx
+                                    .global_state
+                                    .now()
+                                    .saturating_duration_since(start);
+                                if elapsed >= dwell {
+                                    self.state.is_hovering = true;
+                                    modified_event = Some(Event::HoverStart);
+                                    true
+                                } else {
+                                    // The pointer left and came back since the timer started.
+                                    let token = parent_ctx
+                                        .global_state
+                                        .request_timer(dwell - elapsed, self.id());
+                                    self.state.hover_timer = Some(token);
+                                    false
+                                }
+                            }
+                            _ => false,
+                        }
+                    } else if *widget_id == self.id() {
                         modified_event = Some(Event::Timer(*token));
                         true
                     } else {
@@ -452,6 +627,7 @@ impl<W: Widget> WidgetPod<W> {
                     &mut self.state,
                     parent_ctx.global_state,
                     rect,
+                    parent_ctx.widget_state.id,
                     Some(mouse_event.pos),
                     env,
                 );
@@ -470,6 +646,7 @@ impl<W: Widget> WidgetPod<W> {
                     &mut self.state,
                     parent_ctx.global_state,
                     rect,
+                    parent_ctx.widget_state.id,
                     Some(mouse_event.pos),
                     env,
                 );
@@ -488,6 +665,7 @@ impl<W: Widget> WidgetPod<W> {
                     &mut self.state,
                     parent_ctx.global_state,
                     rect,
+                    parent_ctx.widget_state.id,
                     Some(mouse_event.pos),
                     env,
                 );
@@ -509,6 +687,7 @@ impl<W: Widget> WidgetPod<W> {
                     &mut self.state,
                     parent_ctx.global_state,
                     rect,
+                    parent_ctx.widget_state.id,
                     Some(mouse_event.pos),
                     env,
                 );
@@ -521,6 +700,16 @@ impl<W: Widget> WidgetPod<W> {
                     false
                 }
             }
+            Event::ContextMenu { pos } => {
+                if rect.contains(*pos) && !self.state.is_stashed {
+                    modified_event = Some(Event::ContextMenu {
+                        pos: *pos - rect.origin().to_vec2(),
+                    });
+                    true
+                } else {
+                    false
+                }
+            }
             // TODO - switch anim frames to being about age / an absolute timestamp
             // instead of time elapsed.
             // (this will help in cases where we want to skip anim frames)
@@ -532,14 +721,38 @@ impl<W: Widget> WidgetPod<W> {
             Event::KeyDown(_) => self.state.has_focus,
             Event::KeyUp(_) => self.state.has_focus,
             Event::Paste(_) => self.state.has_focus,
+            Event::Ime(_) => self.state.has_focus,
             Event::Zoom(_) => had_active || self.state.is_hot,
             Event::Timer(_) => false, // This event was targeted only to our parent
+            Event::HoverStart | Event::HoverEnd { .. } => false, // Same as Timer
             Event::ImeStateChange => true, // once delivered to the focus widget, recurse to the component?
+            Event::Command(cmd) if cmd.is_for_subscribers() => match cmd.target() {
+                Target::Widget(_) => true,
+                _ => self.route_subscribed_command(cmd, &mut modified_event),
+            },
             Event::Command(_) => true,
             Event::Notification(_) => false,
             Event::PromiseResult(_) => false,
         };
 
+        if let Some(duration) = self.state.pending_hover_end.take() {
+            if !self.state.is_stashed {
+                self.call_widget_method_with_checks("event", |widget_pod| {
+                    let mut notifications = VecDeque::new();
+                    let mut inner_ctx = EventCtx {
+                        global_state: parent_ctx.global_state,
+                        widget_state: &mut widget_pod.state,
+                        notifications: &mut notifications,
+                        is_handled: false,
+                        is_root: false,
+                    };
+                    let hover_end = Event::HoverEnd { duration };
+                    widget_pod.inner.on_event(&mut inner_ctx, &hover_end, env);
+                    widget_pod.process_notifications(parent_ctx, &mut notifications, env);
+                });
+            }
+        }
+
         if call_inner {
             self.call_widget_method_with_checks("event", |widget_pod| {
                 // widget_pod is a reborrow of `self`
@@ -550,7 +763,6 @@ impl<W: Widget> WidgetPod<W> {
                     notifications: &mut notifications,
                     is_handled: false,
                     is_root: false,
-                    request_pan_to_child: None,
                 };
                 let inner_event = modified_event.as_ref().unwrap_or(event);
                 inner_ctx.widget_state.has_active = false;
@@ -560,19 +772,13 @@ impl<W: Widget> WidgetPod<W> {
                 inner_ctx.widget_state.has_active |= inner_ctx.widget_state.is_active;
                 parent_ctx.is_handled |= inner_ctx.is_handled;
 
-                // TODO - there's some dubious logic here
-                if let Some(target_rect) = inner_ctx.request_pan_to_child {
-                    widget_pod.pan_to_child(parent_ctx, env, target_rect);
-                    let new_rect = target_rect
-                        .with_origin(target_rect.origin() + widget_pod.state.origin.to_vec2());
-                    parent_ctx.request_pan_to_child = Some(new_rect);
-                }
-
                 // we try to handle the notifications that occured below us in the tree
                 widget_pod.process_notifications(parent_ctx, &mut notifications, env);
             });
         }
 
+        self.debug_assert_consistent_state();
+
         // Always merge even if not needed, because merging is idempotent and gives us simpler code.
         // Doing this conditionally only makes sense when there's a measurable performance boost.
         parent_ctx.widget_state.merge_up(&mut self.state);
@@ -589,16 +795,6 @@ impl<W: Widget> WidgetPod<W> {
         parent_ctx.global_state.debug_logger.pop_span();
     }
 
-    fn pan_to_child(&mut self, parent_ctx: &mut EventCtx, env: &Env, rect: Rect) {
-        let mut inner_ctx = LifeCycleCtx {
-            global_state: parent_ctx.global_state,
-            widget_state: &mut self.state,
-        };
-        let event = LifeCycle::RequestPanToChild(rect);
-
-        self.inner.lifecycle(&mut inner_ctx, &event, env);
-    }
-
     /// Send notifications originating from this widget's children to this
     /// widget.
     ///
@@ -622,7 +818,6 @@ impl<W: Widget> WidgetPod<W> {
                         widget_state: &mut widget_pod.state,
                         is_handled: false,
                         is_root: false,
-                        request_pan_to_child: None,
                     };
 
                     let event = Event::Notification(notification);
@@ -691,6 +886,11 @@ impl<W: Widget> WidgetPod<W> {
                         if self.state.children_changed {
                             // TODO - Separate "widget removed" case.
                             self.state.children.clear();
+                            // Our children re-add their subscriptions below.
+                            self.state.subscriptions.clear();
+                            for symbol in &self.state.subscribed_selectors {
+                                self.state.subscriptions.add(symbol);
+                            }
                         }
                         self.state.children_changed
                     }
@@ -705,6 +905,7 @@ impl<W: Widget> WidgetPod<W> {
                     if was_disabled != self.state.is_disabled() {
                         // TODO
                         let disabled = self.state.is_disabled();
+                        extra_event = Some(StatusChange::DisabledChanged(disabled));
                         self.call_widget_method_with_checks("lifecycle", |widget_pod| {
                             let mut inner_ctx = LifeCycleCtx {
                                 global_state: parent_ctx.global_state,
@@ -734,6 +935,10 @@ impl<W: Widget> WidgetPod<W> {
 
                     if let Some(change) = this_changed {
                         self.state.has_focus = change;
+                        if change {
+                            let rect = self.state.size.to_rect();
+                            self.state.scroll_to_visible = Some(rect);
+                        }
                         extra_event = Some(StatusChange::FocusChanged(change));
                     } else {
                         self.state.has_focus = false;
@@ -790,7 +995,11 @@ impl<W: Widget> WidgetPod<W> {
                 // the change direction (true -> false or false -> true) of our parent and ourself
                 // is always the same, or we dont change at all, because we stay disabled if either
                 // we or our parent are disabled.
-                was_disabled != self.state.is_disabled()
+                let changed = was_disabled != self.state.is_disabled();
+                if changed {
+                    extra_event = Some(StatusChange::DisabledChanged(self.state.is_disabled()));
+                }
+                changed
             }
             LifeCycle::BuildFocusChain => {
                 if self.state.update_focus_chain {
@@ -804,8 +1013,6 @@ impl<W: Widget> WidgetPod<W> {
                     false
                 }
             }
-            // This is called by children when going up the widget tree.
-            LifeCycle::RequestPanToChild(_) => false,
         };
 
         // widget_pod is a reborrow of `self`
@@ -839,6 +1046,10 @@ impl<W: Widget> WidgetPod<W> {
                 self.state.children_changed = false;
                 parent_ctx.widget_state.children =
                     parent_ctx.widget_state.children.union(self.state.children);
+                parent_ctx.widget_state.subscriptions = parent_ctx
+                    .widget_state
+                    .subscriptions
+                    .union(self.state.subscriptions);
                 parent_ctx.register_child(self.id());
             }
             LifeCycle::DisabledChanged(_)
@@ -879,6 +1090,7 @@ impl<W: Widget> WidgetPod<W> {
             _ => (),
         }
 
+        self.debug_assert_consistent_state();
         parent_ctx.widget_state.merge_up(&mut self.state);
 
         parent_ctx
@@ -926,6 +1138,9 @@ impl<W: Widget> WidgetPod<W> {
         self.state.needs_layout = false;
         self.state.needs_window_origin = false;
         self.state.is_expecting_place_child_call = true;
+        self.state.layout_constraints = Some(*bc);
+        // The widget may paint differently after layout, eg after a theme change.
+        self.state.paint_cache.get_mut().take();
 
         bc.debug_check(self.inner.short_type_name());
 
@@ -947,9 +1162,32 @@ impl<W: Widget> WidgetPod<W> {
                 mouse_pos: inner_mouse_pos,
             };
 
-            widget_pod.inner.layout(&mut inner_ctx, bc, env)
+            let size = widget_pod.inner.layout(&mut inner_ctx, bc, env);
+            if cfg!(debug_assertions) {
+                let name = widget_pod.inner.short_type_name();
+                inner_ctx.validate_children(name, &widget_pod.inner.children());
+            }
+            size
         });
 
+        // Infinite sizes are allowed: widgets may return `bc.max()` under
+        // unbounded constraints.
+        let is_valid = |length: f64| !length.is_nan() && length >= 0.0;
+        let new_size = if is_valid(new_size.width) && is_valid(new_size.height) {
+            new_size
+        } else {
+            debug_panic!(
+                "Error in '{}' #{}: layout returned invalid size {}. Sizes must not be NaN or negative.",
+                self.inner.short_type_name(),
+                self.state().id.to_raw(),
+                new_size,
+            );
+            // A NaN or negative size would poison hit-testing and paint rects,
+            // so fall back to zero on the offending axis.
+            let clamp = |length: f64| if is_valid(length) { length } else { 0.0 };
+            Size::new(clamp(new_size.width), clamp(new_size.height))
+        };
+
         self.state.local_paint_rect = self
             .state
             .local_paint_rect
@@ -957,16 +1195,6 @@ impl<W: Widget> WidgetPod<W> {
 
         if cfg!(debug_assertions) {
             for child in self.inner.children() {
-                if child.state().is_expecting_place_child_call {
-                    debug_panic!(
-                        "Error in '{}' #{}: missing call to place_child method for child widget '{}' #{}. During layout pass, if a widget calls WidgetPod::layout() on its child, it then needs to call LayoutCtx::place_child() on the same child.",
-                        self.inner.short_type_name(),
-                        self.state().id.to_raw(),
-                        child.deref().short_type_name(),
-                        child.state().id.to_raw(),
-                    );
-                }
-
                 // TODO - This check might be redundant with the code updating local_paint_rect
                 let child_rect = child.state().paint_rect();
                 if !rect_contains(&self.state.local_paint_rect, &child_rect)
@@ -995,6 +1223,7 @@ impl<W: Widget> WidgetPod<W> {
         // size is (0,0)
         // See issue #4
 
+        self.debug_assert_consistent_state();
         parent_ctx.widget_state.merge_up(&mut self.state);
         self.state.size = new_size;
         self.log_layout_issues(new_size);
@@ -1026,7 +1255,24 @@ impl<W: Widget> WidgetPod<W> {
 
     // --- PAINT ---
 
-    // TODO - make non-pub?
+    /// Add the accessibility nodes of the widget and its descendants to the
+    /// parent's node.
+    ///
+    /// Children returned by [`Widget::children`] are visited automatically; see
+    /// [`Widget::accessibility`]. This is public for widgets, including ones
+    /// outside this crate, whose children aren't returned there.
+    pub fn accessibility(&self, parent_ctx: &mut AccessCtx, env: &Env) {
+        accessibility::add_widget_nodes(self.as_dyn(), parent_ctx, env);
+    }
+
+    /// Clear the transient interaction state of the child widget.
+    ///
+    /// Generally called by container widgets as part of their
+    /// [`Widget::reset_interaction_state`] method.
+    pub fn reset_interaction_state(&mut self) {
+        self.inner.reset_interaction_state();
+    }
+
     /// Paint a child widget.
     ///
     /// Generally called by container widgets as part of their [`Widget::paint`]
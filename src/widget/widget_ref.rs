@@ -2,7 +2,9 @@
 // "as-is" basis without warranties of any kind. See the LICENSE file for
 // details.
 
-use std::ops::Deref;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::ops::{ControlFlow, Deref};
 
 use smallvec::SmallVec;
 
@@ -122,6 +124,9 @@ impl<'w> WidgetRef<'w, dyn Widget> {
     }
 
     /// Recursively find child widget with given id.
+    ///
+    /// This is a depth-first search, starting with this widget. Returns `None`
+    /// if no widget in the subtree has that id.
     pub fn find_widget_by_id(&self, id: WidgetId) -> Option<WidgetRef<'w, dyn Widget>> {
         if self.state().id == id {
             Some(*self)
@@ -132,6 +137,59 @@ impl<'w> WidgetRef<'w, dyn Widget> {
         }
     }
 
+    /// Recursively find child widget with given id.
+    ///
+    /// This is an alias of [`find_widget_by_id`](Self::find_widget_by_id).
+    pub fn find_by_id(&self, id: WidgetId) -> Option<WidgetRef<'w, dyn Widget>> {
+        self.find_widget_by_id(id)
+    }
+
+    /// Visit this widget and its descendants in breadth-first order.
+    ///
+    /// The visit starts with this widget, then its children in order, then its
+    /// grandchildren, etc. If `visitor` returns [`ControlFlow::Break`], the visit
+    /// stops immediately, and `ControlFlow::Break` is returned.
+    pub fn visit_bfs(
+        &self,
+        visitor: &mut impl FnMut(WidgetRef<'w, dyn Widget>) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
+        let mut queue = VecDeque::from([*self]);
+        while let Some(widget) = queue.pop_front() {
+            visitor(widget)?;
+            queue.extend(widget.children());
+        }
+        ControlFlow::Continue(())
+    }
+
+    /// Return a description of this widget and its descendants, one widget per line.
+    ///
+    /// Each line has the widget's type name, its [`get_debug_text`](Widget::get_debug_text)
+    /// in angle brackets if it has one, its id and its layout rect. Children are
+    /// listed below their parent, indented by two more spaces:
+    ///
+    /// ```text
+    /// Flex #1 Rect { (0, 0) (60×20) }
+    ///   Label<Hello> #2 Rect { (0, 0) (30×20) }
+    /// ```
+    pub fn debug_print_tree(&self) -> String {
+        let mut tree = String::new();
+        self.write_tree(&mut tree, 0);
+        tree
+    }
+
+    fn write_tree(&self, tree: &mut String, depth: usize) {
+        let widget_name = self.deref().short_type_name();
+        let _ = write!(tree, "{:indent$}{widget_name}", "", indent = 2 * depth);
+        if let Some(debug_text) = self.deref().get_debug_text() {
+            let _ = write!(tree, "<{debug_text}>");
+        }
+        let id = self.state().id.to_raw();
+        let _ = writeln!(tree, " #{id} {}", self.state().layout_rect());
+        for child in self.children() {
+            child.write_tree(tree, depth + 1);
+        }
+    }
+
     /// Recursively find innermost widget at given position.
     ///
     /// **pos** - the position in local coordinates (zero being the top-left of the
@@ -210,8 +268,9 @@ mod tests {
     use assert_matches::assert_matches;
 
     use super::*;
-    use crate::testing::{widget_ids, TestHarness, TestWidgetExt as _};
-    use crate::widget::{Button, Label};
+    use crate::testing::{widget_ids, TestHarness};
+    use crate::widget::{Button, Flex, Label};
+    use crate::WidgetExt as _;
     use crate::{Widget, WidgetPod};
 
     #[test]
@@ -235,4 +294,105 @@ mod tests {
         assert_matches!(harness.get_widget(label_id).downcast::<Label>(), Some(_));
         assert_matches!(harness.get_widget(label_id).downcast::<Button>(), None);
     }
+
+    #[test]
+    fn find_widget_by_id() {
+        let [root, inner, label, missing] = widget_ids();
+        let widget = Flex::row()
+            .with_child(Label::new("first"))
+            .with_child_id(
+                Flex::column()
+                    .with_child(Label::new("second"))
+                    .with_child_id(Label::new("nested"), label),
+                inner,
+            )
+            .with_id(root);
+
+        let harness = TestHarness::create(widget);
+        let root = harness.get_widget(root);
+
+        let found = root.find_widget_by_id(label).unwrap();
+        assert_eq!(found.id(), label);
+        assert_eq!(&*found.downcast::<Label>().unwrap().text(), "nested");
+        assert_eq!(root.find_widget_by_id(inner).unwrap().id(), inner);
+        assert_matches!(root.find_widget_by_id(missing), None);
+    }
+
+    #[test]
+    fn find_by_id() {
+        let [root, label, missing] = widget_ids();
+        let widget = Flex::column()
+            .with_child(Label::new("first"))
+            .with_child(
+                Flex::row()
+                    .with_child(Label::new("second"))
+                    .with_child(Flex::column().with_child_id(Label::new("deep"), label)),
+            )
+            .with_id(root);
+
+        let harness = TestHarness::create(widget);
+        let root = harness.get_widget(root);
+
+        let found = root.find_by_id(label).unwrap();
+        assert_eq!(found.id(), label);
+        assert_eq!(&*found.downcast::<Label>().unwrap().text(), "deep");
+        assert_matches!(root.find_by_id(missing), None);
+    }
+
+    #[test]
+    fn debug_print_tree() {
+        let [root, first, second] = widget_ids();
+        let widget = Flex::row()
+            .with_child_id(Label::new("first"), first)
+            .with_child_id(Label::new("second"), second)
+            .with_id(root);
+
+        let harness = TestHarness::create(widget);
+        let tree = harness.get_widget(root).debug_print_tree();
+        let lines: Vec<&str> = tree.lines().collect();
+
+        assert_eq!(lines.len(), 3, "{tree}");
+        assert!(lines[0].starts_with(&format!("Flex #{} Rect {{ ", root.to_raw())));
+        let first_line = format!("  Label<first> #{} Rect {{ ", first.to_raw());
+        let second_line = format!("  Label<second> #{} Rect {{ ", second.to_raw());
+        assert!(lines[1].starts_with(&first_line), "{tree}");
+        assert!(lines[2].starts_with(&second_line), "{tree}");
+    }
+
+    #[test]
+    fn visit_bfs() {
+        let [root, a, b, a1, a2, b1] = widget_ids();
+        let widget = Flex::row()
+            .with_child_id(
+                Flex::column()
+                    .with_child_id(Label::new("a1"), a1)
+                    .with_child_id(Label::new("a2"), a2),
+                a,
+            )
+            .with_child_id(Flex::column().with_child_id(Label::new("b1"), b1), b)
+            .with_id(root);
+
+        let harness = TestHarness::create(widget);
+
+        let mut visited = Vec::new();
+        let flow = harness.get_widget(root).visit_bfs(&mut |widget| {
+            visited.push(widget.id());
+            ControlFlow::Continue(())
+        });
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(visited, vec![root, a, b, a1, a2, b1]);
+
+        // Stops as soon as the visitor breaks.
+        let mut visited = Vec::new();
+        let flow = harness.get_widget(root).visit_bfs(&mut |widget| {
+            visited.push(widget.id());
+            if widget.id() == a1 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        });
+        assert_eq!(flow, ControlFlow::Break(()));
+        assert_eq!(visited, vec![root, a, b, a1]);
+    }
 }
@@ -4,15 +4,19 @@
 
 #![cfg(not(tarpaulin_include))]
 
+use std::cell::RefCell;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use druid_shell::{Cursor, Region};
+use druid_shell::{Cursor, Region, TimerToken};
+use instant::{Duration, Instant};
 
 use crate::bloom::Bloom;
+use crate::command::SelectorSymbol;
 use crate::kurbo::{Insets, Point, Rect, Size};
+use crate::piet::PietImage;
 use crate::text::TextFieldRegistration;
 use crate::widget::{CursorChange, FocusChange};
-use crate::WidgetId;
+use crate::{BoxConstraints, ContextMenu, WidgetId};
 
 // FIXME #5 - Make a note documenting this: the only way to get a &mut WidgetState should be in a pass.
 // A pass should reborrow the parent widget state (to avoid crossing wires) and call merge_up at
@@ -44,6 +48,8 @@ pub struct WidgetState {
     /// The size of the child; this is the value returned by the child's layout
     /// method.
     pub(crate) size: Size,
+    /// The constraints the widget was last laid out with.
+    pub(crate) layout_constraints: Option<BoxConstraints>,
     /// The origin of the child in the parent's coordinate space; together with
     /// `size` these constitute the child's layout rect.
     pub(crate) origin: Point,
@@ -62,14 +68,23 @@ pub struct WidgetState {
     /// the baseline. Widgets that contain text or controls that expect to be
     /// laid out alongside text can set this as appropriate.
     pub(crate) baseline_offset: f64,
+    /// The y positions of the first and last baselines, in local coordinates.
+    ///
+    /// If the widget didn't set them, both are derived from `baseline_offset`.
+    pub(crate) baselines: Option<(f64, f64)>,
     // TODO - Document
     pub(crate) is_portal: bool,
+    /// The order in which the parent paints this widget relative to its siblings.
+    pub(crate) z_order: i32,
 
     // --- PASSES ---
 
     // TODO: consider using bitflags for the booleans.
     // The region that needs to be repainted, relative to the widget's bounds.
     pub(crate) invalid: Region,
+    // What the widget painted in `PaintCtx::with_cache`. Cleared when the widget
+    // is laid out or it or one of its descendants requests a paint.
+    pub(crate) paint_cache: RefCell<Option<PaintCache>>,
     /// A flag used to track and debug missing calls to place_child.
     pub(crate) is_expecting_place_child_call: bool,
 
@@ -99,6 +114,10 @@ pub struct WidgetState {
 
     pub(crate) children: Bloom<WidgetId>,
     pub(crate) children_changed: bool,
+    /// The selectors this widget subscribed to, with `LifeCycleCtx::subscribe_to`.
+    pub(crate) subscribed_selectors: Vec<SelectorSymbol>,
+    /// The selectors this widget and its descendants subscribed to.
+    pub(crate) subscriptions: Bloom<SelectorSymbol>,
     /// The cursor that was set using one of the context methods.
     pub(crate) cursor_change: CursorChange,
     /// The result of merging up children cursors. This gets cleared when merging state up (unlike
@@ -106,6 +125,14 @@ pub struct WidgetState {
     pub(crate) cursor: Option<Cursor>,
 
     pub(crate) text_registrations: Vec<TextFieldRegistration>,
+    /// The IME caret rect most recently reported by this widget or a descendant,
+    /// in this widget's coordinate space.
+    pub(crate) ime_caret_rect: Option<Rect>,
+    /// A rect that this widget or a descendant asked to scroll into view, in this
+    /// widget's coordinate space.
+    pub(crate) scroll_to_visible: Option<Rect>,
+    /// A context menu requested by a descendant, with its position in window coordinates.
+    pub(crate) context_menu: Option<(ContextMenu, Point)>,
 
     // --- STATUS ---
     // `true` if one of our ancestors is disabled (meaning we are also disabled).
@@ -117,6 +144,15 @@ pub struct WidgetState {
 
     pub(crate) is_hot: bool,
 
+    // When the pointer last entered the widget, if it's hot.
+    pub(crate) hover_start: Option<Instant>,
+    // The timer used to send `Event::HoverStart`, if it hasn't fired yet.
+    pub(crate) hover_timer: Option<TimerToken>,
+    // `Event::HoverStart` was sent, and `Event::HoverEnd` wasn't.
+    pub(crate) is_hovering: bool,
+    // The hover duration of an `Event::HoverEnd` waiting to be sent.
+    pub(crate) pending_hover_end: Option<Duration>,
+
     pub(crate) is_active: bool,
 
     /// Any descendant is active.
@@ -140,6 +176,18 @@ pub struct WidgetState {
     pub(crate) widget_name: &'static str,
 }
 
+/// The output of [`PaintCtx::with_cache`](crate::PaintCtx::with_cache).
+#[derive(Clone)]
+pub(crate) struct PaintCache {
+    /// The hash of the key passed to `with_cache`.
+    pub key: u64,
+    /// The area the image covers, in the widget's coordinate space.
+    pub rect: Rect,
+    /// The number of pixels per logical pixel the image was rendered at.
+    pub scale: f64,
+    pub image: PietImage,
+}
+
 // This is a hack to have a simple Clone impl for WidgetState
 #[derive(Debug)]
 pub(crate) struct VisitBool(pub AtomicBool);
@@ -151,17 +199,25 @@ impl WidgetState {
             origin: Point::ORIGIN,
             parent_window_origin: Point::ORIGIN,
             size: size.unwrap_or_default(),
+            layout_constraints: None,
             is_expecting_place_child_call: false,
             paint_insets: Insets::ZERO,
             local_paint_rect: Rect::ZERO,
             invalid: Region::EMPTY,
+            paint_cache: RefCell::new(None),
             is_portal: false,
+            z_order: 0,
             is_new: true,
             children_disabled_changed: false,
             ancestor_disabled: false,
             is_explicitly_disabled: false,
             baseline_offset: 0.0,
+            baselines: None,
             is_hot: false,
+            hover_start: None,
+            hover_timer: None,
+            is_hovering: false,
+            pending_hover_end: None,
             needs_layout: false,
             needs_window_origin: false,
             is_active: false,
@@ -172,10 +228,15 @@ impl WidgetState {
             focus_chain: Vec::new(),
             children: Bloom::new(),
             children_changed: false,
+            subscribed_selectors: Vec::new(),
+            subscriptions: Bloom::new(),
             cursor_change: CursorChange::Default,
             cursor: None,
             is_explicitly_disabled_new: false,
             text_registrations: Vec::new(),
+            ime_caret_rect: None,
+            scroll_to_visible: None,
+            context_menu: None,
             update_focus_chain: false,
             is_stashed: false,
             #[cfg(debug_assertions)]
@@ -209,9 +270,17 @@ impl WidgetState {
 
     /// Update to incorporate state changes from a child.
     ///
+    /// This aggregates the flags that tell the framework it needs to recurse
+    /// into this widget: invalidated paint regions, layout and animation requests,
+    /// focus requests, active and focus status, disabled and children changes,
+    /// and the cursor. Hot status isn't merged, since every widget computes its
+    /// own from the mouse position.
+    ///
     /// This will also clear some requests in the child state.
     ///
     /// This method is idempotent and can be called multiple times.
+    ///
+    /// See also [`EventCtx::merge_child_state`](crate::EventCtx::merge_child_state).
     pub(crate) fn merge_up(&mut self, child_state: &mut WidgetState) {
         // TODO - Ideally, we'd want to do this in global coordinates. The problem
         // is that a parent could change this widget's coordinates through place_child
@@ -221,10 +290,14 @@ impl WidgetState {
             .with_origin(Point::ORIGIN)
             .inset(self.paint_insets);
         let offset = child_state.layout_rect().origin().to_vec2();
+        if !child_state.invalid.is_empty() {
+            child_state.paint_cache.get_mut().take();
+        }
         for &rect in child_state.invalid.rects() {
             let rect = (rect + offset).intersect(clip);
             if rect.area() != 0.0 {
                 self.invalid.add_rect(rect);
+                self.paint_cache.get_mut().take();
             }
         }
         // Clearing the invalid rects here is less fragile than doing it while painting. The
@@ -245,6 +318,15 @@ impl WidgetState {
         self.request_focus = child_state.request_focus.take().or(self.request_focus);
         self.text_registrations
             .append(&mut child_state.text_registrations);
+        if let Some(caret_rect) = child_state.ime_caret_rect.take() {
+            self.ime_caret_rect = Some(caret_rect + offset);
+        }
+        if let Some(rect) = child_state.scroll_to_visible.take() {
+            self.scroll_to_visible = Some(rect + offset);
+        }
+        if let Some(context_menu) = child_state.context_menu.take() {
+            self.context_menu = Some(context_menu);
+        }
         self.update_focus_chain |= child_state.update_focus_chain;
 
         // We reset `child_state.cursor` no matter what, so that on the every pass through the tree,
@@ -270,6 +352,40 @@ impl WidgetState {
         self.cursor.take().or_else(|| self.cursor_change.cursor())
     }
 
+    /// Whether this widget or one of its descendants has requested a paint pass.
+    pub fn needs_paint(&self) -> bool {
+        !self.invalid.is_empty()
+    }
+
+    /// Whether this widget or one of its descendants has requested a layout pass.
+    pub fn needs_layout(&self) -> bool {
+        self.needs_layout
+    }
+
+    /// The y position of the first baseline, in the widget's coordinate space.
+    ///
+    /// For multi-line widgets such as labels, this is the baseline of the top line.
+    /// Widgets that don't set their baselines with [`LayoutCtx::set_baselines`]
+    /// have a single baseline, given by their baseline offset.
+    ///
+    /// [`LayoutCtx::set_baselines`]: crate::LayoutCtx::set_baselines
+    pub fn first_baseline(&self) -> f64 {
+        match self.baselines {
+            Some((first, _)) => first,
+            None => self.size.height - self.baseline_offset,
+        }
+    }
+
+    /// The y position of the last baseline, in the widget's coordinate space.
+    ///
+    /// See [`first_baseline`](Self::first_baseline).
+    pub fn last_baseline(&self) -> f64 {
+        match self.baselines {
+            Some((_, last)) => last,
+            None => self.size.height - self.baseline_offset,
+        }
+    }
+
     #[inline]
     pub(crate) fn size(&self) -> Size {
         self.size
@@ -289,6 +405,13 @@ impl WidgetState {
         Rect::from_origin_size(self.origin, self.size)
     }
 
+    /// The paint order of the widget among its siblings.
+    ///
+    /// For more information, see [`WidgetPod::z_order`](crate::WidgetPod::z_order).
+    pub fn z_order(&self) -> i32 {
+        self.z_order
+    }
+
     /// The [layout_rect](crate::WidgetPod::layout_rect) in window coordinates.
     ///
     /// This might not map to a visible area of the screen, eg if the widget is scrolled
@@ -302,6 +425,16 @@ impl WidgetState {
     }
 }
 
+impl std::fmt::Debug for PaintCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PaintCache")
+            .field("key", &self.key)
+            .field("rect", &self.rect)
+            .field("scale", &self.scale)
+            .finish_non_exhaustive()
+    }
+}
+
 impl Clone for VisitBool {
     fn clone(&self) -> Self {
         VisitBool(self.0.load(Ordering::SeqCst).into())